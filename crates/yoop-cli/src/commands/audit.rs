@@ -0,0 +1,108 @@
+//! Audit command implementation.
+
+use anyhow::{Context, Result};
+
+use yoop_core::audit::{AuditEntry, AuditLog};
+
+use super::AuditArgs;
+
+/// Run the audit command.
+pub fn run(args: &AuditArgs) -> Result<()> {
+    let log = AuditLog::open_default();
+
+    if args.verify {
+        return verify(&log, args.json);
+    }
+
+    let mut entries = log.entries().context("Failed to read audit log")?;
+    entries.reverse();
+    if args.failures {
+        entries.retain(|e| e.kind.is_failure());
+    }
+    let total = entries.len();
+    entries.truncate(args.limit);
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    show_list(&log, &entries, total);
+    Ok(())
+}
+
+/// Check the hash chain and report the first broken entry.
+fn verify(log: &AuditLog, json: bool) -> Result<()> {
+    let report = log.verify().context("Failed to read audit log")?;
+
+    if json {
+        let output = serde_json::json!({
+            "path": log.path().display().to_string(),
+            "entries": report.entries,
+            "head": report.head,
+            "intact": report.is_intact(),
+            "broken_line": report.broken.as_ref().map(|b| b.line),
+            "reason": report.broken.as_ref().map(|b| b.reason.clone()),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        println!();
+        println!("  Audit log: {}", log.path().display());
+        println!("  Entries:   {}", report.entries);
+        if let Some(head) = &report.head {
+            println!("  Head:      {}", head);
+        }
+        println!();
+        match &report.broken {
+            None => println!("  ✓ Hash chain intact"),
+            Some(broken) => println!(
+                "  ✗ Hash chain broken at line {}: {}",
+                broken.line, broken.reason
+            ),
+        }
+        println!();
+    }
+
+    if let Some(broken) = report.broken {
+        anyhow::bail!("audit log has been modified (line {})", broken.line);
+    }
+    Ok(())
+}
+
+/// Show the most recent events.
+fn show_list(log: &AuditLog, entries: &[AuditEntry], total: usize) {
+    println!();
+    println!("Security Events:");
+    println!("{}", "─".repeat(78));
+    println!("  {:19}  {:24}  {:28}", "Date (UTC)", "Event", "Peer");
+    println!("{}", "─".repeat(78));
+
+    if entries.is_empty() {
+        println!("  (no events recorded)");
+    } else {
+        for entry in entries {
+            let marker = if entry.kind.is_failure() { "!" } else { " " };
+            println!(
+                "{} {:19}  {:24}  {}",
+                marker,
+                entry.formatted_timestamp(),
+                entry.kind.as_str(),
+                entry.peer_label(),
+            );
+            if let Some(detail) = &entry.detail {
+                println!("  {:19}  {}", "", detail);
+            }
+        }
+
+        if total > entries.len() {
+            println!();
+            println!("  (showing {} of {} events)", entries.len(), total);
+        }
+    }
+
+    println!("{}", "─".repeat(78));
+    println!();
+    println!("  Log: {}", log.path().display());
+    println!("  Use --verify to check the log for tampering.");
+    println!();
+}
@@ -0,0 +1,244 @@
+//! Bench command implementation.
+//!
+//! Measures RTT, raw TLS throughput, compression and hashing speed, either
+//! over loopback or against a trusted device running `yoop bench --listen`.
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use yoop_core::bench::{self, BenchConfig, BenchReport, BenchServer, Throughput};
+use yoop_core::crypto::DeviceIdentity;
+use yoop_core::discovery::{BeaconBroadcaster, BeaconListener, DeviceBeacon};
+use yoop_core::file::format_size;
+use yoop_core::trust::{TrustStore, TrustedDevice};
+use yoop_core::VERSION;
+
+use super::BenchArgs;
+
+/// Run the bench command.
+pub async fn run(args: BenchArgs) -> Result<()> {
+    if args.listen {
+        return run_server(&args).await;
+    }
+
+    let global_config = super::load_config();
+
+    let payload_size = super::sync::parse_size(&args.size)?;
+    let config = BenchConfig {
+        payload_size,
+        chunk_size: global_config.transfer.chunk_size,
+        rtt_samples: args.samples,
+        compression_level: global_config.transfer.compression_level,
+    };
+
+    if !args.json {
+        println!();
+        println!("Yoop v{} - Benchmark", VERSION);
+        println!("{}", "─".repeat(50));
+        println!();
+    }
+
+    let report = if let Some(ref device_name) = args.device {
+        let trust_store = TrustStore::load().context("Failed to load trust store")?;
+        let device = trust_store
+            .find_by_name(device_name)
+            .ok_or_else(|| anyhow::anyhow!("Device '{}' not found in trust store", device_name))?
+            .clone();
+        let identity =
+            DeviceIdentity::load_or_generate().context("Failed to load device identity")?;
+
+        let addr = resolve_peer(&device, &args, &global_config).await?;
+
+        if !args.json {
+            println!(
+                "  Benchmarking {} at {} ({})...",
+                device.device_name,
+                addr,
+                format_size(payload_size)
+            );
+            println!();
+        }
+
+        bench::run_peer(&device, addr, &identity, &config)
+            .await
+            .context("Benchmark failed")?
+    } else {
+        if !args.json {
+            println!("  Benchmarking loopback ({})...", format_size(payload_size));
+            println!();
+        }
+
+        bench::run_loopback(&config)
+            .await
+            .context("Benchmark failed")?
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        output_text(&report);
+    }
+
+    Ok(())
+}
+
+/// Run a bench server for trusted devices.
+async fn run_server(args: &BenchArgs) -> Result<()> {
+    let global_config = super::load_config();
+
+    let identity = DeviceIdentity::load_or_generate().context("Failed to load device identity")?;
+    let trust_store = TrustStore::load().context("Failed to load trust store")?;
+
+    let device_name = global_config.general.device_name.clone();
+    let beacon = DeviceBeacon::new(
+        identity.device_id(),
+        &device_name,
+        &identity.public_key_base64(),
+        args.port,
+    );
+
+    let server = BenchServer::new(args.port, identity.clone(), trust_store)
+        .await
+        .context("Failed to start bench server")?;
+
+    let broadcaster = BeaconBroadcaster::with_options(
+        global_config.network.port,
+        &global_config.network.discovery_options(),
+    )
+    .await
+    .context("Failed to start beacon broadcaster")?;
+    broadcaster
+        .start(beacon, &identity, Duration::from_secs(2))
+        .await?;
+
+    println!();
+    println!("Yoop v{} - Benchmark Server", VERSION);
+    println!("{}", "─".repeat(50));
+    println!();
+    println!("  Listening on {}", server.local_addr()?);
+    println!(
+        "  Run 'yoop bench --device {}' on a trusted device",
+        device_name
+    );
+    println!("  Press Ctrl+C to stop");
+    println!();
+
+    loop {
+        tokio::select! {
+            result = server.serve_one() => match result {
+                Ok((name, addr, bytes)) => {
+                    println!("  Served {} ({}): {} received", name, addr, format_size(bytes));
+                }
+                Err(e) => {
+                    eprintln!("  Benchmark from peer failed: {}", e);
+                }
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    broadcaster.stop().await;
+    Ok(())
+}
+
+/// Find the address of a peer's bench server.
+///
+/// Priority:
+/// 1. Explicit `--host`
+/// 2. Beacon discovery
+/// 3. Stored address from the trust store (with `--port`)
+async fn resolve_peer(
+    device: &TrustedDevice,
+    args: &BenchArgs,
+    global_config: &yoop_core::config::Config,
+) -> Result<SocketAddr> {
+    if let Some(ref host) = args.host {
+        if let Ok(addr) = host.parse::<SocketAddr>() {
+            return Ok(addr);
+        }
+        let ip: IpAddr = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse()
+            .with_context(|| format!("Invalid host address: {}", host))?;
+        return Ok(SocketAddr::new(ip, args.port));
+    }
+
+    let network = &global_config.network;
+    if let Ok(listener) =
+        BeaconListener::with_options(network.port, &network.discovery_options()).await
+    {
+        if let Ok(found) = listener
+            .with_trusted([device])
+            .find_device(device.device_id, Duration::from_secs(5))
+            .await
+        {
+            return Ok(found.transfer_addr());
+        }
+    }
+
+    device
+        .address()
+        .map(|(ip, _)| SocketAddr::new(ip, args.port))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not find '{}' on the network; is 'yoop bench --listen' running there? \
+                 Use --host to connect directly",
+                device.device_name
+            )
+        })
+}
+
+/// Output the report as text.
+fn output_text(report: &BenchReport) {
+    if let Some(ref rtt) = report.rtt {
+        println!("  Latency ({} samples):", rtt.samples);
+        println!(
+            "    RTT:           {:.2} ms avg ({:.2} min / {:.2} max)",
+            rtt.avg_ms, rtt.min_ms, rtt.max_ms
+        );
+        println!();
+    }
+
+    if let Some(ref network) = report.network {
+        println!(
+            "  Network (TLS, {} chunks):",
+            format_size(report.chunk_size as u64)
+        );
+        println!("    Throughput:    {}", format_rate(network));
+        println!();
+    }
+
+    let compression = &report.compression;
+    println!(
+        "  Compression ({:?}, level {}):",
+        compression.algorithm, compression.level
+    );
+    println!("    Compress:      {}", format_rate(&compression.compress));
+    println!(
+        "    Decompress:    {}",
+        format_rate(&compression.decompress)
+    );
+    println!("    Ratio:         {:.1}%", compression.ratio * 100.0);
+    println!();
+
+    println!("  Hashing:");
+    println!("    SHA-256:       {}", format_rate(&report.hashing.sha256));
+    println!(
+        "    xxHash64:      {}",
+        format_rate(&report.hashing.xxhash64)
+    );
+    println!();
+
+    println!("{}", "─".repeat(50));
+    println!();
+    println!("  Bottleneck: {}", report.bottleneck());
+    println!();
+}
+
+/// Format a throughput as a human-readable rate.
+fn format_rate(throughput: &Throughput) -> String {
+    format!("{:.1} MB/s", throughput.mb_per_sec())
+}
@@ -11,7 +11,6 @@ use yoop_core::clipboard::{
     diagnose_clipboard, ClipboardReceiveSession, ClipboardShareSession, ClipboardSyncSession,
     SyncHostSession, SyncSessionRunner,
 };
-use yoop_core::config::Config;
 use yoop_core::connection::parse_host_address;
 use yoop_core::transfer::TransferConfig;
 use yoop_core::trust::TrustStore;
@@ -20,17 +19,6 @@ use super::{ClipboardAction, ClipboardArgs};
 use crate::tui::session::{ClipboardSyncEntry, SessionStateFile};
 use crate::ui::{format_remaining, CodeBox};
 
-/// Create a TransferConfig using global config values.
-fn create_transfer_config(global_config: &Config) -> TransferConfig {
-    TransferConfig {
-        chunk_size: global_config.transfer.chunk_size,
-        parallel_streams: global_config.transfer.parallel_chunks,
-        verify_checksums: global_config.transfer.verify_checksum,
-        discovery_port: global_config.network.port,
-        ..Default::default()
-    }
-}
-
 /// Run the clipboard command.
 pub async fn run(args: ClipboardArgs) -> Result<()> {
     super::spawn_update_check();
@@ -54,7 +42,7 @@ async fn run_share(_args: super::ClipboardShareArgs, quiet: bool, json: bool) ->
         println!();
     }
 
-    let config = create_transfer_config(&global_config);
+    let config = global_config.transfer_config();
 
     let session = match ClipboardShareSession::new(config).await {
         Ok(s) => s,
@@ -78,11 +66,13 @@ async fn run_share(_args: super::ClipboardShareArgs, quiet: bool, json: bool) ->
     let code = session.code().to_string();
     let content_preview = session.content().preview(50);
     let content_size = session.content().format_size();
+    let port = session.local_addr()?.port();
 
     if json {
         let output = serde_json::json!({
             "status": "waiting",
             "code": code,
+            "port": port,
             "content": {
                 "preview": content_preview,
                 "size": content_size,
@@ -96,6 +86,7 @@ async fn run_share(_args: super::ClipboardShareArgs, quiet: bool, json: bool) ->
         println!();
         CodeBox::new(&code).display();
         println!();
+        println!("  Listening on port {}", port);
         println!("  Waiting for receiver...");
         println!();
     }
@@ -135,7 +126,7 @@ async fn run_share(_args: super::ClipboardShareArgs, quiet: bool, json: bool) ->
 #[allow(clippy::too_many_lines)]
 async fn run_receive(args: super::ClipboardReceiveArgs, quiet: bool, json: bool) -> Result<()> {
     let global_config = super::load_config();
-    let config = create_transfer_config(&global_config);
+    let config = global_config.transfer_config();
 
     if let Some(ref device_name) = args.device {
         return run_receive_trusted(device_name, config, &args, quiet, json).await;
@@ -422,7 +413,7 @@ async fn run_receive_trusted(
 /// Run clipboard sync (bidirectional live sync).
 async fn run_sync(args: super::ClipboardSyncArgs, quiet: bool, json: bool) -> Result<()> {
     let global_config = super::load_config();
-    let config = create_transfer_config(&global_config);
+    let config = global_config.transfer_config();
 
     if !quiet && !json {
         println!();
@@ -482,16 +473,20 @@ async fn run_sync(args: super::ClipboardSyncArgs, quiet: bool, json: bool) -> Re
         };
 
         let code = host_session.code().to_string();
+        let port = host_session.local_addr()?.port();
 
         if json {
             let output = serde_json::json!({
                 "status": "waiting",
                 "code": &code,
+                "port": port,
             });
             println!("{}", serde_json::to_string_pretty(&output)?);
         } else if !quiet {
             CodeBox::new(&code).display();
             println!();
+            println!("  Listening on port {}", port);
+            println!();
         }
 
         let trust_store = TrustStore::load().ok();
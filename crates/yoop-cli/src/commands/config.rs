@@ -58,7 +58,32 @@ pub async fn run(args: ConfigArgs) -> Result<()> {
                 config.network.transfer_port_range.0, config.network.transfer_port_range.1
             );
             println!("  interface = \"{}\"", config.network.interface);
+            println!(
+                "  allow_interfaces = {}",
+                format_list(&config.network.allow_interfaces)
+            );
+            println!(
+                "  deny_interfaces = {}",
+                format_list(&config.network.deny_interfaces)
+            );
             println!("  ipv6 = {}", config.network.ipv6);
+            println!("  privacy_mode = {}", config.network.privacy_mode);
+            println!("  sweep = {}", config.network.sweep);
+            println!(
+                "  sweep_ranges = {}",
+                format_list(&config.network.sweep_ranges)
+            );
+            println!("  multicast = {}", config.network.multicast);
+            println!("  multicast_group = {}", config.network.multicast_group);
+            println!("  multicast_ttl = {}", config.network.multicast_ttl);
+            println!(
+                "  relay = {}",
+                config
+                    .network
+                    .relay
+                    .as_ref()
+                    .map_or_else(|| "none".to_string(), |r| format!("\"{r}\""))
+            );
             println!();
 
             // [transfer]
@@ -77,6 +102,46 @@ pub async fn run(args: ConfigArgs) -> Result<()> {
                 format!("{:?}", config.transfer.compression).to_lowercase()
             );
             println!("  verify_checksum = {}", config.transfer.verify_checksum);
+            println!(
+                "  reconnect_grace = \"{}s\"",
+                config.transfer.reconnect_grace.as_secs()
+            );
+            println!();
+
+            // [transfer.limits]
+            let limits = &config.transfer.limits;
+            println!("[transfer.limits]");
+            println!(
+                "  max_files = {}",
+                limits
+                    .max_files
+                    .map_or_else(|| "unlimited".to_string(), |n| n.to_string())
+            );
+            println!(
+                "  max_total_size = {}",
+                limits
+                    .max_total_size
+                    .map_or_else(|| "unlimited".to_string(), |n| n.to_string())
+            );
+            println!("  max_path_length = {}", limits.max_path_length);
+            println!("  max_path_depth = {}", limits.max_path_depth);
+            println!("  symlinks = \"{}\"", limits.symlinks);
+            println!();
+
+            // [transfer.quarantine]
+            let quarantine = &config.transfer.quarantine;
+            println!("[transfer.quarantine]");
+            println!(
+                "  scan_command = {}",
+                quarantine
+                    .scan_command
+                    .as_ref()
+                    .map_or_else(|| "none".to_string(), |c| format!("{c:?}"))
+            );
+            println!(
+                "  scan_timeout = \"{}s\"",
+                quarantine.scan_timeout.as_secs()
+            );
             println!();
 
             // [security]
@@ -92,6 +157,7 @@ pub async fn run(args: ConfigArgs) -> Result<()> {
                 "  rate_limit_window = \"{}s\"",
                 config.security.rate_limit_window.as_secs()
             );
+            println!("  audit_log = {}", config.security.audit_log);
             println!();
 
             // [preview]
@@ -129,7 +195,16 @@ pub async fn run(args: ConfigArgs) -> Result<()> {
             println!("  enabled = {}", config.web.enabled);
             println!("  port = {}", config.web.port);
             println!("  auth = {}", config.web.auth);
+            println!(
+                "  password = {}",
+                if config.web.password.is_some() {
+                    "(set)"
+                } else {
+                    "none"
+                }
+            );
             println!("  localhost_only = {}", config.web.localhost_only);
+            println!("  https = {}", config.web.https);
             println!();
 
             // [ui]
@@ -172,14 +247,39 @@ pub async fn run(args: ConfigArgs) -> Result<()> {
             println!("  port                Discovery port (UDP)");
             println!("  transfer_port_range Transfer port range (e.g., 52530-52540)");
             println!("  interface           Network interface (auto or specific)");
+            println!("  allow_interfaces    Interfaces discovery may use (e.g., en*,wlan0)");
+            println!("  deny_interfaces     Interfaces discovery skips (e.g., docker*,veth*)");
             println!("  ipv6                Enable IPv6 (true/false)");
+            println!("  privacy_mode        Hide device identity in discovery (true/false)");
+            println!(
+                "  sweep               Probe subnet hosts if discovery finds nothing (true/false)"
+            );
+            println!(
+                "  sweep_ranges        CIDR ranges to sweep (e.g., 10.20.0.0/24; none = local)"
+            );
+            println!("  multicast           Also use IPv4 multicast for discovery (true/false)");
+            println!("  multicast_group     IPv4 multicast group (e.g., 239.255.79.70)");
+            println!("  multicast_ttl       IPv4 multicast TTL (1 = local link only)");
+            println!("  relay               Relay server for other networks (host:port, none)");
             println!();
             println!("[transfer]");
             println!("  chunk_size          Chunk size for transfers (e.g., 1MB, 512KB)");
             println!("  parallel_chunks     Number of parallel chunk streams");
             println!("  bandwidth_limit     Bandwidth limit (e.g., 50MB, unlimited)");
-            println!("  compression         Compression mode (auto, always, never)");
+            println!("  compression         Compression mode (auto, always, never, adaptive)");
             println!("  verify_checksum     Verify checksums after transfer (true/false)");
+            println!("  reconnect_grace     Wait for a dropped peer to reconnect (e.g., 60s, 0s)");
+            println!();
+            println!("[transfer.limits] (checked before accepting a transfer)");
+            println!("  limits.max_files       Maximum files per transfer (or unlimited)");
+            println!("  limits.max_total_size  Maximum transfer size (e.g., 10GB, unlimited)");
+            println!("  limits.max_path_length Maximum path length in bytes");
+            println!("  limits.max_path_depth  Maximum directory depth");
+            println!("  limits.symlinks        Symlinks accepted (contained, reject, any)");
+            println!();
+            println!("[transfer.quarantine] (checked before a received file is released)");
+            println!("  quarantine.scan_command Scanner run on each file, e.g. \"clamdscan --no-summary\" (or none)");
+            println!("  quarantine.scan_timeout How long the scanner may run (e.g., 60s)");
             println!();
             println!("[security]");
             println!("  require_pin         Require additional PIN (true/false)");
@@ -187,6 +287,7 @@ pub async fn run(args: ConfigArgs) -> Result<()> {
             println!("  tls_verify          Verify TLS certificates (true/false)");
             println!("  rate_limit_attempts Failed attempts before lockout");
             println!("  rate_limit_window   Lockout duration (e.g., 30s, 1m)");
+            println!("  audit_log           Record security events to the audit log (true/false)");
             println!();
             println!("[preview]");
             println!("  preview.enabled         Enable preview generation (true/false)");
@@ -207,7 +308,9 @@ pub async fn run(args: ConfigArgs) -> Result<()> {
             println!("  web.enabled         Enable web server by default (true/false)");
             println!("  web.port            Web server port");
             println!("  web.auth            Require authentication (true/false)");
+            println!("  web.password        Fixed web UI password (none = generate each start)");
             println!("  web.localhost_only  Bind to localhost only (true/false)");
+            println!("  web.https           Serve over HTTPS (true/false)");
             println!();
             println!("[ui]");
             println!("  ui.theme            Theme (auto, light, dark)");
@@ -239,6 +342,7 @@ pub async fn run(args: ConfigArgs) -> Result<()> {
     Ok(())
 }
 
+#[allow(clippy::too_many_lines)]
 fn get_config_value(config: &yoop_core::config::Config, key: &str) -> Option<String> {
     match key {
         // general
@@ -257,7 +361,16 @@ fn get_config_value(config: &yoop_core::config::Config, key: &str) -> Option<Str
             config.network.transfer_port_range.0, config.network.transfer_port_range.1
         )),
         "interface" => Some(config.network.interface.clone()),
+        "allow_interfaces" => Some(config.network.allow_interfaces.join(",")),
+        "deny_interfaces" => Some(config.network.deny_interfaces.join(",")),
         "ipv6" => Some(config.network.ipv6.to_string()),
+        "privacy_mode" => Some(config.network.privacy_mode.to_string()),
+        "sweep" => Some(config.network.sweep.to_string()),
+        "sweep_ranges" => Some(config.network.sweep_ranges.join(",")),
+        "multicast" => Some(config.network.multicast.to_string()),
+        "multicast_group" => Some(config.network.multicast_group.to_string()),
+        "multicast_ttl" => Some(config.network.multicast_ttl.to_string()),
+        "relay" => config.network.relay.clone(),
 
         // transfer
         "chunk_size" => Some(config.transfer.chunk_size.to_string()),
@@ -270,6 +383,36 @@ fn get_config_value(config: &yoop_core::config::Config, key: &str) -> Option<Str
         ),
         "compression" => Some(format!("{:?}", config.transfer.compression).to_lowercase()),
         "verify_checksum" => Some(config.transfer.verify_checksum.to_string()),
+        "reconnect_grace" => Some(format!("{}s", config.transfer.reconnect_grace.as_secs())),
+        "limits.max_files" => Some(
+            config
+                .transfer
+                .limits
+                .max_files
+                .map_or_else(|| "unlimited".to_string(), |n| n.to_string()),
+        ),
+        "limits.max_total_size" => Some(
+            config
+                .transfer
+                .limits
+                .max_total_size
+                .map_or_else(|| "unlimited".to_string(), |n| n.to_string()),
+        ),
+        "limits.max_path_length" => Some(config.transfer.limits.max_path_length.to_string()),
+        "limits.max_path_depth" => Some(config.transfer.limits.max_path_depth.to_string()),
+        "limits.symlinks" => Some(config.transfer.limits.symlinks.to_string()),
+        "quarantine.scan_command" => Some(
+            config
+                .transfer
+                .quarantine
+                .scan_command
+                .as_ref()
+                .map_or_else(|| "none".to_string(), |c| c.join(" ")),
+        ),
+        "quarantine.scan_timeout" => Some(format!(
+            "{}s",
+            config.transfer.quarantine.scan_timeout.as_secs()
+        )),
 
         // security
         "require_pin" => Some(config.security.require_pin.to_string()),
@@ -277,6 +420,7 @@ fn get_config_value(config: &yoop_core::config::Config, key: &str) -> Option<Str
         "tls_verify" => Some(config.security.tls_verify.to_string()),
         "rate_limit_attempts" => Some(config.security.rate_limit_attempts.to_string()),
         "rate_limit_window" => Some(format!("{}s", config.security.rate_limit_window.as_secs())),
+        "audit_log" => Some(config.security.audit_log.to_string()),
 
         // preview
         "preview.enabled" => Some(config.preview.enabled.to_string()),
@@ -302,7 +446,15 @@ fn get_config_value(config: &yoop_core::config::Config, key: &str) -> Option<Str
         "web.enabled" => Some(config.web.enabled.to_string()),
         "web.port" => Some(config.web.port.to_string()),
         "web.auth" => Some(config.web.auth.to_string()),
+        "web.password" => Some(
+            config
+                .web
+                .password
+                .as_ref()
+                .map_or_else(|| "none".to_string(), |p| format!("\"{p}\"")),
+        ),
         "web.localhost_only" => Some(config.web.localhost_only.to_string()),
+        "web.https" => Some(config.web.https.to_string()),
 
         // ui
         "ui.theme" => Some(config.ui.theme.clone()),
@@ -360,6 +512,9 @@ fn set_config_value(
             }
             let start: u16 = parts[0].parse()?;
             let end: u16 = parts[1].parse()?;
+            if start > end {
+                anyhow::bail!("Invalid port range: start must not be greater than end");
+            }
             config.network.transfer_port_range = (start, end);
             Ok(true)
         }
@@ -367,10 +522,54 @@ fn set_config_value(
             config.network.interface = value.to_string();
             Ok(true)
         }
+        "allow_interfaces" => {
+            config.network.allow_interfaces = parse_list(value);
+            Ok(true)
+        }
+        "deny_interfaces" => {
+            config.network.deny_interfaces = parse_list(value);
+            Ok(true)
+        }
         "ipv6" => {
             config.network.ipv6 = value.parse()?;
             Ok(true)
         }
+        "privacy_mode" => {
+            config.network.privacy_mode = value.parse()?;
+            Ok(true)
+        }
+        "sweep" => {
+            config.network.sweep = value.parse()?;
+            Ok(true)
+        }
+        "sweep_ranges" => {
+            config.network.sweep_ranges = parse_list(value);
+            Ok(true)
+        }
+        "multicast" => {
+            config.network.multicast = value.parse()?;
+            Ok(true)
+        }
+        "multicast_group" => {
+            let group: std::net::Ipv4Addr = value.parse()?;
+            if !group.is_multicast() {
+                anyhow::bail!("{} is not an IPv4 multicast address", group);
+            }
+            config.network.multicast_group = group;
+            Ok(true)
+        }
+        "multicast_ttl" => {
+            config.network.multicast_ttl = value.parse()?;
+            Ok(true)
+        }
+        "relay" => {
+            if value.is_empty() || value == "none" {
+                config.network.relay = None;
+            } else {
+                config.network.relay = Some(value.to_string());
+            }
+            Ok(true)
+        }
 
         // transfer
         "chunk_size" => {
@@ -394,7 +593,10 @@ fn set_config_value(
                 "auto" => yoop_core::config::CompressionMode::Auto,
                 "always" => yoop_core::config::CompressionMode::Always,
                 "never" => yoop_core::config::CompressionMode::Never,
-                _ => anyhow::bail!("Invalid compression mode. Use: auto, always, or never"),
+                "adaptive" => yoop_core::config::CompressionMode::Adaptive,
+                _ => {
+                    anyhow::bail!("Invalid compression mode. Use: auto, always, never, or adaptive")
+                }
             };
             Ok(true)
         }
@@ -402,6 +604,50 @@ fn set_config_value(
             config.transfer.verify_checksum = value.parse()?;
             Ok(true)
         }
+        "reconnect_grace" => {
+            config.transfer.reconnect_grace = parse_duration(value)?;
+            Ok(true)
+        }
+        "limits.max_files" => {
+            config.transfer.limits.max_files = if value == "unlimited" || value.is_empty() {
+                None
+            } else {
+                Some(value.parse()?)
+            };
+            Ok(true)
+        }
+        "limits.max_total_size" => {
+            config.transfer.limits.max_total_size = if value == "unlimited" || value.is_empty() {
+                None
+            } else {
+                Some(parse_size(value)? as u64)
+            };
+            Ok(true)
+        }
+        "limits.max_path_length" => {
+            config.transfer.limits.max_path_length = value.parse()?;
+            Ok(true)
+        }
+        "limits.max_path_depth" => {
+            config.transfer.limits.max_path_depth = value.parse()?;
+            Ok(true)
+        }
+        "limits.symlinks" => {
+            config.transfer.limits.symlinks = value.parse()?;
+            Ok(true)
+        }
+        "quarantine.scan_command" => {
+            config.transfer.quarantine.scan_command = if value == "none" || value.is_empty() {
+                None
+            } else {
+                Some(value.split_whitespace().map(String::from).collect())
+            };
+            Ok(true)
+        }
+        "quarantine.scan_timeout" => {
+            config.transfer.quarantine.scan_timeout = parse_duration(value)?;
+            Ok(true)
+        }
 
         // security
         "require_pin" => {
@@ -424,6 +670,10 @@ fn set_config_value(
             config.security.rate_limit_window = parse_duration(value)?;
             Ok(true)
         }
+        "audit_log" => {
+            config.security.audit_log = value.parse()?;
+            Ok(true)
+        }
 
         // preview
         "preview.enabled" => {
@@ -488,10 +738,22 @@ fn set_config_value(
             config.web.auth = value.parse()?;
             Ok(true)
         }
+        "web.password" => {
+            config.web.password = if value.is_empty() || value == "none" {
+                None
+            } else {
+                Some(value.to_string())
+            };
+            Ok(true)
+        }
         "web.localhost_only" => {
             config.web.localhost_only = value.parse()?;
             Ok(true)
         }
+        "web.https" => {
+            config.web.https = value.parse()?;
+            Ok(true)
+        }
 
         // ui
         "ui.theme" => {
@@ -578,3 +840,52 @@ fn parse_size(s: &str) -> Result<usize> {
         Ok(s.parse()?)
     }
 }
+
+/// Parse a comma-separated list like "docker*,veth*" ("none" or empty clears it)
+fn parse_list(s: &str) -> Vec<String> {
+    let s = s.trim();
+    if s.is_empty() || s == "none" {
+        return Vec::new();
+    }
+    s.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Format a list as a TOML array
+fn format_list(items: &[String]) -> String {
+    let quoted: Vec<String> = items.iter().map(|item| format!("\"{}\"", item)).collect();
+    format!("[{}]", quoted.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose_hints_use_known_keys() {
+        let source = include_str!("diagnose.rs");
+        let hints: Vec<&str> = source
+            .match_indices("yoop config set ")
+            .map(|(i, m)| {
+                let rest = &source[i + m.len()..];
+                let end = rest.find(['"', '\n']).unwrap_or(rest.len());
+                &rest[..end]
+            })
+            .collect();
+        assert!(!hints.is_empty());
+
+        for hint in hints {
+            let (key, value) = hint
+                .split_once(' ')
+                .unwrap_or_else(|| panic!("hint without a value: {hint}"));
+            let mut config = yoop_core::config::Config::default();
+            assert!(
+                set_config_value(&mut config, key, value).unwrap(),
+                "diagnose suggests unknown config key: {key}"
+            );
+        }
+    }
+}
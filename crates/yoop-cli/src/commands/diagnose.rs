@@ -8,7 +8,7 @@ use std::time::Duration;
 use anyhow::Result;
 
 use yoop_core::config::Config;
-use yoop_core::discovery::HybridListener;
+use yoop_core::discovery::{self, DeliveryReport, DiscoveryInterface, HybridListener};
 use yoop_core::trust::TrustStore;
 use yoop_core::VERSION;
 
@@ -23,6 +23,12 @@ struct NetworkInfo {
     udp_broadcast_ok: bool,
     /// Whether UDP listening is working
     udp_listen_ok: bool,
+    /// Number of interfaces usable for IPv6 discovery (`None` if disabled)
+    ipv6_interfaces: Option<usize>,
+    /// Interface addresses and whether discovery uses them
+    interfaces: Vec<DiscoveryInterface>,
+    /// Which mechanisms delivered a test packet (`None` if the check failed)
+    delivery: Option<DeliveryReport>,
 }
 
 /// Run the diagnose command.
@@ -63,10 +69,26 @@ async fn check_network(config: &Config) -> NetworkInfo {
 
     let udp_listen_ok = test_udp_listen(config.network.port);
 
+    let filter = config.network.interface_filter();
+
+    let ipv6_interfaces = config
+        .network
+        .ipv6
+        .then(|| discovery::ipv6_interfaces(&filter).len());
+
+    let delivery =
+        discovery::check_delivery(&config.network.discovery_options(), Duration::from_secs(1))
+            .await
+            .map_err(|e| tracing::debug!("Delivery check failed: {}", e))
+            .ok();
+
     NetworkInfo {
         local_ip,
         udp_broadcast_ok,
         udp_listen_ok,
+        ipv6_interfaces,
+        interfaces: discovery::list_interfaces(&filter),
+        delivery,
     }
 }
 
@@ -120,7 +142,9 @@ fn get_trusted_devices() -> Vec<TrustedDeviceInfo> {
                 .map(|d| TrustedDeviceInfo {
                     name: d.device_name.clone(),
                     trust_level: format!("{:?}", d.trust_level),
-                    stored_address: d.address().map(|(ip, port)| format!("{}:{}", ip, port)),
+                    stored_address: d
+                        .address()
+                        .map(|(ip, port)| SocketAddr::new(ip, port).to_string()),
                 })
                 .collect()
         },
@@ -129,7 +153,9 @@ fn get_trusted_devices() -> Vec<TrustedDeviceInfo> {
 
 /// Scan for active shares on the network.
 async fn scan_for_shares(config: &Config) -> Vec<String> {
-    match HybridListener::new(config.network.port).await {
+    match HybridListener::with_options(config.network.port, &config.network.discovery_options())
+        .await
+    {
         Ok(listener) => {
             let shares = listener.scan(Duration::from_secs(2)).await;
             let _ = listener.shutdown();
@@ -140,7 +166,7 @@ async fn scan_for_shares(config: &Config) -> Vec<String> {
                         "{} from {} ({})",
                         s.packet.code,
                         s.packet.device_name,
-                        s.source.ip()
+                        s.transfer_addr().ip()
                     )
                 })
                 .collect()
@@ -164,6 +190,18 @@ fn output_json(
             "udp_broadcast": if net_info.udp_broadcast_ok { "ok" } else { "failed" },
             "udp_listen": if net_info.udp_listen_ok { "ok" } else { "failed" },
             "mdns": if mdns_ok { "ok" } else { "unavailable" },
+            "ipv6": match net_info.ipv6_interfaces {
+                None => "disabled",
+                Some(0) => "unavailable",
+                Some(_) => "ok",
+            },
+            "interface": &config.network.interface,
+            "interfaces": &net_info.interfaces,
+            "multicast_group": config.network.multicast.then(|| format!(
+                "{} (ttl {})",
+                config.network.multicast_group, config.network.multicast_ttl
+            )),
+            "delivery": &net_info.delivery,
         },
         "ports": {
             "discovery": config.network.port,
@@ -205,8 +243,23 @@ fn output_text(
     );
     println!("    UDP Listen:    {}", status_icon(net_info.udp_listen_ok));
     println!("    mDNS:          {}", status_icon(mdns_ok));
+    match net_info.ipv6_interfaces {
+        None => println!("    IPv6:          disabled"),
+        Some(0) => println!(
+            "    IPv6:          {} (no IPv6 interfaces)",
+            status_icon(false)
+        ),
+        Some(n) => println!(
+            "    IPv6:          {} ({} interfaces)",
+            status_icon(true),
+            n
+        ),
+    }
     println!();
 
+    print_delivery(net_info, config);
+    print_interfaces(net_info, config);
+
     println!("  Ports:");
     println!("    Discovery:     {}", discovery_port);
     println!("    Transfer:      {}-{}", transfer_start, transfer_end);
@@ -248,6 +301,9 @@ fn output_text(
         println!("  Recommendations:");
         if !net_info.udp_broadcast_ok {
             println!("    - Enable UDP broadcast on your network");
+            if !config.network.multicast {
+                println!("    - Or enable IPv4 multicast: yoop config set multicast true");
+            }
             if has_stored_addresses {
                 println!("    - Or use --device <name> to connect via stored address");
             }
@@ -266,6 +322,55 @@ fn output_text(
     println!();
 }
 
+/// Print which discovery mechanisms reached this host, including multicast.
+fn print_delivery(net_info: &NetworkInfo, config: &Config) {
+    println!("  Delivery (test packets to this host):");
+    match net_info.delivery {
+        None => println!("    (check failed)"),
+        Some(delivery) => {
+            println!("    Broadcast:      {}", status_icon(delivery.broadcast));
+            match delivery.multicast {
+                None => println!("    IPv4 Multicast: disabled"),
+                Some(ok) => println!(
+                    "    IPv4 Multicast: {} ({})",
+                    status_icon(ok),
+                    config.network.multicast_group
+                ),
+            }
+            match delivery.ipv6 {
+                None => println!("    IPv6 Multicast: disabled"),
+                Some(ok) => println!("    IPv6 Multicast: {}", status_icon(ok)),
+            }
+        }
+    }
+    println!();
+}
+
+/// Print each interface and how discovery uses it.
+fn print_interfaces(net_info: &NetworkInfo, config: &Config) {
+    println!("  Interfaces ({}):", config.network.interface);
+    if net_info.interfaces.is_empty() {
+        println!("    (none found)");
+    }
+    for iface in &net_info.interfaces {
+        let usage = if !iface.selected {
+            "excluded".to_string()
+        } else if let Some(broadcast) = iface.broadcast {
+            if config.network.multicast {
+                format!("broadcast {} + multicast", broadcast)
+            } else {
+                format!("broadcast {}", broadcast)
+            }
+        } else if iface.addr.is_ipv6() && net_info.ipv6_interfaces.is_some() {
+            "IPv6 multicast".to_string()
+        } else {
+            "unused".to_string()
+        };
+        println!("    {:<12} {:<28} {}", iface.name, iface.addr, usage);
+    }
+    println!();
+}
+
 /// Get a status icon for display.
 const fn status_icon(ok: bool) -> &'static str {
     if ok {
@@ -0,0 +1,239 @@
+//! Identity command implementation.
+//!
+//! Rotating the key keeps the device ID. The signed rotation statement is
+//! stored with the identity and sent in trusted handshakes, so trusted
+//! devices replace the old key the next time they connect to, or are
+//! connected from, this device.
+//!
+//! The key can be encrypted with a passphrase. Commands that use it prompt
+//! for the passphrase when the identity is locked; `yoop identity unlock`
+//! keeps it unlocked for background sessions such as the outbox worker.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use yoop_core::crypto::{unlock, DeviceIdentity, KeyRotation, UnlockCache};
+use yoop_core::Error as YoopError;
+
+use super::{IdentityAction, IdentityArgs};
+use crate::ui::parse_duration;
+
+/// Passphrase attempts before giving up.
+const MAX_ATTEMPTS: usize = 3;
+
+#[derive(Serialize)]
+struct IdentityInfo<'a> {
+    device_id: uuid::Uuid,
+    public_key: String,
+    encrypted: bool,
+    unlocked_until: Option<u64>,
+    rotations: &'a [KeyRotation],
+}
+
+/// Run the identity command.
+pub fn run(args: IdentityArgs) -> Result<()> {
+    match args.action {
+        IdentityAction::Show { json } => show(json),
+        IdentityAction::Rotate { yes } => rotate(yes),
+        IdentityAction::Encrypt => encrypt(),
+        IdentityAction::Decrypt => decrypt(),
+        IdentityAction::Unlock { ttl } => unlock_for(&ttl),
+        IdentityAction::Lock => lock(),
+    }
+}
+
+/// Prompt for the passphrase if the identity is encrypted and locked.
+///
+/// Without a terminal this does nothing; the command then fails with a
+/// hint to run `yoop identity unlock` when it loads the identity.
+pub fn unlock_if_needed() -> Result<()> {
+    if !matches!(
+        DeviceIdentity::load_or_generate(),
+        Err(YoopError::IdentityLocked)
+    ) {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        return Ok(());
+    }
+    prompt_unlock().map(|_| ())
+}
+
+/// Load the identity, prompting for the passphrase if it is locked.
+fn load_identity() -> Result<DeviceIdentity> {
+    match DeviceIdentity::load_or_generate() {
+        Err(YoopError::IdentityLocked) => prompt_unlock(),
+        result => result.context("Failed to load device identity"),
+    }
+}
+
+fn prompt_unlock() -> Result<DeviceIdentity> {
+    for _ in 0..MAX_ATTEMPTS {
+        let passphrase = rpassword::prompt_password("Identity passphrase: ")?;
+        match DeviceIdentity::unlock(&passphrase) {
+            Ok(identity) => return Ok(identity),
+            Err(YoopError::InvalidPassphrase) => eprintln!("Incorrect passphrase."),
+            Err(e) => return Err(e).context("Failed to unlock device identity"),
+        }
+    }
+    anyhow::bail!("Too many incorrect passphrases")
+}
+
+fn show(json: bool) -> Result<()> {
+    let identity = load_identity()?;
+    let unlocked_until = if identity.is_encrypted() {
+        UnlockCache::default_path().and_then(|path| UnlockCache::new(path).expires_at())
+    } else {
+        None
+    };
+
+    if json {
+        let info = IdentityInfo {
+            device_id: identity.device_id(),
+            public_key: identity.public_key_base64(),
+            encrypted: identity.is_encrypted(),
+            unlocked_until,
+            rotations: identity.rotations(),
+        };
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    println!();
+    println!("  Device ID:  {}", identity.device_id());
+    println!("  Public key: {}", identity.public_key_base64());
+    if identity.is_encrypted() {
+        match unlocked_until {
+            Some(until) => println!(
+                "  Encrypted:  yes, unlocked until {}",
+                format_timestamp(until)
+            ),
+            None => println!("  Encrypted:  yes"),
+        }
+    } else {
+        println!("  Encrypted:  no");
+    }
+    if let Some(last) = identity.rotations().last() {
+        println!(
+            "  Rotated:    {} time(s), last at {}",
+            identity.rotations().len(),
+            format_timestamp(last.rotated_at)
+        );
+    }
+    println!();
+    Ok(())
+}
+
+fn rotate(yes: bool) -> Result<()> {
+    let mut identity = load_identity()?;
+
+    if !yes {
+        println!();
+        println!(
+            "  This replaces the identity key for {}.",
+            identity.device_id()
+        );
+        println!("  Trusted devices accept the new key the next time they connect with");
+        println!("  this device. Until then, they may not recognise its beacons.");
+        print!("  Rotate key? [y/N]: ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().lock().read_line(&mut input)?;
+        if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("  Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let rotation = identity.rotate();
+    identity.save().context("Failed to save device identity")?;
+
+    println!();
+    println!("  Rotated identity key.");
+    println!("  Old public key: {}", rotation.old_public_key);
+    println!("  New public key: {}", rotation.new_public_key);
+    println!();
+    Ok(())
+}
+
+fn encrypt() -> Result<()> {
+    let mut identity = load_identity()?;
+
+    let passphrase = rpassword::prompt_password("New passphrase: ")?;
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase cannot be empty; use 'yoop identity decrypt' to remove it");
+    }
+    if rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+        anyhow::bail!("Passphrases do not match");
+    }
+
+    let was_encrypted = identity.is_encrypted();
+    identity.set_passphrase(Some(&passphrase))?;
+    identity.save().context("Failed to save device identity")?;
+    // a cached unlock was derived from the old passphrase
+    DeviceIdentity::lock()?;
+
+    if was_encrypted {
+        println!("Changed identity passphrase.");
+    } else {
+        println!("Encrypted identity key.");
+        println!(
+            "Background sessions need 'yoop identity unlock' or {}.",
+            unlock::PASSPHRASE_ENV
+        );
+    }
+    Ok(())
+}
+
+fn decrypt() -> Result<()> {
+    let mut identity = load_identity()?;
+    if !identity.is_encrypted() {
+        println!("Identity key is not encrypted.");
+        return Ok(());
+    }
+
+    identity.set_passphrase(None)?;
+    identity.save().context("Failed to save device identity")?;
+    DeviceIdentity::lock()?;
+    println!("Removed passphrase from identity key.");
+    Ok(())
+}
+
+fn unlock_for(ttl: &str) -> Result<()> {
+    let ttl = parse_duration(ttl)
+        .ok_or_else(|| anyhow::anyhow!("Invalid duration '{}'. Use e.g. 30m, 8h, 1d", ttl))?;
+
+    let identity = load_identity()?;
+    if !identity.is_encrypted() {
+        println!("Identity key is not encrypted; nothing to unlock.");
+        return Ok(());
+    }
+
+    identity
+        .cache_unlock(ttl)
+        .context("Failed to cache identity unlock")?;
+    println!(
+        "Identity unlocked until {}.",
+        format_timestamp(chrono::Utc::now().timestamp().unsigned_abs() + ttl.as_secs())
+    );
+    Ok(())
+}
+
+fn lock() -> Result<()> {
+    if DeviceIdentity::lock()? {
+        println!("Identity locked.");
+    } else {
+        println!("Identity was not unlocked.");
+    }
+    Ok(())
+}
+
+fn format_timestamp(secs: u64) -> String {
+    chrono::DateTime::from_timestamp(i64::try_from(secs).unwrap_or(i64::MAX), 0).map_or_else(
+        || secs.to_string(),
+        |t| t.format("%Y-%m-%d %H:%M UTC").to_string(),
+    )
+}
@@ -54,13 +54,18 @@ pub fn spawn_update_check() {
 #[cfg(not(feature = "update"))]
 pub fn spawn_update_check() {}
 
+pub mod audit;
+pub mod bench;
 pub mod clipboard;
 pub mod completions;
 pub mod config;
 pub mod diagnose;
 pub mod history;
+pub mod identity;
 pub mod internal;
+pub mod outbox;
 pub mod receive;
+pub mod relay;
 pub mod scan;
 pub mod send;
 pub mod share;
@@ -106,6 +111,12 @@ pub enum Command {
     /// Manage trusted devices
     Trust(TrustArgs),
 
+    /// Manage sends queued for offline trusted devices
+    Outbox(OutboxArgs),
+
+    /// Show or rotate this device's identity key
+    Identity(IdentityArgs),
+
     /// Start web interface
     Web(WebArgs),
 
@@ -115,9 +126,18 @@ pub enum Command {
     /// Run network diagnostics
     Diagnose(DiagnoseArgs),
 
+    /// Benchmark throughput and latency (loopback or trusted device)
+    Bench(BenchArgs),
+
+    /// Run a relay server for shares across networks
+    Relay(RelayArgs),
+
     /// View transfer history
     History(HistoryArgs),
 
+    /// View or verify the security audit log
+    Audit(AuditArgs),
+
     /// Generate shell completions
     Completions(CompletionsArgs),
 
@@ -133,6 +153,29 @@ pub enum Command {
     InternalClipboardHold(InternalClipboardHoldArgs),
 }
 
+impl Command {
+    /// Whether the command signs with the device identity, so an encrypted
+    /// identity must be unlocked before it runs.
+    #[must_use]
+    pub const fn uses_identity(&self) -> bool {
+        matches!(
+            self,
+            Self::Share(_)
+                | Self::Receive(_)
+                | Self::Send(_)
+                | Self::Clipboard(_)
+                | Self::Sync(_)
+                | Self::Outbox(_)
+                | Self::Web(_)
+                | Self::Bench(_)
+                | Self::Tui(_)
+                | Self::Trust(TrustArgs {
+                    action: TrustAction::Pair { .. }
+                })
+        )
+    }
+}
+
 /// Arguments for the share command
 #[derive(Parser)]
 pub struct ShareArgs {
@@ -196,7 +239,7 @@ pub struct ReceiveArgs {
     #[arg(required_unless_present = "device")]
     pub code: Option<String>,
 
-    /// Connect directly to peer IP, bypassing discovery (e.g., 192.168.1.100 or 192.168.1.100:52530)
+    /// Connect directly to peer IP, bypassing discovery (e.g., 192.168.1.100:52530 or [fe80::1%eth0]:52530)
     #[arg(long, value_name = "IP[:PORT]", conflicts_with = "device")]
     pub host: Option<String>,
 
@@ -232,7 +275,7 @@ pub struct ReceiveArgs {
 /// Arguments for the send command (trusted device)
 #[derive(Parser)]
 pub struct SendArgs {
-    /// Name of the trusted device
+    /// Name of the trusted device, or @group to send to every device in a group
     pub device: String,
 
     /// Files and folders to send
@@ -251,6 +294,14 @@ pub struct SendArgs {
     #[arg(long, value_parser = clap::value_parser!(u8).range(1..=3))]
     pub compression_level: Option<u8>,
 
+    /// Queue the send in the outbox if the device can't be found
+    #[arg(long)]
+    pub queue: bool,
+
+    /// How long a queued send is kept before it expires (e.g., 12h, 7d)
+    #[arg(long, default_value = "7d", requires = "queue")]
+    pub expire: String,
+
     /// Minimal output
     #[arg(short, long)]
     pub quiet: bool,
@@ -286,21 +337,175 @@ pub enum TrustAction {
     /// List trusted devices
     List,
 
+    /// Probe configured peers and show which are reachable
+    Status {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Remove a trusted device
     Remove {
         /// Device name or ID
         device: String,
     },
 
-    /// Set trust level for a device
-    Set {
-        /// Device name or ID
-        device: String,
+    /// Set trust level and permissions for a device
+    Set(TrustSetArgs),
 
-        /// Trust level (full, ask)
-        #[arg(long)]
+    /// Pair with a device by comparing a short code on both screens
+    Pair {
+        /// Pairing code shown on the other device (omit to start pairing)
+        code: Option<String>,
+
+        /// Trust level for the paired device (full, ask)
+        #[arg(long, default_value = "full")]
         level: String,
     },
+
+    /// Manage device groups
+    Group {
+        /// Group subcommand
+        #[command(subcommand)]
+        action: GroupAction,
+    },
+}
+
+/// Arguments for `yoop trust set`
+#[derive(Parser)]
+pub struct TrustSetArgs {
+    /// Device name or ID
+    pub device: String,
+
+    /// Trust level (full, ask)
+    #[arg(long)]
+    pub level: Option<String>,
+
+    /// Grant a capability (files, clipboard-push, clipboard-read, sync)
+    #[arg(long, value_name = "CAPABILITY")]
+    pub allow: Vec<String>,
+
+    /// Revoke a capability (files, clipboard-push, clipboard-read, sync)
+    #[arg(long, value_name = "CAPABILITY")]
+    pub deny: Vec<String>,
+
+    /// Only allow syncing this directory (repeatable; replaces the list)
+    #[arg(long, value_name = "DIR")]
+    pub sync_folder: Vec<PathBuf>,
+
+    /// Only save received files under this directory (repeatable; replaces the list)
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Vec<PathBuf>,
+
+    /// Largest single transfer accepted (e.g. 500MB, or "none")
+    #[arg(long, value_name = "SIZE")]
+    pub max_size: Option<String>,
+
+    /// Restore default permissions before applying the other options
+    #[arg(long)]
+    pub reset: bool,
+}
+
+/// Device group subcommands
+#[derive(Subcommand)]
+pub enum GroupAction {
+    /// List groups and their devices
+    List,
+
+    /// Add devices to a group (created if it doesn't exist)
+    Add {
+        /// Group name
+        group: String,
+
+        /// Device names or IDs
+        #[arg(required = true)]
+        devices: Vec<String>,
+    },
+
+    /// Remove devices from a group, or the whole group if no devices are given
+    Remove {
+        /// Group name
+        group: String,
+
+        /// Device names or IDs
+        devices: Vec<String>,
+    },
+}
+
+/// Arguments for the identity command
+#[derive(Parser)]
+pub struct IdentityArgs {
+    /// Identity subcommand
+    #[command(subcommand)]
+    pub action: IdentityAction,
+}
+
+/// Identity subcommands
+#[derive(Subcommand)]
+pub enum IdentityAction {
+    /// Show the device ID and public key
+    Show {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Replace the identity key; trusted devices learn the new key on next contact
+    Rotate {
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Protect the identity key with a passphrase (or change it)
+    Encrypt,
+
+    /// Remove passphrase protection from the identity key
+    Decrypt,
+
+    /// Keep the identity unlocked for background sessions
+    Unlock {
+        /// How long to stay unlocked (e.g., 30m, 8h, 1d)
+        #[arg(long, default_value = "8h")]
+        ttl: String,
+    },
+
+    /// Forget a cached unlock
+    Lock,
+}
+
+/// Arguments for the outbox command
+#[derive(Parser)]
+pub struct OutboxArgs {
+    /// Outbox subcommand
+    #[command(subcommand)]
+    pub action: OutboxAction,
+}
+
+/// Outbox subcommands
+#[derive(Subcommand)]
+pub enum OutboxAction {
+    /// List queued sends
+    List {
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Cancel a queued send
+    Cancel {
+        /// Queued send ID (or unique prefix)
+        id: String,
+    },
+
+    /// Retry a queued send on the next chance, skipping its backoff
+    Retry {
+        /// Queued send ID (or unique prefix)
+        id: String,
+    },
+
+    /// Deliver queued sends as devices come online (runs until the outbox is empty)
+    Run,
 }
 
 /// Arguments for the web command
@@ -314,9 +519,21 @@ pub struct WebArgs {
     #[arg(long)]
     pub auth: bool,
 
+    /// Password to require (implies --auth; generated if omitted)
+    #[arg(long, value_name = "PASSWORD")]
+    pub password: Option<String>,
+
+    /// Save the password to the config so it survives restarts
+    #[arg(long)]
+    pub save_password: bool,
+
     /// Bind to localhost only
     #[arg(long)]
     pub localhost_only: bool,
+
+    /// Serve over HTTPS with a self-signed certificate
+    #[arg(long)]
+    pub https: bool,
 }
 
 /// Arguments for the config command
@@ -366,6 +583,50 @@ pub struct DiagnoseArgs {
     pub json: bool,
 }
 
+/// Arguments for the bench command
+#[derive(Parser)]
+pub struct BenchArgs {
+    /// Benchmark against a trusted device running `yoop bench --listen`
+    #[arg(short, long, conflicts_with = "listen")]
+    pub device: Option<String>,
+
+    /// Connect directly to peer IP (e.g., 192.168.1.100:52541 or [2001:db8::1]:52541)
+    #[arg(long, requires = "device")]
+    pub host: Option<String>,
+
+    /// Serve benchmarks for trusted devices
+    #[arg(short, long)]
+    pub listen: bool,
+
+    /// Bench server port
+    #[arg(short, long, default_value_t = yoop_core::bench::DEFAULT_BENCH_PORT)]
+    pub port: u16,
+
+    /// Amount of data to send for the throughput test (e.g., 64MB, 1GB)
+    #[arg(short, long, default_value = "64MB")]
+    pub size: String,
+
+    /// Number of RTT samples
+    #[arg(long, default_value_t = 20)]
+    pub samples: usize,
+
+    /// Output in JSON format
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Arguments for the relay command
+#[derive(Parser)]
+pub struct RelayArgs {
+    /// Relay server port
+    #[arg(short, long, default_value_t = yoop_core::relay::DEFAULT_RELAY_PORT)]
+    pub port: u16,
+
+    /// Listen on IPv4 only
+    #[arg(long)]
+    pub ipv4_only: bool,
+}
+
 /// Arguments for the history command
 #[derive(Parser)]
 pub struct HistoryArgs {
@@ -382,6 +643,26 @@ pub struct HistoryArgs {
     pub json: bool,
 }
 
+/// Arguments for the audit command
+#[derive(Parser)]
+pub struct AuditArgs {
+    /// Number of recent events to show
+    #[arg(short = 'n', long, default_value = "20")]
+    pub limit: usize,
+
+    /// Only show failed or rejected attempts
+    #[arg(long)]
+    pub failures: bool,
+
+    /// Check the hash chain for tampering
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Output in JSON format
+    #[arg(long)]
+    pub json: bool,
+}
+
 /// Arguments for the completions command
 #[derive(Parser)]
 pub struct CompletionsArgs {
@@ -475,7 +756,7 @@ pub struct ClipboardReceiveArgs {
     #[arg(required_unless_present = "device")]
     pub code: Option<String>,
 
-    /// Connect directly to peer IP, bypassing discovery (e.g., 192.168.1.100 or 192.168.1.100:52530)
+    /// Connect directly to peer IP, bypassing discovery (e.g., 192.168.1.100:52530 or [fe80::1%eth0]:52530)
     #[arg(long, value_name = "IP[:PORT]", conflicts_with = "device")]
     pub host: Option<String>,
 
@@ -494,7 +775,7 @@ pub struct ClipboardSyncArgs {
     /// Share code to connect to (omit to host new session)
     pub code: Option<String>,
 
-    /// Connect directly to peer IP, bypassing discovery (e.g., 192.168.1.100 or 192.168.1.100:52530)
+    /// Connect directly to peer IP, bypassing discovery (e.g., 192.168.1.100:52530 or [fe80::1%eth0]:52530)
     #[arg(long, value_name = "IP[:PORT]", conflicts_with = "device")]
     pub host: Option<String>,
 
@@ -0,0 +1,401 @@
+//! Outbox command implementation.
+//!
+//! Lists and manages sends queued for offline trusted devices, and runs the
+//! background worker that delivers them once the target comes online.
+
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use yoop_core::crypto::DeviceIdentity;
+use yoop_core::discovery::HybridBeaconListener;
+use yoop_core::file::{format_size, LockFile};
+use yoop_core::history::{
+    HistoryFileEntry, HistoryStore, TransferDirection, TransferHistoryEntry,
+    TransferState as HistoryState,
+};
+use yoop_core::outbox::{self, Outbox, OutboxEntry};
+use yoop_core::trust::{peers, TrustStore, TrustedDevice};
+
+use super::{OutboxAction, OutboxArgs};
+use crate::tui::session::state_file::is_process_alive;
+
+/// How long the worker listens for beacons per round; devices that stay
+/// silent are probed at their configured and stored addresses afterwards.
+const SCAN_WINDOW: Duration = Duration::from_secs(10);
+
+/// Run the outbox command.
+pub async fn run(args: OutboxArgs) -> Result<()> {
+    match args.action {
+        OutboxAction::List { json } => list(json),
+        OutboxAction::Cancel { id } => cancel(&id),
+        OutboxAction::Retry { id } => retry(&id),
+        OutboxAction::Run => run_worker().await,
+    }
+}
+
+fn list(json: bool) -> Result<()> {
+    let outbox = Outbox::load().context("Failed to load outbox")?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(outbox.list())?);
+        return Ok(());
+    }
+
+    if outbox.is_empty() {
+        println!("Outbox is empty.");
+        return Ok(());
+    }
+
+    let now = outbox::now_secs();
+
+    println!();
+    println!("Queued Sends:");
+    println!("{}", "─".repeat(60));
+    for entry in outbox.list() {
+        println!(
+            "  {}  {} - {} files ({})",
+            entry.short_id(),
+            entry.device_name,
+            entry.file_count,
+            format_size(entry.total_bytes)
+        );
+        println!(
+            "            expires in {}, {} attempts",
+            format_secs(entry.expires_at.saturating_sub(now)),
+            entry.attempts
+        );
+        if let Some(ref error) = entry.last_error {
+            println!("            last error: {}", error);
+        }
+    }
+    println!("{}", "─".repeat(60));
+
+    if !worker_running() {
+        println!();
+        println!("  Delivery worker is not running; start it with 'yoop outbox run'");
+    }
+    println!();
+
+    Ok(())
+}
+
+fn cancel(id: &str) -> Result<()> {
+    let mut outbox = Outbox::load().context("Failed to load outbox")?;
+
+    let entry = outbox
+        .find(id)
+        .ok_or_else(|| anyhow::anyhow!("No queued send matching '{}'", id))?
+        .clone();
+
+    outbox.remove(&entry.id)?;
+    record_history(&entry, Vec::new(), HistoryState::Cancelled, None, 0);
+
+    println!(
+        "Cancelled send to {} ({})",
+        entry.device_name,
+        entry.short_id()
+    );
+    Ok(())
+}
+
+fn retry(id: &str) -> Result<()> {
+    let mut outbox = Outbox::load().context("Failed to load outbox")?;
+
+    let entry = outbox
+        .find(id)
+        .ok_or_else(|| anyhow::anyhow!("No queued send matching '{}'", id))?
+        .clone();
+
+    outbox.retry(&entry.id)?;
+    println!(
+        "Will retry send to {} ({}) when it is next seen",
+        entry.device_name,
+        entry.short_id()
+    );
+
+    if spawn_worker()? {
+        println!("Started delivery worker");
+    }
+    Ok(())
+}
+
+/// Deliver queued sends until the outbox is empty.
+async fn run_worker() -> Result<()> {
+    let pid_path = pid_path();
+    if let Some(parent) = pid_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    {
+        // Check and claim under the lock so two workers starting together
+        // can't both see no worker and deliver the same entries.
+        let _lock = LockFile::acquire(&pid_path).context("Failed to lock worker PID file")?;
+        if worker_running() {
+            println!("Delivery worker is already running.");
+            return Ok(());
+        }
+        fs::write(&pid_path, std::process::id().to_string())?;
+    }
+
+    let result = deliver_loop().await;
+
+    if read_pid().is_some_and(|pid| pid == std::process::id()) {
+        let _ = fs::remove_file(&pid_path);
+    }
+    result
+}
+
+async fn deliver_loop() -> Result<()> {
+    let global_config = super::load_config();
+    let listener = HybridBeaconListener::with_options(
+        global_config.network.port,
+        &global_config.network.discovery_options(),
+    )
+    .await
+    .context("Failed to start beacon listener")?
+    .with_trusted(
+        TrustStore::load()
+            .context("Failed to load trust store")?
+            .list(),
+    );
+
+    let config = global_config.transfer_config();
+
+    loop {
+        let mut outbox = Outbox::load().context("Failed to load outbox")?;
+        for entry in outbox.remove_expired(outbox::now_secs())? {
+            tracing::info!("Queued send {} expired", entry.short_id());
+            record_history(
+                &entry,
+                Vec::new(),
+                HistoryState::Failed,
+                Some("Expired before delivery".to_string()),
+                0,
+            );
+        }
+        if outbox.is_empty() {
+            tracing::info!("Outbox is empty, stopping delivery worker");
+            return Ok(());
+        }
+
+        let reachable = find_reachable(&listener, &global_config, &outbox).await?;
+        for (trusted, addr) in reachable {
+            let due = Outbox::load()?.due_for(&trusted.device_id, outbox::now_secs());
+            if due.is_empty() {
+                continue;
+            }
+
+            for entry in due {
+                let identity =
+                    DeviceIdentity::load_or_generate().context("Failed to load device identity")?;
+
+                tracing::info!(
+                    "Delivering queued send {} to {} at {}",
+                    entry.short_id(),
+                    trusted.device_name,
+                    addr
+                );
+
+                let start = Instant::now();
+                let result =
+                    outbox::deliver(&entry, trusted.clone(), addr, identity, config.clone()).await;
+                let elapsed = start.elapsed().as_secs();
+
+                let mut outbox = Outbox::load()?;
+                match result {
+                    Ok(files) => {
+                        outbox.remove(&entry.id)?;
+                        let history_files = files
+                            .iter()
+                            .map(|f| HistoryFileEntry {
+                                name: f.file_name().to_string(),
+                                size: f.size,
+                                success: true,
+                            })
+                            .collect();
+                        record_history(
+                            &entry,
+                            history_files,
+                            HistoryState::Completed,
+                            None,
+                            elapsed,
+                        );
+
+                        if let Ok(mut store) = TrustStore::load() {
+                            let _ =
+                                store.update_address(&trusted.device_id, addr.ip(), addr.port());
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Delivery of {} failed: {}", entry.short_id(), e);
+                        outbox.record_failure(&entry.id, e.to_string())?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Trusted devices with queued sends that can be reached now: those whose
+/// beacons arrive during the scan window, then those answering at a
+/// configured or stored address.
+async fn find_reachable(
+    listener: &HybridBeaconListener,
+    config: &yoop_core::config::Config,
+    outbox: &Outbox,
+) -> Result<Vec<(TrustedDevice, SocketAddr)>> {
+    let mut reachable = Vec::new();
+    for device in listener.scan(SCAN_WINDOW).await {
+        if !device.beacon.ready_to_receive {
+            continue;
+        }
+
+        let trust_store = TrustStore::load().context("Failed to load trust store")?;
+        let Some(trusted) = trust_store.find_by_id(&device.beacon.device_id).cloned() else {
+            continue;
+        };
+        if trusted.public_key != device.beacon.public_key {
+            tracing::warn!(
+                "Ignoring beacon for {} with mismatched public key",
+                trusted.device_name
+            );
+            continue;
+        }
+        reachable.push((trusted, device.transfer_addr()));
+    }
+
+    let trust_store = TrustStore::load().context("Failed to load trust store")?;
+    for device in trust_store.list() {
+        let found = reachable
+            .iter()
+            .any(|(d, _)| d.device_id == device.device_id);
+        if found
+            || outbox
+                .due_for(&device.device_id, outbox::now_secs())
+                .is_empty()
+        {
+            continue;
+        }
+        let addresses = peers::addresses_for(&config.peers, device);
+        if let Some(addr) =
+            peers::probe_device(device, &addresses, config.network.transfer_port_range.0).await
+        {
+            reachable.push((device.clone(), addr));
+        }
+    }
+
+    Ok(reachable)
+}
+
+/// Start the delivery worker in the background unless one is already running.
+///
+/// Returns `true` if a new worker was started.
+pub fn spawn_worker() -> Result<bool> {
+    if worker_running() {
+        return Ok(false);
+    }
+
+    let exe = std::env::current_exe().context("Cannot find current executable")?;
+
+    let mut cmd = Command::new(exe);
+    cmd.arg("outbox")
+        .arg("run")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // keep the worker alive when the terminal's process group is interrupted
+        cmd.process_group(0);
+    }
+
+    cmd.spawn().context("Failed to start delivery worker")?;
+    Ok(true)
+}
+
+fn pid_path() -> PathBuf {
+    Outbox::default_path()
+        .unwrap_or_else(|| PathBuf::from("outbox.json"))
+        .with_file_name("outbox.pid")
+}
+
+fn read_pid() -> Option<u32> {
+    fs::read_to_string(pid_path()).ok()?.trim().parse().ok()
+}
+
+fn worker_running() -> bool {
+    read_pid().is_some_and(|pid| pid != std::process::id() && is_process_alive(pid))
+}
+
+/// Record a queued send in the transfer history.
+///
+/// `files` is empty for sends that were never delivered.
+fn record_history(
+    entry: &OutboxEntry,
+    files: Vec<HistoryFileEntry>,
+    state: HistoryState,
+    error: Option<String>,
+    duration_secs: u64,
+) {
+    let mut history_entry = TransferHistoryEntry::new(
+        TransferDirection::Sent,
+        entry.device_name.clone(),
+        format!("outbox:{}", entry.device_name),
+    )
+    .with_device_id(entry.device_id);
+
+    if files.is_empty() {
+        history_entry.total_bytes = entry.total_bytes;
+    } else {
+        history_entry = history_entry.with_files(files);
+    }
+
+    let bytes = if state == HistoryState::Completed {
+        history_entry.total_bytes
+    } else {
+        0
+    };
+    history_entry = history_entry
+        .with_stats(bytes, duration_secs)
+        .with_state(state);
+
+    if let Some(err_msg) = error {
+        history_entry = history_entry.with_error(err_msg);
+    }
+
+    if let Ok(mut store) = HistoryStore::load() {
+        if let Err(e) = store.add(history_entry) {
+            tracing::warn!("Failed to record history: {}", e);
+        }
+    }
+}
+
+/// Format a number of seconds as a coarse duration ("3d", "5h", "12m").
+fn format_secs(secs: u64) -> String {
+    if secs >= 86400 {
+        format!("{}d", secs / 86400)
+    } else if secs >= 3600 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{}m", secs.div_ceil(60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_secs() {
+        assert_eq!(format_secs(7 * 86400), "7d");
+        assert_eq!(format_secs(5 * 3600 + 10), "5h");
+        assert_eq!(format_secs(61), "2m");
+        assert_eq!(format_secs(0), "0m");
+    }
+}
@@ -18,7 +18,7 @@ use yoop_core::history::{
     TransferState as HistoryState,
 };
 use yoop_core::preview::PreviewType;
-use yoop_core::transfer::{ReceiveSession, TransferConfig, TransferProgress, TransferState};
+use yoop_core::transfer::{limits, ReceiveSession, TransferProgress, TransferState};
 use yoop_core::trust::{TrustStore, TrustedDevice};
 
 use super::ReceiveArgs;
@@ -37,13 +37,7 @@ pub async fn run(args: ReceiveArgs) -> Result<()> {
         .or_else(|| global_config.general.default_output.clone())
         .unwrap_or_else(|| PathBuf::from("."));
 
-    let config = TransferConfig {
-        chunk_size: global_config.transfer.chunk_size,
-        parallel_streams: global_config.transfer.parallel_chunks,
-        verify_checksums: global_config.transfer.verify_checksum,
-        discovery_port: global_config.network.port,
-        ..Default::default()
-    };
+    let config = global_config.transfer_config();
 
     let (mut session, code_for_history) = if let Some(ref device_name) = args.device {
         if !args.quiet && !args.json {
@@ -105,11 +99,12 @@ pub async fn run(args: ReceiveArgs) -> Result<()> {
     let (sender_addr, sender_name) = session.sender();
     let sender_name = sender_name.to_string();
     let sender_addr = *sender_addr;
+    let relayed = session.is_relayed();
     let sender_device_id = session.sender_device_id();
     let sender_public_key = session.sender_public_key().map(String::from);
     let files = session.files().to_vec();
     let total_files = files.len();
-    let total_size: u64 = files.iter().map(|f| f.size).sum();
+    let total_size: u64 = limits::total_size(&files);
 
     if args.json {
         let output = serde_json::json!({
@@ -117,6 +112,7 @@ pub async fn run(args: ReceiveArgs) -> Result<()> {
             "sender": {
                 "name": &sender_name,
                 "address": sender_addr.to_string(),
+                "relayed": relayed,
             },
             "files": files.iter().map(|f| {
                 let mut file_json = serde_json::json!({
@@ -144,7 +140,14 @@ pub async fn run(args: ReceiveArgs) -> Result<()> {
         });
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else if !args.quiet {
-        println!("  Found sender: {} ({})", &sender_name, sender_addr);
+        if relayed {
+            println!(
+                "  Found sender: {} (via relay {})",
+                &sender_name, sender_addr
+            );
+        } else {
+            println!("  Found sender: {} ({})", &sender_name, sender_addr);
+        }
         println!();
         println!(
             "  Receiving {} items ({}) to {}",
@@ -272,7 +275,7 @@ pub async fn run(args: ReceiveArgs) -> Result<()> {
                 sender_device_id,
             );
 
-            if let Some(device_id) = sender_device_id {
+            if let Some(device_id) = sender_device_id.filter(|_| !relayed) {
                 if let Ok(mut store) = TrustStore::load() {
                     if store.find_by_id(&device_id).is_some() {
                         let _ =
@@ -293,7 +296,7 @@ pub async fn run(args: ReceiveArgs) -> Result<()> {
                         &sender_name,
                         sender_device_id,
                         sender_public_key.as_deref(),
-                        sender_addr,
+                        (!relayed).then_some(sender_addr),
                     )
                     .await;
                 }
@@ -456,12 +459,19 @@ async fn display_progress(mut rx: watch::Receiver<TransferProgress>, session_id:
         }
 
         if progress.state != last_state {
-            last_state = progress.state;
+            let previous = std::mem::replace(&mut last_state, progress.state);
 
             match progress.state {
+                TransferState::Transferring if previous == TransferState::Reconnecting => {
+                    println!("  Reconnected, resuming download...");
+                }
                 TransferState::Transferring => {
                     println!("  Starting download...");
                 }
+                TransferState::Reconnecting => {
+                    println!();
+                    println!("  Connection lost, reconnecting to sender...");
+                }
                 TransferState::Completed => {
                     break;
                 }
@@ -540,7 +550,7 @@ async fn prompt_trust_device(
     sender_name: &str,
     sender_device_id: Option<Uuid>,
     sender_public_key: Option<&str>,
-    sender_addr: SocketAddr,
+    sender_addr: Option<SocketAddr>,
 ) {
     let (Some(device_id), Some(public_key)) = (sender_device_id, sender_public_key) else {
         return;
@@ -590,9 +600,11 @@ async fn prompt_trust_device(
         TrustLevel::Full
     };
 
-    let device = TrustedDevice::new(device_id, sender_name.to_string(), public_key.to_string())
-        .with_trust_level(trust_level)
-        .with_address(sender_addr.ip(), sender_addr.port());
+    let mut device = TrustedDevice::new(device_id, sender_name.to_string(), public_key.to_string())
+        .with_trust_level(trust_level);
+    if let Some(addr) = sender_addr {
+        device = device.with_address(addr.ip(), addr.port());
+    }
 
     match TrustStore::load() {
         Ok(mut store) => {
@@ -600,7 +612,10 @@ async fn prompt_trust_device(
                 eprintln!("  Failed to save trust: {}", e);
             } else {
                 println!();
-                println!("  Device trusted (address saved: {}).", sender_addr);
+                match sender_addr {
+                    Some(addr) => println!("  Device trusted (address saved: {}).", addr),
+                    None => println!("  Device trusted."),
+                }
                 println!();
             }
         }
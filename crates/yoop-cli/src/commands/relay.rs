@@ -0,0 +1,37 @@
+//! Relay command implementation.
+//!
+//! Runs a rendezvous/relay server so shares can be received from other
+//! networks. Clients use it by setting `network.relay` in their config.
+
+use anyhow::{Context, Result};
+
+use yoop_core::relay::RelayServer;
+use yoop_core::VERSION;
+
+use super::RelayArgs;
+
+/// Run the relay command.
+pub async fn run(args: RelayArgs) -> Result<()> {
+    let server = RelayServer::new(args.port, !args.ipv4_only)
+        .await
+        .context("Failed to start relay server")?;
+
+    println!();
+    println!("Yoop v{} - Relay Server", VERSION);
+    println!("{}", "─".repeat(50));
+    println!();
+    println!("  Listening on {}", server.local_addr()?);
+    println!(
+        "  Clients use: yoop config set relay <this-host>:{}",
+        args.port
+    );
+    println!("  Press Ctrl+C to stop");
+    println!();
+
+    tokio::select! {
+        result = server.run() => result.context("Relay server failed")?,
+        _ = tokio::signal::ctrl_c() => {}
+    }
+
+    Ok(())
+}
@@ -27,9 +27,12 @@ pub async fn run(args: ScanArgs) -> Result<()> {
         println!();
     }
 
-    let listener = HybridListener::new(global_config.network.port)
-        .await
-        .context("Failed to create discovery listener")?;
+    let listener = HybridListener::with_options(
+        global_config.network.port,
+        &global_config.network.discovery_options(),
+    )
+    .await
+    .context("Failed to create discovery listener")?;
 
     let shares = listener.scan(duration).await;
 
@@ -102,10 +105,17 @@ fn display_shares(shares: &[yoop_core::discovery::DiscoveredShare]) {
             "expired".to_string()
         };
 
+        // Shares announced in privacy mode leave the device name empty
+        let device_name = if share.packet.device_name.is_empty() {
+            "(private)"
+        } else {
+            &share.packet.device_name
+        };
+
         println!(
             "  {:6}  {:16}  {:6}  {:10}  {:7}",
             share.packet.code,
-            truncate_string(&share.packet.device_name, 16),
+            truncate_string(device_name, 16),
             share.packet.file_count,
             format_size(share.packet.total_size),
             expires_str
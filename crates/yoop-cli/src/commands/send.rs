@@ -18,14 +18,20 @@ use yoop_core::history::{
     HistoryFileEntry, HistoryStore, TransferDirection, TransferHistoryEntry,
     TransferState as HistoryState,
 };
+use yoop_core::outbox::{Outbox, OutboxEntry};
 use yoop_core::transfer::{TransferConfig, TransferProgress, TransferState, TrustedSendSession};
-use yoop_core::trust::TrustStore;
+use yoop_core::trust::{peers, TrustStore, TrustedDevice};
 
 use super::SendArgs;
+use crate::ui::parse_duration;
 
 /// Run the send command.
 #[allow(clippy::too_many_lines)]
 pub async fn run(args: SendArgs) -> Result<()> {
+    if let Some(group) = args.device.strip_prefix('@') {
+        return run_group(group, &args).await;
+    }
+
     let global_config = super::load_config();
 
     let trust_store = TrustStore::load().context("Failed to load trust store")?;
@@ -37,26 +43,7 @@ pub async fn run(args: SendArgs) -> Result<()> {
 
     let identity = DeviceIdentity::load_or_generate().context("Failed to load device identity")?;
 
-    let compression = if args.no_compress {
-        CompressionMode::Never
-    } else if args.compress {
-        CompressionMode::Always
-    } else {
-        global_config.transfer.compression
-    };
-    let compression_level = args
-        .compression_level
-        .unwrap_or(global_config.transfer.compression_level);
-
-    let config = TransferConfig {
-        compression,
-        compression_level,
-        chunk_size: global_config.transfer.chunk_size,
-        parallel_streams: global_config.transfer.parallel_chunks,
-        verify_checksums: global_config.transfer.verify_checksum,
-        discovery_port: global_config.network.port,
-        ..Default::default()
-    };
+    let config = transfer_config(&args, &global_config);
 
     let mut session =
         TrustedSendSession::new(trusted_device.clone(), identity, &args.paths, config)
@@ -75,27 +62,30 @@ pub async fn run(args: SendArgs) -> Result<()> {
 
     display_send_info(&files, total_size, &trusted_device.device_name, &args);
 
-    let discovered_addr = find_device(&mut session, &trusted_device, &args).await?;
-
-    if trusted_device.trust_level == TrustLevel::AskEachTime && !args.quiet {
-        print!(
-            "  Send {} files ({}) to {}? [Y/n] ",
-            files.len(),
-            format_size(total_size),
-            trusted_device.device_name
-        );
-        let _ = io::stdout().flush();
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        let input = input.trim().to_lowercase();
-
-        if !input.is_empty() && input != "y" && input != "yes" {
-            println!();
-            println!("  Transfer cancelled.");
-            return Ok(());
+    let peer_addresses = peers::addresses_for(&global_config.peers, &trusted_device);
+    let discovered_addr = match find_device(
+        &mut session,
+        &trusted_device,
+        &peer_addresses,
+        global_config.network.transfer_port_range.0,
+        args.quiet,
+    )
+    .await
+    {
+        Ok(addr) => addr,
+        Err(e) if args.queue => {
+            return queue_send(&trusted_device, &args, files.len(), total_size, &e);
         }
+        Err(e) => return Err(e),
+    };
+
+    if trusted_device.trust_level == TrustLevel::AskEachTime
+        && !args.quiet
+        && !confirm_send(&trusted_device.device_name, files.len(), total_size)?
+    {
         println!();
+        println!("  Transfer cancelled.");
+        return Ok(());
     }
 
     let progress_rx = session.progress();
@@ -127,6 +117,310 @@ pub async fn run(args: SendArgs) -> Result<()> {
     )
 }
 
+/// Ask whether to send to a device, returning `true` unless the user declines.
+fn confirm_send(device_name: &str, file_count: usize, total_size: u64) -> Result<bool> {
+    print!(
+        "  Send {} files ({}) to {}? [Y/n] ",
+        file_count,
+        format_size(total_size),
+        device_name
+    );
+    let _ = io::stdout().flush();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+
+    let confirmed = input.is_empty() || input == "y" || input == "yes";
+    if confirmed {
+        println!();
+    }
+    Ok(confirmed)
+}
+
+/// Build the transfer configuration from command-line overrides and config.
+fn transfer_config(args: &SendArgs, global_config: &yoop_core::config::Config) -> TransferConfig {
+    let compression = if args.no_compress {
+        CompressionMode::Never
+    } else if args.compress {
+        CompressionMode::Always
+    } else {
+        global_config.transfer.compression
+    };
+    let compression_level = args
+        .compression_level
+        .unwrap_or(global_config.transfer.compression_level);
+
+    TransferConfig {
+        compression,
+        compression_level,
+        ..global_config.transfer_config()
+    }
+}
+
+/// Queue the send in the outbox after discovery failed.
+fn queue_send(
+    trusted_device: &TrustedDevice,
+    args: &SendArgs,
+    file_count: usize,
+    total_size: u64,
+    reason: &anyhow::Error,
+) -> Result<()> {
+    let short_id = enqueue(trusted_device, args, file_count, total_size)?;
+    let started = super::outbox::spawn_worker()?;
+
+    if !args.quiet {
+        println!();
+        println!("  {}", reason);
+        println!(
+            "  Queued as {}; it will be sent when {} comes online.",
+            short_id, trusted_device.device_name
+        );
+        if started {
+            println!("  Started delivery worker in the background.");
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Add the send to the outbox, returning the short ID of the queued entry.
+fn enqueue(
+    trusted_device: &TrustedDevice,
+    args: &SendArgs,
+    file_count: usize,
+    total_size: u64,
+) -> Result<String> {
+    let expire = parse_duration(&args.expire)
+        .ok_or_else(|| anyhow::anyhow!("Invalid expiry: {}", args.expire))?;
+
+    let paths = args
+        .paths
+        .iter()
+        .map(|p| {
+            std::fs::canonicalize(p).with_context(|| format!("Failed to resolve {}", p.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let entry = OutboxEntry::new(
+        trusted_device.device_id,
+        trusted_device.device_name.clone(),
+        paths,
+        expire,
+    )
+    .with_totals(file_count, total_size);
+    let short_id = entry.short_id();
+
+    let mut outbox = Outbox::load().context("Failed to load outbox")?;
+    outbox.enqueue(entry).context("Failed to queue send")?;
+
+    Ok(short_id)
+}
+
+/// Outcome of one device's transfer in a group send.
+enum GroupOutcome {
+    /// Files were delivered
+    Sent(SocketAddr),
+    /// The device could not be found on the network
+    NotFound(anyhow::Error),
+    /// The transfer started but failed
+    Failed(yoop_core::error::Error),
+}
+
+/// Send to every device in a group concurrently.
+#[allow(clippy::too_many_lines)]
+async fn run_group(group: &str, args: &SendArgs) -> Result<()> {
+    let global_config = super::load_config();
+    let trust_store = TrustStore::load().context("Failed to load trust store")?;
+
+    let members: Vec<TrustedDevice> = trust_store
+        .group_members(group)
+        .ok_or_else(|| anyhow::anyhow!("Group '@{}' not found in trust store", group))?
+        .into_iter()
+        .cloned()
+        .collect();
+    if members.is_empty() {
+        anyhow::bail!("Group '@{}' has no devices", group);
+    }
+
+    let config = transfer_config(args, &global_config);
+
+    let mut sessions = Vec::with_capacity(members.len());
+    for device in &members {
+        let identity =
+            DeviceIdentity::load_or_generate().context("Failed to load device identity")?;
+        let session =
+            TrustedSendSession::new(device.clone(), identity, &args.paths, config.clone())
+                .await
+                .context("Failed to create send session")?;
+        sessions.push(session);
+    }
+
+    if !args.quiet {
+        println!();
+        println!("Yoop v{}", yoop_core::VERSION);
+        println!("{}", "-".repeat(37));
+        println!();
+    }
+
+    let files = sessions[0].files().to_vec();
+    let total_size: u64 = files.iter().map(|f| f.size).sum();
+
+    let target = format!("@{} ({} devices)", group, members.len());
+    display_send_info(&files, total_size, &target, args);
+
+    if !args.quiet {
+        let mut confirmed = Vec::with_capacity(sessions.len());
+        for session in sessions {
+            let device = session.target();
+            if device.trust_level != TrustLevel::AskEachTime
+                || confirm_send(&device.device_name, files.len(), total_size)?
+            {
+                confirmed.push(session);
+            } else {
+                println!("  Skipping {}.", device.device_name);
+                println!();
+            }
+        }
+        sessions = confirmed;
+        if sessions.is_empty() {
+            println!("  Transfer cancelled.");
+            return Ok(());
+        }
+    }
+
+    let located = locate_group(&mut sessions, &global_config, &config, args.quiet).await?;
+
+    let progress: Vec<(String, watch::Receiver<TransferProgress>)> = sessions
+        .iter()
+        .map(|s| (s.target().device_name.clone(), s.progress()))
+        .collect();
+    let progress_handle = if args.quiet {
+        None
+    } else {
+        Some(tokio::spawn(display_group_progress(progress)))
+    };
+
+    let handles: Vec<_> = sessions
+        .into_iter()
+        .zip(located)
+        .map(|(mut session, location)| {
+            tokio::spawn(async move {
+                let start = Instant::now();
+                let device = session.target().clone();
+                let outcome = match location {
+                    Err(e) => GroupOutcome::NotFound(e),
+                    Ok(addr) => match session.send().await {
+                        Ok(()) => GroupOutcome::Sent(addr),
+                        Err(e) => GroupOutcome::Failed(e),
+                    },
+                };
+                (device, outcome, start.elapsed().as_secs())
+            })
+        })
+        .collect();
+
+    let attempted = handles.len();
+    let mut results = Vec::with_capacity(attempted);
+    for handle in handles {
+        results.push(handle.await?);
+    }
+
+    if let Some(handle) = progress_handle {
+        handle.abort();
+        println!();
+    }
+
+    let mut failed = 0;
+    let mut queued = 0;
+    if !args.quiet {
+        println!();
+    }
+    for (device, outcome, elapsed) in results {
+        let status = match outcome {
+            GroupOutcome::Sent(addr) => {
+                record_history(
+                    &device.device_name,
+                    &files,
+                    total_size,
+                    elapsed,
+                    HistoryState::Completed,
+                    None,
+                );
+                if let Ok(mut store) = TrustStore::load() {
+                    let _ = store.update_address(&device.device_id, addr.ip(), addr.port());
+                }
+                "sent".to_string()
+            }
+            GroupOutcome::NotFound(e) if args.queue => {
+                let short_id = enqueue(&device, args, files.len(), total_size)?;
+                queued += 1;
+                format!("queued as {} ({})", short_id, e)
+            }
+            GroupOutcome::NotFound(e) => {
+                failed += 1;
+                format!("FAILED: {}", e)
+            }
+            GroupOutcome::Failed(e) => {
+                record_history(
+                    &device.device_name,
+                    &files,
+                    total_size,
+                    elapsed,
+                    HistoryState::Failed,
+                    Some(e.to_string()),
+                );
+                failed += 1;
+                format!("FAILED: {}", e)
+            }
+        };
+        if !args.quiet {
+            println!("  {:<20} {}", device.device_name, status);
+        }
+    }
+
+    if queued > 0 && super::outbox::spawn_worker()? && !args.quiet {
+        println!();
+        println!("  Started delivery worker in the background.");
+    }
+    if !args.quiet {
+        println!();
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} transfers failed", failed, attempted);
+    }
+    Ok(())
+}
+
+/// Show one progress line covering every device in a group send.
+async fn display_group_progress(progress: Vec<(String, watch::Receiver<TransferProgress>)>) {
+    loop {
+        let line: Vec<String> = progress
+            .iter()
+            .map(|(name, rx)| {
+                let p = rx.borrow().clone();
+                let status = match p.state {
+                    TransferState::Preparing | TransferState::Waiting => "waiting".to_string(),
+                    TransferState::Connected => "connected".to_string(),
+                    TransferState::Reconnecting => "reconnecting".to_string(),
+                    TransferState::Transferring => format!("{:.0}%", p.percentage()),
+                    TransferState::Completed => "done".to_string(),
+                    TransferState::Cancelled => "cancelled".to_string(),
+                    TransferState::Failed => "failed".to_string(),
+                };
+                format!("{} {}", name, status)
+            })
+            .collect();
+
+        print!("\r  {}    ", line.join(" | "));
+        let _ = io::stdout().flush();
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
 /// Display send information (files and device).
 fn display_send_info(
     files: &[yoop_core::file::FileMetadata],
@@ -151,63 +445,43 @@ fn display_send_info(
     println!();
 }
 
-/// Find the target device using stored address first, then discovery as fallback.
+/// Find the target device using configured and stored addresses first, then
+/// discovery as fallback.
 ///
 /// Connection priority:
-/// 1. Try stored address if available (with short timeout TCP probe)
-/// 2. Fall back to network discovery if stored address is unreachable
+/// 1. Try the device's `[[peers]]` addresses, resolving hostnames
+/// 2. Try stored address if available (with short timeout TCP probe)
+/// 3. Fall back to network discovery if neither is reachable
 async fn find_device(
     session: &mut TrustedSendSession,
-    trusted_device: &yoop_core::trust::TrustedDevice,
-    args: &SendArgs,
+    trusted_device: &TrustedDevice,
+    peer_addresses: &[String],
+    peer_port: u16,
+    quiet: bool,
 ) -> Result<SocketAddr> {
-    let stored_addr = trusted_device
-        .address()
-        .map(|(ip, port)| SocketAddr::new(ip, port));
+    if !quiet {
+        print!("  Trying known addresses...");
+        let _ = io::stdout().flush();
+    }
 
-    if let Some(addr) = stored_addr {
-        if !args.quiet {
-            print!("  Trying stored address {}...", addr);
-            let _ = io::stdout().flush();
+    if let Some(addr) = peers::probe_device(trusted_device, peer_addresses, peer_port).await {
+        if !quiet {
+            println!(" reachable at {}", addr);
+            println!();
         }
-
-        match tokio::time::timeout(Duration::from_secs(3), tokio::net::TcpStream::connect(addr))
-            .await
-        {
-            Ok(Ok(stream)) => {
-                drop(stream);
-                if !args.quiet {
-                    println!(" reachable");
-                    println!();
-                }
-                session.set_direct_address(addr);
-                return Ok(addr);
-            }
-            Ok(Err(e)) => {
-                tracing::debug!("Stored address {} unreachable: {}", addr, e);
-                if !args.quiet {
-                    println!(" unreachable");
-                    print!(
-                        "  Searching for {} on the network...",
-                        trusted_device.device_name
-                    );
-                    let _ = io::stdout().flush();
-                }
-            }
-            Err(_) => {
-                tracing::debug!("Stored address {} connection timed out", addr);
-                if !args.quiet {
-                    println!(" timed out");
-                    print!(
-                        "  Searching for {} on the network...",
-                        trusted_device.device_name
-                    );
-                    let _ = io::stdout().flush();
-                }
-            }
+        if let Ok(mut store) = TrustStore::load() {
+            let _ = store.update_address(&trusted_device.device_id, addr.ip(), addr.port());
         }
-    } else if !args.quiet {
-        print!("  Searching for {}...", trusted_device.device_name);
+        session.set_direct_address(addr);
+        return Ok(addr);
+    }
+
+    if !quiet {
+        println!(" unreachable");
+        print!(
+            "  Searching for {} on the network...",
+            trusted_device.device_name
+        );
         let _ = io::stdout().flush();
     }
 
@@ -219,7 +493,7 @@ async fn find_device(
             Ok(Ok(device)) => break device,
             Ok(Err(e)) => {
                 if start_discovery.elapsed() > discovery_timeout {
-                    if !args.quiet {
+                    if !quiet {
                         println!();
                     }
                     return Err(anyhow::anyhow!(
@@ -231,7 +505,7 @@ async fn find_device(
             }
             Err(_) => {
                 if start_discovery.elapsed() > discovery_timeout {
-                    if !args.quiet {
+                    if !quiet {
                         println!();
                     }
                     return Err(anyhow::anyhow!(
@@ -245,7 +519,7 @@ async fn find_device(
 
     let discovered_addr = discovered.source;
 
-    if !args.quiet {
+    if !quiet {
         println!(" found at {}", discovered_addr);
         println!();
     }
@@ -253,6 +527,105 @@ async fn find_device(
     Ok(discovered_addr)
 }
 
+/// Find every device of a group send, returning one result per session.
+///
+/// Known addresses are probed concurrently, then the devices that did not
+/// answer share a single discovery scan rather than each running its own.
+async fn locate_group(
+    sessions: &mut [TrustedSendSession],
+    global_config: &yoop_core::config::Config,
+    config: &TransferConfig,
+    quiet: bool,
+) -> Result<Vec<Result<SocketAddr>>> {
+    if !quiet {
+        print!("  Trying known addresses...");
+        let _ = io::stdout().flush();
+    }
+
+    let peer_port = global_config.network.transfer_port_range.0;
+    let probes: Vec<_> = sessions
+        .iter()
+        .map(|session| {
+            let device = session.target().clone();
+            let peer_addresses = peers::addresses_for(&global_config.peers, &device);
+            tokio::spawn(
+                async move { peers::probe_device(&device, &peer_addresses, peer_port).await },
+            )
+        })
+        .collect();
+
+    let mut located = Vec::with_capacity(sessions.len());
+    for (session, probe) in sessions.iter_mut().zip(probes) {
+        let located_addr = probe.await?;
+        if let Some(addr) = located_addr {
+            if let Ok(mut store) = TrustStore::load() {
+                let _ = store.update_address(&session.target().device_id, addr.ip(), addr.port());
+            }
+            session.set_direct_address(addr);
+        }
+        located.push(located_addr);
+    }
+
+    let unreached: Vec<TrustedDevice> = sessions
+        .iter()
+        .zip(&located)
+        .filter(|(_, addr)| addr.is_none())
+        .map(|(session, _)| session.target().clone())
+        .collect();
+    if !quiet {
+        println!(
+            " {} of {} reachable",
+            sessions.len() - unreached.len(),
+            sessions.len()
+        );
+    }
+
+    let mut discovered = if unreached.is_empty() {
+        std::collections::HashMap::new()
+    } else {
+        if !quiet {
+            print!(
+                "  Searching for {} devices on the network...",
+                unreached.len()
+            );
+            let _ = io::stdout().flush();
+        }
+        let found =
+            yoop_core::transfer::discover_devices(&unreached, config, Duration::from_secs(30))
+                .await
+                .context("Failed to search for devices")?;
+        if !quiet {
+            println!(" found {} of {}", found.len(), unreached.len());
+        }
+        found
+    };
+    if !quiet {
+        println!();
+    }
+
+    Ok(sessions
+        .iter_mut()
+        .zip(located)
+        .map(|(session, addr)| {
+            if let Some(addr) = addr {
+                return Ok(addr);
+            }
+            let device = session.target();
+            match discovered.remove(&device.device_id) {
+                Some(found) => {
+                    let addr = found.source;
+                    session.set_discovered_target(found);
+                    Ok(addr)
+                }
+                None => Err(anyhow::anyhow!(
+                    "Could not find '{}' on the network",
+                    device.device_name
+                )),
+            }
+        })
+        .collect())
+}
+
 /// Handle the result of the transfer and update history.
 #[allow(clippy::too_many_arguments)]
 fn handle_transfer_result(
@@ -400,7 +773,8 @@ async fn display_progress(mut rx: watch::Receiver<TransferProgress>) {
                     println!("  Transfer failed.");
                     break;
                 }
-                TransferState::Preparing | TransferState::Waiting => {}
+                TransferState::Preparing | TransferState::Waiting | TransferState::Reconnecting => {
+                }
             }
         }
 
@@ -9,6 +9,7 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::watch;
 use uuid::Uuid;
 
+use yoop_core::compression::CompressionStats;
 use yoop_core::config::{CompressionMode, TrustLevel};
 use yoop_core::file::format_size;
 use yoop_core::history::{
@@ -23,6 +24,7 @@ use crate::tui::session::{FileEntry, ProgressEntry, SessionEntry, SessionStateFi
 use crate::ui::{format_remaining, parse_duration, CodeBox};
 
 /// Run the share command.
+#[allow(clippy::too_many_lines)]
 pub async fn run(args: ShareArgs) -> Result<()> {
     let global_config = super::load_config();
 
@@ -42,11 +44,7 @@ pub async fn run(args: ShareArgs) -> Result<()> {
     let config = TransferConfig {
         compression,
         compression_level,
-        chunk_size: global_config.transfer.chunk_size,
-        parallel_streams: global_config.transfer.parallel_chunks,
-        verify_checksums: global_config.transfer.verify_checksum,
-        discovery_port: global_config.network.port,
-        ..Default::default()
+        ..global_config.transfer_config()
     };
 
     let mut session = ShareSession::new(&args.paths, config).await?;
@@ -62,7 +60,9 @@ pub async fn run(args: ShareArgs) -> Result<()> {
     let total_size: u64 = files.iter().map(|f| f.size).sum();
     let code = session.code().to_string();
 
-    display_share_info(&files, total_size, &code, &args, &global_config)?;
+    let port = session.local_addr()?.port();
+
+    display_share_info(&files, total_size, &code, port, &args, &global_config)?;
 
     let progress_rx = session.progress();
     let expire_duration =
@@ -136,6 +136,7 @@ pub async fn run(args: ShareArgs) -> Result<()> {
         receiver_device_id,
         receiver_public_key.as_deref(),
         receiver_addr,
+        session.compression_stats(),
         global_config.trust.auto_prompt,
     )
     .await
@@ -146,6 +147,7 @@ fn display_share_info(
     files: &[yoop_core::file::FileMetadata],
     total_size: u64,
     code: &str,
+    port: u16,
     args: &ShareArgs,
     global_config: &yoop_core::config::Config,
 ) -> Result<()> {
@@ -167,6 +169,7 @@ fn display_share_info(
     if args.json {
         let output = serde_json::json!({
             "code": code,
+            "port": port,
             "files": files.iter().map(|f| serde_json::json!({
                 "name": f.file_name(),
                 "size": f.size,
@@ -182,6 +185,8 @@ fn display_share_info(
             .with_qr(global_config.ui.show_qr)
             .display();
         println!();
+        println!("  Listening on port {}", port);
+        println!();
     }
 
     Ok(())
@@ -200,6 +205,7 @@ async fn handle_transfer_result(
     receiver_device_id: Option<Uuid>,
     receiver_public_key: Option<&str>,
     receiver_addr: Option<SocketAddr>,
+    compression_stats: &CompressionStats,
     trust_auto_prompt: bool,
 ) -> Result<()> {
     match result {
@@ -218,6 +224,13 @@ async fn handle_transfer_result(
             if !args.quiet {
                 println!();
                 println!("  Transfer complete!");
+                if compression_stats.chunks_compressed > 0 {
+                    println!(
+                        "  Compression: {} ({})",
+                        compression_stats.savings_display(),
+                        compression_stats.algorithm_display()
+                    );
+                }
                 println!();
 
                 if trust_auto_prompt {
@@ -237,6 +250,7 @@ async fn handle_transfer_result(
                     "status": "complete",
                     "code": code,
                     "total_transferred": total_size,
+                    "compression": compression_stats,
                 });
                 println!("{}", serde_json::to_string_pretty(&output)?);
             }
@@ -353,15 +367,22 @@ async fn display_progress(
                 println!();
                 waiting_printed = false;
             }
-            last_state = progress.state;
+            let previous = std::mem::replace(&mut last_state, progress.state);
 
             match progress.state {
                 TransferState::Connected => {
                     println!("  Receiver connected!");
                 }
+                TransferState::Transferring if previous == TransferState::Reconnecting => {
+                    println!("  Receiver reconnected, resuming transfer...");
+                }
                 TransferState::Transferring => {
                     println!("  Starting transfer...");
                 }
+                TransferState::Reconnecting => {
+                    println!();
+                    println!("  Connection lost, waiting for receiver to reconnect...");
+                }
                 TransferState::Completed => {
                     break;
                 }
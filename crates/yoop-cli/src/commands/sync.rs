@@ -98,13 +98,7 @@ pub async fn run(args: SyncArgs) -> anyhow::Result<()> {
     }
 
     let global_config = load_config();
-    let transfer_config = TransferConfig {
-        chunk_size: global_config.transfer.chunk_size,
-        parallel_streams: global_config.transfer.parallel_chunks,
-        verify_checksums: global_config.transfer.verify_checksum,
-        discovery_port: global_config.network.port,
-        ..Default::default()
-    };
+    let transfer_config = global_config.transfer_config();
 
     match resolve_sync_params(&args)? {
         SyncMode::Host => run_host(config, transfer_config, &args).await,
@@ -112,7 +106,7 @@ pub async fn run(args: SyncArgs) -> anyhow::Result<()> {
             run_client(&code, direct_addr, config, transfer_config, &args).await
         }
         SyncMode::TrustedClient { device } => {
-            run_trusted_client(device, config, transfer_config, &args).await
+            run_trusted_client(*device, config, transfer_config, &args).await
         }
     }
 }
@@ -127,7 +121,7 @@ enum SyncMode {
         direct_addr: Option<SocketAddr>,
     },
     /// Connect as client to a trusted device (codeless).
-    TrustedClient { device: TrustedDevice },
+    TrustedClient { device: Box<TrustedDevice> },
 }
 
 /// Resolve connection parameters from sync args.
@@ -146,7 +140,9 @@ fn resolve_sync_params(args: &SyncArgs) -> anyhow::Result<SyncMode> {
             )
         })?;
 
-        return Ok(SyncMode::TrustedClient { device });
+        return Ok(SyncMode::TrustedClient {
+            device: Box::new(device),
+        });
     }
 
     if args.host.is_some() && args.code.is_none() {
@@ -194,6 +190,7 @@ async fn run_host(
         println!("  │                                     │");
         println!("  └─────────────────────────────────────┘");
         println!();
+        println!("  Listening on port {}", host_session.local_addr()?.port());
         println!("  Waiting for peer to connect...");
         println!();
     }
@@ -359,7 +356,7 @@ async fn run_trusted_client(
         }],
         peer: Some(PeerEntry {
             name: session.peer_name().to_string(),
-            address: format!("{}:{}", ip, port),
+            address: std::net::SocketAddr::new(ip, port).to_string(),
         }),
         progress: ProgressEntry::default(),
     };
@@ -467,8 +464,9 @@ fn print_stats(stats: &yoop_core::sync::SyncStats) {
     }
 }
 
+/// Format a byte count for display, e.g. "1.50 MB".
 #[allow(clippy::cast_precision_loss)]
-fn format_size(bytes: u64) -> String {
+pub fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
     const GB: u64 = MB * 1024;
@@ -495,7 +493,8 @@ fn load_ignore_file(path: &PathBuf) -> anyhow::Result<Vec<String>> {
     Ok(patterns)
 }
 
-fn parse_size(size_str: &str) -> anyhow::Result<u64> {
+/// Parse a size string like "10MB" into bytes.
+pub fn parse_size(size_str: &str) -> anyhow::Result<u64> {
     let size_str = size_str.trim().to_uppercase();
     let (num_part, unit_part) = if size_str.ends_with("GB") {
         (&size_str[..size_str.len() - 2], 1024 * 1024 * 1024)
@@ -1,8 +1,16 @@
 //! Trust command implementation.
 
-use anyhow::Result;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
 
-use super::{TrustAction, TrustArgs};
+use anyhow::{Context, Result};
+
+use yoop_core::config::TrustLevel;
+use yoop_core::crypto::DeviceIdentity;
+use yoop_core::trust::pairing::{self, Pairing, PairingHost, PairingOutcome};
+use yoop_core::trust::{peers, Capability, DevicePermissions, TrustStore};
+
+use super::{GroupAction, TrustAction, TrustArgs, TrustSetArgs};
 
 /// Run the trust command.
 pub async fn run(args: TrustArgs) -> Result<()> {
@@ -22,11 +30,16 @@ pub async fn run(args: TrustArgs) -> Result<()> {
                         "  {} - {:?} ({} transfers)",
                         device.device_name, device.trust_level, device.transfer_count
                     );
+                    if !device.permissions.is_unrestricted() {
+                        println!("      {}", describe_permissions(&device.permissions));
+                    }
                 }
                 println!("{}", "─".repeat(60));
             }
         }
 
+        TrustAction::Status { json } => status(&mut trust_store, json).await?,
+
         TrustAction::Remove { device } => {
             let device_id = trust_store
                 .find_by_name(&device)
@@ -44,31 +57,393 @@ pub async fn run(args: TrustArgs) -> Result<()> {
             }
         }
 
-        TrustAction::Set { device, level } => {
-            let trust_level = match level.to_lowercase().as_str() {
-                "full" => yoop_core::config::TrustLevel::Full,
-                "ask" | "ask_each_time" => yoop_core::config::TrustLevel::AskEachTime,
-                _ => {
-                    anyhow::bail!("Invalid trust level: {}. Use 'full' or 'ask'.", level);
-                }
+        TrustAction::Set(set_args) => set(&mut trust_store, set_args)?,
+
+        TrustAction::Pair { code, level } => {
+            pair(&mut trust_store, code.as_deref(), parse_level(&level)?).await?;
+        }
+
+        TrustAction::Group { action } => run_group(&mut trust_store, action)?,
+    }
+
+    Ok(())
+}
+
+/// Update the trust level and permissions of a device.
+fn set(trust_store: &mut TrustStore, args: TrustSetArgs) -> Result<()> {
+    let TrustSetArgs {
+        device,
+        level,
+        allow,
+        deny,
+        sync_folder,
+        output_dir,
+        max_size,
+        reset,
+    } = args;
+
+    let Some(found) = trust_store
+        .find_by_name(&device)
+        .or_else(|| {
+            uuid::Uuid::parse_str(&device)
+                .ok()
+                .and_then(|id| trust_store.find_by_id(&id))
+        })
+        .cloned()
+    else {
+        println!("Device not found: {}", device);
+        return Ok(());
+    };
+
+    let changes_permissions = reset
+        || !allow.is_empty()
+        || !deny.is_empty()
+        || !sync_folder.is_empty()
+        || !output_dir.is_empty()
+        || max_size.is_some();
+    if level.is_none() && !changes_permissions {
+        anyhow::bail!(
+            "Nothing to change. Use --level, --allow, --deny, --sync-folder, --output-dir, --max-size or --reset."
+        );
+    }
+
+    if let Some(level) = level {
+        let trust_level = parse_level(&level)?;
+        trust_store.set_trust_level(&found.device_id, trust_level)?;
+        println!(
+            "Set trust level for {} to {:?}",
+            found.device_name, trust_level
+        );
+    }
+
+    if changes_permissions {
+        let mut permissions = if reset {
+            DevicePermissions::default()
+        } else {
+            found.permissions.clone()
+        };
+        for name in &allow {
+            permissions.grant(parse_capability(name)?);
+        }
+        for name in &deny {
+            permissions.revoke(parse_capability(name)?);
+        }
+        if !sync_folder.is_empty() {
+            permissions.sync_folders = Some(absolute_paths(sync_folder)?);
+        }
+        if !output_dir.is_empty() {
+            permissions.output_dirs = Some(absolute_paths(output_dir)?);
+        }
+        if let Some(max_size) = max_size {
+            permissions.max_transfer_size = if max_size.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                Some(super::sync::parse_size(&max_size).with_context(|| {
+                    format!("Invalid size '{}'. Use e.g. 500MB or none", max_size)
+                })?)
             };
+        }
 
-            let device_id = trust_store
-                .find_by_name(&device)
-                .map(|d| d.device_id)
-                .or_else(|| uuid::Uuid::parse_str(&device).ok());
+        trust_store.set_permissions(&found.device_id, permissions.clone())?;
+        println!(
+            "Permissions for {}: {}",
+            found.device_name,
+            describe_permissions(&permissions)
+        );
+    }
 
-            if let Some(id) = device_id {
-                if trust_store.set_trust_level(&id, trust_level)? {
-                    println!("Set trust level for {} to {:?}", device, trust_level);
-                } else {
-                    println!("Device not found: {}", device);
+    Ok(())
+}
+
+fn parse_level(level: &str) -> Result<TrustLevel> {
+    match level.to_lowercase().as_str() {
+        "full" => Ok(TrustLevel::Full),
+        "ask" | "ask_each_time" => Ok(TrustLevel::AskEachTime),
+        _ => anyhow::bail!("Invalid trust level: {}. Use 'full' or 'ask'.", level),
+    }
+}
+
+fn parse_capability(name: &str) -> Result<Capability> {
+    Capability::parse(name).ok_or_else(|| {
+        let names: Vec<&str> = Capability::ALL.iter().map(|c| c.as_str()).collect();
+        anyhow::anyhow!(
+            "Unknown capability '{}'. Use one of: {}",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+fn absolute_paths(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    paths
+        .into_iter()
+        .map(|path| {
+            std::path::absolute(&path)
+                .with_context(|| format!("Invalid directory: {}", path.display()))
+        })
+        .collect()
+}
+
+fn describe_permissions(permissions: &DevicePermissions) -> String {
+    let granted: Vec<&str> = Capability::ALL
+        .iter()
+        .filter(|c| permissions.allows(**c))
+        .map(|c| c.as_str())
+        .collect();
+    let mut parts = vec![if granted.is_empty() {
+        "no capabilities".to_string()
+    } else {
+        format!("allows {}", granted.join(", "))
+    }];
+    if let Some(limit) = permissions.max_transfer_size {
+        parts.push(format!("max {}", super::sync::format_size(limit)));
+    }
+    match permissions.sync_folders.as_deref() {
+        Some([]) => parts.push("sync nowhere".to_string()),
+        Some(folders) => parts.push(format!("sync only {}", join_paths(folders))),
+        None => {}
+    }
+    match permissions.output_dirs.as_deref() {
+        Some([]) => parts.push("save nowhere".to_string()),
+        Some(dirs) => parts.push(format!("save only to {}", join_paths(dirs))),
+        None => {}
+    }
+    parts.join("; ")
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Pair with another device running `yoop trust pair`.
+///
+/// Without a code this device hosts and shows one; with a code it joins.
+/// Either way both users compare the short authentication string before
+/// anything is written to the trust store.
+async fn pair(trust_store: &mut TrustStore, code: Option<&str>, level: TrustLevel) -> Result<()> {
+    let identity = DeviceIdentity::load_or_generate().context("Failed to load device identity")?;
+    let global_config = super::load_config();
+    let device_name = global_config.general.device_name.clone();
+    let transfer_config = global_config.transfer_config();
+
+    println!();
+    let session = if let Some(code) = code {
+        println!("  Looking for pairing code {}...", code.to_uppercase());
+        pairing::join(code, identity.clone(), &device_name, &transfer_config).await?
+    } else {
+        let host = PairingHost::start(identity.clone(), device_name, &transfer_config).await?;
+        println!("  ┌─────────────────────────────────────┐");
+        println!("  │                                     │");
+        println!("  │       Code:  {}               │", host.code());
+        println!("  │                                     │");
+        println!("  └─────────────────────────────────────┘");
+        println!();
+        println!(
+            "  Run 'yoop trust pair {}' on the other device.",
+            host.code()
+        );
+        println!("  Waiting for it to connect...");
+        host.accept().await?
+    };
+
+    show_pairing(&identity, &session);
+
+    print!("  Do the number and emoji match on both devices? [y/N]: ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().lock().read_line(&mut input)?;
+    let accepted = matches!(input.trim().to_lowercase().as_str(), "y" | "yes");
+    if accepted {
+        println!("  Waiting for the other device to confirm...");
+    }
+
+    let peer_name = session.peer_name().to_string();
+    let peer_addr = session.peer_addr();
+    match session.complete(accepted, level, trust_store).await? {
+        PairingOutcome::Paired(device) => {
+            println!();
+            println!(
+                "  Paired with {} ({:?}, address saved: {}).",
+                device.device_name, device.trust_level, peer_addr
+            );
+        }
+        PairingOutcome::Rejected => println!("  Pairing cancelled. Nothing was saved."),
+        PairingOutcome::RejectedByPeer => {
+            println!(
+                "  {} did not confirm the pairing. Nothing was saved.",
+                peer_name
+            );
+        }
+    }
+    println!();
+    Ok(())
+}
+
+fn show_pairing(identity: &DeviceIdentity, session: &Pairing) {
+    let sas = session.short_auth_string();
+
+    println!();
+    println!(
+        "  Pairing with \"{}\" ({})",
+        session.peer_name(),
+        session.peer_addr().ip()
+    );
+    println!();
+    println!(
+        "  This device:  {}",
+        pairing::fingerprint(&identity.public_key_base64())
+    );
+    println!(
+        "  Other device: {}",
+        pairing::fingerprint(session.peer_public_key())
+    );
+    println!();
+    if let Ok(qr) = yoop_core::qr::generate_ascii_text(&pairing::fingerprint_uri(
+        identity.device_id(),
+        &identity.public_key_base64(),
+    )) {
+        println!("  Fingerprint of this device:");
+        for line in qr.lines() {
+            println!("  {}", line);
+        }
+        println!();
+    }
+    println!("  Compare with the other screen:");
+    println!();
+    println!("      {}", sas.digits);
+    println!("      {}", sas.emoji_line());
+    println!("      {}", sas.names_line());
+    println!();
+}
+
+/// Probe configured peers and show where each trusted device can be reached.
+async fn status(trust_store: &mut TrustStore, json: bool) -> Result<()> {
+    let config = super::load_config();
+    let statuses = peers::check_peers(
+        &config.peers,
+        trust_store,
+        config.network.transfer_port_range.0,
+    )
+    .await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+    }
+
+    if statuses.is_empty() {
+        println!("No peers configured. Add [[peers]] entries to the config file.");
+        return Ok(());
+    }
+
+    println!();
+    println!("Peer Status:");
+    println!("{}", "─".repeat(60));
+    for status in &statuses {
+        match status.address {
+            Some(addr) => println!("  {} - reachable via {}", status.device_name, addr),
+            None => println!("  {} - unreachable", status.device_name),
+        }
+        for probe in &status.probes {
+            let result = match (probe.reachable, &probe.error) {
+                (Some(addr), _) => format!("ok ({})", addr),
+                (None, Some(error)) => error.clone(),
+                (None, None) => "no response".to_string(),
+            };
+            println!("      {:<30} {}", probe.address, result);
+        }
+    }
+    println!("{}", "─".repeat(60));
+
+    let unconfigured: Vec<&str> = trust_store
+        .list()
+        .iter()
+        .filter(|device| !statuses.iter().any(|s| s.device_id == device.device_id))
+        .map(|device| device.device_name.as_str())
+        .collect();
+    if !unconfigured.is_empty() {
+        println!("  Discovery only: {}", unconfigured.join(", "));
+    }
+
+    Ok(())
+}
+
+fn run_group(trust_store: &mut TrustStore, action: GroupAction) -> Result<()> {
+    match action {
+        GroupAction::List => {
+            let groups = trust_store.groups();
+            if groups.is_empty() {
+                println!("No device groups.");
+            } else {
+                println!();
+                println!("Device Groups:");
+                println!("{}", "─".repeat(60));
+                for group in groups {
+                    let names: Vec<&str> = group
+                        .members
+                        .iter()
+                        .filter_map(|id| trust_store.find_by_id(id))
+                        .map(|d| d.device_name.as_str())
+                        .collect();
+                    println!("  @{} - {}", group.name, names.join(", "));
                 }
+                println!("{}", "─".repeat(60));
+            }
+        }
+
+        GroupAction::Add { group, devices } => {
+            let ids = resolve_devices(trust_store, &devices)?;
+            trust_store.add_to_group(&group, &ids)?;
+            println!(
+                "Added {} device(s) to @{}",
+                ids.len(),
+                group.trim_start_matches('@')
+            );
+        }
+
+        GroupAction::Remove { group, devices } => {
+            let group_name = group.trim_start_matches('@');
+            let found = if devices.is_empty() {
+                trust_store.remove_group(&group)?
             } else {
-                println!("Device not found: {}", device);
+                let ids = resolve_devices(trust_store, &devices)?;
+                trust_store.remove_from_group(&group, &ids)?
+            };
+
+            if !found {
+                println!("Group not found: @{}", group_name);
+            } else if devices.is_empty() {
+                println!("Removed group: @{}", group_name);
+            } else {
+                println!("Removed {} device(s) from @{}", devices.len(), group_name);
             }
         }
     }
 
     Ok(())
 }
+
+/// Resolve device names or IDs to trusted device IDs.
+fn resolve_devices(
+    trust_store: &yoop_core::trust::TrustStore,
+    devices: &[String],
+) -> Result<Vec<uuid::Uuid>> {
+    devices
+        .iter()
+        .map(|device| {
+            trust_store
+                .find_by_name(device)
+                .map(|d| d.device_id)
+                .or_else(|| {
+                    uuid::Uuid::parse_str(device)
+                        .ok()
+                        .filter(|id| trust_store.is_trusted(id))
+                })
+                .ok_or_else(|| anyhow::anyhow!("Device not found: {}", device))
+        })
+        .collect()
+}
@@ -1,12 +1,14 @@
 //! Web command implementation.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+
+use yoop_core::crypto::SelfSignedCertificate;
 
 use super::WebArgs;
 
 /// Run the web command.
 pub async fn run(args: WebArgs) -> Result<()> {
-    let global_config = super::load_config();
+    let mut global_config = super::load_config();
 
     let port = if args.port == 8080 {
         global_config.web.port
@@ -14,8 +16,19 @@ pub async fn run(args: WebArgs) -> Result<()> {
         args.port
     };
 
-    let auth_enabled = args.auth || global_config.web.auth;
+    let auth_enabled =
+        args.auth || args.password.is_some() || args.save_password || global_config.web.auth;
     let localhost_only = args.localhost_only || global_config.web.localhost_only;
+    let certificate = if args.https || global_config.web.https {
+        let path = SelfSignedCertificate::default_path()
+            .context("Could not determine the certificate location")?;
+        Some(
+            SelfSignedCertificate::load_or_generate(&path)
+                .context("Failed to load the web certificate")?,
+        )
+    } else {
+        None
+    };
 
     println!();
     println!("Yoop Web UI");
@@ -26,27 +39,63 @@ pub async fn run(args: WebArgs) -> Result<()> {
         port,
         localhost_only,
         auth_enabled,
-        auth_password: if auth_enabled {
-            Some(yoop_core::web::WebServerConfig::generate_password())
-        } else {
-            None
-        },
+        auth_password: args.password.or_else(|| global_config.web.password.clone()),
+        max_login_attempts: global_config.security.rate_limit_attempts,
+        lockout: global_config.security.rate_limit_window,
+        certificate,
+        transfer: global_config.transfer_config(),
     };
+    let mut server = yoop_core::web::WebServer::new(config);
+    let config = server.config();
+
+    let addresses = server.addresses();
+    for (i, address) in addresses.iter().enumerate() {
+        if i == 0 {
+            println!("  {}", address);
+        } else {
+            println!("  {} (for other devices)", address);
+        }
+    }
+
+    if let Some(fingerprint) = config.fingerprint() {
+        println!();
+        println!("  Certificate fingerprint (SHA-256):");
+        println!("  {}", fingerprint);
+        println!("  Check that your browser shows the same value before trusting it.");
 
-    println!("  http://localhost:{}", port);
-    if !localhost_only {
-        println!("  http://192.168.x.x:{} (for other devices)", port);
+        // scanning opens the web UI on a phone with the expected fingerprint
+        // in the fragment, which the page checks against the certificate
+        // fingerprint the server reports
+        let lan_url = addresses.get(1).filter(|_| !localhost_only);
+        if let Some(url) = lan_url {
+            let link = format!("{url}/#fp={}", fingerprint.replace(':', ""));
+            if let Ok(qr) = yoop_core::qr::generate_ascii_text(&link) {
+                println!();
+                for line in qr.lines() {
+                    println!("  {}", line);
+                }
+            }
+        }
     }
 
-    if let Some(ref password) = config.auth_password {
+    if let Some(password) = config
+        .auth_password
+        .as_ref()
+        .filter(|_| config.auth_enabled)
+    {
         println!();
         println!("  Password: {}", password);
+
+        if args.save_password && global_config.web.password.as_ref() != Some(password) {
+            global_config.web.password = Some(password.clone());
+            global_config.save()?;
+            println!("  (saved to config)");
+        }
     }
 
     println!();
     println!("Press Ctrl+C to stop the server.");
 
-    let mut server = yoop_core::web::WebServer::new(config);
     server.start().await?;
 
     println!();
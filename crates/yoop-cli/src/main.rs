@@ -35,18 +35,35 @@ async fn main() -> Result<()> {
         init_logging();
     }
 
-    match cli.command {
+    let config = commands::load_config();
+    if config.security.audit_log {
+        if let Some(path) = yoop_core::audit::AuditLog::default_path() {
+            yoop_core::audit::enable(path);
+        }
+    }
+    yoop_core::hooks::enable(config.hooks);
+
+    if cli.command.uses_identity() {
+        commands::identity::unlock_if_needed()?;
+    }
+
+    let result = match cli.command {
         Command::Share(args) => commands::share::run(args).await,
         Command::Receive(args) => commands::receive::run(args).await,
         Command::Send(args) => commands::send::run(args).await,
         Command::Clipboard(args) => commands::clipboard::run(args).await,
         Command::Sync(args) => commands::sync::run(args).await,
-        Command::Scan(args) => commands::scan::run(args).await,
+        Command::Scan(args) => Box::pin(commands::scan::run(args)).await,
         Command::Trust(args) => commands::trust::run(args).await,
+        Command::Outbox(args) => commands::outbox::run(args).await,
+        Command::Identity(args) => commands::identity::run(args),
         Command::Web(args) => commands::web::run(args).await,
         Command::Config(args) => commands::config::run(args).await,
         Command::Diagnose(args) => commands::diagnose::run(args).await,
+        Command::Bench(args) => commands::bench::run(args).await,
+        Command::Relay(args) => commands::relay::run(args).await,
         Command::History(args) => commands::history::run(args).await,
+        Command::Audit(args) => commands::audit::run(&args),
         Command::Completions(args) => commands::completions::run(args.action),
         #[cfg(feature = "update")]
         Command::Update(args) => commands::update::run(args).await,
@@ -54,7 +71,12 @@ async fn main() -> Result<()> {
         Command::InternalClipboardHold(args) => {
             commands::internal::run_clipboard_hold(&args.content_type, args.timeout)
         }
-    }
+    };
+
+    yoop_core::hooks::wait().await;
+    yoop_core::audit::flush();
+
+    result
 }
 
 fn init_logging() {
@@ -1760,7 +1760,7 @@ impl App {
                 yoop_core::config::CompressionMode::Never
             },
             compression_level: self.state.share.options.compression_level,
-            ..Default::default()
+            ..crate::commands::load_config().transfer_config()
         };
 
         match yoop_core::transfer::ShareSession::new(&self.state.share.selected_files, config).await
@@ -1870,7 +1870,7 @@ impl App {
             .clone()
             .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
 
-        let config = yoop_core::transfer::TransferConfig::default();
+        let config = crate::commands::load_config().transfer_config();
 
         match self.state.receive.input_mode {
             ReceiveInputMode::Code => {
@@ -2016,8 +2016,10 @@ impl App {
         self.receive_command_tx = Some(command_tx);
 
         let task_handle = tokio::spawn(async move {
-            run_receive_trusted_task(device, output_dir, config, event_tx, command_rx, cancel_rx)
-                .await;
+            Box::pin(run_receive_trusted_task(
+                device, output_dir, config, event_tx, command_rx, cancel_rx,
+            ))
+            .await;
         });
 
         self.receive_session_handle = Some(ReceiveSessionHandle {
@@ -2135,7 +2137,6 @@ impl App {
     async fn share_clipboard(&mut self) {
         use super::state::ClipboardTaskResult;
         use yoop_core::clipboard::ClipboardShareSession;
-        use yoop_core::transfer::TransferConfig;
 
         self.log_info("Starting clipboard share...");
 
@@ -2151,7 +2152,7 @@ impl App {
             Some(super::state::ClipboardOperation::Sharing);
         self.state.clipboard.status_message = Some("Starting share...".to_string());
 
-        let config = TransferConfig::default();
+        let config = crate::commands::load_config().transfer_config();
 
         match ClipboardShareSession::new(config).await {
             Ok(session) => {
@@ -2190,7 +2191,6 @@ impl App {
     async fn receive_clipboard(&mut self) {
         use super::state::ClipboardTaskResult;
         use yoop_core::clipboard::ClipboardReceiveSession;
-        use yoop_core::transfer::TransferConfig;
 
         let code = self.state.clipboard.code_input.clone();
         if code.len() < 4 {
@@ -2205,7 +2205,7 @@ impl App {
             Some(super::state::ClipboardOperation::Receiving);
         self.state.clipboard.status_message = Some(format!("Searching for {}...", code));
 
-        let config = TransferConfig::default();
+        let config = crate::commands::load_config().transfer_config();
         let tx = self.clipboard_task_tx.clone();
 
         let task_handle = tokio::spawn(async move {
@@ -2231,10 +2231,9 @@ impl App {
     async fn start_clipboard_sync(&mut self) {
         use super::state::ClipboardTaskResult;
         use yoop_core::clipboard::ClipboardSyncSession;
-        use yoop_core::transfer::TransferConfig;
 
         let code = self.state.clipboard.code_input.clone();
-        let config = TransferConfig::default();
+        let config = crate::commands::load_config().transfer_config();
 
         self.state.clipboard.operation_in_progress =
             Some(super::state::ClipboardOperation::StartingSync);
@@ -2373,7 +2372,6 @@ impl App {
     /// Start hosting a sync session.
     fn start_sync_host(&mut self) {
         use yoop_core::sync::SyncConfig;
-        use yoop_core::transfer::TransferConfig;
 
         let Some(ref sync_dir) = self.state.sync.directory else {
             self.log_error("Please select a directory first");
@@ -2393,7 +2391,7 @@ impl App {
             ..Default::default()
         };
 
-        let transfer_config = TransferConfig::default();
+        let transfer_config = crate::commands::load_config().transfer_config();
 
         let (event_tx, event_rx) = mpsc::unbounded_channel();
         self.sync_host_event_rx = Some(event_rx);
@@ -2449,7 +2447,6 @@ impl App {
     /// Join an existing sync session.
     async fn join_sync(&mut self) {
         use yoop_core::sync::{SyncConfig, SyncSession};
-        use yoop_core::transfer::TransferConfig;
 
         let code = self.state.sync.code_input.clone();
         if code.len() < 4 {
@@ -2470,7 +2467,7 @@ impl App {
             ..Default::default()
         };
 
-        let transfer_config = TransferConfig::default();
+        let transfer_config = crate::commands::load_config().transfer_config();
 
         match SyncSession::connect(&code, sync_config, transfer_config).await {
             Ok(session) => {
@@ -2732,7 +2729,7 @@ async fn run_receive_task(
 
     let (sender_addr, sender_name) = session.sender();
     let files = session.files().to_vec();
-    let total_size: u64 = files.iter().map(|f| f.size).sum();
+    let total_size: u64 = yoop_core::transfer::limits::total_size(&files);
 
     let _ = event_tx.send(ReceiveEvent::Connected {
         sender_name: sender_name.to_string(),
@@ -2832,7 +2829,7 @@ async fn run_receive_trusted_task(
 
     let (sender_addr, sender_name) = session.sender();
     let files = session.files().to_vec();
-    let total_size: u64 = files.iter().map(|f| f.size).sum();
+    let total_size: u64 = yoop_core::transfer::limits::total_size(&files);
 
     let _ = event_tx.send(ReceiveEvent::Connected {
         sender_name: sender_name.to_string(),
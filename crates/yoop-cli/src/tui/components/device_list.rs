@@ -32,7 +32,7 @@ impl DeviceInfo {
     pub fn from_trusted_device(device: &yoop_core::trust::TrustedDevice) -> Self {
         let address = device
             .address()
-            .map(|(ip, port)| format!("{}:{}", ip, port));
+            .map(|(ip, port)| std::net::SocketAddr::new(ip, port).to_string());
 
         let last_seen = {
             let duration = device.last_seen.elapsed().ok();
@@ -123,7 +123,9 @@ impl FilePreview {
         sender_name: &str,
         theme: &Theme,
     ) {
-        let total_size: u64 = files.iter().map(|f| f.size).sum();
+        let total_size: u64 = files
+            .iter()
+            .fold(0u64, |total, f| total.saturating_add(f.size));
 
         let block = Block::default()
             .title(format!(
@@ -183,7 +185,9 @@ impl FilePreview {
         sender_name: &str,
         theme: &Theme,
     ) {
-        let total_size: u64 = files.iter().map(|f| f.size).sum();
+        let total_size: u64 = files
+            .iter()
+            .fold(0u64, |total, f| total.saturating_add(f.size));
 
         let block = Block::default()
             .title(" Incoming ")
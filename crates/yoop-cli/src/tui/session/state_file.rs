@@ -268,7 +268,7 @@ impl Default for SessionStateFile {
 
 /// Check if a process is alive.
 #[cfg(unix)]
-fn is_process_alive(pid: u32) -> bool {
+pub fn is_process_alive(pid: u32) -> bool {
     #[allow(unsafe_code, clippy::cast_possible_wrap)]
     unsafe {
         libc::kill(pid as i32, 0) == 0
@@ -277,7 +277,7 @@ fn is_process_alive(pid: u32) -> bool {
 
 /// Check if a process is alive (windows).
 #[cfg(windows)]
-fn is_process_alive(pid: u32) -> bool {
+pub fn is_process_alive(pid: u32) -> bool {
     std::process::Command::new("tasklist")
         .args(["/FI", &format!("PID eq {}", pid), "/NH"])
         .output()
@@ -290,7 +290,7 @@ fn is_process_alive(pid: u32) -> bool {
 
 /// Check if a process is alive (fallback for unsupported platforms).
 #[cfg(not(any(unix, windows)))]
-fn is_process_alive(_pid: u32) -> bool {
+pub fn is_process_alive(_pid: u32) -> bool {
     true
 }
 
@@ -217,7 +217,7 @@ impl ConfigView {
                 description: "Compression mode",
                 value: format!("{:?}", config.transfer.compression).to_lowercase(),
                 pending_value: None,
-                setting_type: ConfigSettingType::Enum(vec!["auto", "always", "never"]),
+                setting_type: ConfigSettingType::Enum(vec!["auto", "always", "never", "adaptive"]),
             },
             ConfigSetting {
                 key: "compression_level",
@@ -294,6 +294,18 @@ impl ConfigView {
                 pending_value: None,
                 setting_type: ConfigSettingType::Duration,
             },
+            ConfigSetting {
+                key: "audit_log",
+                label: "Audit Log",
+                description: "Record security events to the audit log",
+                value: if config.security.audit_log {
+                    "Yes".to_string()
+                } else {
+                    "No".to_string()
+                },
+                pending_value: None,
+                setting_type: ConfigSettingType::Boolean,
+            },
         ]);
 
         self.settings_cache.push(vec![
@@ -444,6 +456,18 @@ impl ConfigView {
                 pending_value: None,
                 setting_type: ConfigSettingType::Boolean,
             },
+            ConfigSetting {
+                key: "web.https",
+                label: "HTTPS",
+                description: "Serve over HTTPS with a self-signed certificate",
+                value: if config.web.https {
+                    "Yes".to_string()
+                } else {
+                    "No".to_string()
+                },
+                pending_value: None,
+                setting_type: ConfigSettingType::Boolean,
+            },
         ]);
 
         self.settings_cache.push(vec![
@@ -1070,6 +1094,7 @@ impl ConfigView {
                         "auto" => yoop_core::config::CompressionMode::Auto,
                         "always" => yoop_core::config::CompressionMode::Always,
                         "never" => yoop_core::config::CompressionMode::Never,
+                        "adaptive" => yoop_core::config::CompressionMode::Adaptive,
                         _ => return Err(format!("Invalid compression mode: {}", value)),
                     };
                 }
@@ -1092,6 +1117,7 @@ impl ConfigView {
                 "rate_limit_window" => {
                     config.security.rate_limit_window = parse_duration(value)?;
                 }
+                "audit_log" => config.security.audit_log = parse_bool(value),
                 _ => {}
             },
             4 => match key {
@@ -1144,6 +1170,7 @@ impl ConfigView {
                 }
                 "web.auth" => config.web.auth = parse_bool(value),
                 "web.localhost_only" => config.web.localhost_only = parse_bool(value),
+                "web.https" => config.web.https = parse_bool(value),
                 _ => {}
             },
             8 => match key {
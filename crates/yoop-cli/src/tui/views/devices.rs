@@ -2,7 +2,7 @@
 //!
 //! Provides the interface for managing trusted devices.
 
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::time::SystemTime;
 
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
@@ -11,9 +11,14 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
 use ratatui::Frame;
 
+use yoop_core::audit::{AuditEntry, AuditLog};
+
 use crate::tui::state::{AppState, DevicesFocus};
 use crate::tui::theme::Theme;
 
+/// Number of security events shown below the device list.
+const RECENT_AUDIT_EVENTS: u16 = 5;
+
 /// Trusted device information for display.
 #[derive(Debug, Clone)]
 pub struct TuiTrustedDevice {
@@ -65,7 +70,7 @@ impl TuiTrustedDevice {
     /// Get address string if available.
     pub fn address_str(&self) -> Option<String> {
         match (self.last_known_ip, self.last_known_port) {
-            (Some(ip), Some(port)) => Some(format!("{}:{}", ip, port)),
+            (Some(ip), Some(port)) => Some(SocketAddr::new(ip, port).to_string()),
             (Some(ip), None) => Some(ip.to_string()),
             _ => None,
         }
@@ -78,6 +83,8 @@ pub struct DevicesView {
     list_state: ListState,
     /// Cached list of devices
     pub devices: Vec<TuiTrustedDevice>,
+    /// Most recent security events, newest first
+    pub audit_events: Vec<AuditEntry>,
 }
 
 impl DevicesView {
@@ -88,18 +95,28 @@ impl DevicesView {
         Self {
             list_state,
             devices: Vec::new(),
+            audit_events: Vec::new(),
         }
     }
 
     /// Render the devices view.
     pub fn render(&mut self, frame: &mut Frame, area: Rect, state: &AppState, theme: &Theme) {
-        if self.devices.is_empty() {
-            self.render_empty_state(frame, area, theme);
+        if state.devices.confirm_remove && !self.devices.is_empty() {
+            self.render_remove_confirmation(frame, area, state, theme);
             return;
         }
 
-        if state.devices.confirm_remove {
-            self.render_remove_confirmation(frame, area, state, theme);
+        let [area, events_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(10),
+                Constraint::Length(RECENT_AUDIT_EVENTS + 2),
+            ])
+            .areas(area);
+        self.render_audit_events(frame, events_area, theme);
+
+        if self.devices.is_empty() {
+            self.render_empty_state(frame, area, theme);
             return;
         }
 
@@ -440,6 +457,49 @@ impl DevicesView {
         frame.render_widget(paragraph, inner);
     }
 
+    /// Render the most recent security events.
+    fn render_audit_events(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let block = Block::default()
+            .title(" Security Events ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if self.audit_events.is_empty() {
+            let text = Paragraph::new(Span::styled(
+                "No security events recorded",
+                Style::default().fg(theme.text_muted),
+            ))
+            .alignment(Alignment::Center);
+            frame.render_widget(text, inner);
+            return;
+        }
+
+        let lines: Vec<Line> = self
+            .audit_events
+            .iter()
+            .map(|entry| {
+                let kind_style = if entry.kind.is_failure() {
+                    Style::default().fg(theme.warning)
+                } else {
+                    Style::default().fg(theme.text_secondary)
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!("{}  ", entry.formatted_timestamp()),
+                        Style::default().fg(theme.text_muted),
+                    ),
+                    Span::styled(format!("{:<26}", entry.kind.as_str()), kind_style),
+                    Span::styled(entry.peer_label(), Style::default().fg(theme.text_primary)),
+                ])
+            })
+            .collect();
+
+        frame.render_widget(Paragraph::new(lines), inner);
+    }
+
     /// Get the currently selected device.
     pub fn get_selected_device(&self, state: &AppState) -> Option<&TuiTrustedDevice> {
         if state.devices.selected_index < self.devices.len() {
@@ -449,7 +509,7 @@ impl DevicesView {
         }
     }
 
-    /// Load devices from the trust store.
+    /// Load devices from the trust store, and recent security events.
     pub fn load_devices(&mut self) {
         self.devices.clear();
 
@@ -487,6 +547,10 @@ impl DevicesView {
                     _ => b.last_seen.cmp(&a.last_seen),
                 });
         }
+
+        self.audit_events = AuditLog::open_default()
+            .recent(usize::from(RECENT_AUDIT_EVENTS))
+            .unwrap_or_default();
     }
 
     /// Cycle focus to next element.
@@ -82,7 +82,7 @@ fn center_in_box(content: &str, width: usize) -> String {
     format!("{}{}{}", " ".repeat(left), content, " ".repeat(right))
 }
 
-/// Parse a duration string like "5m", "30s", or "1h".
+/// Parse a duration string like "5m", "30s", "1h", or "7d".
 pub fn parse_duration(s: &str) -> Option<Duration> {
     let s = s.trim();
     if s.is_empty() {
@@ -98,6 +98,9 @@ pub fn parse_duration(s: &str) -> Option<Duration> {
     } else if let Some(num_str) = s.strip_suffix('h') {
         let num: u64 = num_str.parse().ok()?;
         Some(Duration::from_secs(num * 3600))
+    } else if let Some(num_str) = s.strip_suffix('d') {
+        let num: u64 = num_str.parse().ok()?;
+        Some(Duration::from_secs(num * 86400))
     } else {
         None
     }
@@ -136,6 +139,7 @@ mod tests {
         assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
         assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
         assert_eq!(parse_duration("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("7d"), Some(Duration::from_secs(604_800)));
         assert_eq!(parse_duration("invalid"), None);
         assert_eq!(parse_duration(""), None);
     }
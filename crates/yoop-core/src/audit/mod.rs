@@ -0,0 +1,770 @@
+//! Tamper-evident security audit log.
+//!
+//! Security-relevant events — failed share code verifications, handshakes
+//! from untrusted devices or with bad signatures, denied capabilities and
+//! changes to the trust store — are appended to a JSON Lines file in the data
+//! directory (`audit.log`), one [`AuditEntry`] per line.
+//!
+//! ## Hash Chain
+//!
+//! Every entry stores the hash of the entry before it and its own hash over
+//! its contents, so editing or deleting an entry breaks the chain from that
+//! point on; [`AuditLog::verify`] reports the first broken entry. Cutting
+//! entries off the end leaves a valid chain, so the head hash printed by
+//! `yoop audit verify` is worth noting somewhere else.
+//!
+//! ## Recording
+//!
+//! Sessions report events through [`record`], which queues them for the log
+//! set with [`enable`]. A single writer drains the queue on the blocking
+//! thread pool (or inline outside a runtime), so a burst of failed
+//! connections never ties up async workers on file locks. Events still
+//! queued when the runtime shuts down would be lost, so the CLI calls
+//! [`flush`] before exiting. Nothing is
+//! recorded until a log is enabled (the CLI does so unless
+//! `security.audit_log` is off), and a failure to write is logged rather
+//! than failing the connection that triggered it.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write as _};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::crypto;
+use crate::error::{Error, Result};
+use crate::file::LockFile;
+use crate::trust::TrustedDevice;
+
+/// `prev_hash` of the first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Bytes read from the end of the log to find the last entry.
+const TAIL_BYTES: u64 = 16 * 1024;
+
+static ACTIVE_LOG: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Events waiting for the writer.
+static PENDING: Mutex<Pending> = Mutex::new(Pending {
+    events: VecDeque::new(),
+    writing: false,
+});
+
+struct Pending {
+    events: VecDeque<(PathBuf, AuditEvent)>,
+    /// Whether a writer is draining `events`
+    writing: bool,
+}
+
+/// Held while an event is taken off the queue and appended, so events are
+/// written in order and [`flush`] waits for a write already in progress.
+static WRITING: Mutex<()> = Mutex::new(());
+
+/// Record events to the log at `path` for the rest of the process.
+pub fn enable(path: PathBuf) {
+    if let Ok(mut active) = ACTIVE_LOG.lock() {
+        *active = Some(path);
+    }
+}
+
+/// Stop recording events.
+pub fn disable() {
+    if let Ok(mut active) = ACTIVE_LOG.lock() {
+        *active = None;
+    }
+}
+
+/// Append an event to the enabled audit log, if any.
+///
+/// The event is written in the background when called inside a Tokio
+/// runtime. Errors are logged and otherwise ignored.
+pub fn record(event: AuditEvent) {
+    tracing::debug!("Audit: {}", event.kind);
+    let Some(path) = ACTIVE_LOG.lock().ok().and_then(|active| active.clone()) else {
+        return;
+    };
+
+    let start_writer = {
+        let mut pending = PENDING.lock().unwrap_or_else(PoisonError::into_inner);
+        pending.events.push_back((path, event));
+        !std::mem::replace(&mut pending.writing, true)
+    };
+    if !start_writer {
+        return;
+    }
+
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            let writer = Writer { started: false };
+            handle.spawn_blocking(move || {
+                let mut writer = writer;
+                writer.started = true;
+                write_pending();
+            });
+        }
+        Err(_) => write_pending(),
+    }
+}
+
+/// Write every queued event before returning.
+///
+/// Call before the process exits: events recorded just before the runtime
+/// shuts down may otherwise never reach the log.
+pub fn flush() {
+    while write_next(false) {}
+}
+
+/// Append queued events until the queue is empty.
+fn write_pending() {
+    while write_next(true) {}
+}
+
+/// Append the oldest queued event, returning `false` once none is left.
+///
+/// `writer` is set for the background writer, which clears the `writing`
+/// flag when it finds the queue empty.
+fn write_next(writer: bool) -> bool {
+    let _writing = WRITING.lock().unwrap_or_else(PoisonError::into_inner);
+    let next = {
+        let mut pending = PENDING.lock().unwrap_or_else(PoisonError::into_inner);
+        let next = pending.events.pop_front();
+        if writer {
+            pending.writing = next.is_some();
+        }
+        next
+    };
+    let Some((path, event)) = next else {
+        return false;
+    };
+    if let Err(e) = AuditLog::new(path).append(event) {
+        tracing::warn!("Failed to write audit log: {}", e);
+    }
+    true
+}
+
+/// Hands the queue to the next [`record`] call if a spawned writer is
+/// dropped before it runs, e.g. because its runtime shut down.
+struct Writer {
+    started: bool,
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        if !self.started {
+            PENDING
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .writing = false;
+        }
+    }
+}
+
+/// Record a failed trusted handshake with a known device.
+///
+/// Only trust failures are recorded; network errors are not security events.
+pub(crate) fn handshake_failed(error: &Error, peer_addr: SocketAddr, device: &TrustedDevice) {
+    if matches!(
+        error,
+        Error::TrustError(_) | Error::SignatureInvalid | Error::DeviceNotTrusted(_)
+    ) {
+        record(
+            AuditEvent::new(AuditEventKind::HandshakeFailed)
+                .with_peer(peer_addr)
+                .with_device(device.device_id, device.device_name.clone())
+                .with_detail(error.to_string()),
+        );
+    }
+}
+
+/// Kind of security event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventKind {
+    /// A receiver presented a wrong share code
+    CodeVerificationFailed,
+    /// A handshake signature did not verify
+    SignatureInvalid,
+    /// A device that is not in the trust store attempted a trusted connection
+    UntrustedDevice,
+    /// A trusted device presented a key that does not match the stored one
+    KeyMismatch,
+    /// A trusted handshake with a known device failed
+    HandshakeFailed,
+    /// A trusted device attempted something its permissions do not allow
+    CapabilityDenied,
+    /// A wrong password was given, e.g. to the web interface
+    AuthenticationFailed,
+    /// A trusted device's key was replaced following its signed rotation
+    KeyRotated,
+    /// A device was added to the trust store
+    TrustAdded,
+    /// A device was removed from the trust store
+    TrustRemoved,
+    /// A device's trust level or permissions were changed
+    TrustChanged,
+    /// Pairing completed and the peer was trusted
+    PairingCompleted,
+    /// Pairing was rejected by either side
+    PairingRejected,
+}
+
+impl AuditEventKind {
+    /// Short name used in output and filters.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CodeVerificationFailed => "code-verification-failed",
+            Self::SignatureInvalid => "signature-invalid",
+            Self::UntrustedDevice => "untrusted-device",
+            Self::KeyMismatch => "key-mismatch",
+            Self::HandshakeFailed => "handshake-failed",
+            Self::CapabilityDenied => "capability-denied",
+            Self::AuthenticationFailed => "authentication-failed",
+            Self::KeyRotated => "key-rotated",
+            Self::TrustAdded => "trust-added",
+            Self::TrustRemoved => "trust-removed",
+            Self::TrustChanged => "trust-changed",
+            Self::PairingCompleted => "pairing-completed",
+            Self::PairingRejected => "pairing-rejected",
+        }
+    }
+
+    /// Whether the event is a rejected or failed attempt, as opposed to a
+    /// change made on purpose.
+    #[must_use]
+    pub const fn is_failure(self) -> bool {
+        matches!(
+            self,
+            Self::CodeVerificationFailed
+                | Self::SignatureInvalid
+                | Self::UntrustedDevice
+                | Self::KeyMismatch
+                | Self::HandshakeFailed
+                | Self::CapabilityDenied
+                | Self::AuthenticationFailed
+                | Self::PairingRejected
+        )
+    }
+}
+
+impl std::fmt::Display for AuditEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An event to be recorded.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    kind: AuditEventKind,
+    peer_addr: Option<SocketAddr>,
+    device_id: Option<Uuid>,
+    device_name: Option<String>,
+    detail: Option<String>,
+}
+
+impl AuditEvent {
+    /// Create an event of the given kind.
+    #[must_use]
+    pub fn new(kind: AuditEventKind) -> Self {
+        Self {
+            kind,
+            peer_addr: None,
+            device_id: None,
+            device_name: None,
+            detail: None,
+        }
+    }
+
+    /// Set the address of the peer involved.
+    #[must_use]
+    pub fn with_peer(mut self, addr: SocketAddr) -> Self {
+        self.peer_addr = Some(addr);
+        self
+    }
+
+    /// Set the device involved.
+    #[must_use]
+    pub fn with_device(mut self, device_id: Uuid, device_name: impl Into<String>) -> Self {
+        self.device_id = Some(device_id);
+        self.device_name = Some(device_name.into());
+        self
+    }
+
+    /// Set a human-readable detail, such as the error returned.
+    #[must_use]
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// A recorded entry of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 1
+    pub seq: u64,
+    /// Unix timestamp of the event
+    pub timestamp: u64,
+    /// What happened
+    pub kind: AuditEventKind,
+    /// Address of the peer involved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_addr: Option<SocketAddr>,
+    /// Device ID of the peer involved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<Uuid>,
+    /// Device name of the peer involved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    /// Additional detail
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Hash of the previous entry
+    pub prev_hash: String,
+    /// SHA-256 over this entry's other fields, hex-encoded
+    pub hash: String,
+}
+
+/// The hashed part of an entry: every field except `hash`.
+#[derive(Serialize)]
+struct EntryBody<'a> {
+    seq: u64,
+    timestamp: u64,
+    kind: AuditEventKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peer_addr: Option<SocketAddr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<&'a str>,
+    prev_hash: &'a str,
+}
+
+impl AuditEntry {
+    /// Compute the hash this entry should have.
+    fn compute_hash(&self) -> Result<String> {
+        let body = EntryBody {
+            seq: self.seq,
+            timestamp: self.timestamp,
+            kind: self.kind,
+            peer_addr: self.peer_addr,
+            device_id: self.device_id,
+            device_name: self.device_name.as_deref(),
+            detail: self.detail.as_deref(),
+            prev_hash: &self.prev_hash,
+        };
+        let bytes = serde_json::to_vec(&body)
+            .map_err(|e| Error::Serialization(format!("Failed to serialize audit entry: {e}")))?;
+        Ok(to_hex(&crypto::sha256(&bytes)))
+    }
+
+    /// Get the timestamp as a human-readable string.
+    #[must_use]
+    pub fn formatted_timestamp(&self) -> String {
+        use chrono::{DateTime, Utc};
+        let timestamp_i64 = i64::try_from(self.timestamp).unwrap_or(i64::MAX);
+        let dt = DateTime::<Utc>::from_timestamp(timestamp_i64, 0);
+        dt.map_or_else(
+            || "Unknown".to_string(),
+            |dt| dt.format("%Y-%m-%d %H:%M:%S").to_string(),
+        )
+    }
+
+    /// The peer as `name (address)`, whichever parts are known.
+    #[must_use]
+    pub fn peer_label(&self) -> String {
+        match (&self.device_name, self.peer_addr) {
+            (Some(name), Some(addr)) => format!("{name} ({addr})"),
+            (Some(name), None) => name.clone(),
+            (None, Some(addr)) => addr.to_string(),
+            (None, None) => "-".to_string(),
+        }
+    }
+}
+
+/// Result of checking the hash chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditVerification {
+    /// Number of entries in the log
+    pub entries: u64,
+    /// Hash of the last entry, if any
+    pub head: Option<String>,
+    /// The first entry that does not chain correctly
+    pub broken: Option<AuditBreak>,
+}
+
+impl AuditVerification {
+    /// Whether the whole chain is intact.
+    #[must_use]
+    pub fn is_intact(&self) -> bool {
+        self.broken.is_none()
+    }
+}
+
+/// Where and why the hash chain is broken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditBreak {
+    /// Line number (1-based) of the offending entry
+    pub line: u64,
+    /// What is wrong with it
+    pub reason: String,
+}
+
+/// Append-only audit log file.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    /// Open the log stored at `path`.
+    #[must_use]
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Open the log at the default location.
+    #[must_use]
+    pub fn open_default() -> Self {
+        Self::new(Self::default_path().unwrap_or_else(|| PathBuf::from("audit.log")))
+    }
+
+    /// Get the default audit log path.
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "yoop", "Yoop")
+            .map(|dirs| dirs.data_dir().join("audit.log"))
+    }
+
+    /// Path of the log file.
+    #[must_use]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Append an event, chaining it to the last entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log cannot be locked, read or written.
+    pub fn append(&self, event: AuditEvent) -> Result<AuditEntry> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                Error::ConfigError(format!(
+                    "Failed to create audit log directory {}: {}",
+                    parent.display(),
+                    e
+                ))
+            })?;
+        }
+
+        let _lock = LockFile::acquire(&self.path)?;
+
+        let (seq, prev_hash) = self.next_link()?;
+
+        let mut entry = AuditEntry {
+            seq,
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            kind: event.kind,
+            peer_addr: event.peer_addr,
+            device_id: event.device_id,
+            device_name: event.device_name,
+            detail: event.detail,
+            prev_hash,
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash()?;
+
+        let mut line = serde_json::to_string(&entry)
+            .map_err(|e| Error::Serialization(format!("Failed to serialize audit entry: {e}")))?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| {
+                Error::ConfigError(format!(
+                    "Failed to open audit log at {}: {}",
+                    self.path.display(),
+                    e
+                ))
+            })?;
+        crate::file::apply_permissions(&self.path, Some(0o600))?;
+        file.write_all(line.as_bytes())?;
+
+        Ok(entry)
+    }
+
+    /// All entries that can be parsed, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log exists but cannot be read.
+    pub fn entries(&self) -> Result<Vec<AuditEntry>> {
+        Ok(self
+            .read_lines()?
+            .iter()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// The `limit` most recent entries, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log exists but cannot be read.
+    pub fn recent(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let mut entries = self.entries()?;
+        entries.reverse();
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    /// Check every entry's hash and its link to the entry before it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log exists but cannot be read.
+    pub fn verify(&self) -> Result<AuditVerification> {
+        let lines = self.read_lines()?;
+        let mut prev_hash = GENESIS_HASH.to_string();
+        let mut broken = None;
+
+        for (index, line) in lines.iter().enumerate() {
+            let line_number = index as u64 + 1;
+            let reason = match serde_json::from_str::<AuditEntry>(line) {
+                Err(e) => Some(format!("unreadable entry: {e}")),
+                Ok(entry) if entry.seq != line_number => {
+                    Some(format!("sequence number {} out of place", entry.seq))
+                }
+                Ok(entry) if entry.prev_hash != prev_hash => {
+                    Some("does not follow the previous entry".to_string())
+                }
+                Ok(entry) if entry.compute_hash()? != entry.hash => {
+                    Some("contents do not match the hash".to_string())
+                }
+                Ok(_) => None,
+            };
+
+            if let Some(reason) = reason {
+                broken = Some(AuditBreak {
+                    line: line_number,
+                    reason,
+                });
+                break;
+            }
+            prev_hash = serde_json::from_str::<AuditEntry>(line)
+                .map(|e| e.hash)
+                .unwrap_or_default();
+        }
+
+        Ok(AuditVerification {
+            entries: lines.len() as u64,
+            head: lines
+                .last()
+                .and_then(|line| serde_json::from_str::<AuditEntry>(line).ok())
+                .map(|e| e.hash),
+            broken,
+        })
+    }
+
+    /// Sequence number and previous hash for the next entry.
+    ///
+    /// Only the end of the file is read while the last entry is intact.
+    fn next_link(&self) -> Result<(u64, String)> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok((1, GENESIS_HASH.to_string()))
+            }
+            Err(e) => {
+                return Err(Error::ConfigError(format!(
+                    "Failed to read audit log at {}: {}",
+                    self.path.display(),
+                    e
+                )))
+            }
+        };
+        let len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(len.saturating_sub(TAIL_BYTES)))?;
+        let mut tail = Vec::new();
+        file.read_to_end(&mut tail)?;
+
+        let tail = String::from_utf8_lossy(&tail);
+        let last = tail.lines().rev().find(|line| !line.trim().is_empty());
+        match last.map(serde_json::from_str::<AuditEntry>) {
+            None if len <= TAIL_BYTES => return Ok((1, GENESIS_HASH.to_string())),
+            Some(Ok(entry)) => return Ok((entry.seq + 1, entry.hash)),
+            _ => {}
+        }
+
+        // an unreadable last line is chained by its raw hash, so a
+        // corrupted entry cannot stop further events from being recorded
+        let lines = self.read_lines()?;
+        let prev_hash = lines.last().map_or_else(
+            || GENESIS_HASH.to_string(),
+            |line| {
+                serde_json::from_str::<AuditEntry>(line)
+                    .map_or_else(|_| to_hex(&crypto::sha256(line.as_bytes())), |e| e.hash)
+            },
+        );
+        Ok((lines.len() as u64 + 1, prev_hash))
+    }
+
+    fn read_lines(&self) -> Result<Vec<String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => Ok(content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(str::to_string)
+                .collect()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(Error::ConfigError(format!(
+                "Failed to read audit log at {}: {}",
+                self.path.display(),
+                e
+            ))),
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut acc, b| {
+        let _ = write!(acc, "{b:02x}");
+        acc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_log(dir: &TempDir) -> AuditLog {
+        let log = AuditLog::new(dir.path().join("audit.log"));
+        log.append(
+            AuditEvent::new(AuditEventKind::CodeVerificationFailed)
+                .with_peer("192.168.1.20:52530".parse().unwrap()),
+        )
+        .unwrap();
+        log.append(
+            AuditEvent::new(AuditEventKind::TrustAdded)
+                .with_device(Uuid::new_v4(), "Laptop")
+                .with_detail("trust level Full"),
+        )
+        .unwrap();
+        log.append(AuditEvent::new(AuditEventKind::SignatureInvalid))
+            .unwrap();
+        log
+    }
+
+    #[tokio::test]
+    async fn test_flush_writes_queued_events() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("audit.log");
+        enable(path.clone());
+        record(AuditEvent::new(AuditEventKind::SignatureInvalid).with_detail("flush test"));
+        flush();
+        disable();
+
+        let entries = AuditLog::new(path).entries().unwrap();
+        assert!(entries
+            .iter()
+            .any(|entry| entry.detail.as_deref() == Some("flush test")));
+    }
+
+    #[test]
+    fn test_append_and_verify() {
+        let tmp_dir = TempDir::new().unwrap();
+        let log = sample_log(&tmp_dir);
+
+        let entries = log.entries().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+        assert_eq!(entries[1].prev_hash, entries[0].hash);
+        assert_eq!(entries[2].seq, 3);
+
+        let recent = log.recent(2).unwrap();
+        assert_eq!(recent[0].kind, AuditEventKind::SignatureInvalid);
+        assert_eq!(recent[1].device_name.as_deref(), Some("Laptop"));
+
+        let report = log.verify().unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.entries, 3);
+        assert_eq!(report.head.as_deref(), Some(entries[2].hash.as_str()));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(log.path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() {
+        let tmp_dir = TempDir::new().unwrap();
+        let log = sample_log(&tmp_dir);
+
+        let content = fs::read_to_string(log.path()).unwrap();
+        fs::write(log.path(), content.replace("Laptop", "Phone")).unwrap();
+        let report = log.verify().unwrap();
+        assert_eq!(report.broken.unwrap().line, 2);
+
+        let mut lines: Vec<&str> = content.lines().collect();
+        lines.remove(0);
+        fs::write(log.path(), lines.join("\n")).unwrap();
+        let report = log.verify().unwrap();
+        assert_eq!(report.broken.unwrap().line, 1);
+    }
+
+    #[test]
+    fn test_append_after_entry_longer_than_tail() {
+        let tmp_dir = TempDir::new().unwrap();
+        let log = sample_log(&tmp_dir);
+
+        let long_detail = "x".repeat(usize::try_from(TAIL_BYTES).unwrap() * 2);
+        log.append(AuditEvent::new(AuditEventKind::TrustChanged).with_detail(long_detail))
+            .unwrap();
+        let entry = log
+            .append(AuditEvent::new(AuditEventKind::KeyMismatch))
+            .unwrap();
+        assert_eq!(entry.seq, 5);
+
+        let next = log
+            .append(AuditEvent::new(AuditEventKind::TrustRemoved))
+            .unwrap();
+        assert_eq!(next.seq, 6);
+        assert_eq!(next.prev_hash, entry.hash);
+        assert!(log.verify().unwrap().is_intact());
+    }
+
+    #[test]
+    fn test_append_after_corrupt_line() {
+        let tmp_dir = TempDir::new().unwrap();
+        let log = sample_log(&tmp_dir);
+
+        let mut content = fs::read_to_string(log.path()).unwrap();
+        content.push_str("garbage\n");
+        fs::write(log.path(), content).unwrap();
+
+        let entry = log
+            .append(AuditEvent::new(AuditEventKind::KeyMismatch))
+            .unwrap();
+        assert_eq!(entry.seq, 5);
+        assert_eq!(log.entries().unwrap().len(), 4);
+        assert_eq!(log.verify().unwrap().broken.unwrap().line, 4);
+    }
+}
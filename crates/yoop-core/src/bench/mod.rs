@@ -0,0 +1,859 @@
+//! Throughput and latency benchmarking.
+//!
+//! This module runs synthetic transfers to separate the costs that make up a
+//! real transfer, so slow transfers can be attributed to the network, TLS,
+//! compression or hashing:
+//!
+//! - **RTT**: `Ping`/`Pong` round trips over an established TLS connection
+//! - **Network**: `ChunkData` frames acknowledged with `ChunkAck`, exactly as
+//!   the transfer engine sends them, but without touching the disk
+//! - **Compression**: zstd compress/decompress of a synthetic payload
+//! - **Hashing**: SHA-256 (file integrity) and xxHash64 (chunk checksums)
+//!
+//! Benchmarks run either over loopback (both ends in this process) or against
+//! a trusted peer running a [`BenchServer`]. Peer connections use the same
+//! `TrustedHello` signature handshake as trusted transfers.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use yoop_core::bench::{self, BenchConfig};
+//!
+//! let report = bench::run_loopback(&BenchConfig::default()).await?;
+//! println!("TLS throughput: {:.1} MB/s", report.network.unwrap().mb_per_sec());
+//! ```
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+use crate::compression::CompressionAlgorithm;
+use crate::connection;
+use crate::crypto::{self, DeviceIdentity, TlsConfig};
+use crate::error::{Error, Result};
+use crate::protocol::{
+    self, ChunkAckPayload, ChunkDataPayload, MessageType, TrustedHelloAckPayload,
+    TrustedHelloPayload,
+};
+use crate::trust::{TrustStore, TrustedDevice};
+
+/// Default TCP port for [`BenchServer`].
+///
+/// Sits just above the transfer port range so a bench server can run
+/// alongside an active share or trusted receive.
+pub const DEFAULT_BENCH_PORT: u16 = crate::DEFAULT_TRANSFER_PORT_END + 1;
+
+/// Timeout for any single frame exchange during a benchmark.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Benchmark configuration.
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    /// Total bytes to push through the network test
+    pub payload_size: u64,
+    /// Size of each `ChunkData` frame
+    pub chunk_size: usize,
+    /// Number of `Ping`/`Pong` round trips to sample
+    pub rtt_samples: usize,
+    /// zstd level for the compression test
+    pub compression_level: u8,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            payload_size: 64 * 1024 * 1024,
+            chunk_size: crate::DEFAULT_CHUNK_SIZE,
+            rtt_samples: 20,
+            compression_level: 1,
+        }
+    }
+}
+
+/// Round-trip latency statistics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyStats {
+    /// Number of samples taken
+    pub samples: usize,
+    /// Fastest round trip in milliseconds
+    pub min_ms: f64,
+    /// Mean round trip in milliseconds
+    pub avg_ms: f64,
+    /// Slowest round trip in milliseconds
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    /// Build statistics from a list of round-trip durations.
+    #[must_use]
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        if samples.is_empty() {
+            return Self {
+                samples: 0,
+                min_ms: 0.0,
+                avg_ms: 0.0,
+                max_ms: 0.0,
+            };
+        }
+
+        let millis: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        let min_ms = millis.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_ms = millis.iter().copied().fold(0.0, f64::max);
+        let avg_ms = millis.iter().sum::<f64>() / millis.len() as f64;
+
+        Self {
+            samples: samples.len(),
+            min_ms,
+            avg_ms,
+            max_ms,
+        }
+    }
+}
+
+/// Result of a throughput measurement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Throughput {
+    /// Bytes processed
+    pub bytes: u64,
+    /// Wall-clock time in seconds
+    pub elapsed_secs: f64,
+    /// Bytes processed per second
+    pub bytes_per_sec: f64,
+}
+
+impl Throughput {
+    /// Create a throughput result from a byte count and elapsed time.
+    #[must_use]
+    pub fn new(bytes: u64, elapsed: Duration) -> Self {
+        let elapsed_secs = elapsed.as_secs_f64();
+        let bytes_per_sec = if elapsed_secs > 0.0 {
+            bytes as f64 / elapsed_secs
+        } else {
+            0.0
+        };
+
+        Self {
+            bytes,
+            elapsed_secs,
+            bytes_per_sec,
+        }
+    }
+
+    /// Throughput in megabytes (MiB) per second.
+    #[must_use]
+    pub fn mb_per_sec(&self) -> f64 {
+        self.bytes_per_sec / (1024.0 * 1024.0)
+    }
+}
+
+/// Compression benchmark result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionBench {
+    /// Algorithm measured
+    pub algorithm: CompressionAlgorithm,
+    /// Compression level used
+    pub level: u8,
+    /// Compressed size as a fraction of the original (lower is better)
+    pub ratio: f64,
+    /// Compression throughput (measured on uncompressed bytes)
+    pub compress: Throughput,
+    /// Decompression throughput (measured on uncompressed bytes)
+    pub decompress: Throughput,
+}
+
+/// Hashing benchmark result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashingBench {
+    /// SHA-256 throughput (whole-file integrity)
+    pub sha256: Throughput,
+    /// xxHash64 throughput (per-chunk checksums)
+    pub xxhash64: Throughput,
+}
+
+/// Full benchmark report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// What was benchmarked ("loopback" or the peer's name)
+    pub target: String,
+    /// Peer address (None for loopback)
+    pub peer_addr: Option<SocketAddr>,
+    /// Yoop version that produced this report
+    pub version: String,
+    /// When the benchmark ran
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Chunk size used for the network test
+    pub chunk_size: usize,
+    /// Round-trip latency
+    pub rtt: Option<LatencyStats>,
+    /// Raw TLS throughput using LDRP chunk framing
+    pub network: Option<Throughput>,
+    /// Compression throughput
+    pub compression: CompressionBench,
+    /// Hashing throughput
+    pub hashing: HashingBench,
+}
+
+impl BenchReport {
+    /// Name the slowest measured component ("network", "compression" or "hashing").
+    #[must_use]
+    pub fn bottleneck(&self) -> &'static str {
+        let hashing = self
+            .hashing
+            .sha256
+            .bytes_per_sec
+            .min(self.hashing.xxhash64.bytes_per_sec);
+
+        let mut slowest = ("hashing", hashing);
+
+        if let Some(ref network) = self.network {
+            if network.bytes_per_sec < slowest.1 {
+                slowest = ("network", network.bytes_per_sec);
+            }
+        }
+
+        if self.compression.compress.bytes_per_sec < slowest.1 {
+            slowest = ("compression", self.compression.compress.bytes_per_sec);
+        }
+
+        slowest.0
+    }
+}
+
+/// Generate a synthetic, moderately compressible payload.
+///
+/// The data mixes text-like runs with random bytes so that compression
+/// results are somewhere between best-case (all zeros) and worst-case
+/// (already-compressed media).
+#[must_use]
+pub fn synthetic_payload(size: usize) -> Vec<u8> {
+    use rand::Rng;
+
+    const WORDS: &[&[u8]] = &[
+        b"yoop ",
+        b"share ",
+        b"transfer ",
+        b"chunk ",
+        b"local ",
+        b"network ",
+        b"file ",
+        b"device ",
+        b"trusted ",
+        b"sync ",
+    ];
+
+    let mut rng = rand::thread_rng();
+    let mut data = Vec::with_capacity(size);
+
+    while data.len() < size {
+        if rng.gen_bool(0.75) {
+            data.extend_from_slice(WORDS[rng.gen_range(0..WORDS.len())]);
+        } else {
+            let run = rng.gen_range(8..64);
+            data.extend((0..run).map(|_| rng.gen::<u8>()));
+        }
+    }
+
+    data.truncate(size);
+    data
+}
+
+/// Measure zstd compression and decompression throughput.
+///
+/// # Errors
+///
+/// Returns an error if compression or decompression fails.
+pub fn bench_compression(data: &[u8], level: u8) -> Result<CompressionBench> {
+    let start = Instant::now();
+    let compressed = crate::compression::compress(data, i32::from(level))?;
+    let compress = Throughput::new(data.len() as u64, start.elapsed());
+
+    let start = Instant::now();
+    let decompressed = crate::compression::decompress(&compressed)?;
+    let decompress = Throughput::new(decompressed.len() as u64, start.elapsed());
+
+    let ratio = if data.is_empty() {
+        1.0
+    } else {
+        compressed.len() as f64 / data.len() as f64
+    };
+
+    Ok(CompressionBench {
+        algorithm: CompressionAlgorithm::Zstd,
+        level,
+        ratio,
+        compress,
+        decompress,
+    })
+}
+
+/// Measure SHA-256 and xxHash64 throughput.
+#[must_use]
+pub fn bench_hashing(data: &[u8]) -> HashingBench {
+    let start = Instant::now();
+    let _ = std::hint::black_box(crypto::sha256(data));
+    let sha256 = Throughput::new(data.len() as u64, start.elapsed());
+
+    let start = Instant::now();
+    let _ = std::hint::black_box(crypto::xxhash64(data));
+    let xxhash64 = Throughput::new(data.len() as u64, start.elapsed());
+
+    HashingBench { sha256, xxhash64 }
+}
+
+/// Run the client side of the network benchmark over an established stream.
+///
+/// Samples RTT with `Ping`/`Pong`, then sends `payload_size` bytes as
+/// uncompressed `ChunkData` frames, waiting for a `ChunkAck` after each one
+/// just like the transfer engine does.
+///
+/// # Errors
+///
+/// Returns an error if the peer misbehaves or the connection fails.
+pub async fn measure_stream<S>(
+    stream: &mut S,
+    config: &BenchConfig,
+) -> Result<(LatencyStats, Throughput)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut samples = Vec::with_capacity(config.rtt_samples);
+    for _ in 0..config.rtt_samples {
+        let start = Instant::now();
+        protocol::write_frame(stream, MessageType::Ping, &[]).await?;
+        let (header, _) = protocol::read_frame_with_timeout(stream, FRAME_TIMEOUT).await?;
+        if header.message_type != MessageType::Pong {
+            return Err(Error::UnexpectedMessage {
+                expected: "Pong".to_string(),
+                actual: format!("{:?}", header.message_type),
+            });
+        }
+        samples.push(start.elapsed());
+    }
+
+    let chunk_size = config.chunk_size.max(1);
+    let chunk = synthetic_payload(chunk_size);
+    let checksum = crypto::xxhash64(&chunk);
+
+    let mut sent = 0u64;
+    let mut chunk_index = 0u64;
+    let start = Instant::now();
+
+    while sent < config.payload_size {
+        #[allow(clippy::cast_possible_truncation)]
+        let len = (config.payload_size - sent).min(chunk_size as u64) as usize;
+        let data = ChunkDataPayload {
+            file_index: 0,
+            chunk_index,
+            data: chunk[..len].to_vec(),
+            checksum: if len == chunk_size {
+                checksum
+            } else {
+                crypto::xxhash64(&chunk[..len])
+            },
+            compression: CompressionAlgorithm::None,
+            original_size: None,
+        };
+        let payload = protocol::encode_chunk_data(&data);
+        protocol::write_frame(stream, MessageType::ChunkData, &payload).await?;
+
+        let (header, ack_payload) =
+            protocol::read_frame_with_timeout(stream, FRAME_TIMEOUT).await?;
+        if header.message_type != MessageType::ChunkAck {
+            return Err(Error::UnexpectedMessage {
+                expected: "ChunkAck".to_string(),
+                actual: format!("{:?}", header.message_type),
+            });
+        }
+        let ack: ChunkAckPayload = protocol::decode_payload(&ack_payload)?;
+        if !ack.success {
+            return Err(Error::ChecksumMismatch {
+                file: "bench".to_string(),
+                chunk: chunk_index,
+            });
+        }
+
+        sent += len as u64;
+        chunk_index += 1;
+    }
+
+    let throughput = Throughput::new(sent, start.elapsed());
+
+    protocol::write_frame(stream, MessageType::TransferComplete, &[]).await?;
+
+    Ok((LatencyStats::from_samples(&samples), throughput))
+}
+
+/// Run the server side of the network benchmark over an established stream.
+///
+/// Answers `Ping` with `Pong` and acknowledges each `ChunkData` frame after
+/// verifying its checksum, until the client sends `TransferComplete`.
+///
+/// Returns the number of payload bytes received.
+///
+/// # Errors
+///
+/// Returns an error if the client sends an unexpected message.
+pub async fn serve_stream<S>(stream: &mut S) -> Result<u64>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut received = 0u64;
+
+    loop {
+        let (header, payload) = protocol::read_frame_with_timeout(stream, FRAME_TIMEOUT).await?;
+
+        match header.message_type {
+            MessageType::Ping => {
+                protocol::write_frame(stream, MessageType::Pong, &[]).await?;
+            }
+            MessageType::ChunkData => {
+                let chunk = protocol::decode_chunk_data(&payload)?;
+                let success = crypto::xxhash64(&chunk.data) == chunk.checksum;
+                received += chunk.data.len() as u64;
+
+                let ack = ChunkAckPayload {
+                    file_index: chunk.file_index,
+                    chunk_index: chunk.chunk_index,
+                    success,
+                };
+                let ack_payload = protocol::encode_payload(&ack)?;
+                protocol::write_frame(stream, MessageType::ChunkAck, &ack_payload).await?;
+            }
+            MessageType::TransferComplete => break,
+            _ => {
+                return Err(Error::UnexpectedMessage {
+                    expected: "Ping, ChunkData, or TransferComplete".to_string(),
+                    actual: format!("{:?}", header.message_type),
+                });
+            }
+        }
+    }
+
+    Ok(received)
+}
+
+/// Run the local (CPU-bound) parts of the benchmark.
+fn bench_local(config: &BenchConfig) -> Result<(CompressionBench, HashingBench)> {
+    #[allow(clippy::cast_possible_truncation)]
+    let sample_size = config.payload_size.clamp(1024 * 1024, 16 * 1024 * 1024) as usize;
+    let data = synthetic_payload(sample_size);
+
+    let compression = bench_compression(&data, config.compression_level)?;
+    let hashing = bench_hashing(&data);
+
+    Ok((compression, hashing))
+}
+
+/// Run a full benchmark over loopback.
+///
+/// Both ends of the TLS connection run in this process, so the network
+/// figure reflects the TLS and framing cost of this machine alone.
+///
+/// # Errors
+///
+/// Returns an error if the loopback connection or any measurement fails.
+pub async fn run_loopback(config: &BenchConfig) -> Result<BenchReport> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let server_tls = TlsConfig::server()?;
+    let acceptor = TlsAcceptor::from(Arc::new(
+        server_tls
+            .server_config()
+            .ok_or_else(|| Error::TlsError("no server config".to_string()))?
+            .clone(),
+    ));
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await?;
+        let mut tls_stream = acceptor
+            .accept(stream)
+            .await
+            .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
+        serve_stream(&mut tls_stream).await
+    });
+
+    let mut tls_stream = connect_tls(addr).await?;
+    let (rtt, network) = measure_stream(&mut tls_stream, config).await?;
+    let _ = tls_stream.shutdown().await;
+
+    server
+        .await
+        .map_err(|e| Error::Internal(format!("bench server task failed: {e}")))??;
+
+    let (compression, hashing) = bench_local(config)?;
+
+    Ok(BenchReport {
+        target: "loopback".to_string(),
+        peer_addr: None,
+        version: crate::VERSION.to_string(),
+        timestamp: chrono::Utc::now(),
+        chunk_size: config.chunk_size,
+        rtt: Some(rtt),
+        network: Some(network),
+        compression,
+        hashing,
+    })
+}
+
+/// Run a full benchmark against a trusted peer running a [`BenchServer`].
+///
+/// # Errors
+///
+/// Returns an error if the peer is unreachable, fails trust verification,
+/// or any measurement fails.
+pub async fn run_peer(
+    device: &TrustedDevice,
+    addr: SocketAddr,
+    identity: &DeviceIdentity,
+    config: &BenchConfig,
+) -> Result<BenchReport> {
+    let mut tls_stream = connect_tls(addr).await?;
+
+    client_handshake(&mut tls_stream, device, identity).await?;
+
+    let (rtt, network) = measure_stream(&mut tls_stream, config).await?;
+    let _ = tls_stream.shutdown().await;
+
+    let (compression, hashing) = bench_local(config)?;
+
+    Ok(BenchReport {
+        target: device.device_name.clone(),
+        peer_addr: Some(addr),
+        version: crate::VERSION.to_string(),
+        timestamp: chrono::Utc::now(),
+        chunk_size: config.chunk_size,
+        rtt: Some(rtt),
+        network: Some(network),
+        compression,
+        hashing,
+    })
+}
+
+/// Server that answers benchmark requests from trusted devices.
+#[derive(Debug)]
+pub struct BenchServer {
+    /// TCP listener
+    listener: TcpListener,
+    /// TLS config
+    tls_config: TlsConfig,
+    /// Our device identity
+    identity: DeviceIdentity,
+    /// Trust store for verifying clients
+    trust_store: TrustStore,
+}
+
+impl BenchServer {
+    /// Create a benchmark server listening on the given port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the port cannot be bound.
+    pub async fn new(port: u16, identity: DeviceIdentity, trust_store: TrustStore) -> Result<Self> {
+        let listener = connection::bind_listener(port, true)?;
+        let tls_config = TlsConfig::server()?;
+
+        Ok(Self {
+            listener,
+            tls_config,
+            identity,
+            trust_store,
+        })
+    }
+
+    /// Get the local address the server is bound to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be determined.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accept and serve a single benchmark client.
+    ///
+    /// Returns the client's device name, address and bytes received.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client is not trusted or the benchmark fails.
+    pub async fn serve_one(&self) -> Result<(String, SocketAddr, u64)> {
+        let (stream, peer_addr) = self.listener.accept().await?;
+        let peer_addr = connection::canonical_addr(peer_addr);
+        tracing::info!("Benchmark connection from {}", peer_addr);
+
+        let acceptor = TlsAcceptor::from(Arc::new(
+            self.tls_config
+                .server_config()
+                .ok_or_else(|| Error::TlsError("no server config".to_string()))?
+                .clone(),
+        ));
+        let mut tls_stream = acceptor
+            .accept(stream)
+            .await
+            .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
+
+        let device_name =
+            server_handshake(&mut tls_stream, &self.identity, &self.trust_store).await?;
+
+        let received = serve_stream(&mut tls_stream).await?;
+        let _ = tls_stream.shutdown().await;
+
+        Ok((device_name, peer_addr, received))
+    }
+}
+
+async fn connect_tls(addr: SocketAddr) -> Result<tokio_rustls::client::TlsStream<TcpStream>> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+
+    let tls_config = TlsConfig::client()?;
+    let connector = TlsConnector::from(Arc::new(
+        tls_config
+            .client_config()
+            .ok_or_else(|| Error::TlsError("no client config".to_string()))?
+            .clone(),
+    ));
+
+    connector
+        .connect("localhost".try_into().unwrap(), stream)
+        .await
+        .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))
+}
+
+/// Authenticate to a bench server with a signed `TrustedHello`.
+async fn client_handshake<S>(
+    stream: &mut S,
+    device: &TrustedDevice,
+    identity: &DeviceIdentity,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let nonce: [u8; 32] = crypto::random_bytes();
+    let device_name = hostname::get().map_or_else(
+        |_| "Unknown".to_string(),
+        |h| h.to_string_lossy().to_string(),
+    );
+
+    let hello = TrustedHelloPayload {
+        device_name,
+        protocol_version: "1.0".to_string(),
+        device_id: identity.device_id(),
+        public_key: identity.public_key_base64(),
+        nonce: BASE64_STANDARD.encode(nonce),
+        nonce_signature: BASE64_STANDARD.encode(identity.sign(&nonce)),
+        key_rotations: identity.rotations().to_vec(),
+    };
+    let payload = protocol::encode_payload(&hello)?;
+    protocol::write_frame(stream, MessageType::TrustedHello, &payload).await?;
+
+    let (header, ack_payload) = protocol::read_frame_with_timeout(stream, FRAME_TIMEOUT).await?;
+    if header.message_type != MessageType::TrustedHelloAck {
+        return Err(Error::UnexpectedMessage {
+            expected: "TrustedHelloAck".to_string(),
+            actual: format!("{:?}", header.message_type),
+        });
+    }
+
+    let ack: TrustedHelloAckPayload = protocol::decode_payload(&ack_payload)?;
+    if !ack.trusted {
+        return Err(Error::DeviceNotTrusted(
+            ack.error.unwrap_or_else(|| "not trusted".to_string()),
+        ));
+    }
+
+    let signature = ack
+        .nonce_signature
+        .as_deref()
+        .ok_or(Error::SignatureInvalid)
+        .and_then(decode_signature)?;
+
+    let public_key = ack.public_key.as_deref().ok_or(Error::SignatureInvalid)?;
+    if !DeviceIdentity::verify_base64(public_key, &nonce, &signature) {
+        return Err(Error::SignatureInvalid);
+    }
+    if !crate::trust::accept_rotated_key(device, public_key, &ack.key_rotations, None)? {
+        return Err(Error::DeviceNotTrusted(format!(
+            "public key mismatch for {}",
+            device.device_name
+        )));
+    }
+
+    Ok(())
+}
+
+/// Verify a bench client's `TrustedHello` against the trust store.
+///
+/// Returns the client's device name.
+async fn server_handshake<S>(
+    stream: &mut S,
+    identity: &DeviceIdentity,
+    trust_store: &TrustStore,
+) -> Result<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (header, payload) = protocol::read_frame_with_timeout(stream, FRAME_TIMEOUT).await?;
+    if header.message_type != MessageType::TrustedHello {
+        return Err(Error::UnexpectedMessage {
+            expected: "TrustedHello".to_string(),
+            actual: format!("{:?}", header.message_type),
+        });
+    }
+
+    let hello: TrustedHelloPayload = protocol::decode_payload(&payload)?;
+
+    let nonce = BASE64_STANDARD
+        .decode(&hello.nonce)
+        .map_err(|e| Error::ProtocolError(format!("invalid nonce: {e}")))?;
+    let signature = decode_signature(&hello.nonce_signature)?;
+
+    let trusted = DeviceIdentity::verify_base64(&hello.public_key, &nonce, &signature)
+        && match trust_store.find_by_id(&hello.device_id) {
+            Some(device) => crate::trust::accept_rotated_key(
+                device,
+                &hello.public_key,
+                &hello.key_rotations,
+                Some(trust_store.path().clone()),
+            )?,
+            None => false,
+        };
+
+    if !trusted {
+        let ack = TrustedHelloAckPayload {
+            trusted: false,
+            device_name: None,
+            device_id: None,
+            public_key: None,
+            nonce_signature: None,
+            error: Some("device not trusted".to_string()),
+            trust_level: None,
+            key_rotations: Vec::new(),
+        };
+        let ack_payload = protocol::encode_payload(&ack)?;
+        protocol::write_frame(stream, MessageType::TrustedHelloAck, &ack_payload).await?;
+        return Err(Error::DeviceNotTrusted(hello.device_name));
+    }
+
+    let ack = TrustedHelloAckPayload {
+        trusted: true,
+        device_name: Some(hostname::get().map_or_else(
+            |_| "Unknown".to_string(),
+            |h| h.to_string_lossy().to_string(),
+        )),
+        device_id: Some(identity.device_id()),
+        public_key: Some(identity.public_key_base64()),
+        nonce_signature: Some(BASE64_STANDARD.encode(identity.sign(&nonce))),
+        error: None,
+        trust_level: None,
+        key_rotations: identity.rotations().to_vec(),
+    };
+    let ack_payload = protocol::encode_payload(&ack)?;
+    protocol::write_frame(stream, MessageType::TrustedHelloAck, &ack_payload).await?;
+
+    Ok(hello.device_name)
+}
+
+fn decode_signature(signature: &str) -> Result<[u8; 64]> {
+    BASE64_STANDARD
+        .decode(signature)
+        .map_err(|e| Error::ProtocolError(format!("invalid signature: {e}")))?
+        .try_into()
+        .map_err(|_| Error::ProtocolError("invalid signature length".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_stats() {
+        let samples = [
+            Duration::from_millis(1),
+            Duration::from_millis(2),
+            Duration::from_millis(3),
+        ];
+        let stats = LatencyStats::from_samples(&samples);
+
+        assert_eq!(stats.samples, 3);
+        assert!((stats.min_ms - 1.0).abs() < 0.001);
+        assert!((stats.avg_ms - 2.0).abs() < 0.001);
+        assert!((stats.max_ms - 3.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_latency_stats_empty() {
+        let stats = LatencyStats::from_samples(&[]);
+        assert_eq!(stats.samples, 0);
+        assert!(stats.avg_ms.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_throughput() {
+        let throughput = Throughput::new(2 * 1024 * 1024, Duration::from_secs(2));
+        assert!((throughput.mb_per_sec() - 1.0).abs() < 0.001);
+
+        let zero = Throughput::new(100, Duration::ZERO);
+        assert!(zero.bytes_per_sec.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_synthetic_payload_is_compressible() {
+        let data = synthetic_payload(256 * 1024);
+        assert_eq!(data.len(), 256 * 1024);
+
+        let result = bench_compression(&data, 1).unwrap();
+        assert!(result.ratio < 0.9, "ratio was {}", result.ratio);
+        assert!(result.ratio > 0.1, "ratio was {}", result.ratio);
+    }
+
+    #[test]
+    fn test_bench_hashing() {
+        let data = synthetic_payload(64 * 1024);
+        let result = bench_hashing(&data);
+        assert_eq!(result.sha256.bytes, 64 * 1024);
+        assert_eq!(result.xxhash64.bytes, 64 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_run_loopback() {
+        let config = BenchConfig {
+            payload_size: 2 * 1024 * 1024 + 17,
+            chunk_size: 256 * 1024,
+            rtt_samples: 3,
+            compression_level: 1,
+        };
+
+        let report = run_loopback(&config).await.unwrap();
+
+        assert_eq!(report.target, "loopback");
+        assert_eq!(report.rtt.as_ref().unwrap().samples, 3);
+        assert_eq!(report.network.as_ref().unwrap().bytes, config.payload_size);
+        assert!(!report.bottleneck().is_empty());
+
+        let json = serde_json::to_string(&report).unwrap();
+        let parsed: BenchReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.chunk_size, config.chunk_size);
+    }
+
+    #[tokio::test]
+    async fn test_serve_stream_rejects_unexpected_message() {
+        let (mut client, mut server) = tokio::io::duplex(64 * 1024);
+
+        let server_task = tokio::spawn(async move { serve_stream(&mut server).await });
+
+        protocol::write_frame(&mut client, MessageType::FileList, b"{}")
+            .await
+            .unwrap();
+
+        let result = server_task.await.unwrap();
+        assert!(matches!(result, Err(Error::UnexpectedMessage { .. })));
+    }
+}
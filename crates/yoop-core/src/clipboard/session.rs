@@ -19,17 +19,19 @@ use tokio_rustls::{TlsAcceptor, TlsConnector};
 
 use base64::prelude::*;
 
-use crate::code::{CodeGenerator, ShareCode};
-use crate::crypto::{self, DeviceIdentity, TlsConfig};
+use crate::audit::{self, AuditEvent, AuditEventKind};
+use crate::code::{self, CodeGenerator, ShareCode};
+use crate::connection;
+use crate::crypto::{ChannelBinding, DeviceIdentity, TlsConfig};
 use crate::discovery::{DiscoveryPacket, HybridBroadcaster, HybridListener};
 use crate::error::{Error, Result};
+use crate::hooks::{self, HookEvent, HookEventKind};
 use crate::protocol::{
     self, ClipboardAckPayload, ClipboardChangedPayload, ClipboardContentType, ClipboardMetaPayload,
-    CodeVerifyAckPayload, CodeVerifyPayload, HelloPayload, MessageType, TrustedHelloAckPayload,
-    TrustedHelloPayload,
+    HelloPayload, MessageType, TrustedHelloAckPayload, TrustedHelloPayload,
 };
 use crate::transfer::TransferConfig;
-use crate::trust::TrustedDevice;
+use crate::trust::{Capability, DevicePermissions, TrustedDevice};
 
 use super::watcher::ClipboardWatcher;
 use super::{create_clipboard, ClipboardContent, ClipboardMetadata};
@@ -38,6 +40,20 @@ use super::{create_clipboard, ClipboardContent, ClipboardMetadata};
 type ClientTlsStream = tokio_rustls::client::TlsStream<TcpStream>;
 type ServerTlsStream = tokio_rustls::server::TlsStream<TcpStream>;
 
+/// Run the `clipboard-received` hooks for content received from `peer`.
+fn emit_received(peer: &str, content_type: ClipboardContentType, size: u64) {
+    let mime = match content_type {
+        ClipboardContentType::PlainText => "text/plain",
+        ClipboardContentType::ImagePng => "image/png",
+    };
+    hooks::emit(
+        HookEvent::new(HookEventKind::ClipboardReceived)
+            .with_peer(peer)
+            .with_bytes(size)
+            .with_detail(mime),
+    );
+}
+
 /// One-shot clipboard share session (sender side).
 pub struct ClipboardShareSession {
     /// Share code
@@ -50,8 +66,6 @@ pub struct ClipboardShareSession {
     _config: TransferConfig,
     /// Device name
     device_name: String,
-    /// Session key for HMAC verification
-    session_key: [u8; 32],
     /// TCP listener for incoming connections
     listener: TcpListener,
     /// TLS configuration
@@ -88,14 +102,18 @@ impl ClipboardShareSession {
 
         let metadata = ClipboardMetadata::from_content(&content, &device_name);
 
-        let session_key = crypto::derive_session_key(code.as_str());
-
         let tls_config = TlsConfig::server()?;
 
-        let listener = TcpListener::bind(format!("0.0.0.0:{}", config.transfer_port)).await?;
+        let listener = connection::bind_listener_in_range(
+            config.transfer_port,
+            config.transfer_port_end,
+            config.ipv6,
+        )?;
         let local_addr = listener.local_addr()?;
 
-        let broadcaster = HybridBroadcaster::new(config.discovery_port).await?;
+        let broadcaster =
+            HybridBroadcaster::with_options(config.discovery_port, &config.discovery_options())
+                .await?;
 
         let device_id = uuid::Uuid::new_v4();
         let packet = DiscoveryPacket::new(
@@ -115,7 +133,6 @@ impl ClipboardShareSession {
             metadata,
             _config: config,
             device_name,
-            session_key,
             listener,
             tls_config,
             broadcaster,
@@ -140,6 +157,17 @@ impl ClipboardShareSession {
         &self.content
     }
 
+    /// Get the local address the transfer listener is bound to.
+    ///
+    /// The port is the first free one in the configured transfer port range.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be determined.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
     /// Get content preview.
     #[must_use]
     pub fn preview(&self) -> String {
@@ -153,6 +181,7 @@ impl ClipboardShareSession {
     /// Returns an error if the transfer fails.
     pub async fn wait(self) -> Result<()> {
         let (stream, peer_addr) = self.listener.accept().await?;
+        let peer_addr = connection::canonical_addr(peer_addr);
         tracing::info!("Connection from {}", peer_addr);
 
         let acceptor = TlsAcceptor::from(Arc::new(
@@ -168,7 +197,8 @@ impl ClipboardShareSession {
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
         let _receiver_name = self.do_handshake(&mut tls_stream).await?;
-        self.do_code_verification(&mut tls_stream).await?;
+        self.do_code_verification(&mut tls_stream, peer_addr)
+            .await?;
         self.do_clipboard_transfer(&mut tls_stream).await?;
 
         self.broadcaster.stop().await;
@@ -207,34 +237,17 @@ impl ClipboardShareSession {
         Ok(ack.device_name)
     }
 
-    async fn do_code_verification<S>(&self, stream: &mut S) -> Result<()>
+    async fn do_code_verification<S>(&self, stream: &mut S, peer_addr: SocketAddr) -> Result<()>
     where
-        S: AsyncRead + AsyncWrite + Unpin,
+        S: AsyncRead + AsyncWrite + ChannelBinding + Unpin,
     {
-        let (header, payload) = protocol::read_frame(stream).await?;
-        if header.message_type != MessageType::CodeVerify {
-            return Err(Error::UnexpectedMessage {
-                expected: "CodeVerify".to_string(),
-                actual: format!("{:?}", header.message_type),
-            });
-        }
-
-        let verify: CodeVerifyPayload = protocol::decode_payload(&payload)?;
-        let expected_hmac = crypto::hmac_sha256(&self.session_key, self.code.as_str().as_bytes());
-        let success = crypto::constant_time_eq(&verify.code_hmac, &expected_hmac);
-
-        let ack = CodeVerifyAckPayload {
-            success,
-            error: if success {
-                None
-            } else {
-                Some("Invalid code".to_string())
-            },
-        };
-        let ack_payload = protocol::encode_payload(&ack)?;
-        protocol::write_frame(stream, MessageType::CodeVerifyAck, &ack_payload).await?;
-
-        if !success {
+        let channel_binding = stream.channel_binding()?;
+        if !code::check_code(stream, &self.code, &channel_binding).await? {
+            audit::record(
+                AuditEvent::new(AuditEventKind::CodeVerificationFailed)
+                    .with_peer(peer_addr)
+                    .with_detail(format!("clipboard share {}", self.code)),
+            );
             return Err(Error::CodeNotFound(self.code.to_string()));
         }
 
@@ -367,7 +380,9 @@ impl ClipboardReceiveSession {
             tracing::info!("Connecting directly to {}", addr);
             addr
         } else {
-            let listener = HybridListener::new(config.discovery_port).await?;
+            let listener =
+                HybridListener::with_options(config.discovery_port, &config.discovery_options())
+                    .await?;
             let discovered = listener.find(&code, config.discovery_timeout).await?;
 
             if let Err(e) = listener.shutdown() {
@@ -380,7 +395,7 @@ impl ClipboardReceiveSession {
                 discovered.source
             );
 
-            SocketAddr::new(discovered.source.ip(), discovered.packet.transfer_port)
+            discovered.transfer_addr()
         };
 
         let stream = TcpStream::connect(transfer_addr).await?;
@@ -398,10 +413,8 @@ impl ClipboardReceiveSession {
             .await
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
-        let session_key = crypto::derive_session_key(code.as_str());
-
         let sender_name = Self::do_handshake(&mut tls_stream).await?;
-        Self::do_code_verification(&mut tls_stream, &code, &session_key).await?;
+        Self::do_code_verification(&mut tls_stream, &code).await?;
         let metadata = Self::receive_metadata(&mut tls_stream, &sender_name).await?;
 
         Ok(Self {
@@ -434,7 +447,9 @@ impl ClipboardReceiveSession {
             tracing::info!("Connecting directly to {}", addr);
             addr
         } else {
-            let listener = HybridListener::new(config.discovery_port).await?;
+            let listener =
+                HybridListener::with_options(config.discovery_port, &config.discovery_options())
+                    .await?;
             let discovered = listener
                 .find_with_fallback(&code, config.discovery_timeout, fallback_addresses)
                 .await?;
@@ -449,7 +464,7 @@ impl ClipboardReceiveSession {
                 discovered.source
             );
 
-            SocketAddr::new(discovered.source.ip(), discovered.packet.transfer_port)
+            discovered.transfer_addr()
         };
 
         let stream = TcpStream::connect(transfer_addr).await?;
@@ -467,10 +482,8 @@ impl ClipboardReceiveSession {
             .await
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
-        let session_key = crypto::derive_session_key(code.as_str());
-
         let sender_name = Self::do_handshake(&mut tls_stream).await?;
-        Self::do_code_verification(&mut tls_stream, &code, &session_key).await?;
+        Self::do_code_verification(&mut tls_stream, &code).await?;
         let metadata = Self::receive_metadata(&mut tls_stream, &sender_name).await?;
 
         Ok(Self {
@@ -489,8 +502,13 @@ impl ClipboardReceiveSession {
     ///
     /// # Errors
     ///
-    /// Returns an error if connection fails or trust verification fails.
+    /// Returns an error if connection fails, trust verification fails, or
+    /// the device's permissions do not cover reading clipboards.
     pub async fn connect_trusted(device: &TrustedDevice, _config: TransferConfig) -> Result<Self> {
+        device
+            .permissions
+            .check(&device.device_name, Capability::ReadClipboard)?;
+
         let (ip, port) = device.address().ok_or_else(|| {
             Error::ConfigError(format!(
                 "Device '{}' has no stored address. Connect with --host first.",
@@ -520,8 +538,13 @@ impl ClipboardReceiveSession {
             .await
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
-        let sender_name = Self::do_trusted_handshake(&mut tls_stream, device).await?;
+        let sender_name = Self::do_trusted_handshake(&mut tls_stream, device)
+            .await
+            .inspect_err(|e| audit::handshake_failed(e, transfer_addr, device))?;
         let metadata = Self::receive_metadata(&mut tls_stream, &sender_name).await?;
+        device
+            .permissions
+            .check_size(&device.device_name, metadata.size)?;
 
         Ok(Self {
             sender_addr: transfer_addr,
@@ -698,6 +721,8 @@ impl ClipboardReceiveSession {
             });
         }
 
+        emit_received(&self.sender_name, content.content_type(), content.size());
+
         Ok(content)
     }
 
@@ -855,35 +880,12 @@ impl ClipboardReceiveSession {
         Ok(hello.device_name)
     }
 
-    async fn do_code_verification<S>(
-        stream: &mut S,
-        code: &ShareCode,
-        session_key: &[u8; 32],
-    ) -> Result<()>
+    async fn do_code_verification<S>(stream: &mut S, code: &ShareCode) -> Result<()>
     where
-        S: AsyncRead + AsyncWrite + Unpin,
+        S: AsyncRead + AsyncWrite + ChannelBinding + Unpin,
     {
-        let hmac = crypto::hmac_sha256(session_key, code.as_str().as_bytes());
-        let verify = CodeVerifyPayload {
-            code_hmac: hmac.to_vec(),
-        };
-        let payload = protocol::encode_payload(&verify)?;
-        protocol::write_frame(stream, MessageType::CodeVerify, &payload).await?;
-
-        let (header, ack_payload) = protocol::read_frame(stream).await?;
-        if header.message_type != MessageType::CodeVerifyAck {
-            return Err(Error::UnexpectedMessage {
-                expected: "CodeVerifyAck".to_string(),
-                actual: format!("{:?}", header.message_type),
-            });
-        }
-
-        let ack: CodeVerifyAckPayload = protocol::decode_payload(&ack_payload)?;
-        if !ack.success {
-            return Err(Error::CodeNotFound(code.to_string()));
-        }
-
-        Ok(())
+        let channel_binding = stream.channel_binding()?;
+        code::prove_code(stream, code, &channel_binding).await
     }
 
     async fn do_trusted_handshake<S>(
@@ -906,12 +908,6 @@ impl ClipboardReceiveSession {
                     )));
                 }
 
-                if hello.public_key != expected_device.public_key {
-                    return Err(Error::TrustError(
-                        "Public key mismatch - device may have been reinstalled".to_string(),
-                    ));
-                }
-
                 let nonce_bytes = BASE64_STANDARD
                     .decode(&hello.nonce)
                     .map_err(|e| Error::ProtocolError(format!("Invalid nonce: {e}")))?;
@@ -928,6 +924,17 @@ impl ClipboardReceiveSession {
                     return Err(Error::TrustError("Invalid signature".to_string()));
                 }
 
+                if !crate::trust::accept_rotated_key(
+                    expected_device,
+                    &hello.public_key,
+                    &hello.key_rotations,
+                    None,
+                )? {
+                    return Err(Error::TrustError(
+                        "Public key mismatch - device may have been reinstalled".to_string(),
+                    ));
+                }
+
                 let identity = DeviceIdentity::load_or_generate()?;
                 let device_name = hostname::get().map_or_else(
                     |_| "Unknown".to_string(),
@@ -944,6 +951,7 @@ impl ClipboardReceiveSession {
                     nonce_signature: Some(BASE64_STANDARD.encode(response_signature)),
                     error: None,
                     trust_level: Some("Full".to_string()),
+                    key_rotations: identity.rotations().to_vec(),
                 };
 
                 let ack_payload = protocol::encode_payload(&ack)?;
@@ -1043,7 +1051,6 @@ pub struct SyncStats {
 pub struct SyncHostSession {
     code: ShareCode,
     device_name: String,
-    session_key: [u8; 32],
     tls_config: TlsConfig,
     listener: TcpListener,
     broadcaster: HybridBroadcaster,
@@ -1056,6 +1063,17 @@ impl SyncHostSession {
         &self.code
     }
 
+    /// Get the local address the transfer listener is bound to.
+    ///
+    /// The port is the first free one in the configured transfer port range.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be determined.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
     /// Wait for a peer to connect and complete the handshake.
     ///
     /// This method blocks until a peer connects, performs TLS handshake,
@@ -1088,6 +1106,7 @@ impl SyncHostSession {
         use rand::RngCore;
 
         let (stream, peer_addr) = self.listener.accept().await?;
+        let peer_addr = connection::canonical_addr(peer_addr);
         self.broadcaster.stop().await;
 
         let acceptor = TlsAcceptor::from(Arc::new(
@@ -1115,13 +1134,14 @@ impl SyncHostSession {
             public_key: identity.public_key_base64(),
             nonce: BASE64_STANDARD.encode(nonce),
             nonce_signature: BASE64_STANDARD.encode(nonce_signature),
+            key_rotations: identity.rotations().to_vec(),
         };
         let payload = protocol::encode_payload(&hello)?;
         protocol::write_frame(&mut tls_stream, MessageType::TrustedHello, &payload).await?;
 
         let (header, payload) = protocol::read_frame(&mut tls_stream).await?;
 
-        let (peer_name, is_trusted) = match header.message_type {
+        let (peer_name, trusted_permissions) = match header.message_type {
             MessageType::TrustedHelloAck => {
                 let ack: TrustedHelloAckPayload = protocol::decode_payload(&payload)?;
 
@@ -1139,9 +1159,6 @@ impl SyncHostSession {
                     Error::TrustError("Missing public_key in TrustedHelloAck".to_string())
                 })?;
 
-                let is_trusted_peer = trust_store
-                    .is_some_and(|store| store.verify_key(&peer_device_id, peer_public_key));
-
                 if let Some(sig_b64) = &ack.nonce_signature {
                     let sig_bytes = BASE64_STANDARD
                         .decode(sig_b64)
@@ -1151,27 +1168,63 @@ impl SyncHostSession {
                     })?;
 
                     if !DeviceIdentity::verify_base64(peer_public_key, &nonce, &sig_array) {
+                        audit::record(
+                            AuditEvent::new(AuditEventKind::SignatureInvalid)
+                                .with_peer(peer_addr)
+                                .with_device(
+                                    peer_device_id,
+                                    ack.device_name.clone().unwrap_or_default(),
+                                ),
+                        );
                         return Err(Error::TrustError("Invalid peer signature".to_string()));
                     }
                 }
 
+                let trusted_permissions = match trust_store
+                    .and_then(|store| store.find_by_id(&peer_device_id).map(|d| (store, d)))
+                {
+                    Some((store, device)) if ack.nonce_signature.is_some() => {
+                        let accepted = crate::trust::accept_rotated_key(
+                            device,
+                            peer_public_key,
+                            &ack.key_rotations,
+                            Some(store.path().clone()),
+                        )?;
+                        if !accepted {
+                            audit::record(
+                                AuditEvent::new(AuditEventKind::KeyMismatch)
+                                    .with_peer(peer_addr)
+                                    .with_device(device.device_id, device.device_name.clone()),
+                            );
+                        }
+                        accepted.then(|| device.permissions.clone())
+                    }
+                    Some((store, device)) => store
+                        .verify_key(&peer_device_id, peer_public_key)
+                        .then(|| device.permissions.clone()),
+                    None => None,
+                };
+
                 (
                     ack.device_name.unwrap_or_else(|| "Unknown".to_string()),
-                    is_trusted_peer,
+                    trusted_permissions,
                 )
             }
             MessageType::HelloAck => {
                 let ack: HelloPayload = protocol::decode_payload(&payload)?;
 
-                let is_trusted_peer = if let (Some(device_id), Some(public_key)) =
+                let trusted_permissions = if let (Some(device_id), Some(public_key)) =
                     (&ack.device_id, &ack.public_key)
                 {
-                    trust_store.is_some_and(|store| store.verify_key(device_id, public_key))
+                    trust_store
+                        .filter(|store| store.verify_key(device_id, public_key))
+                        .and_then(|store| store.find_by_id(device_id))
+                        .map(|device| device.permissions.clone())
                 } else {
-                    false
+                    None
                 };
 
-                (ack.device_name, is_trusted_peer)
+                (ack.device_name, trusted_permissions)
             }
             _ => {
                 return Err(Error::UnexpectedMessage {
@@ -1181,38 +1234,24 @@ impl SyncHostSession {
             }
         };
 
-        if is_trusted {
+        // whoever holds the code was let in by the user, so only trusted
+        // connections are limited by stored permissions
+        let permissions = if let Some(permissions) = trusted_permissions {
             tracing::info!("Trusted connection established with {}", peer_name);
+            permissions
         } else {
-            let (header, payload) = protocol::read_frame(&mut tls_stream).await?;
-            if header.message_type != MessageType::CodeVerify {
-                return Err(Error::UnexpectedMessage {
-                    expected: "CodeVerify".to_string(),
-                    actual: format!("{:?}", header.message_type),
-                });
-            }
-
-            let verify: CodeVerifyPayload = protocol::decode_payload(&payload)?;
-            let expected_hmac =
-                crypto::hmac_sha256(&self.session_key, self.code.as_str().as_bytes());
-            let success = crypto::constant_time_eq(&verify.code_hmac, &expected_hmac);
-
-            let ack = CodeVerifyAckPayload {
-                success,
-                error: if success {
-                    None
-                } else {
-                    Some("Invalid code".to_string())
-                },
-            };
-            let ack_payload = protocol::encode_payload(&ack)?;
-            protocol::write_frame(&mut tls_stream, MessageType::CodeVerifyAck, &ack_payload)
-                .await?;
-
-            if !success {
+            let channel_binding = tls_stream.channel_binding()?;
+            if !code::check_code(&mut tls_stream, &self.code, &channel_binding).await? {
+                audit::record(
+                    AuditEvent::new(AuditEventKind::CodeVerificationFailed)
+                        .with_peer(peer_addr)
+                        .with_detail(format!("clipboard sync {} from {peer_name}", self.code)),
+                );
                 return Err(Error::CodeNotFound(self.code.to_string()));
             }
-        }
+
+            DevicePermissions::default()
+        };
 
         let (shutdown_tx, _) = broadcast::channel(1);
 
@@ -1232,6 +1271,8 @@ impl SyncHostSession {
             last_local_hash: Arc::clone(&session.last_local_hash),
             last_remote_hash: Arc::clone(&session.last_remote_hash),
             shutdown_rx: shutdown_tx.subscribe(),
+            peer_name: session.peer_name.clone(),
+            permissions,
         };
 
         Ok((session, runner))
@@ -1275,14 +1316,18 @@ impl ClipboardSyncSession {
             |h| h.to_string_lossy().to_string(),
         );
 
-        let session_key = crypto::derive_session_key(code.as_str());
-
         let tls_config = TlsConfig::server()?;
 
-        let listener = TcpListener::bind(format!("0.0.0.0:{}", config.transfer_port)).await?;
+        let listener = connection::bind_listener_in_range(
+            config.transfer_port,
+            config.transfer_port_end,
+            config.ipv6,
+        )?;
         let local_addr = listener.local_addr()?;
 
-        let broadcaster = HybridBroadcaster::new(config.discovery_port).await?;
+        let broadcaster =
+            HybridBroadcaster::with_options(config.discovery_port, &config.discovery_options())
+                .await?;
 
         let device_id = uuid::Uuid::new_v4();
         let packet = DiscoveryPacket::new(&code, &device_name, device_id, local_addr.port(), 0, 0);
@@ -1292,7 +1337,6 @@ impl ClipboardSyncSession {
         Ok(SyncHostSession {
             code,
             device_name,
-            session_key,
             tls_config,
             listener,
             broadcaster,
@@ -1353,8 +1397,9 @@ impl ClipboardSyncSession {
             .await
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
-        let peer_name =
-            Self::do_trusted_handshake_client(&mut tls_stream, device, &device_name).await?;
+        let peer_name = Self::do_trusted_handshake_client(&mut tls_stream, device, &device_name)
+            .await
+            .inspect_err(|e| audit::handshake_failed(e, transfer_addr, device))?;
 
         let (shutdown_tx, _) = broadcast::channel(1);
 
@@ -1374,6 +1419,8 @@ impl ClipboardSyncSession {
             last_local_hash: Arc::clone(&session.last_local_hash),
             last_remote_hash: Arc::clone(&session.last_remote_hash),
             shutdown_rx: shutdown_tx.subscribe(),
+            peer_name: session.peer_name.clone(),
+            permissions: device.permissions.clone(),
         };
 
         Ok((session, runner))
@@ -1400,12 +1447,6 @@ impl ClipboardSyncSession {
                     )));
                 }
 
-                if hello.public_key != expected_device.public_key {
-                    return Err(Error::TrustError(
-                        "Public key mismatch - device may have been reinstalled".to_string(),
-                    ));
-                }
-
                 let nonce_bytes = BASE64_STANDARD
                     .decode(&hello.nonce)
                     .map_err(|e| Error::ProtocolError(format!("Invalid nonce: {e}")))?;
@@ -1422,6 +1463,17 @@ impl ClipboardSyncSession {
                     return Err(Error::TrustError("Invalid signature".to_string()));
                 }
 
+                if !crate::trust::accept_rotated_key(
+                    expected_device,
+                    &hello.public_key,
+                    &hello.key_rotations,
+                    None,
+                )? {
+                    return Err(Error::TrustError(
+                        "Public key mismatch - device may have been reinstalled".to_string(),
+                    ));
+                }
+
                 let identity = DeviceIdentity::load_or_generate()?;
                 let response_signature = identity.sign(&nonce_bytes);
 
@@ -1433,6 +1485,7 @@ impl ClipboardSyncSession {
                     nonce_signature: Some(BASE64_STANDARD.encode(response_signature)),
                     error: None,
                     trust_level: Some("Full".to_string()),
+                    key_rotations: identity.rotations().to_vec(),
                 };
 
                 let ack_payload = protocol::encode_payload(&ack)?;
@@ -1503,14 +1556,16 @@ impl ClipboardSyncSession {
             tracing::info!("Connecting directly to {}", addr);
             addr
         } else {
-            let listener = HybridListener::new(config.discovery_port).await?;
+            let listener =
+                HybridListener::with_options(config.discovery_port, &config.discovery_options())
+                    .await?;
             let discovered = listener.find(&code, config.discovery_timeout).await?;
 
             if let Err(e) = listener.shutdown() {
                 tracing::debug!("Listener shutdown: {e}");
             }
 
-            SocketAddr::new(discovered.source.ip(), discovered.packet.transfer_port)
+            discovered.transfer_addr()
         };
 
         let stream = TcpStream::connect(transfer_addr).await?;
@@ -1528,8 +1583,6 @@ impl ClipboardSyncSession {
             .await
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
-        let session_key = crypto::derive_session_key(code.as_str());
-
         let (header, payload) = protocol::read_frame(&mut tls_stream).await?;
 
         let peer_name = match header.message_type {
@@ -1572,25 +1625,8 @@ impl ClipboardSyncSession {
             }
         };
 
-        let hmac = crypto::hmac_sha256(&session_key, code.as_str().as_bytes());
-        let verify = CodeVerifyPayload {
-            code_hmac: hmac.to_vec(),
-        };
-        let payload = protocol::encode_payload(&verify)?;
-        protocol::write_frame(&mut tls_stream, MessageType::CodeVerify, &payload).await?;
-
-        let (header, ack_payload) = protocol::read_frame(&mut tls_stream).await?;
-        if header.message_type != MessageType::CodeVerifyAck {
-            return Err(Error::UnexpectedMessage {
-                expected: "CodeVerifyAck".to_string(),
-                actual: format!("{:?}", header.message_type),
-            });
-        }
-
-        let ack: CodeVerifyAckPayload = protocol::decode_payload(&ack_payload)?;
-        if !ack.success {
-            return Err(Error::CodeNotFound(code.to_string()));
-        }
+        let channel_binding = tls_stream.channel_binding()?;
+        code::prove_code(&mut tls_stream, &code, &channel_binding).await?;
 
         let (shutdown_tx, _) = broadcast::channel(1);
 
@@ -1610,6 +1646,8 @@ impl ClipboardSyncSession {
             last_local_hash: Arc::clone(&session.last_local_hash),
             last_remote_hash: Arc::clone(&session.last_remote_hash),
             shutdown_rx: shutdown_tx.subscribe(),
+            peer_name: session.peer_name.clone(),
+            permissions: DevicePermissions::default(),
         };
 
         Ok((session, runner))
@@ -1641,7 +1679,9 @@ impl ClipboardSyncSession {
             tracing::info!("Connecting directly to {}", addr);
             addr
         } else {
-            let listener = HybridListener::new(config.discovery_port).await?;
+            let listener =
+                HybridListener::with_options(config.discovery_port, &config.discovery_options())
+                    .await?;
             let discovered = listener
                 .find_with_fallback(&code, config.discovery_timeout, fallback_addresses)
                 .await?;
@@ -1650,7 +1690,7 @@ impl ClipboardSyncSession {
                 tracing::debug!("Listener shutdown: {e}");
             }
 
-            SocketAddr::new(discovered.source.ip(), discovered.packet.transfer_port)
+            discovered.transfer_addr()
         };
 
         let stream = TcpStream::connect(transfer_addr).await?;
@@ -1668,8 +1708,6 @@ impl ClipboardSyncSession {
             .await
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
-        let session_key = crypto::derive_session_key(code.as_str());
-
         let (header, payload) = protocol::read_frame(&mut tls_stream).await?;
 
         let peer_name = match header.message_type {
@@ -1712,25 +1750,8 @@ impl ClipboardSyncSession {
             }
         };
 
-        let hmac = crypto::hmac_sha256(&session_key, code.as_str().as_bytes());
-        let verify = CodeVerifyPayload {
-            code_hmac: hmac.to_vec(),
-        };
-        let payload = protocol::encode_payload(&verify)?;
-        protocol::write_frame(&mut tls_stream, MessageType::CodeVerify, &payload).await?;
-
-        let (header, ack_payload) = protocol::read_frame(&mut tls_stream).await?;
-        if header.message_type != MessageType::CodeVerifyAck {
-            return Err(Error::UnexpectedMessage {
-                expected: "CodeVerifyAck".to_string(),
-                actual: format!("{:?}", header.message_type),
-            });
-        }
-
-        let ack: CodeVerifyAckPayload = protocol::decode_payload(&ack_payload)?;
-        if !ack.success {
-            return Err(Error::CodeNotFound(code.to_string()));
-        }
+        let channel_binding = tls_stream.channel_binding()?;
+        code::prove_code(&mut tls_stream, &code, &channel_binding).await?;
 
         let (shutdown_tx, _) = broadcast::channel(1);
 
@@ -1750,6 +1771,8 @@ impl ClipboardSyncSession {
             last_local_hash: Arc::clone(&session.last_local_hash),
             last_remote_hash: Arc::clone(&session.last_remote_hash),
             shutdown_rx: shutdown_tx.subscribe(),
+            peer_name: session.peer_name.clone(),
+            permissions: DevicePermissions::default(),
         };
 
         Ok((session, runner))
@@ -1851,6 +1874,10 @@ pub struct SyncSessionRunner {
     last_local_hash: Arc<AtomicU64>,
     last_remote_hash: Arc<AtomicU64>,
     shutdown_rx: broadcast::Receiver<()>,
+    /// Peer device name
+    peer_name: String,
+    /// What the peer may do with our clipboard
+    permissions: DevicePermissions,
 }
 
 impl SyncSessionRunner {
@@ -1908,9 +1935,15 @@ impl SyncSessionRunner {
             let items_sent_clone = Arc::clone(&items_sent);
             let bytes_sent_clone = Arc::clone(&bytes_sent);
             let mut change_rx = change_rx;
+            let may_read = self.permissions.allows(Capability::ReadClipboard);
             tokio::spawn(async move {
                 tracing::debug!("Outbound sync task started");
                 while let Some(change) = change_rx.recv().await {
+                    if !may_read {
+                        tracing::debug!("Outbound: peer may not read the clipboard, not sending");
+                        continue;
+                    }
+
                     let remote_hash = last_remote.load(Ordering::SeqCst);
                     if change.hash == remote_hash {
                         tracing::debug!(
@@ -1981,6 +2014,8 @@ impl SyncSessionRunner {
             let watcher_hash = watcher_handle.last_hash_ref();
             let items_received_clone = Arc::clone(&items_received);
             let bytes_received_clone = Arc::clone(&bytes_received);
+            let permissions = self.permissions;
+            let peer_name = self.peer_name.clone();
             tokio::spawn(async move {
                 tracing::debug!("Inbound sync task started");
                 let mut clipboard = create_clipboard()?;
@@ -2003,6 +2038,24 @@ impl SyncSessionRunner {
                                 changed.checksum
                             );
 
+                            if !permissions.allows(Capability::PushClipboard) {
+                                tracing::warn!(
+                                    "Inbound: peer may not write to the clipboard, ignoring change"
+                                );
+                                continue;
+                            }
+                            if let Some(limit) = permissions
+                                .max_transfer_size
+                                .filter(|limit| changed.size > *limit)
+                            {
+                                tracing::warn!(
+                                    "Inbound: ignoring {} byte change over the {} byte limit",
+                                    changed.size,
+                                    limit
+                                );
+                                continue;
+                            }
+
                             protocol::write_frame(
                                 &mut *writer_clone.lock().await,
                                 MessageType::ClipboardRequest,
@@ -2090,6 +2143,11 @@ impl SyncSessionRunner {
                                                 size: changed.size,
                                             })
                                             .await;
+                                        emit_received(
+                                            &peer_name,
+                                            changed.content_type,
+                                            changed.size,
+                                        );
 
                                         tracing::info!(
                                             "Inbound: clipboard updated successfully ({:?}, {} bytes)",
@@ -2119,6 +2177,22 @@ impl SyncSessionRunner {
                             )
                             .await?;
                         }
+                        MessageType::ClipboardRequest
+                            if !permissions.allows(Capability::ReadClipboard) =>
+                        {
+                            tracing::warn!("Peer may not read the clipboard, refusing request");
+                            let ack = ClipboardAckPayload {
+                                success: false,
+                                error: Some("Not permitted to read the clipboard".to_string()),
+                            };
+                            let ack_payload = protocol::encode_payload(&ack)?;
+                            protocol::write_frame(
+                                &mut *writer_clone.lock().await,
+                                MessageType::ClipboardAck,
+                                &ack_payload,
+                            )
+                            .await?;
+                        }
                         MessageType::ClipboardRequest => {
                             let cached_content = {
                                 let cache_guard = cache.lock().await;
@@ -2253,4 +2327,18 @@ mod tests {
         assert_eq!(stats.items_sent, 0);
         assert_eq!(stats.items_received, 0);
     }
+
+    #[tokio::test]
+    async fn test_pull_requires_read_permission() {
+        let permissions = DevicePermissions {
+            capabilities: vec![Capability::PushClipboard],
+            ..DevicePermissions::default()
+        };
+        let device = TrustedDevice::new(uuid::Uuid::new_v4(), "Peer".to_string(), String::new())
+            .with_permissions(permissions);
+
+        let result =
+            ClipboardReceiveSession::connect_trusted(&device, TransferConfig::default()).await;
+        assert!(matches!(result, Err(Error::CapabilityDenied { .. })));
+    }
 }
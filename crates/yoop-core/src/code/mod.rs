@@ -22,6 +22,15 @@
 //!
 //! let code = ShareCode::parse("A7K9")?;
 //! ```
+//!
+//! ## Verification
+//!
+//! Once connected, peers check that they hold the same code with
+//! [`prove_code`] and [`check_code`]; see [`verify`].
+
+pub mod verify;
+
+pub use verify::{check_code, prove_code};
 
 use crate::error::{Error, Result};
 
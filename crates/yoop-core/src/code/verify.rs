@@ -0,0 +1,204 @@
+//! Share code verification over an established TLS connection.
+//!
+//! Both sides prove they know the code without sending anything the code
+//! could be recovered from:
+//!
+//! 1. The connecting side sends a [`Spake2`] message derived from the code.
+//! 2. The sharing side replies with its own message and a key confirmation.
+//! 3. The connecting side checks that confirmation and sends its own.
+//! 4. The sharing side checks it and reports the result.
+//!
+//! Both confirmations cover the TLS session's
+//! [channel binding](crypto::ChannelBinding), so a relay or other man in the
+//! middle that runs a separate TLS session with each side cannot forward
+//! them, and each connection allows only one guess at the code.
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::ShareCode;
+use crate::crypto::{self, Spake2, Spake2Role};
+use crate::error::{Error, Result};
+use crate::protocol::{self, CodeVerifyAckPayload, CodeVerifyPayload, MessageType};
+
+/// Prove knowledge of `code` to the sharing side, and check that it knows
+/// the code too.
+///
+/// `channel_binding` must come from the TLS session `stream` runs over.
+///
+/// # Errors
+///
+/// Returns [`Error::CodeNotFound`] if either side used a different code, or
+/// an error if the exchange fails.
+pub async fn prove_code<S>(
+    stream: &mut S,
+    code: &ShareCode,
+    channel_binding: &[u8; 32],
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let spake = Spake2::start(code.as_str().as_bytes(), Spake2Role::Client);
+    let verify = CodeVerifyPayload {
+        pake_message: spake.message().to_vec(),
+        ..CodeVerifyPayload::default()
+    };
+    protocol::write_frame(
+        stream,
+        MessageType::CodeVerify,
+        &protocol::encode_payload(&verify)?,
+    )
+    .await?;
+
+    let ack: CodeVerifyAckPayload = read_payload(stream, MessageType::CodeVerifyAck).await?;
+    if !ack.success {
+        return Err(Error::CodeNotFound(code.to_string()));
+    }
+    let key = spake.finish(&ack.pake_message)?;
+    let server_confirmed = crypto::constant_time_eq(
+        &ack.confirmation,
+        &crypto::code_confirmation(&key, Spake2Role::Server, channel_binding),
+    );
+
+    let confirm = CodeVerifyPayload {
+        confirmation: crypto::code_confirmation(&key, Spake2Role::Client, channel_binding).to_vec(),
+        ..CodeVerifyPayload::default()
+    };
+    protocol::write_frame(
+        stream,
+        MessageType::CodeVerify,
+        &protocol::encode_payload(&confirm)?,
+    )
+    .await?;
+
+    let result: CodeVerifyAckPayload = read_payload(stream, MessageType::CodeVerifyAck).await?;
+    if !server_confirmed || !result.success {
+        return Err(Error::CodeNotFound(code.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Check that the connecting side knows `code`, proving that we know it too.
+///
+/// `channel_binding` must come from the TLS session `stream` runs over.
+/// Returns `false` if the peer used a different code; the peer has been told
+/// and the caller should drop the connection.
+///
+/// # Errors
+///
+/// Returns an error if the exchange fails.
+pub async fn check_code<S>(
+    stream: &mut S,
+    code: &ShareCode,
+    channel_binding: &[u8; 32],
+) -> Result<bool>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let verify: CodeVerifyPayload = read_payload(stream, MessageType::CodeVerify).await?;
+
+    let spake = Spake2::start(code.as_str().as_bytes(), Spake2Role::Server);
+    let message = spake.message().to_vec();
+    let Ok(key) = spake.finish(&verify.pake_message) else {
+        send_result(stream, false).await?;
+        return Ok(false);
+    };
+
+    let ack = CodeVerifyAckPayload {
+        success: true,
+        error: None,
+        pake_message: message,
+        confirmation: crypto::code_confirmation(&key, Spake2Role::Server, channel_binding).to_vec(),
+    };
+    protocol::write_frame(
+        stream,
+        MessageType::CodeVerifyAck,
+        &protocol::encode_payload(&ack)?,
+    )
+    .await?;
+
+    let confirm: CodeVerifyPayload = read_payload(stream, MessageType::CodeVerify).await?;
+    let success = crypto::constant_time_eq(
+        &confirm.confirmation,
+        &crypto::code_confirmation(&key, Spake2Role::Client, channel_binding),
+    );
+    send_result(stream, success).await?;
+
+    Ok(success)
+}
+
+async fn send_result<S>(stream: &mut S, success: bool) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let ack = CodeVerifyAckPayload {
+        success,
+        error: (!success).then(|| "Invalid code".to_string()),
+        pake_message: Vec::new(),
+        confirmation: Vec::new(),
+    };
+    protocol::write_frame(
+        stream,
+        MessageType::CodeVerifyAck,
+        &protocol::encode_payload(&ack)?,
+    )
+    .await
+}
+
+async fn read_payload<S, T>(stream: &mut S, expected: MessageType) -> Result<T>
+where
+    S: AsyncRead + Unpin,
+    T: for<'de> serde::Deserialize<'de>,
+{
+    let (header, payload) = protocol::read_frame(stream).await?;
+    if header.message_type != expected {
+        return Err(Error::UnexpectedMessage {
+            expected: format!("{expected:?}"),
+            actual: format!("{:?}", header.message_type),
+        });
+    }
+    protocol::decode_payload(&payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(
+        client_code: &str,
+        server_code: &str,
+        client_binding: [u8; 32],
+        server_binding: [u8; 32],
+    ) -> (Result<()>, Result<bool>) {
+        let client_code = ShareCode::parse(client_code).unwrap();
+        let server_code = ShareCode::parse(server_code).unwrap();
+        let (mut client_stream, mut server_stream) = tokio::io::duplex(64 * 1024);
+
+        let server = tokio::spawn(async move {
+            check_code(&mut server_stream, &server_code, &server_binding).await
+        });
+        let client = prove_code(&mut client_stream, &client_code, &client_binding).await;
+        (client, server.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_matching_code_verifies() {
+        let (client, server) = run("A7K9", "A7K9", [1; 32], [1; 32]).await;
+        client.expect("client verified");
+        assert!(server.expect("server exchange"));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_code_fails_both_sides() {
+        let (client, server) = run("A7K9", "B2C3", [1; 32], [1; 32]).await;
+        assert!(matches!(client, Err(Error::CodeNotFound(_))));
+        assert!(!server.expect("server exchange"));
+    }
+
+    #[tokio::test]
+    async fn test_different_tls_sessions_fail() {
+        let (client, server) = run("A7K9", "A7K9", [1; 32], [2; 32]).await;
+        assert!(matches!(client, Err(Error::CodeNotFound(_))));
+        assert!(!server.expect("server exchange"));
+    }
+}
@@ -0,0 +1,346 @@
+//! Throughput-adaptive compression selection.
+//!
+//! Compression only pays off when compressing a chunk and sending the smaller
+//! result is faster than sending the chunk as-is. That depends on both the
+//! link speed and how fast (and how well) each algorithm compresses the data
+//! being sent, so this module measures both during the transfer and picks
+//! the algorithm and level per chunk.
+//!
+//! For each candidate the estimated cost of sending one uncompressed byte is
+//!
+//! ```text
+//! 1 / compress_speed + ratio / link_speed
+//! ```
+//!
+//! and sending uncompressed costs `1 / link_speed`. The cheapest option wins.
+//! Candidates without measurements are tried first, and the stalest one is
+//! re-probed periodically so the choice follows changing data and links.
+
+use std::time::Duration;
+
+use super::CompressionAlgorithm;
+
+/// Weight of the newest sample in the moving averages.
+const SMOOTHING: f64 = 0.3;
+
+/// Re-probe the stalest candidate every this many chunks.
+const PROBE_INTERVAL: u64 = 32;
+
+/// Zstd levels considered in adaptive mode, up to the configured level.
+const ZSTD_LEVELS: &[u8] = &[1, 3];
+
+/// An algorithm and level chosen for a chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionChoice {
+    /// Algorithm to compress with (`None` = send uncompressed)
+    pub algorithm: CompressionAlgorithm,
+    /// Compression level (only meaningful for zstd)
+    pub level: u8,
+}
+
+impl CompressionChoice {
+    /// Send the chunk uncompressed.
+    pub const NONE: Self = Self {
+        algorithm: CompressionAlgorithm::None,
+        level: 0,
+    };
+}
+
+/// Measurements for one candidate algorithm/level.
+#[derive(Debug, Clone)]
+struct Candidate {
+    choice: CompressionChoice,
+    /// Compression speed in uncompressed bytes per second
+    speed_bps: Option<f64>,
+    /// Compressed size / original size
+    ratio: Option<f64>,
+    /// Chunk number when this candidate was last measured
+    last_measured: u64,
+}
+
+/// Picks a compression algorithm and level per chunk from measured speeds.
+#[derive(Debug, Clone)]
+pub struct AdaptiveCompressor {
+    candidates: Vec<Candidate>,
+    /// Effective link speed in wire bytes per second
+    link_bps: Option<f64>,
+    /// Number of chunks chosen so far
+    chunks: u64,
+}
+
+impl AdaptiveCompressor {
+    /// Create a selector for the algorithms both peers support.
+    ///
+    /// `max_level` is the configured zstd level: default levels above it
+    /// are left out and the level itself is always a candidate.
+    #[must_use]
+    pub fn new(algorithms: &[CompressionAlgorithm], max_level: u8) -> Self {
+        let mut choices = Vec::new();
+
+        for algorithm in algorithms {
+            match algorithm {
+                CompressionAlgorithm::None => {}
+                CompressionAlgorithm::Lz4 => choices.push(CompressionChoice {
+                    algorithm: CompressionAlgorithm::Lz4,
+                    level: 0,
+                }),
+                CompressionAlgorithm::Zstd => {
+                    let max_level = max_level.max(ZSTD_LEVELS[0]);
+                    let levels = ZSTD_LEVELS
+                        .iter()
+                        .copied()
+                        .filter(|&level| level < max_level)
+                        .chain(std::iter::once(max_level));
+                    choices.extend(levels.map(|level| CompressionChoice {
+                        algorithm: CompressionAlgorithm::Zstd,
+                        level,
+                    }));
+                }
+            }
+        }
+
+        Self {
+            candidates: choices
+                .into_iter()
+                .map(|choice| Candidate {
+                    choice,
+                    speed_bps: None,
+                    ratio: None,
+                    last_measured: 0,
+                })
+                .collect(),
+            link_bps: None,
+            chunks: 0,
+        }
+    }
+
+    /// Choose how to send the next chunk.
+    pub fn choose(&mut self) -> CompressionChoice {
+        self.chunks += 1;
+
+        let Some(link_bps) = self.link_bps else {
+            // measure the link with an uncompressed chunk first
+            return CompressionChoice::NONE;
+        };
+
+        if let Some(untried) = self.candidates.iter().find(|c| c.speed_bps.is_none()) {
+            return untried.choice;
+        }
+
+        if self.chunks % PROBE_INTERVAL == 0 {
+            if let Some(stalest) = self.candidates.iter().min_by_key(|c| c.last_measured) {
+                return stalest.choice;
+            }
+        }
+
+        let mut best = (CompressionChoice::NONE, 1.0 / link_bps);
+        for candidate in &self.candidates {
+            if let (Some(speed), Some(ratio)) = (candidate.speed_bps, candidate.ratio) {
+                let cost = 1.0 / speed + ratio / link_bps;
+                if cost < best.1 {
+                    best = (candidate.choice, cost);
+                }
+            }
+        }
+
+        best.0
+    }
+
+    /// Record how long compressing a chunk took and how well it compressed.
+    pub fn record_compression(
+        &mut self,
+        choice: CompressionChoice,
+        original_size: usize,
+        compressed_size: usize,
+        elapsed: Duration,
+    ) {
+        if original_size == 0 {
+            return;
+        }
+
+        let chunks = self.chunks;
+        let Some(candidate) = self.candidates.iter_mut().find(|c| c.choice == choice) else {
+            return;
+        };
+
+        let secs = elapsed.as_secs_f64().max(1e-9);
+        let speed = original_size as f64 / secs;
+        let ratio = (compressed_size as f64 / original_size as f64).min(1.0);
+
+        candidate.speed_bps = Some(smooth(candidate.speed_bps, speed));
+        candidate.ratio = Some(smooth(candidate.ratio, ratio));
+        candidate.last_measured = chunks;
+    }
+
+    /// Record how long sending a chunk took on the wire (until acknowledged).
+    pub fn record_link(&mut self, wire_size: usize, elapsed: Duration) {
+        if wire_size == 0 {
+            return;
+        }
+
+        let secs = elapsed.as_secs_f64().max(1e-9);
+        let speed = wire_size as f64 / secs;
+        self.link_bps = Some(smooth(self.link_bps, speed));
+    }
+
+    /// Current link speed estimate in bytes per second.
+    #[must_use]
+    pub const fn link_speed(&self) -> Option<f64> {
+        self.link_bps
+    }
+}
+
+fn smooth(previous: Option<f64>, sample: f64) -> f64 {
+    previous.map_or(sample, |prev| {
+        prev.mul_add(1.0 - SMOOTHING, sample * SMOOTHING)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: &[CompressionAlgorithm] = &[CompressionAlgorithm::Zstd, CompressionAlgorithm::Lz4];
+
+    fn zstd(level: u8) -> CompressionChoice {
+        CompressionChoice {
+            algorithm: CompressionAlgorithm::Zstd,
+            level,
+        }
+    }
+
+    const LZ4: CompressionChoice = CompressionChoice {
+        algorithm: CompressionAlgorithm::Lz4,
+        level: 0,
+    };
+
+    /// Feed one measurement per candidate so the selector leaves its probe phase.
+    fn warm_up(
+        selector: &mut AdaptiveCompressor,
+        link_bps: f64,
+        measurements: &[(CompressionChoice, f64, f64)],
+    ) {
+        selector.record_link(1_000_000, Duration::from_secs_f64(1_000_000.0 / link_bps));
+        for (choice, speed, ratio) in measurements {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let compressed = (1_000_000.0 * ratio) as usize;
+            selector.record_compression(
+                *choice,
+                1_000_000,
+                compressed,
+                Duration::from_secs_f64(1_000_000.0 / speed),
+            );
+        }
+    }
+
+    #[test]
+    fn test_first_chunk_is_uncompressed() {
+        let mut selector = AdaptiveCompressor::new(ALL, 3);
+        assert_eq!(selector.choose(), CompressionChoice::NONE);
+    }
+
+    #[test]
+    fn test_probes_untried_candidates() {
+        let mut selector = AdaptiveCompressor::new(ALL, 3);
+        selector.record_link(1_000_000, Duration::from_secs(1));
+
+        let first = selector.choose();
+        assert_ne!(first, CompressionChoice::NONE);
+        selector.record_compression(first, 1000, 500, Duration::from_millis(1));
+
+        let second = selector.choose();
+        assert_ne!(second, first);
+    }
+
+    #[test]
+    fn test_candidates_capped_at_configured_level() {
+        let levels = |max_level| {
+            AdaptiveCompressor::new(&[CompressionAlgorithm::Zstd], max_level)
+                .candidates
+                .iter()
+                .map(|c| c.choice.level)
+                .collect::<Vec<u8>>()
+        };
+        assert_eq!(levels(1), vec![1]);
+        assert_eq!(levels(2), vec![1, 2]);
+        assert_eq!(levels(3), vec![1, 3]);
+        assert_eq!(levels(6), vec![1, 3, 6]);
+
+        let selector = AdaptiveCompressor::new(&[], 1);
+        assert!(selector.candidates.is_empty());
+    }
+
+    #[test]
+    fn test_slow_link_prefers_best_ratio() {
+        let mut selector = AdaptiveCompressor::new(ALL, 3);
+        // 1 MB/s link: ratio matters far more than compression speed
+        warm_up(
+            &mut selector,
+            1_000_000.0,
+            &[
+                (zstd(1), 300_000_000.0, 0.40),
+                (zstd(3), 150_000_000.0, 0.30),
+                (LZ4, 800_000_000.0, 0.55),
+            ],
+        );
+
+        assert_eq!(selector.choose(), zstd(3));
+    }
+
+    #[test]
+    fn test_fast_link_prefers_fast_algorithm() {
+        let mut selector = AdaptiveCompressor::new(ALL, 3);
+        // 500 MB/s link: zstd is slower than just sending, lz4 still helps
+        warm_up(
+            &mut selector,
+            500_000_000.0,
+            &[
+                (zstd(1), 300_000_000.0, 0.40),
+                (zstd(3), 150_000_000.0, 0.30),
+                (LZ4, 4_000_000_000.0, 0.55),
+            ],
+        );
+
+        assert_eq!(selector.choose(), LZ4);
+    }
+
+    #[test]
+    fn test_very_fast_link_sends_uncompressed() {
+        let mut selector = AdaptiveCompressor::new(ALL, 3);
+        warm_up(
+            &mut selector,
+            5_000_000_000.0,
+            &[
+                (zstd(1), 300_000_000.0, 0.40),
+                (zstd(3), 150_000_000.0, 0.30),
+                (LZ4, 800_000_000.0, 0.90),
+            ],
+        );
+
+        assert_eq!(selector.choose(), CompressionChoice::NONE);
+    }
+
+    #[test]
+    fn test_periodic_reprobe() {
+        let mut selector = AdaptiveCompressor::new(ALL, 3);
+        warm_up(
+            &mut selector,
+            1_000_000.0,
+            &[
+                (zstd(1), 300_000_000.0, 0.40),
+                (zstd(3), 150_000_000.0, 0.30),
+                (LZ4, 800_000_000.0, 0.55),
+            ],
+        );
+
+        let mut probed = false;
+        for _ in 0..PROBE_INTERVAL {
+            let choice = selector.choose();
+            if choice != zstd(3) {
+                probed = true;
+            }
+            selector.record_compression(choice, 1_000_000, 300_000, Duration::from_millis(7));
+        }
+        assert!(probed, "expected a re-probe within {PROBE_INTERVAL} chunks");
+    }
+}
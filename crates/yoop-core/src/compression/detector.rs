@@ -120,6 +120,19 @@ pub fn should_compress_file(path: &Path, mode: CompressionMode) -> CompressionDe
                 _ => CompressionDecision::TestFirstChunk,
             }
         }
+        CompressionMode::Adaptive => {
+            let ext = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(str::to_lowercase);
+
+            // the adaptive selector measures each chunk itself, so only
+            // known incompressible types are ruled out up front
+            match ext {
+                Some(ref e) if is_incompressible_extension(e) => CompressionDecision::Skip,
+                _ => CompressionDecision::Compress,
+            }
+        }
     }
 }
 
@@ -168,6 +181,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_should_compress_adaptive_mode() {
+        assert_eq!(
+            should_compress_file(Path::new("photo.jpg"), CompressionMode::Adaptive),
+            CompressionDecision::Skip
+        );
+        assert_eq!(
+            should_compress_file(Path::new("data.json"), CompressionMode::Adaptive),
+            CompressionDecision::Compress
+        );
+    }
+
     #[test]
     fn test_should_compress_auto_compressible() {
         let compressible_files = [
@@ -0,0 +1,75 @@
+//! LZ4 compression implementation.
+//!
+//! This module wraps the lz4_flex block format for compressing and
+//! decompressing chunk data. LZ4 trades compression ratio for speed and is
+//! a better fit than zstd when the link is faster than zstd can compress.
+
+use crate::error::{Error, Result};
+
+/// Compress data using LZ4.
+///
+/// The uncompressed size is prepended to the output so decompression does
+/// not depend on the `original_size` field of the chunk header.
+#[must_use]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    lz4_flex::compress_prepend_size(data)
+}
+
+/// Decompress LZ4 data produced by [`compress`].
+///
+/// The size prefix is checked against the maximum frame payload before any
+/// allocation, so a malicious peer cannot request an oversized buffer.
+///
+/// # Errors
+///
+/// Returns an error if the data is not valid LZ4.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let Some(prefix) = data.get(..4) else {
+        return Err(Error::Compression("lz4 data too short".to_string()));
+    };
+    let size = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]);
+    if size as usize > crate::protocol::MAX_PAYLOAD_SIZE {
+        return Err(Error::Compression(format!(
+            "lz4 decompressed size {size} exceeds maximum"
+        )));
+    }
+
+    lz4_flex::decompress_size_prepended(data)
+        .map_err(|e| Error::Compression(format!("lz4 decompress failed: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_decompress_roundtrip() {
+        let original = b"Hello, this is test data that should compress well. ".repeat(100);
+        let compressed = compress(&original);
+        assert!(compressed.len() < original.len());
+
+        let decompressed = decompress(&compressed).unwrap();
+        assert_eq!(original.as_slice(), decompressed.as_slice());
+    }
+
+    #[test]
+    fn test_compress_empty() {
+        let compressed = compress(b"");
+        let decompressed = decompress(&compressed).unwrap();
+        assert!(decompressed.is_empty());
+    }
+
+    #[test]
+    fn test_decompress_rejects_oversized_prefix() {
+        let mut data = u32::MAX.to_le_bytes().to_vec();
+        data.extend_from_slice(&[0u8; 16]);
+        assert!(decompress(&data).is_err());
+        assert!(decompress(&[0u8; 2]).is_err());
+    }
+
+    #[test]
+    fn test_decompress_invalid_data() {
+        let invalid = b"\xff\xff\xff\x7fthis is not valid lz4 data";
+        assert!(decompress(invalid).is_err());
+    }
+}
@@ -1,13 +1,16 @@
 //! Compression support for file transfers.
 //!
-//! This module provides transparent compression for file transfers using zstd.
-//! Compression is applied per-chunk to enable streaming, resumable transfers.
+//! This module provides transparent compression for file transfers using zstd
+//! or LZ4. Compression is applied per-chunk to enable streaming, resumable
+//! transfers.
 //!
 //! ## Features
 //!
 //! - **Per-chunk compression**: Each chunk is compressed independently
 //! - **Smart detection**: Auto mode skips known incompressible file types
-//! - **Statistics tracking**: Track compression ratios and savings
+//! - **Adaptive mode**: Picks algorithm and level per chunk based on measured
+//!   link speed versus compression speed
+//! - **Statistics tracking**: Track compression ratios and savings per algorithm
 //!
 //! ## Example
 //!
@@ -21,18 +24,26 @@
 //! };
 //! ```
 
+mod adaptive;
 mod detector;
+mod lz4_impl;
 mod stats;
 mod zstd_impl;
 
+pub use adaptive::{AdaptiveCompressor, CompressionChoice};
 pub use detector::{should_compress_file, CompressionDecision, INCOMPRESSIBLE_EXTENSIONS};
-pub use stats::CompressionStats;
+pub use lz4_impl::{compress as lz4_compress, decompress as lz4_decompress};
+pub use stats::{AlgorithmStats, CompressionStats};
 pub use zstd_impl::{compress, decompress, should_compress};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::Result;
 
 /// Compression algorithm identifier for wire protocol.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default,
+)]
 #[repr(u8)]
 pub enum CompressionAlgorithm {
     /// No compression
@@ -40,6 +51,8 @@ pub enum CompressionAlgorithm {
     None = 0,
     /// Zstandard compression
     Zstd = 1,
+    /// LZ4 compression (faster, lower ratio)
+    Lz4 = 2,
 }
 
 impl CompressionAlgorithm {
@@ -49,6 +62,7 @@ impl CompressionAlgorithm {
         match byte {
             0 => Some(Self::None),
             1 => Some(Self::Zstd),
+            2 => Some(Self::Lz4),
             _ => None,
         }
     }
@@ -58,6 +72,45 @@ impl CompressionAlgorithm {
     pub const fn as_byte(self) -> u8 {
         self as u8
     }
+
+    /// Short lowercase name for display.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Zstd => "zstd",
+            Self::Lz4 => "lz4",
+        }
+    }
+}
+
+/// Compress a chunk with the given algorithm.
+///
+/// `level` only applies to zstd; LZ4 has a single speed setting.
+/// `CompressionAlgorithm::None` returns the data unchanged.
+///
+/// # Errors
+///
+/// Returns an error if compression fails.
+pub fn compress_chunk(algorithm: CompressionAlgorithm, data: &[u8], level: u8) -> Result<Vec<u8>> {
+    match algorithm {
+        CompressionAlgorithm::None => Ok(data.to_vec()),
+        CompressionAlgorithm::Zstd => compress(data, i32::from(level)),
+        CompressionAlgorithm::Lz4 => Ok(lz4_compress(data)),
+    }
+}
+
+/// Decompress a chunk that was compressed with the given algorithm.
+///
+/// # Errors
+///
+/// Returns an error if the data cannot be decompressed.
+pub fn decompress_chunk(algorithm: CompressionAlgorithm, data: &[u8]) -> Result<Vec<u8>> {
+    match algorithm {
+        CompressionAlgorithm::None => Ok(data.to_vec()),
+        CompressionAlgorithm::Zstd => decompress(data),
+        CompressionAlgorithm::Lz4 => lz4_decompress(data),
+    }
 }
 
 /// Compression mode setting for transfers.
@@ -71,6 +124,8 @@ pub enum CompressionMode {
     Always,
     /// Never compress
     Never,
+    /// Pick algorithm and level per chunk from measured link and compression speed
+    Adaptive,
 }
 
 /// Compression configuration for transfers.
@@ -121,14 +176,71 @@ impl CompressionConfig {
 }
 
 /// Compression capabilities for protocol negotiation.
+///
+/// On the wire, `algorithms` only lists algorithms every released peer can
+/// parse. The full preference list, including newer algorithms such as LZ4,
+/// goes in a separate `extended_algorithms` field that older peers ignore.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(from = "WireCapabilities", into = "WireCapabilities")]
 pub struct CompressionCapabilities {
-    /// Supported compression algorithms
+    /// Supported compression algorithms, in order of preference
     pub algorithms: Vec<CompressionAlgorithm>,
     /// Preferred compression level
     pub level: u8,
 }
 
+/// Algorithms known to peers released before LZ4 support.
+const LEGACY_ALGORITHMS: [CompressionAlgorithm; 2] =
+    [CompressionAlgorithm::None, CompressionAlgorithm::Zstd];
+
+/// Wire form of [`CompressionCapabilities`].
+#[derive(Serialize, Deserialize)]
+struct WireCapabilities {
+    #[serde(deserialize_with = "deserialize_known_algorithms")]
+    algorithms: Vec<CompressionAlgorithm>,
+    level: u8,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_known_algorithms",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    extended_algorithms: Vec<CompressionAlgorithm>,
+}
+
+impl From<WireCapabilities> for CompressionCapabilities {
+    fn from(wire: WireCapabilities) -> Self {
+        Self {
+            algorithms: if wire.extended_algorithms.is_empty() {
+                wire.algorithms
+            } else {
+                wire.extended_algorithms
+            },
+            level: wire.level,
+        }
+    }
+}
+
+impl From<CompressionCapabilities> for WireCapabilities {
+    fn from(caps: CompressionCapabilities) -> Self {
+        let algorithms: Vec<_> = caps
+            .algorithms
+            .iter()
+            .copied()
+            .filter(|algo| LEGACY_ALGORITHMS.contains(algo))
+            .collect();
+        let extended_algorithms = if algorithms.len() == caps.algorithms.len() {
+            Vec::new()
+        } else {
+            caps.algorithms
+        };
+        Self {
+            algorithms,
+            level: caps.level,
+            extended_algorithms,
+        }
+    }
+}
+
 impl CompressionCapabilities {
     /// Create capabilities with zstd support.
     #[must_use]
@@ -139,6 +251,15 @@ impl CompressionCapabilities {
         }
     }
 
+    /// Create capabilities with every supported algorithm (zstd preferred).
+    #[must_use]
+    pub fn all(level: u8) -> Self {
+        Self {
+            algorithms: vec![CompressionAlgorithm::Zstd, CompressionAlgorithm::Lz4],
+            level,
+        }
+    }
+
     /// Create empty capabilities (no compression).
     #[must_use]
     pub fn none() -> Self {
@@ -162,16 +283,40 @@ impl CompressionCapabilities {
 
     /// Negotiate compression with another peer's capabilities.
     ///
-    /// Returns the agreed algorithm (or None if no common support).
+    /// Returns our most preferred algorithm that the peer also supports
+    /// (or None if no common support).
     #[must_use]
     pub fn negotiate(&self, other: &Self) -> Option<CompressionAlgorithm> {
-        if self.supports(CompressionAlgorithm::Zstd) && other.supports(CompressionAlgorithm::Zstd) {
-            return Some(CompressionAlgorithm::Zstd);
-        }
-        None
+        self.common_algorithms(other).into_iter().next()
+    }
+
+    /// All algorithms supported by both peers, in our order of preference.
+    #[must_use]
+    pub fn common_algorithms(&self, other: &Self) -> Vec<CompressionAlgorithm> {
+        self.algorithms
+            .iter()
+            .copied()
+            .filter(|algo| *algo != CompressionAlgorithm::None && other.supports(*algo))
+            .collect()
     }
 }
 
+/// Deserialize an algorithm list, dropping entries this build doesn't know.
+///
+/// Lets peers advertise newer algorithms without breaking the handshake.
+fn deserialize_known_algorithms<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Vec<CompressionAlgorithm>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values = Vec::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(values
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,7 +331,26 @@ mod tests {
             CompressionAlgorithm::from_byte(1),
             Some(CompressionAlgorithm::Zstd)
         );
-        assert_eq!(CompressionAlgorithm::from_byte(2), None);
+        assert_eq!(
+            CompressionAlgorithm::from_byte(2),
+            Some(CompressionAlgorithm::Lz4)
+        );
+        assert_eq!(CompressionAlgorithm::from_byte(3), None);
+    }
+
+    #[test]
+    fn test_compress_chunk_roundtrip() {
+        let data = b"chunk data that compresses nicely. ".repeat(200);
+
+        for algo in [
+            CompressionAlgorithm::None,
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Lz4,
+        ] {
+            let compressed = compress_chunk(algo, &data, 1).unwrap();
+            let decompressed = decompress_chunk(algo, &compressed).unwrap();
+            assert_eq!(decompressed, data, "roundtrip failed for {algo:?}");
+        }
     }
 
     #[test]
@@ -208,6 +372,64 @@ mod tests {
         assert_eq!(caps1.negotiate(&caps3), None);
     }
 
+    #[test]
+    fn test_compression_capabilities_negotiate_lz4() {
+        let all = CompressionCapabilities::all(1);
+        let lz4_only = CompressionCapabilities {
+            algorithms: vec![CompressionAlgorithm::Lz4],
+            level: 1,
+        };
+
+        assert_eq!(all.negotiate(&all), Some(CompressionAlgorithm::Zstd));
+        assert_eq!(all.negotiate(&lz4_only), Some(CompressionAlgorithm::Lz4));
+        assert_eq!(
+            all.negotiate(&CompressionCapabilities::with_zstd(1)),
+            Some(CompressionAlgorithm::Zstd)
+        );
+        assert_eq!(
+            all.common_algorithms(&all),
+            vec![CompressionAlgorithm::Zstd, CompressionAlgorithm::Lz4]
+        );
+    }
+
+    #[test]
+    fn test_compression_capabilities_ignores_unknown_algorithms() {
+        let json = r#"{"algorithms":["Zstd","Brotli","Lz4"],"level":1}"#;
+        let caps: CompressionCapabilities = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            caps.algorithms,
+            vec![CompressionAlgorithm::Zstd, CompressionAlgorithm::Lz4]
+        );
+    }
+
+    #[test]
+    fn test_compression_capabilities_wire_compatible() {
+        #[derive(Deserialize)]
+        enum LegacyAlgorithm {
+            None,
+            Zstd,
+        }
+
+        #[derive(Deserialize)]
+        struct LegacyCapabilities {
+            algorithms: Vec<LegacyAlgorithm>,
+        }
+
+        let json = serde_json::to_string(&CompressionCapabilities::all(1)).unwrap();
+
+        let legacy: LegacyCapabilities = serde_json::from_str(&json).unwrap();
+        assert!(matches!(legacy.algorithms[..], [LegacyAlgorithm::Zstd]));
+
+        let caps: CompressionCapabilities = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            caps.algorithms,
+            vec![CompressionAlgorithm::Zstd, CompressionAlgorithm::Lz4]
+        );
+
+        let json = serde_json::to_string(&CompressionCapabilities::with_zstd(1)).unwrap();
+        assert!(!json.contains("extended_algorithms"));
+    }
+
     #[test]
     fn test_compression_mode_serde() {
         let mode = CompressionMode::Auto;
@@ -3,8 +3,23 @@
 //! This module provides types for tracking compression performance
 //! during file transfers.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
+use super::CompressionAlgorithm;
+
+/// Per-algorithm compression statistics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlgorithmStats {
+    /// Number of chunks sent with this algorithm
+    pub chunks: u32,
+    /// Uncompressed bytes sent with this algorithm
+    pub original_bytes: u64,
+    /// Bytes on the wire for this algorithm
+    pub compressed_bytes: u64,
+}
+
 /// Statistics for compression during a transfer.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CompressionStats {
@@ -16,6 +31,9 @@ pub struct CompressionStats {
     pub chunks_compressed: u32,
     /// Number of chunks sent uncompressed
     pub chunks_uncompressed: u32,
+    /// Breakdown by algorithm (only populated via [`Self::add_chunk`])
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub per_algorithm: BTreeMap<CompressionAlgorithm, AlgorithmStats>,
 }
 
 impl CompressionStats {
@@ -27,6 +45,7 @@ impl CompressionStats {
             compressed_bytes: 0,
             chunks_compressed: 0,
             chunks_uncompressed: 0,
+            per_algorithm: BTreeMap::new(),
         }
     }
 
@@ -77,6 +96,41 @@ impl CompressionStats {
         self.chunks_uncompressed += 1;
     }
 
+    /// Add a chunk sent with the given algorithm.
+    ///
+    /// Updates the totals and the per-algorithm breakdown.
+    /// `CompressionAlgorithm::None` counts as an uncompressed chunk.
+    pub fn add_chunk(
+        &mut self,
+        algorithm: CompressionAlgorithm,
+        original_size: u64,
+        wire_size: u64,
+    ) {
+        if algorithm == CompressionAlgorithm::None {
+            self.add_uncompressed(original_size);
+        } else {
+            self.add_compressed(original_size, wire_size);
+        }
+
+        let entry = self.per_algorithm.entry(algorithm).or_default();
+        entry.chunks += 1;
+        entry.original_bytes += original_size;
+        entry.compressed_bytes += wire_size;
+    }
+
+    /// Get a human-readable breakdown of chunks per algorithm.
+    ///
+    /// Returns a string like "zstd 120, lz4 30, none 4" or an empty string
+    /// if no per-algorithm data was recorded.
+    #[must_use]
+    pub fn algorithm_display(&self) -> String {
+        self.per_algorithm
+            .iter()
+            .map(|(algo, stats)| format!("{} {}", algo.name(), stats.chunks))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     /// Total number of chunks processed.
     #[must_use]
     pub fn total_chunks(&self) -> u32 {
@@ -100,6 +154,13 @@ impl CompressionStats {
         self.compressed_bytes += other.compressed_bytes;
         self.chunks_compressed += other.chunks_compressed;
         self.chunks_uncompressed += other.chunks_uncompressed;
+
+        for (algo, stats) in &other.per_algorithm {
+            let entry = self.per_algorithm.entry(*algo).or_default();
+            entry.chunks += stats.chunks;
+            entry.original_bytes += stats.original_bytes;
+            entry.compressed_bytes += stats.compressed_bytes;
+        }
     }
 }
 
@@ -220,6 +281,45 @@ mod tests {
         assert_eq!(stats1.chunks_uncompressed, 1);
     }
 
+    #[test]
+    fn test_add_chunk_per_algorithm() {
+        let mut stats = CompressionStats::new();
+        stats.add_chunk(CompressionAlgorithm::Zstd, 1000, 300);
+        stats.add_chunk(CompressionAlgorithm::Lz4, 1000, 500);
+        stats.add_chunk(CompressionAlgorithm::Lz4, 1000, 450);
+        stats.add_chunk(CompressionAlgorithm::None, 500, 500);
+
+        assert_eq!(stats.original_bytes, 3500);
+        assert_eq!(stats.compressed_bytes, 1750);
+        assert_eq!(stats.chunks_compressed, 3);
+        assert_eq!(stats.chunks_uncompressed, 1);
+
+        let lz4 = stats.per_algorithm[&CompressionAlgorithm::Lz4];
+        assert_eq!(lz4.chunks, 2);
+        assert_eq!(lz4.compressed_bytes, 950);
+
+        assert_eq!(stats.algorithm_display(), "none 1, zstd 1, lz4 2");
+    }
+
+    #[test]
+    fn test_merge_per_algorithm() {
+        let mut stats1 = CompressionStats::new();
+        stats1.add_chunk(CompressionAlgorithm::Zstd, 1000, 300);
+
+        let mut stats2 = CompressionStats::new();
+        stats2.add_chunk(CompressionAlgorithm::Zstd, 1000, 400);
+        stats2.add_chunk(CompressionAlgorithm::Lz4, 1000, 600);
+
+        stats1.merge(&stats2);
+
+        assert_eq!(stats1.per_algorithm[&CompressionAlgorithm::Zstd].chunks, 2);
+        assert_eq!(
+            stats1.per_algorithm[&CompressionAlgorithm::Zstd].compressed_bytes,
+            700
+        );
+        assert_eq!(stats1.per_algorithm[&CompressionAlgorithm::Lz4].chunks, 1);
+    }
+
     #[test]
     fn test_stats_serialization() {
         let mut stats = CompressionStats::new();
@@ -52,6 +52,10 @@ pub struct Config {
     pub ui: UiConfig,
     /// Update settings
     pub update: UpdateConfig,
+    /// Static peers for networks discovery cannot reach (`[[peers]]`)
+    pub peers: Vec<PeerConfig>,
+    /// Commands run on session events (`[[hooks]]`)
+    pub hooks: Vec<crate::hooks::Hook>,
 }
 
 impl Default for Config {
@@ -67,6 +71,8 @@ impl Default for Config {
             web: WebConfig::default(),
             ui: UiConfig::default(),
             update: UpdateConfig::default(),
+            peers: Vec::new(),
+            hooks: Vec::new(),
         }
     }
 }
@@ -100,15 +106,35 @@ impl Default for GeneralConfig {
 /// Network configuration options.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct NetworkConfig {
     /// Discovery port (UDP)
     pub port: u16,
-    /// Transfer port range
+    /// Transfer port range; listeners bind the first free port in it
     pub transfer_port_range: (u16, u16),
     /// Network interface (auto or specific)
     pub interface: String,
+    /// Interfaces discovery may use (names, `*` suffix for prefixes; empty = all)
+    pub allow_interfaces: Vec<String>,
+    /// Interfaces discovery must not use (e.g. `docker*`, `veth*`)
+    pub deny_interfaces: Vec<String>,
     /// Enable IPv6
     pub ipv6: bool,
+    /// Broadcast rotating identifiers instead of the device identity, and
+    /// only what is needed to connect for code shares
+    pub privacy_mode: bool,
+    /// Relay server (`host[:port]`) for shares that discovery cannot reach
+    pub relay: Option<String>,
+    /// Probe hosts directly when broadcast and mDNS discovery find nothing
+    pub sweep: bool,
+    /// CIDR ranges to sweep (empty = subnets of the selected interfaces)
+    pub sweep_ranges: Vec<String>,
+    /// Also announce and listen on an IPv4 multicast group
+    pub multicast: bool,
+    /// IPv4 multicast group for discovery
+    pub multicast_group: std::net::Ipv4Addr,
+    /// IPv4 multicast TTL (1 = local link only)
+    pub multicast_ttl: u32,
 }
 
 impl Default for NetworkConfig {
@@ -120,7 +146,67 @@ impl Default for NetworkConfig {
                 crate::DEFAULT_TRANSFER_PORT_END,
             ),
             interface: "auto".to_string(),
+            allow_interfaces: Vec::new(),
+            deny_interfaces: Vec::new(),
             ipv6: true,
+            privacy_mode: false,
+            relay: None,
+            sweep: false,
+            sweep_ranges: Vec::new(),
+            multicast: true,
+            multicast_group: crate::discovery::IPV4_MULTICAST_GROUP,
+            multicast_ttl: 1,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Interface filter for discovery.
+    #[must_use]
+    pub fn interface_filter(&self) -> crate::discovery::InterfaceFilter {
+        crate::discovery::InterfaceFilter {
+            interface: self.interface.clone(),
+            allow: self.allow_interfaces.clone(),
+            deny: self.deny_interfaces.clone(),
+        }
+    }
+
+    /// Subnet sweep settings for discovery.
+    #[must_use]
+    pub fn sweep_options(&self) -> crate::discovery::SweepOptions {
+        crate::discovery::SweepOptions {
+            enabled: self.sweep,
+            ranges: self.sweep_ranges.clone(),
+        }
+    }
+
+    /// IPv4 multicast settings for discovery.
+    ///
+    /// A `multicast_group` that is not a multicast address disables it.
+    #[must_use]
+    pub fn multicast_options(&self) -> crate::discovery::MulticastOptions {
+        if self.multicast && !self.multicast_group.is_multicast() {
+            tracing::warn!(
+                "Ignoring network.multicast_group {}: not a multicast address",
+                self.multicast_group
+            );
+        }
+        crate::discovery::MulticastOptions {
+            enabled: self.multicast,
+            group: self.multicast_group,
+            ttl: self.multicast_ttl,
+        }
+    }
+
+    /// Options for discovery broadcasters and listeners.
+    #[must_use]
+    pub fn discovery_options(&self) -> crate::discovery::DiscoveryOptions {
+        crate::discovery::DiscoveryOptions {
+            ipv6: self.ipv6,
+            interfaces: self.interface_filter(),
+            privacy: self.privacy_mode,
+            sweep: self.sweep_options(),
+            multicast: self.multicast_options(),
         }
     }
 }
@@ -135,12 +221,19 @@ pub struct TransferConfig {
     pub parallel_chunks: usize,
     /// Bandwidth limit (bytes per second, None for unlimited)
     pub bandwidth_limit: Option<u64>,
-    /// Compression mode (auto, always, never)
+    /// Compression mode (auto, always, never, adaptive)
     pub compression: CompressionMode,
     /// Compression level (1-3, lower = faster)
     pub compression_level: u8,
     /// Verify checksums after transfer
     pub verify_checksum: bool,
+    /// How long to wait for a dropped peer to reconnect (0s disables)
+    #[serde(with = "humantime_serde")]
+    pub reconnect_grace: Duration,
+    /// Limits on what a sender may offer when receiving
+    pub limits: crate::transfer::ReceiveLimits,
+    /// Checks received files must pass before they are released
+    pub quarantine: crate::file::QuarantineConfig,
 }
 
 impl Default for TransferConfig {
@@ -152,6 +245,9 @@ impl Default for TransferConfig {
             compression: CompressionMode::Auto,
             compression_level: 1,
             verify_checksum: true,
+            reconnect_grace: crate::transfer::DEFAULT_RECONNECT_GRACE,
+            limits: crate::transfer::ReceiveLimits::default(),
+            quarantine: crate::file::QuarantineConfig::default(),
         }
     }
 }
@@ -159,6 +255,7 @@ impl Default for TransferConfig {
 /// Security configuration options.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct SecurityConfig {
     /// Require additional PIN
     pub require_pin: bool,
@@ -171,6 +268,8 @@ pub struct SecurityConfig {
     /// Lockout duration
     #[serde(with = "humantime_serde")]
     pub rate_limit_window: Duration,
+    /// Record security events to the audit log
+    pub audit_log: bool,
 }
 
 impl Default for SecurityConfig {
@@ -181,6 +280,7 @@ impl Default for SecurityConfig {
             tls_verify: true,
             rate_limit_attempts: 3,
             rate_limit_window: Duration::from_secs(30),
+            audit_log: true,
         }
     }
 }
@@ -251,6 +351,26 @@ impl Default for TrustConfig {
     }
 }
 
+/// A trusted device reachable at fixed addresses, e.g. over a VPN.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeerConfig {
+    /// Trusted device name or ID
+    pub device: String,
+    /// Hostnames or IPs, optionally with a port (`host`, `host:port`,
+    /// `[v6]:port`); without a port the transfer port is used
+    pub addresses: Vec<String>,
+}
+
+impl PeerConfig {
+    /// Check whether this entry refers to the given trusted device.
+    #[must_use]
+    pub fn matches(&self, device: &crate::trust::TrustedDevice) -> bool {
+        self.device.eq_ignore_ascii_case(&device.device_name)
+            || uuid::Uuid::parse_str(&self.device).is_ok_and(|id| id == device.device_id)
+    }
+}
+
 /// Trust level for devices.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -265,6 +385,7 @@ pub enum TrustLevel {
 /// Web interface configuration options.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct WebConfig {
     /// Enable web server by default
     pub enabled: bool,
@@ -272,8 +393,14 @@ pub struct WebConfig {
     pub port: u16,
     /// Require authentication
     pub auth: bool,
+    /// Fixed password for authentication (a new one is generated each
+    /// start if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
     /// Bind to localhost only
     pub localhost_only: bool,
+    /// Serve over HTTPS with a persistent self-signed certificate
+    pub https: bool,
 }
 
 impl Default for WebConfig {
@@ -282,7 +409,9 @@ impl Default for WebConfig {
             enabled: false,
             port: 8080,
             auth: false,
+            password: None,
             localhost_only: false,
+            https: false,
         }
     }
 }
@@ -453,6 +582,33 @@ impl Config {
             .map_err(|e| crate::error::Error::ConfigError(format!("Failed to write config: {e}")))
     }
 
+    /// Settings for transfer sessions from the `transfer` and `network`
+    /// sections.
+    #[must_use]
+    pub fn transfer_config(&self) -> crate::transfer::TransferConfig {
+        crate::transfer::TransferConfig {
+            chunk_size: self.transfer.chunk_size,
+            parallel_streams: self.transfer.parallel_chunks,
+            bandwidth_limit: self.transfer.bandwidth_limit,
+            compression: self.transfer.compression,
+            compression_level: self.transfer.compression_level,
+            verify_checksums: self.transfer.verify_checksum,
+            discovery_port: self.network.port,
+            transfer_port: self.network.transfer_port_range.0,
+            transfer_port_end: self.network.transfer_port_range.1,
+            reconnect_grace: self.transfer.reconnect_grace,
+            ipv6: self.network.ipv6,
+            interfaces: self.network.interface_filter(),
+            privacy_mode: self.network.privacy_mode,
+            sweep: self.network.sweep_options(),
+            multicast: self.network.multicast_options(),
+            relay: self.network.relay.clone(),
+            limits: self.transfer.limits.clone(),
+            quarantine: self.transfer.quarantine.clone(),
+            ..Default::default()
+        }
+    }
+
     /// Get the default configuration directory path.
     #[must_use]
     pub fn config_dir() -> Option<PathBuf> {
@@ -469,7 +625,7 @@ impl Config {
     }
 }
 
-mod humantime_serde {
+pub(crate) mod humantime_serde {
     use serde::{Deserialize, Deserializer, Serializer};
     use std::time::Duration;
 
@@ -587,6 +743,93 @@ port = 9999
         assert_eq!(config.transfer.chunk_size, crate::DEFAULT_CHUNK_SIZE);
     }
 
+    #[test]
+    fn test_network_interface_lists() {
+        let toml_str = r#"
+[network]
+interface = "eth0"
+deny_interfaces = ["docker*", "veth*"]
+"#;
+
+        let config: Config = toml::from_str(toml_str).expect("parse network config");
+        assert!(config.network.allow_interfaces.is_empty());
+
+        let options = config.network.discovery_options();
+        assert!(options.ipv6);
+        assert_eq!(options.interfaces.interface, "eth0");
+        assert_eq!(options.interfaces.deny, vec!["docker*", "veth*"]);
+        assert!(options.interfaces.allows("eth0"));
+        assert!(!options.interfaces.allows("docker0"));
+        assert!(!options.privacy);
+    }
+
+    #[test]
+    fn test_network_privacy_mode() {
+        let config: Config =
+            toml::from_str("[network]\nprivacy_mode = true\n").expect("parse network config");
+        assert!(config.network.privacy_mode);
+        assert!(config.network.discovery_options().privacy);
+    }
+
+    #[test]
+    fn test_network_sweep() {
+        let config: Config =
+            toml::from_str("[network]\nsweep = true\nsweep_ranges = [\"10.20.0.0/24\"]\n")
+                .expect("parse network config");
+        let sweep = config.network.discovery_options().sweep;
+        assert!(sweep.enabled);
+        assert_eq!(sweep.ranges, vec!["10.20.0.0/24".to_string()]);
+        assert!(!Config::default().network.sweep);
+    }
+
+    #[test]
+    fn test_network_multicast() {
+        let defaults = Config::default().network.discovery_options().multicast;
+        assert!(defaults.enabled);
+        assert_eq!(defaults.group, crate::discovery::IPV4_MULTICAST_GROUP);
+        assert_eq!(defaults.ttl, 1);
+
+        let config: Config =
+            toml::from_str("[network]\nmulticast_group = \"239.192.0.7\"\nmulticast_ttl = 2\n")
+                .expect("parse network config");
+        let multicast = config.network.multicast_options();
+        assert_eq!(multicast.group, std::net::Ipv4Addr::new(239, 192, 0, 7));
+        assert_eq!(multicast.ttl, 2);
+    }
+
+    #[test]
+    fn test_network_relay() {
+        assert!(Config::default().network.relay.is_none());
+
+        let config: Config = toml::from_str("[network]\nrelay = \"relay.example.com:52542\"\n")
+            .expect("parse network config");
+        assert_eq!(
+            config.network.relay.as_deref(),
+            Some("relay.example.com:52542")
+        );
+    }
+
+    #[test]
+    fn test_peers() {
+        let config: Config = toml::from_str(
+            "[[peers]]\ndevice = \"Laptop\"\naddresses = [\"laptop.tailnet.ts.net\", \"100.64.0.2:52531\"]\n",
+        )
+        .expect("parse peers");
+        assert_eq!(config.peers.len(), 1);
+        assert_eq!(config.peers[0].addresses.len(), 2);
+
+        let device = crate::trust::TrustedDevice::new(
+            uuid::Uuid::new_v4(),
+            "laptop".to_string(),
+            "key".to_string(),
+        );
+        assert!(config.peers[0].matches(&device));
+
+        let serialized = toml::to_string(&config).expect("serialize config");
+        let reparsed: Config = toml::from_str(&serialized).expect("reparse config");
+        assert_eq!(reparsed.peers, config.peers);
+    }
+
     #[test]
     fn test_config_path() {
         let path = Config::config_path();
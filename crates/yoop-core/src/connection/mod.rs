@@ -4,7 +4,9 @@
 //! bypassing the normal discovery mechanism. This is useful for VPN/overlay networks
 //! (Tailscale, WireGuard, ZeroTier) where UDP broadcast and mDNS discovery don't work.
 
-use std::net::{IpAddr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use tokio::net::TcpListener;
 
 use crate::error::{Error, Result};
 use crate::transfer::DEFAULT_TRANSFER_PORT;
@@ -16,6 +18,8 @@ use crate::transfer::DEFAULT_TRANSFER_PORT;
 /// - `IP:PORT` (e.g., `192.168.1.100:52540`) - uses specified port
 /// - `[IPv6]` (e.g., `[::1]`) - uses default port 52530
 /// - `[IPv6]:PORT` (e.g., `[::1]:52540`) - uses specified port
+/// - `[IPv6%ZONE]:PORT` (e.g., `[fe80::1%eth0]:52540`) - link-local address
+///   with an interface name or index as the zone
 ///
 /// # Examples
 ///
@@ -29,6 +33,10 @@ use crate::transfer::DEFAULT_TRANSFER_PORT;
 /// // IPv4 with custom port
 /// let addr = parse_host_address("192.168.1.100:52540").unwrap();
 /// assert_eq!(addr.port(), 52540);
+///
+/// // IPv6 with custom port
+/// let addr = parse_host_address("[2001:db8::1]:52540").unwrap();
+/// assert!(addr.is_ipv6());
 /// ```
 ///
 /// # Errors
@@ -36,44 +44,159 @@ use crate::transfer::DEFAULT_TRANSFER_PORT;
 /// Returns an error if the host string cannot be parsed.
 pub fn parse_host_address(host: &str) -> Result<SocketAddr> {
     let host = host.trim();
+    let invalid = || {
+        Error::InvalidInput(format!(
+            "Invalid host format '{host}'. Use IP, IP:PORT or [IPv6]:PORT (e.g., 192.168.1.100:52530 or [fe80::1%eth0]:52530)"
+        ))
+    };
 
-    if let Ok(addr) = host.parse::<SocketAddr>() {
-        return Ok(addr);
+    if let Some(rest) = host.strip_prefix('[') {
+        let (inner, after) = rest.split_once(']').ok_or_else(invalid)?;
+        let port = if after.is_empty() {
+            DEFAULT_TRANSFER_PORT
+        } else {
+            parse_port(after.strip_prefix(':').ok_or_else(invalid)?)?
+        };
+        return parse_scoped_ip(inner, port).ok_or_else(invalid);
     }
 
-    if host.starts_with('[') && host.ends_with(']') {
-        let ip_str = &host[1..host.len() - 1];
-        let ip: IpAddr = ip_str.parse().map_err(|_| {
-            Error::InvalidInput(format!(
-                "Invalid host format '{host}'. Use IP or IP:PORT (e.g., 192.168.1.100 or 192.168.1.100:52530)"
-            ))
-        })?;
-        return Ok(SocketAddr::new(ip, DEFAULT_TRANSFER_PORT));
+    if let Ok(addr) = host.parse::<SocketAddr>() {
+        return Ok(addr);
     }
 
-    if let Ok(ip) = host.parse::<IpAddr>() {
-        return Ok(SocketAddr::new(ip, DEFAULT_TRANSFER_PORT));
+    if let Some(addr) = parse_scoped_ip(host, DEFAULT_TRANSFER_PORT) {
+        return Ok(addr);
     }
 
     if let Some((ip_part, port_part)) = host.rsplit_once(':') {
         if !ip_part.contains(':') {
-            let ip: IpAddr = ip_part.parse().map_err(|_| {
-                Error::InvalidInput(format!(
-                    "Invalid host format '{host}'. Use IP or IP:PORT (e.g., 192.168.1.100 or 192.168.1.100:52530)"
-                ))
-            })?;
-            let port: u16 = port_part.parse().map_err(|_| {
-                Error::InvalidInput(format!(
-                    "Invalid port '{port_part}'. Port must be a number between 1 and 65535"
-                ))
-            })?;
-            return Ok(SocketAddr::new(ip, port));
+            let ip: IpAddr = ip_part.parse().map_err(|_| invalid())?;
+            return Ok(SocketAddr::new(ip, parse_port(port_part)?));
+        }
+    }
+
+    Err(invalid())
+}
+
+fn parse_port(port: &str) -> Result<u16> {
+    port.parse().map_err(|_| {
+        Error::InvalidInput(format!(
+            "Invalid port '{port}'. Port must be a number between 1 and 65535"
+        ))
+    })
+}
+
+/// Parse an IP address with an optional IPv6 `%zone` suffix.
+fn parse_scoped_ip(s: &str, port: u16) -> Option<SocketAddr> {
+    let (ip, zone) = match s.split_once('%') {
+        Some((ip, zone)) => (ip, Some(zone)),
+        None => (s, None),
+    };
+
+    match ip.parse::<IpAddr>().ok()? {
+        IpAddr::V4(v4) if zone.is_none() => Some(SocketAddr::V4(SocketAddrV4::new(v4, port))),
+        IpAddr::V4(_) => None,
+        IpAddr::V6(v6) => {
+            let scope_id = match zone {
+                None => 0,
+                Some(zone) => zone.parse().ok().or_else(|| interface_index(zone))?,
+            };
+            Some(SocketAddr::V6(SocketAddrV6::new(v6, port, 0, scope_id)))
         }
     }
+}
 
-    Err(Error::InvalidInput(format!(
-        "Invalid host format '{host}'. Use IP or IP:PORT (e.g., 192.168.1.100 or 192.168.1.100:52530)"
-    )))
+/// Look up the index of a network interface by name.
+fn interface_index(name: &str) -> Option<u32> {
+    if_addrs::get_if_addrs()
+        .ok()?
+        .into_iter()
+        .find(|iface| iface.name == name)
+        .and_then(|iface| iface.index)
+}
+
+/// Bind a TCP listener for incoming transfers on all interfaces.
+///
+/// With `ipv6` enabled this binds a dual-stack `[::]:port` socket that accepts
+/// both IPv4 and IPv6 peers, falling back to `0.0.0.0:port` on hosts without
+/// IPv6. Must be called from within a Tokio runtime.
+///
+/// # Errors
+///
+/// Returns an error if the port cannot be bound.
+pub fn bind_listener(port: u16, ipv6: bool) -> Result<TcpListener> {
+    if ipv6 {
+        match bind_dual_stack(port) {
+            Ok(listener) => return Ok(listener),
+            Err(e) => tracing::debug!("Dual-stack bind failed, using IPv4 only: {e}"),
+        }
+    }
+
+    let std_listener = std::net::TcpListener::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))?;
+    std_listener.set_nonblocking(true)?;
+    Ok(TcpListener::from_std(std_listener)?)
+}
+
+/// Bind a TCP listener on the first free port from `start` to `end`.
+///
+/// Ports are tried in order, skipping those already in use, so concurrent
+/// sessions on one machine each get their own port. An `end` below `start`
+/// tries `start` only, and a `start` of 0 binds an ephemeral port.
+///
+/// # Errors
+///
+/// Returns [`Error::NoFreePort`] if every port in the range is in use, or
+/// another error if binding fails for a different reason.
+pub fn bind_listener_in_range(start: u16, end: u16, ipv6: bool) -> Result<TcpListener> {
+    for port in start..=end.max(start) {
+        match bind_listener(port, ipv6) {
+            Ok(listener) => return Ok(listener),
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                tracing::debug!("Port {port} in use, trying the next one");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(Error::NoFreePort {
+        start,
+        end: end.max(start),
+    })
+}
+
+fn bind_dual_stack(port: u16) -> std::io::Result<TcpListener> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::IPV6,
+        socket2::Type::STREAM,
+        Some(socket2::Protocol::TCP),
+    )?;
+
+    socket.set_only_v6(false)?;
+
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+
+    let addr = SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, port, 0, 0);
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    socket.set_nonblocking(true)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+/// Convert IPv4-mapped IPv6 peer addresses (`::ffff:a.b.c.d`) to plain IPv4.
+///
+/// Dual-stack listeners report IPv4 peers in mapped form; this keeps stored
+/// and displayed addresses consistent with IPv4-only listeners.
+#[must_use]
+pub fn canonical_addr(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V6(v6) => v6
+            .ip()
+            .to_ipv4_mapped()
+            .map_or(addr, |ip| SocketAddr::V4(SocketAddrV4::new(ip, v6.port()))),
+        SocketAddr::V4(_) => addr,
+    }
 }
 
 #[cfg(test)]
@@ -141,4 +264,86 @@ mod tests {
         let addr = parse_host_address("  192.168.1.100  ").unwrap();
         assert_eq!(addr.ip().to_string(), "192.168.1.100");
     }
+
+    #[test]
+    fn test_parse_host_ipv6_numeric_scope() {
+        let addr = parse_host_address("[fe80::1%3]:52540").unwrap();
+        let SocketAddr::V6(v6) = addr else {
+            panic!("expected IPv6 address");
+        };
+        assert_eq!(v6.ip().to_string(), "fe80::1");
+        assert_eq!(v6.scope_id(), 3);
+        assert_eq!(v6.port(), 52540);
+    }
+
+    #[test]
+    fn test_parse_host_ipv6_bare() {
+        let addr = parse_host_address("2001:db8::1").unwrap();
+        assert_eq!(addr.ip().to_string(), "2001:db8::1");
+        assert_eq!(addr.port(), DEFAULT_TRANSFER_PORT);
+    }
+
+    #[test]
+    fn test_parse_host_ipv6_invalid() {
+        assert!(parse_host_address("[::1").is_err());
+        assert!(parse_host_address("[::1]52540").is_err());
+        assert!(parse_host_address("[::1]:abc").is_err());
+        assert!(parse_host_address("[fe80::1%no-such-iface]").is_err());
+        assert!(parse_host_address("[192.168.1.1%2]").is_err());
+    }
+
+    #[test]
+    fn test_canonical_addr() {
+        let mapped: SocketAddr = "[::ffff:192.168.1.5]:52530".parse().unwrap();
+        assert_eq!(
+            canonical_addr(mapped),
+            "192.168.1.5:52530".parse::<SocketAddr>().unwrap()
+        );
+
+        let v6: SocketAddr = "[2001:db8::1]:52530".parse().unwrap();
+        assert_eq!(canonical_addr(v6), v6);
+    }
+
+    #[tokio::test]
+    async fn test_bind_listener_dual_stack() {
+        let listener = bind_listener(0, true).expect("bind listener");
+        let port = listener.local_addr().unwrap().port();
+
+        let accept = tokio::spawn(async move {
+            let (_, peer) = listener.accept().await.expect("accept");
+            canonical_addr(peer)
+        });
+
+        tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .expect("connect over IPv4");
+        let peer = accept.await.unwrap();
+        assert!(
+            peer.is_ipv4(),
+            "mapped peer should be canonicalised: {peer}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bind_listener_ipv4_only() {
+        let listener = bind_listener(0, false).expect("bind listener");
+        assert!(listener.local_addr().unwrap().is_ipv4());
+    }
+
+    #[tokio::test]
+    async fn test_bind_listener_in_range_skips_used_ports() {
+        let taken = bind_listener(0, false).expect("bind listener");
+        let start = taken.local_addr().unwrap().port();
+        let end = start.saturating_add(10);
+
+        let listener = bind_listener_in_range(start, end, false).expect("bind in range");
+        let port = listener.local_addr().unwrap().port();
+        assert!(
+            port > start && port <= end,
+            "port {port} outside {start}-{end}"
+        );
+
+        let exhausted = bind_listener_in_range(start, start, false);
+        assert!(matches!(exhausted, Err(Error::NoFreePort { .. })));
+    }
 }
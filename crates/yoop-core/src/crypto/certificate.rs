@@ -0,0 +1,242 @@
+//! Persistent self-signed certificates.
+//!
+//! Peer-to-peer transfers use a fresh certificate per session, but a server
+//! that browsers connect to (the web interface) needs one that stays the
+//! same across restarts so users can check its fingerprint once and
+//! recognise it afterwards. The certificate and its private key are kept
+//! together in a single PEM file readable only by the owner.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+
+/// A self-signed TLS certificate and its private key.
+#[derive(Clone)]
+pub struct SelfSignedCertificate {
+    cert_der: Vec<u8>,
+    key_der: Zeroizing<Vec<u8>>,
+}
+
+impl fmt::Debug for SelfSignedCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SelfSignedCertificate")
+            .field("fingerprint", &self.fingerprint())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SelfSignedCertificate {
+    /// Generate a new certificate for the given subject alternative names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if key or certificate generation fails.
+    pub fn generate(subject_alt_names: Vec<String>) -> Result<Self> {
+        let cert_params = rcgen::CertificateParams::new(subject_alt_names)
+            .map_err(|e| Error::TlsError(format!("Failed to create cert params: {e}")))?;
+
+        let key_pair = rcgen::KeyPair::generate()
+            .map_err(|e| Error::TlsError(format!("Failed to generate key pair: {e}")))?;
+
+        let cert = cert_params
+            .self_signed(&key_pair)
+            .map_err(|e| Error::TlsError(format!("Failed to generate self-signed cert: {e}")))?;
+
+        Ok(Self {
+            cert_der: cert.der().to_vec(),
+            key_der: Zeroizing::new(key_pair.serialize_der()),
+        })
+    }
+
+    /// Load the certificate stored at `path`, generating and saving a new one
+    /// if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, parsed or written.
+    pub fn load_or_generate(path: &Path) -> Result<Self> {
+        if path.exists() {
+            return Self::load(path);
+        }
+
+        let cert = Self::generate(vec!["localhost".to_string(), "127.0.0.1".to_string()])?;
+        cert.save(path)?;
+        tracing::info!("Generated web certificate at {}", path.display());
+        Ok(cert)
+    }
+
+    /// Load a certificate and key from a PEM file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or does not contain both a
+    /// certificate and a private key.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = Zeroizing::new(fs::read(path).map_err(|e| {
+            Error::TlsError(format!(
+                "Failed to read certificate {}: {e}",
+                path.display()
+            ))
+        })?);
+
+        let cert_der = rustls_pemfile::certs(&mut content.as_slice())
+            .next()
+            .transpose()
+            .map_err(|e| Error::TlsError(format!("Invalid certificate file: {e}")))?
+            .ok_or_else(|| Error::TlsError("Certificate file has no certificate".to_string()))?;
+
+        let key_der = match rustls_pemfile::private_key(&mut content.as_slice())
+            .map_err(|e| Error::TlsError(format!("Invalid certificate file: {e}")))?
+        {
+            Some(PrivateKeyDer::Pkcs8(key)) => key,
+            Some(_) => {
+                return Err(Error::TlsError(
+                    "Certificate key must be in PKCS#8 format".to_string(),
+                ))
+            }
+            None => {
+                return Err(Error::TlsError(
+                    "Certificate file has no private key".to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            cert_der: cert_der.to_vec(),
+            key_der: Zeroizing::new(key_der.secret_pkcs8_der().to_vec()),
+        })
+    }
+
+    /// Save the certificate and key as PEM, readable only by the owner.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                Error::TlsError(format!(
+                    "Failed to create certificate directory {}: {e}",
+                    parent.display()
+                ))
+            })?;
+        }
+
+        let content = Zeroizing::new(format!(
+            "{}{}",
+            pem_block("CERTIFICATE", &self.cert_der),
+            pem_block("PRIVATE KEY", &self.key_der)
+        ));
+
+        let write = |content: &str| {
+            fs::write(path, content)
+                .map_err(|e| Error::TlsError(format!("Failed to write certificate: {e}")))
+        };
+        // restrict the file before the key is written to it
+        write("")?;
+        crate::file::apply_permissions(path, Some(0o600))?;
+        write(&content)
+    }
+
+    /// Get the default path of the web interface certificate.
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "yoop", "Yoop")
+            .map(|dirs| dirs.data_dir().join("web-cert.pem"))
+    }
+
+    /// The certificate in DER encoding.
+    #[must_use]
+    pub fn cert_der(&self) -> &[u8] {
+        &self.cert_der
+    }
+
+    /// SHA-256 fingerprint of the certificate, as colon-separated uppercase
+    /// hex (the format browsers show in their certificate viewer).
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        super::sha256(&self.cert_der)
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    /// Build a rustls server configuration serving this certificate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rustls rejects the certificate or key.
+    pub fn server_config(&self) -> Result<Arc<rustls::ServerConfig>> {
+        let cert_der = CertificateDer::from(self.cert_der.clone());
+        let key_der = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(self.key_der.to_vec()));
+
+        let config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert_der], key_der)
+            .map_err(|e| Error::TlsError(format!("Failed to build server config: {e}")))?;
+
+        Ok(Arc::new(config))
+    }
+}
+
+/// Encode `der` as a PEM block with 64-column lines.
+fn pem_block(label: &str, der: &[u8]) -> String {
+    use base64::prelude::*;
+
+    let encoded = BASE64_STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str("-----END ");
+    pem.push_str(label);
+    pem.push_str("-----\n");
+    pem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cert.pem");
+
+        let cert = SelfSignedCertificate::load_or_generate(&path).unwrap();
+        assert!(path.exists());
+
+        let loaded = SelfSignedCertificate::load_or_generate(&path).unwrap();
+        assert_eq!(loaded.cert_der(), cert.cert_der());
+        assert_eq!(loaded.fingerprint(), cert.fingerprint());
+        assert!(loaded.server_config().is_ok());
+    }
+
+    #[test]
+    fn test_fingerprint_format() {
+        let cert = SelfSignedCertificate::generate(vec!["localhost".to_string()]).unwrap();
+        let fingerprint = cert.fingerprint();
+
+        assert_eq!(fingerprint.len(), 32 * 3 - 1);
+        assert!(fingerprint
+            .split(':')
+            .all(|b| b.len() == 2 && b.chars().all(|c| c.is_ascii_hexdigit())));
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cert.pem");
+        fs::write(&path, "not a certificate").unwrap();
+
+        assert!(SelfSignedCertificate::load(&path).is_err());
+    }
+}
@@ -14,8 +14,16 @@
 //! ## Security
 //!
 //! - Keys are generated using a cryptographically secure RNG
-//! - The secret key is stored in base64 encoding
+//! - The secret key is stored in base64 encoding, or encrypted with a
+//!   passphrase ([`DeviceIdentity::set_passphrase`]); an encrypted identity
+//!   loads only once unlocked (see [`super::unlock`])
+//! - The identity file is readable by its owner only
 //! - Device ID is derived from the public key hash (stable across sessions)
+//! - A separate random resolving key identifies the device's private
+//!   beacons; it is only given to devices it pairs with
+//! - The device ID is kept when the key is [rotated](DeviceIdentity::rotate);
+//!   the identity file then records the signed rotation chain from the
+//!   original key
 
 use std::fs;
 use std::path::PathBuf;
@@ -25,13 +33,27 @@ use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use zeroize::Zeroizing;
+
+use super::rotation::{self, KeyRotation};
+use super::sealed::{KeyEncryption, SealedKey};
+use super::unlock::{self, UnlockCache};
 use crate::error::{Error, Result};
 
+/// Current identity file format version.
+///
+/// Version 2 added passphrase encryption; version 1 files (always plaintext)
+/// are still read.
+const IDENTITY_FILE_VERSION: u32 = 2;
+
+/// Context for deriving the resolving key of identity files that have none.
+const RESOLVING_KEY_CONTEXT: &[u8] = b"yoop:resolving-key:v1";
+
 /// Device identity containing an Ed25519 key pair.
 ///
 /// This struct manages the cryptographic identity of a device, enabling
 /// signature-based authentication for trusted device communication.
-#[derive(Debug)]
+#[derive(Clone)]
 pub struct DeviceIdentity {
     /// The Ed25519 signing key (contains both secret and public key)
     signing_key: SigningKey,
@@ -39,6 +61,24 @@ pub struct DeviceIdentity {
     device_id: Uuid,
     /// Path where this identity is stored (if loaded from/saved to disk)
     path: Option<PathBuf>,
+    /// Signed rotations from the original key to the current one
+    rotations: Vec<KeyRotation>,
+    /// Passphrase-derived key, if the identity is encrypted at rest
+    encryption: Option<KeyEncryption>,
+    /// Secret key for privacy-mode identifiers, shared with paired devices
+    resolving_key: Zeroizing<[u8; 32]>,
+}
+
+impl std::fmt::Debug for DeviceIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceIdentity")
+            .field("signing_key", &self.signing_key)
+            .field("device_id", &self.device_id)
+            .field("path", &self.path)
+            .field("rotations", &self.rotations)
+            .field("encryption", &self.encryption)
+            .finish_non_exhaustive()
+    }
 }
 
 /// Serializable representation of the identity for storage.
@@ -46,10 +86,20 @@ pub struct DeviceIdentity {
 struct IdentityFile {
     /// Version for future compatibility
     version: u32,
-    /// Base64-encoded secret key bytes
-    secret_key: String,
+    /// Base64-encoded secret key bytes (unencrypted identities)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret_key: Option<String>,
+    /// Passphrase-encrypted secret key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encrypted_key: Option<SealedKey>,
     /// Device ID (cached for convenience)
     device_id: Uuid,
+    /// Signed rotation chain, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rotations: Vec<KeyRotation>,
+    /// Base64-encoded resolving key (generated on load if missing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resolving_key: Option<String>,
 }
 
 impl DeviceIdentity {
@@ -71,9 +121,20 @@ impl DeviceIdentity {
             signing_key,
             device_id,
             path: None,
+            rotations: Vec::new(),
+            encryption: None,
+            resolving_key: Zeroizing::new(super::random_bytes()),
         })
     }
 
+    /// Set where [`DeviceIdentity::save`] writes this identity.
+    #[cfg(all(test, feature = "update"))]
+    #[must_use]
+    pub(crate) fn with_path(mut self, path: PathBuf) -> Self {
+        self.path = Some(path);
+        self
+    }
+
     /// Load device identity from the default storage location.
     ///
     /// # Errors
@@ -91,10 +152,45 @@ impl DeviceIdentity {
 
     /// Load device identity from a specific path.
     ///
+    /// An encrypted identity is opened with a cached unlock or the
+    /// passphrase in `YOOP_IDENTITY_PASSPHRASE`.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be read or parsed.
+    /// Returns an error if the file cannot be read or parsed, or
+    /// [`Error::IdentityLocked`] if it is encrypted and not unlocked.
     pub fn load_from(path: PathBuf) -> Result<Self> {
+        Self::open(path, None)
+    }
+
+    /// Unlock the identity at the default location with its passphrase.
+    ///
+    /// The unlock is remembered for the rest of the process.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidPassphrase`] if the passphrase is wrong, or
+    /// an error if the file cannot be read.
+    pub fn unlock(passphrase: &str) -> Result<Self> {
+        let path = Self::default_path().ok_or_else(|| {
+            Error::ConfigError("Cannot determine identity storage path".to_string())
+        })?;
+        Self::unlock_from(path, passphrase)
+    }
+
+    /// Unlock the identity at `path` with its passphrase.
+    ///
+    /// Unencrypted identities load as usual; the passphrase is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidPassphrase`] if the passphrase is wrong, or
+    /// an error if the file cannot be read.
+    pub fn unlock_from(path: PathBuf, passphrase: &str) -> Result<Self> {
+        Self::open(path, Some(passphrase))
+    }
+
+    fn open(path: PathBuf, passphrase: Option<&str>) -> Result<Self> {
         let content = fs::read_to_string(&path).map_err(|e| {
             Error::ConfigError(format!(
                 "Failed to read identity file {}: {}",
@@ -106,30 +202,101 @@ impl DeviceIdentity {
         let file: IdentityFile = serde_json::from_str(&content)
             .map_err(|e| Error::ConfigError(format!("Failed to parse identity file: {e}")))?;
 
-        let secret_bytes = BASE64_STANDARD
-            .decode(&file.secret_key)
-            .map_err(|e| Error::ConfigError(format!("Failed to decode secret key: {e}")))?;
-
-        let secret_array: [u8; 32] = secret_bytes
-            .try_into()
-            .map_err(|_| Error::ConfigError("Invalid secret key length".to_string()))?;
-
-        let signing_key = SigningKey::from_bytes(&secret_array);
-        let derived_id = Self::derive_device_id(&signing_key.verifying_key());
+        let (secret, encryption) = match (&file.secret_key, &file.encrypted_key) {
+            (Some(secret_key), _) => {
+                let secret_bytes =
+                    Zeroizing::new(BASE64_STANDARD.decode(secret_key).map_err(|e| {
+                        Error::ConfigError(format!("Failed to decode secret key: {e}"))
+                    })?);
+                let mut secret = Zeroizing::new([0u8; 32]);
+                if secret_bytes.len() != secret.len() {
+                    return Err(Error::ConfigError("Invalid secret key length".to_string()));
+                }
+                secret.copy_from_slice(&secret_bytes);
+                (secret, None)
+            }
+            (None, Some(sealed)) => {
+                let encryption = Self::key_encryption(file.device_id, sealed, passphrase)?;
+                let secret = encryption.open(sealed, file.device_id)?;
+                unlock::remember(file.device_id, encryption.salt(), encryption.key());
+                (secret, Some(encryption))
+            }
+            (None, None) => {
+                return Err(Error::ConfigError(
+                    "Identity file contains no key".to_string(),
+                ))
+            }
+        };
 
-        if derived_id != file.device_id {
+        let signing_key = SigningKey::from_bytes(&secret);
+        let current_key = BASE64_STANDARD.encode(signing_key.verifying_key().to_bytes());
+
+        let original_key = file.rotations.first().map_or_else(
+            || Some(signing_key.verifying_key()),
+            |first| {
+                BASE64_STANDARD
+                    .decode(&first.old_public_key)
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+            },
+        );
+        let chain_ok = original_key.is_some_and(|key| {
+            Self::derive_device_id(&key) == file.device_id
+                && rotation::follow_rotations(
+                    file.device_id,
+                    &BASE64_STANDARD.encode(key.to_bytes()),
+                    &file.rotations,
+                ) == current_key
+        });
+
+        if !chain_ok {
             return Err(Error::ConfigError(
                 "Device ID mismatch in identity file".to_string(),
             ));
         }
 
+        // identities created before resolving keys existed derive one from
+        // the secret key, so it stays the same until the 0.3 migration saves
+        // it without loading having to write the file
+        let resolving_key = file
+            .resolving_key
+            .as_deref()
+            .and_then(|key| BASE64_STANDARD.decode(key).ok())
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .unwrap_or_else(|| super::hmac_sha256(secret.as_ref(), RESOLVING_KEY_CONTEXT));
+
         Ok(Self {
             signing_key,
             device_id: file.device_id,
             path: Some(path),
+            rotations: file.rotations,
+            encryption,
+            resolving_key: Zeroizing::new(resolving_key),
         })
     }
 
+    /// Find the key that opens a sealed identity key.
+    ///
+    /// Tries, in order: an explicit passphrase, a cached unlock, and the
+    /// passphrase environment variable.
+    fn key_encryption(
+        device_id: Uuid,
+        sealed: &SealedKey,
+        passphrase: Option<&str>,
+    ) -> Result<KeyEncryption> {
+        if let Some(passphrase) = passphrase {
+            return KeyEncryption::for_sealed(sealed, passphrase);
+        }
+        if let Some(key) = unlock::cached_key(device_id, &sealed.salt_bytes()?) {
+            return KeyEncryption::from_key(sealed, *key);
+        }
+        if let Ok(passphrase) = std::env::var(unlock::PASSPHRASE_ENV) {
+            return KeyEncryption::for_sealed(sealed, &passphrase);
+        }
+        Err(Error::IdentityLocked)
+    }
+
     /// Load device identity from storage, or generate a new one if not found.
     ///
     /// This is the recommended way to get a device identity. On first run,
@@ -177,16 +344,27 @@ impl DeviceIdentity {
             })?;
         }
 
+        let secret = Zeroizing::new(self.signing_key.to_bytes());
+        let (secret_key, encrypted_key) = match &self.encryption {
+            Some(encryption) => (None, Some(encryption.seal(&secret, self.device_id)?)),
+            None => (Some(BASE64_STANDARD.encode(secret.as_ref())), None),
+        };
+
         let file = IdentityFile {
-            version: 1,
-            secret_key: BASE64_STANDARD.encode(self.signing_key.to_bytes()),
+            version: IDENTITY_FILE_VERSION,
+            secret_key,
+            encrypted_key,
             device_id: self.device_id,
+            rotations: self.rotations.clone(),
+            resolving_key: Some(self.resolving_key_base64()),
         };
 
-        let content = serde_json::to_string_pretty(&file)
-            .map_err(|e| Error::Serialization(format!("Failed to serialize identity: {e}")))?;
+        let content = Zeroizing::new(
+            serde_json::to_string_pretty(&file)
+                .map_err(|e| Error::Serialization(format!("Failed to serialize identity: {e}")))?,
+        );
 
-        fs::write(path, content)
+        crate::file::write_private(path, content.as_bytes())
             .map_err(|e| Error::ConfigError(format!("Failed to write identity file: {e}")))?;
 
         Ok(())
@@ -274,16 +452,119 @@ impl DeviceIdentity {
         BASE64_STANDARD.encode(self.public_key_bytes())
     }
 
+    /// Get the resolving key for privacy-mode identifiers.
+    ///
+    /// Unlike the public key this is secret: it is only sent to devices
+    /// during [pairing](crate::trust::pairing), so only they can recognise
+    /// our private beacons.
+    #[must_use]
+    pub fn resolving_key(&self) -> [u8; 32] {
+        *self.resolving_key
+    }
+
+    /// Get the resolving key as a base64-encoded string.
+    #[must_use]
+    pub fn resolving_key_base64(&self) -> String {
+        BASE64_STANDARD.encode(self.resolving_key.as_ref())
+    }
+
     /// Get the verifying key (public key).
     #[must_use]
     pub fn verifying_key(&self) -> VerifyingKey {
         self.signing_key.verifying_key()
     }
 
+    /// Replace the key pair with a freshly generated one.
+    ///
+    /// The device ID is kept. The returned statement is signed by both the
+    /// old and new keys and is appended to [`DeviceIdentity::rotations`], so
+    /// trusted peers accept the new key on next contact. The caller must
+    /// [save](DeviceIdentity::save) the identity.
+    pub fn rotate(&mut self) -> KeyRotation {
+        let new_key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let new_identity = Self {
+            signing_key: new_key.clone(),
+            device_id: self.device_id,
+            path: None,
+            rotations: Vec::new(),
+            encryption: None,
+            resolving_key: self.resolving_key.clone(),
+        };
+        let rotated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        let statement = KeyRotation::new(self.device_id, self, &new_identity, rotated_at);
+        self.rotations.push(statement.clone());
+        self.signing_key = new_key;
+        statement
+    }
+
+    /// Whether the identity is encrypted at rest.
+    #[must_use]
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    /// Encrypt the identity with a passphrase, or remove encryption with
+    /// `None`.
+    ///
+    /// The caller must [save](DeviceIdentity::save) the identity.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key cannot be derived.
+    pub fn set_passphrase(&mut self, passphrase: Option<&str>) -> Result<()> {
+        self.encryption = if let Some(passphrase) = passphrase {
+            let encryption = KeyEncryption::new(passphrase)?;
+            unlock::remember(self.device_id, encryption.salt(), encryption.key());
+            Some(encryption)
+        } else {
+            unlock::forget();
+            None
+        };
+        Ok(())
+    }
+
+    /// Keep this identity unlocked for other processes until `ttl` passes.
+    ///
+    /// Does nothing for unencrypted identities.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the unlock cache cannot be written.
+    pub fn cache_unlock(&self, ttl: std::time::Duration) -> Result<()> {
+        let Some(encryption) = &self.encryption else {
+            return Ok(());
+        };
+        let cache = UnlockCache::default_path()
+            .ok_or_else(|| Error::ConfigError("Cannot determine unlock cache path".to_string()))?;
+        UnlockCache::new(cache).store(self.device_id, encryption.salt(), encryption.key(), ttl)
+    }
+
+    /// Forget any cached unlock, in this process and on disk.
+    ///
+    /// Returns `true` if an unlock cache file was removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the unlock cache file cannot be removed.
+    pub fn lock() -> Result<bool> {
+        unlock::forget();
+        UnlockCache::default_path().map_or(Ok(false), |path| UnlockCache::new(path).clear())
+    }
+
+    /// Signed rotations from the original key to the current one, oldest
+    /// first.
+    #[must_use]
+    pub fn rotations(&self) -> &[KeyRotation] {
+        &self.rotations
+    }
+
     /// Get the stable device ID.
     ///
-    /// This ID is derived from the public key and remains stable across
-    /// sessions as long as the same identity is used.
+    /// This ID is derived from the original public key and remains stable
+    /// across sessions and key rotations.
     #[must_use]
     pub fn device_id(&self) -> Uuid {
         self.device_id
@@ -380,6 +661,7 @@ mod tests {
         assert_eq!(loaded.device_id(), identity.device_id());
         assert_eq!(loaded.public_key_bytes(), identity.public_key_bytes());
         assert_eq!(loaded.public_key_base64(), identity.public_key_base64());
+        assert_eq!(loaded.resolving_key(), identity.resolving_key());
 
         let data = b"test data";
         let signature = loaded.sign(data);
@@ -390,6 +672,115 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_rotate_keeps_device_id() {
+        let temp_dir = tempfile::tempdir().expect("should create temp dir");
+        let path = temp_dir.path().join("test_identity.json");
+
+        let mut identity = DeviceIdentity::generate().expect("should generate identity");
+        identity.path = Some(path.clone());
+        let original_key = identity.public_key_base64();
+
+        let first = identity.rotate();
+        let second = identity.rotate();
+        identity.save().expect("should save identity");
+
+        assert_ne!(identity.public_key_base64(), original_key);
+        assert!(first.verify() && second.verify());
+        assert_eq!(first.old_public_key, original_key);
+        assert_eq!(second.new_public_key, identity.public_key_base64());
+
+        let loaded = DeviceIdentity::load_from(path.clone()).expect("should load identity");
+        assert_eq!(loaded.device_id(), identity.device_id());
+        assert_eq!(loaded.public_key_base64(), identity.public_key_base64());
+        assert_eq!(loaded.rotations().len(), 2);
+
+        let mut file: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).expect("read")).expect("parse");
+        file["rotations"]
+            .as_array_mut()
+            .expect("rotations")
+            .remove(0);
+        fs::write(&path, file.to_string()).expect("write");
+        assert!(DeviceIdentity::load_from(path).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_identity() {
+        let temp_dir = tempfile::tempdir().expect("should create temp dir");
+        let path = temp_dir.path().join("test_identity.json");
+
+        let mut identity = DeviceIdentity::generate().expect("should generate identity");
+        identity.path = Some(path.clone());
+        identity
+            .set_passphrase(Some("correct horse"))
+            .expect("should set passphrase");
+        identity.save().expect("should save identity");
+
+        let content = fs::read_to_string(&path).expect("read");
+        assert!(!content.contains("secret_key"));
+        assert!(content.contains("encrypted_key"));
+        assert!(!content.contains(&BASE64_STANDARD.encode(identity.signing_key.to_bytes())));
+
+        // unlocked in this process by set_passphrase
+        let loaded = DeviceIdentity::load_from(path.clone()).expect("should load identity");
+        assert!(loaded.is_encrypted());
+        assert_eq!(loaded.public_key_base64(), identity.public_key_base64());
+
+        unlock::forget();
+        assert!(matches!(
+            DeviceIdentity::unlock_from(path.clone(), "battery staple"),
+            Err(Error::InvalidPassphrase)
+        ));
+        let mut unlocked =
+            DeviceIdentity::unlock_from(path.clone(), "correct horse").expect("should unlock");
+        assert_eq!(unlocked.device_id(), identity.device_id());
+
+        unlocked
+            .set_passphrase(None)
+            .expect("should clear passphrase");
+        unlocked.save().expect("should save identity");
+        let plain = DeviceIdentity::load_from(path.clone()).expect("should load identity");
+        assert!(!plain.is_encrypted());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).expect("metadata").permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_load_version_1_identity() {
+        let temp_dir = tempfile::tempdir().expect("should create temp dir");
+        let path = temp_dir.path().join("test_identity.json");
+        let identity = DeviceIdentity::generate().expect("should generate identity");
+
+        let v1 = serde_json::json!({
+            "version": 1,
+            "secret_key": BASE64_STANDARD.encode(identity.signing_key.to_bytes()),
+            "device_id": identity.device_id(),
+        });
+        fs::write(&path, v1.to_string()).expect("write");
+
+        let loaded = DeviceIdentity::load_from(path.clone()).expect("should load identity");
+        assert_eq!(loaded.public_key_base64(), identity.public_key_base64());
+        assert!(!loaded.is_encrypted());
+        assert_eq!(
+            fs::read_to_string(&path).expect("read"),
+            v1.to_string(),
+            "Loading should not rewrite the file"
+        );
+
+        let reloaded = DeviceIdentity::load_from(path).expect("should load identity");
+        assert_eq!(
+            reloaded.resolving_key(),
+            loaded.resolving_key(),
+            "A missing resolving key should be derived the same way each time"
+        );
+    }
+
     #[test]
     fn test_different_identities_have_different_keys() {
         let id1 = DeviceIdentity::generate().expect("should generate identity");
@@ -397,6 +788,7 @@ mod tests {
 
         assert_ne!(id1.device_id(), id2.device_id());
         assert_ne!(id1.public_key_bytes(), id2.public_key_bytes());
+        assert_ne!(id1.resolving_key(), id2.resolving_key());
     }
 
     #[test]
@@ -2,8 +2,10 @@
 //!
 //! This module provides:
 //! - TLS 1.3 configuration for secure connections
-//! - Ed25519 key pairs for device identity
-//! - HMAC for code verification
+//! - Persistent self-signed certificates for the web interface
+//! - Ed25519 key pairs for device identity, with signed key rotation and
+//!   optional passphrase encryption at rest
+//! - SPAKE2 share code verification, bound to the TLS session
 //! - SHA-256 for file integrity
 //! - xxHash for fast chunk verification
 //!
@@ -12,16 +14,31 @@
 //! - All transfers are encrypted with TLS 1.3
 //! - Perfect forward secrecy via ephemeral ECDH keys
 //! - Ed25519 signatures for trusted device verification
-//! - HMAC-SHA256 for timing-attack-resistant code verification
+//! - Share codes are checked with a PAKE ([`Spake2`]) whose key
+//!   confirmation covers a [`ChannelBinding`] value, so a man in the middle
+//!   who terminates TLS on both sides cannot pass it, and nothing sent lets
+//!   anyone brute-force the code offline
 
+mod certificate;
 mod identity;
+mod rotation;
+mod sealed;
+mod spake2;
+pub mod unlock;
 
+pub use self::spake2::{Spake2, Spake2Role};
+pub use certificate::SelfSignedCertificate;
 pub use identity::DeviceIdentity;
+pub use rotation::{follow_rotations, KeyRotation};
+pub use unlock::UnlockCache;
 
 use std::sync::Arc;
 
 use crate::error::{Error, Result};
 
+/// TLS exporter label for [`ChannelBinding`].
+const CHANNEL_BINDING_LABEL: &[u8] = b"EXPORTER-yoop-code-verification";
+
 /// TLS configuration for Yoop connections.
 ///
 /// This struct holds either a server or client configuration for TLS 1.3.
@@ -43,28 +60,14 @@ impl TlsConfig {
     ///
     /// Returns an error if certificate generation or configuration fails.
     pub fn server() -> Result<Self> {
-        let subject_alt_names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
-        let cert_params = rcgen::CertificateParams::new(subject_alt_names)
-            .map_err(|e| Error::TlsError(format!("Failed to create cert params: {e}")))?;
-
-        let key_pair = rcgen::KeyPair::generate()
-            .map_err(|e| Error::TlsError(format!("Failed to generate key pair: {e}")))?;
-
-        let cert = cert_params
-            .self_signed(&key_pair)
-            .map_err(|e| Error::TlsError(format!("Failed to generate self-signed cert: {e}")))?;
-
-        let cert_der = rustls::pki_types::CertificateDer::from(cert.der().to_vec());
-        let key_der = rustls::pki_types::PrivateKeyDer::try_from(key_pair.serialize_der())
-            .map_err(|e| Error::TlsError(format!("Failed to convert private key: {e}")))?;
-
-        let config = rustls::ServerConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(vec![cert_der], key_der)
-            .map_err(|e| Error::TlsError(format!("Failed to build server config: {e}")))?;
+        let cert = SelfSignedCertificate::generate(vec![
+            "localhost".to_string(),
+            "127.0.0.1".to_string(),
+        ])?;
+        let config = cert.server_config()?;
 
         Ok(Self {
-            server: Some(Arc::new(config)),
+            server: Some(config),
             client: None,
         })
     }
@@ -102,10 +105,43 @@ impl TlsConfig {
     }
 }
 
+/// A TLS stream that can export a value unique to its session.
+///
+/// Both ends of one TLS session export the same value, while a man in the
+/// middle running a separate session with each side ends up with two
+/// different ones.
+pub trait ChannelBinding {
+    /// Export the channel binding value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the TLS session cannot export keying material.
+    fn channel_binding(&self) -> Result<[u8; 32]>;
+}
+
+impl<IO> ChannelBinding for tokio_rustls::client::TlsStream<IO> {
+    fn channel_binding(&self) -> Result<[u8; 32]> {
+        self.get_ref()
+            .1
+            .export_keying_material([0u8; 32], CHANNEL_BINDING_LABEL, None)
+            .map_err(|e| Error::TlsError(format!("channel binding unavailable: {e}")))
+    }
+}
+
+impl<IO> ChannelBinding for tokio_rustls::server::TlsStream<IO> {
+    fn channel_binding(&self) -> Result<[u8; 32]> {
+        self.get_ref()
+            .1
+            .export_keying_material([0u8; 32], CHANNEL_BINDING_LABEL, None)
+            .map_err(|e| Error::TlsError(format!("channel binding unavailable: {e}")))
+    }
+}
+
 /// Certificate verifier that accepts any certificate.
 ///
-/// This is used for Yoop's client connections where we trust
-/// the peer based on the share code HMAC rather than the certificate.
+/// This is used for Yoop's client connections, where the peer is
+/// authenticated afterwards by share code verification or a trusted
+/// device's signature rather than by its certificate.
 #[derive(Debug)]
 struct AcceptAnyCertVerifier;
 
@@ -226,10 +262,27 @@ pub fn generate_session_key() -> [u8; 32] {
     random_bytes::<32>()
 }
 
+/// Compute one side's key confirmation after a code [`Spake2`] exchange.
+///
+/// The confirmation covers the TLS session's [`ChannelBinding`] value, so it
+/// only verifies on the connection it was computed for.
+#[must_use]
+pub fn code_confirmation(key: &[u8; 32], role: Spake2Role, channel_binding: &[u8; 32]) -> [u8; 32] {
+    let label: &[u8] = match role {
+        Spake2Role::Client => b"yoop:code-confirm:client:",
+        Spake2Role::Server => b"yoop:code-confirm:server:",
+    };
+    let mut data = Vec::with_capacity(label.len() + channel_binding.len());
+    data.extend_from_slice(label);
+    data.extend_from_slice(channel_binding);
+    hmac_sha256(key, &data)
+}
+
 /// Derive a session key from a share code.
 ///
-/// Both sender and receiver derive the same key from the code,
-/// allowing HMAC-based verification that the receiver knows the correct code.
+/// Anyone who sees a value derived only from the code can brute-force it,
+/// so share code verification uses [`Spake2`] instead; this key is only
+/// handed to the directory sync session.
 ///
 /// # Arguments
 ///
@@ -246,6 +299,19 @@ pub fn derive_session_key(code: &str) -> [u8; 32] {
     sha256(&data)
 }
 
+/// Derive the key for the integrity MAC on share announcements for a code.
+///
+/// Announcements carry the code, so this MAC does not authenticate the
+/// sender. Kept separate from [`derive_session_key`] so the MAC broadcast
+/// during discovery never reveals anything about the handshake key.
+#[must_use]
+pub fn derive_discovery_key(code: &str) -> [u8; 32] {
+    let mut data = Vec::with_capacity(15 + code.len());
+    data.extend_from_slice(b"yoop:discovery:");
+    data.extend_from_slice(code.as_bytes());
+    sha256(&data)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -420,4 +486,16 @@ mod tests {
         assert_eq!(bytes2.len(), 16);
         assert_ne!(bytes1, bytes2, "Random bytes should be different");
     }
+
+    #[test]
+    fn test_derive_discovery_key() {
+        let key = derive_discovery_key("A7K9");
+        assert_eq!(key, derive_discovery_key("A7K9"));
+        assert_ne!(key, derive_discovery_key("B2C3"));
+        assert_ne!(
+            key,
+            derive_session_key("A7K9"),
+            "Discovery and session keys must differ"
+        );
+    }
 }
@@ -0,0 +1,159 @@
+//! Signed identity key rotation.
+//!
+//! A device that suspects its key has leaked can replace it with
+//! [`DeviceIdentity::rotate`]. The rotation is recorded as a [`KeyRotation`]
+//! statement naming the old and new public keys, signed by both: the old
+//! signature proves the owner of the trusted key authorised the change, and
+//! the new signature proves possession of the replacement.
+//!
+//! The device ID stays the same across rotations. Every statement is kept in
+//! the identity file and sent in trusted handshakes, so a peer that last saw
+//! any earlier key can follow the chain up to the current one and update its
+//! trust store entry without re-pairing.
+
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::DeviceIdentity;
+
+/// A statement that a device replaced one public key with another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRotation {
+    /// Device whose key was rotated
+    pub device_id: Uuid,
+    /// Base64-encoded public key being retired
+    pub old_public_key: String,
+    /// Base64-encoded public key replacing it
+    pub new_public_key: String,
+    /// Unix timestamp of the rotation
+    pub rotated_at: u64,
+    /// Signature by the old key (base64-encoded)
+    pub old_signature: String,
+    /// Signature by the new key (base64-encoded)
+    pub new_signature: String,
+}
+
+impl KeyRotation {
+    /// Create a rotation statement signed by both identities.
+    #[must_use]
+    pub fn new(
+        device_id: Uuid,
+        old: &DeviceIdentity,
+        new: &DeviceIdentity,
+        rotated_at: u64,
+    ) -> Self {
+        let mut rotation = Self {
+            device_id,
+            old_public_key: old.public_key_base64(),
+            new_public_key: new.public_key_base64(),
+            rotated_at,
+            old_signature: String::new(),
+            new_signature: String::new(),
+        };
+        let message = rotation.signed_message();
+        rotation.old_signature = BASE64_STANDARD.encode(old.sign(&message));
+        rotation.new_signature = BASE64_STANDARD.encode(new.sign(&message));
+        rotation
+    }
+
+    /// Check both signatures.
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        let message = self.signed_message();
+        verify_signature(&self.old_public_key, &message, &self.old_signature)
+            && verify_signature(&self.new_public_key, &message, &self.new_signature)
+    }
+
+    fn signed_message(&self) -> Vec<u8> {
+        let fields = (
+            "yoop:key_rotation:v1",
+            self.device_id,
+            &self.old_public_key,
+            &self.new_public_key,
+            self.rotated_at,
+        );
+        serde_json::to_vec(&fields).unwrap_or_default()
+    }
+}
+
+/// Follow verified rotations for `device_id` starting from `from_key`.
+///
+/// Returns the last key reached, which is `from_key` itself if no statement
+/// applies. Statements for other devices or with bad signatures are skipped.
+#[must_use]
+pub fn follow_rotations(device_id: Uuid, from_key: &str, rotations: &[KeyRotation]) -> String {
+    let mut current = from_key.to_string();
+    // each step consumes one statement, so a cycle cannot loop forever
+    for _ in 0..rotations.len() {
+        let Some(next) = rotations
+            .iter()
+            .find(|r| r.device_id == device_id && r.old_public_key == current && r.verify())
+        else {
+            break;
+        };
+        current.clone_from(&next.new_public_key);
+    }
+    current
+}
+
+fn verify_signature(public_key: &str, message: &[u8], signature: &str) -> bool {
+    let Ok(bytes) = BASE64_STANDARD.decode(signature) else {
+        return false;
+    };
+    let Ok(signature): std::result::Result<[u8; 64], _> = bytes.try_into() else {
+        return false;
+    };
+    DeviceIdentity::verify_base64(public_key, message, &signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_verifies() {
+        let old = DeviceIdentity::generate().expect("generate");
+        let new = DeviceIdentity::generate().expect("generate");
+        let rotation = KeyRotation::new(old.device_id(), &old, &new, 1_700_000_000);
+        assert!(rotation.verify());
+
+        let mut forged = rotation;
+        forged.new_public_key = DeviceIdentity::generate()
+            .expect("generate")
+            .public_key_base64();
+        assert!(!forged.verify());
+    }
+
+    #[test]
+    fn test_follow_rotation_chain() {
+        let first = DeviceIdentity::generate().expect("generate");
+        let second = DeviceIdentity::generate().expect("generate");
+        let third = DeviceIdentity::generate().expect("generate");
+        let id = first.device_id();
+        let rotations = vec![
+            KeyRotation::new(id, &first, &second, 1),
+            KeyRotation::new(id, &second, &third, 2),
+        ];
+
+        assert_eq!(
+            follow_rotations(id, &first.public_key_base64(), &rotations),
+            third.public_key_base64()
+        );
+        assert_eq!(
+            follow_rotations(id, &second.public_key_base64(), &rotations),
+            third.public_key_base64()
+        );
+        assert_eq!(
+            follow_rotations(Uuid::new_v4(), &first.public_key_base64(), &rotations),
+            first.public_key_base64()
+        );
+
+        let attacker = DeviceIdentity::generate().expect("generate");
+        let hijack = vec![KeyRotation::new(id, &attacker, &third, 3)];
+        assert_eq!(
+            follow_rotations(id, &first.public_key_base64(), &hijack),
+            first.public_key_base64()
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Passphrase encryption of the identity key at rest.
+//!
+//! The secret key is encrypted with ChaCha20-Poly1305 under a key derived
+//! from the passphrase with Argon2id. The Argon2 parameters and salt are
+//! stored next to the ciphertext so they can be raised later without
+//! breaking existing files, and the device ID is bound in as associated
+//! data so a sealed key cannot be transplanted into another identity file.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::prelude::*;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+
+const KDF_ARGON2ID: &str = "argon2id";
+
+/// Encrypted secret key as stored in the identity file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedKey {
+    /// Key derivation function (always `argon2id`)
+    pub kdf: String,
+    /// Argon2 memory cost in KiB
+    pub m_cost: u32,
+    /// Argon2 iterations
+    pub t_cost: u32,
+    /// Argon2 parallelism
+    pub p_cost: u32,
+    /// Base64-encoded salt
+    pub salt: String,
+    /// Base64-encoded AEAD nonce
+    pub nonce: String,
+    /// Base64-encoded ciphertext and tag
+    pub ciphertext: String,
+}
+
+impl SealedKey {
+    /// Decoded salt, used to match cached keys.
+    pub fn salt_bytes(&self) -> Result<Vec<u8>> {
+        decode(&self.salt, "salt")
+    }
+}
+
+/// A passphrase-derived key that can seal and open the identity key.
+///
+/// Kept with an unlocked identity so it can be saved again (for example
+/// after a key rotation) without asking for the passphrase.
+#[derive(Clone)]
+pub struct KeyEncryption {
+    key: Zeroizing<[u8; 32]>,
+    salt: Vec<u8>,
+    params: Params,
+}
+
+impl std::fmt::Debug for KeyEncryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyEncryption")
+            .field("params", &self.params)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KeyEncryption {
+    /// Derive a key for a new passphrase with a fresh salt.
+    pub fn new(passphrase: &str) -> Result<Self> {
+        let salt: [u8; 16] = super::random_bytes();
+        Self::derive(passphrase, salt.to_vec(), Params::default())
+    }
+
+    /// Derive the key for an existing sealed key from its passphrase.
+    pub fn for_sealed(sealed: &SealedKey, passphrase: &str) -> Result<Self> {
+        let (salt, params) = sealed_params(sealed)?;
+        Self::derive(passphrase, salt, params)
+    }
+
+    /// Rebuild from a previously derived key, e.g. from the unlock cache.
+    pub fn from_key(sealed: &SealedKey, key: [u8; 32]) -> Result<Self> {
+        let (salt, params) = sealed_params(sealed)?;
+        Ok(Self {
+            key: Zeroizing::new(key),
+            salt,
+            params,
+        })
+    }
+
+    fn derive(passphrase: &str, salt: Vec<u8>, params: Params) -> Result<Self> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| Error::Internal(format!("key derivation failed: {e}")))?;
+        Ok(Self { key, salt, params })
+    }
+
+    /// The derived key.
+    pub fn key(&self) -> &[u8; 32] {
+        &self.key
+    }
+
+    /// The salt the key was derived with.
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    /// Encrypt a secret key for `device_id`.
+    pub fn seal(&self, secret: &[u8; 32], device_id: Uuid) -> Result<SealedKey> {
+        let nonce: [u8; 12] = super::random_bytes();
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: secret,
+                    aad: device_id.as_bytes(),
+                },
+            )
+            .map_err(|_| Error::Internal("failed to encrypt identity key".to_string()))?;
+
+        Ok(SealedKey {
+            kdf: KDF_ARGON2ID.to_string(),
+            m_cost: self.params.m_cost(),
+            t_cost: self.params.t_cost(),
+            p_cost: self.params.p_cost(),
+            salt: BASE64_STANDARD.encode(&self.salt),
+            nonce: BASE64_STANDARD.encode(nonce),
+            ciphertext: BASE64_STANDARD.encode(ciphertext),
+        })
+    }
+
+    /// Decrypt a sealed secret key for `device_id`.
+    ///
+    /// Fails with [`Error::InvalidPassphrase`] if the key does not match.
+    pub fn open(&self, sealed: &SealedKey, device_id: Uuid) -> Result<Zeroizing<[u8; 32]>> {
+        let nonce = decode(&sealed.nonce, "nonce")?;
+        if nonce.len() != 12 {
+            return Err(Error::ConfigError("Invalid identity key nonce".to_string()));
+        }
+        let ciphertext = decode(&sealed.ciphertext, "ciphertext")?;
+
+        let plaintext = Zeroizing::new(
+            self.cipher()
+                .decrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: device_id.as_bytes(),
+                    },
+                )
+                .map_err(|_| Error::InvalidPassphrase)?,
+        );
+
+        let mut secret = Zeroizing::new([0u8; 32]);
+        if plaintext.len() != secret.len() {
+            return Err(Error::ConfigError("Invalid secret key length".to_string()));
+        }
+        secret.copy_from_slice(&plaintext);
+        Ok(secret)
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(self.key.as_ref().into())
+    }
+}
+
+fn sealed_params(sealed: &SealedKey) -> Result<(Vec<u8>, Params)> {
+    if sealed.kdf != KDF_ARGON2ID {
+        return Err(Error::ConfigError(format!(
+            "Unsupported identity key derivation '{}'",
+            sealed.kdf
+        )));
+    }
+    let params = Params::new(sealed.m_cost, sealed.t_cost, sealed.p_cost, Some(32))
+        .map_err(|e| Error::ConfigError(format!("Invalid identity key parameters: {e}")))?;
+    Ok((decode(&sealed.salt, "salt")?, params))
+}
+
+fn decode(value: &str, what: &str) -> Result<Vec<u8>> {
+    BASE64_STANDARD
+        .decode(value)
+        .map_err(|e| Error::ConfigError(format!("Failed to decode identity key {what}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let secret: [u8; 32] = crate::crypto::random_bytes();
+        let device_id = Uuid::new_v4();
+
+        let encryption = KeyEncryption::new("correct horse").expect("derive");
+        let sealed = encryption.seal(&secret, device_id).expect("seal");
+        assert_eq!(sealed.kdf, "argon2id");
+
+        let reopened = KeyEncryption::for_sealed(&sealed, "correct horse").expect("derive");
+        assert_eq!(*reopened.open(&sealed, device_id).expect("open"), secret);
+
+        let cached = KeyEncryption::from_key(&sealed, *encryption.key()).expect("from key");
+        assert_eq!(*cached.open(&sealed, device_id).expect("open"), secret);
+
+        let wrong = KeyEncryption::for_sealed(&sealed, "battery staple").expect("derive");
+        assert!(matches!(
+            wrong.open(&sealed, device_id),
+            Err(Error::InvalidPassphrase)
+        ));
+        assert!(matches!(
+            reopened.open(&sealed, Uuid::new_v4()),
+            Err(Error::InvalidPassphrase)
+        ));
+    }
+}
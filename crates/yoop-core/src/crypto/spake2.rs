@@ -0,0 +1,155 @@
+//! SPAKE2 password-authenticated key exchange for share codes.
+//!
+//! Share codes are short enough that anything computed from the code alone,
+//! such as a hash or an HMAC, can be brute-forced by whoever sees it. SPAKE2
+//! instead turns the code into a key shared by both sides while giving an
+//! eavesdropper or a man in the middle nothing to test guesses against
+//! offline: every connection allows exactly one online guess.
+//!
+//! The group operations and transcript come from the `spake2` crate
+//! (SPAKE2 over Ed25519, as used by Magic Wormhole); this module only fixes
+//! the identities of the two sides and the key size.
+
+use ::spake2::{Ed25519Group, Identity, Password};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+
+/// Identity of the connecting side.
+const CLIENT_IDENTITY: &[u8] = b"yoop:code:client";
+
+/// Identity of the accepting side.
+const SERVER_IDENTITY: &[u8] = b"yoop:code:server";
+
+/// Which side of the exchange this is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spake2Role {
+    /// The connecting side (side `A` of the exchange)
+    Client,
+    /// The accepting side (side `B` of the exchange)
+    Server,
+}
+
+/// One side of a SPAKE2 exchange.
+pub struct Spake2 {
+    role: Spake2Role,
+    state: ::spake2::Spake2<Ed25519Group>,
+    message: Vec<u8>,
+}
+
+impl std::fmt::Debug for Spake2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Spake2")
+            .field("role", &self.role)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Spake2 {
+    /// Start an exchange for `password`.
+    #[must_use]
+    pub fn start(password: &[u8], role: Spake2Role) -> Self {
+        let password = Password::new(password);
+        let client = Identity::new(CLIENT_IDENTITY);
+        let server = Identity::new(SERVER_IDENTITY);
+        let (state, message) = match role {
+            Spake2Role::Client => ::spake2::Spake2::start_a(&password, &client, &server),
+            Spake2Role::Server => ::spake2::Spake2::start_b(&password, &client, &server),
+        };
+
+        Self {
+            role,
+            state,
+            message,
+        }
+    }
+
+    /// The message to send to the other side.
+    #[must_use]
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    /// Derive the shared key from the other side's message.
+    ///
+    /// Both sides only get the same key if they used the same password; the
+    /// caller must confirm that before relying on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message is not a valid message from the
+    /// other side.
+    pub fn finish(self, peer_message: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        let invalid = || Error::ProtocolError("invalid key exchange message".to_string());
+
+        let key = Zeroizing::new(self.state.finish(peer_message).map_err(|_| invalid())?);
+        let key: [u8; 32] = key.as_slice().try_into().map_err(|_| invalid())?;
+        Ok(Zeroizing::new(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(client_password: &[u8], server_password: &[u8]) -> ([u8; 32], [u8; 32]) {
+        let client = Spake2::start(client_password, Spake2Role::Client);
+        let server = Spake2::start(server_password, Spake2Role::Server);
+        let client_message = client.message().to_vec();
+        let server_message = server.message().to_vec();
+
+        let client_key = client.finish(&server_message).expect("client finish");
+        let server_key = server.finish(&client_message).expect("server finish");
+        (*client_key, *server_key)
+    }
+
+    #[test]
+    fn test_same_password_agrees() {
+        let (client_key, server_key) = exchange(b"A7K9", b"A7K9");
+        assert_eq!(client_key, server_key);
+    }
+
+    #[test]
+    fn test_different_password_disagrees() {
+        let (client_key, server_key) = exchange(b"A7K9", b"A7K8");
+        assert_ne!(client_key, server_key);
+    }
+
+    #[test]
+    fn test_wrong_password_fails_confirmation() {
+        let binding = [7u8; 32];
+        let (client_key, server_key) = exchange(b"A7K9", b"A7K8");
+        assert_ne!(
+            crate::crypto::code_confirmation(&client_key, Spake2Role::Client, &binding),
+            crate::crypto::code_confirmation(&server_key, Spake2Role::Client, &binding)
+        );
+        assert_ne!(
+            crate::crypto::code_confirmation(&server_key, Spake2Role::Server, &binding),
+            crate::crypto::code_confirmation(&client_key, Spake2Role::Server, &binding)
+        );
+    }
+
+    #[test]
+    fn test_roles_must_differ() {
+        let first = Spake2::start(b"A7K9", Spake2Role::Client);
+        let second = Spake2::start(b"A7K9", Spake2Role::Client);
+        let message = second.message().to_vec();
+        assert!(first.finish(&message).is_err());
+    }
+
+    #[test]
+    fn test_keys_are_fresh_per_exchange() {
+        let (first, _) = exchange(b"A7K9", b"A7K9");
+        let (second, _) = exchange(b"A7K9", b"A7K9");
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_rejects_invalid_messages() {
+        let server = Spake2::start(b"A7K9", Spake2Role::Server);
+        assert!(server.finish(&[0xff; 33]).is_err());
+
+        let server = Spake2::start(b"A7K9", Spake2Role::Server);
+        assert!(server.finish(&[0u8; 16]).is_err());
+    }
+}
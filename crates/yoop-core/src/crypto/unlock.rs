@@ -0,0 +1,249 @@
+//! Unlock cache for passphrase-protected identities.
+//!
+//! Deriving the identity key takes a deliberately slow Argon2 run, and
+//! background processes such as the outbox worker cannot prompt. Like an
+//! ssh-agent, an unlocked identity's derived key is therefore cached in two
+//! places:
+//!
+//! - in memory for the rest of the process, so every session that loads the
+//!   identity reuses it
+//! - in an [`UnlockCache`] file with an expiry (`yoop identity unlock`), kept
+//!   in the per-user runtime directory (`$XDG_RUNTIME_DIR`, which does not
+//!   survive a reboot) where available
+//!
+//! The cached key only opens the identity file it was derived for: entries
+//! are matched on device ID and salt, so changing the passphrase invalidates
+//! them.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+
+/// Environment variable holding the identity passphrase for non-interactive use.
+pub const PASSPHRASE_ENV: &str = "YOOP_IDENTITY_PASSPHRASE";
+
+struct CachedKey {
+    device_id: Uuid,
+    salt: Vec<u8>,
+    key: Zeroizing<[u8; 32]>,
+}
+
+static PROCESS_CACHE: Mutex<Option<CachedKey>> = Mutex::new(None);
+
+/// Remember a derived key for the rest of this process.
+pub(crate) fn remember(device_id: Uuid, salt: &[u8], key: &[u8; 32]) {
+    if let Ok(mut cache) = PROCESS_CACHE.lock() {
+        *cache = Some(CachedKey {
+            device_id,
+            salt: salt.to_vec(),
+            key: Zeroizing::new(*key),
+        });
+    }
+}
+
+/// Look up a derived key in the process cache, then the unlock cache file.
+pub(crate) fn cached_key(device_id: Uuid, salt: &[u8]) -> Option<Zeroizing<[u8; 32]>> {
+    if let Ok(cache) = PROCESS_CACHE.lock() {
+        if let Some(entry) = cache
+            .as_ref()
+            .filter(|e| e.device_id == device_id && e.salt == salt)
+        {
+            return Some(entry.key.clone());
+        }
+    }
+
+    let key = UnlockCache::default_path()
+        .map(UnlockCache::new)?
+        .load(device_id, salt)?;
+    remember(device_id, salt, &key);
+    Some(key)
+}
+
+/// Drop the derived key from the process cache.
+pub(crate) fn forget() {
+    if let Ok(mut cache) = PROCESS_CACHE.lock() {
+        *cache = None;
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    device_id: Uuid,
+    salt: String,
+    key: String,
+    expires_at: u64,
+}
+
+/// File-backed unlock cache shared by processes of the same user.
+#[derive(Debug, Clone)]
+pub struct UnlockCache {
+    path: PathBuf,
+}
+
+impl UnlockCache {
+    /// Create a cache stored at `path`.
+    #[must_use]
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Default cache location: the runtime directory if the platform has
+    /// one, otherwise the cache directory.
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        let runtime = directories::BaseDirs::new()
+            .and_then(|dirs| dirs.runtime_dir().map(|d| d.join("yoop")));
+        runtime
+            .or_else(|| {
+                directories::ProjectDirs::from("com", "yoop", "Yoop")
+                    .map(|dirs| dirs.cache_dir().to_path_buf())
+            })
+            .map(|dir| dir.join("identity-unlock.json"))
+    }
+
+    /// Path of the cache file.
+    #[must_use]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Store a derived key until `ttl` has passed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache file cannot be written.
+    pub(crate) fn store(
+        &self,
+        device_id: Uuid,
+        salt: &[u8],
+        key: &[u8; 32],
+        ttl: Duration,
+    ) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+            crate::file::apply_permissions(parent, Some(0o700))?;
+        }
+
+        let file = CacheFile {
+            device_id,
+            salt: BASE64_STANDARD.encode(salt),
+            key: BASE64_STANDARD.encode(key),
+            expires_at: now_secs().saturating_add(ttl.as_secs()),
+        };
+        let content = Zeroizing::new(
+            serde_json::to_string(&file)
+                .map_err(|e| Error::Serialization(format!("Failed to serialize unlock: {e}")))?,
+        );
+
+        crate::file::write_private(&self.path, content.as_bytes())
+    }
+
+    /// Load the cached key for an identity, removing the file if expired.
+    #[must_use]
+    pub(crate) fn load(&self, device_id: Uuid, salt: &[u8]) -> Option<Zeroizing<[u8; 32]>> {
+        let content = Zeroizing::new(fs::read_to_string(&self.path).ok()?);
+        let file: CacheFile = serde_json::from_str(&content).ok()?;
+
+        if file.expires_at <= now_secs() {
+            let _ = fs::remove_file(&self.path);
+            return None;
+        }
+        if file.device_id != device_id || BASE64_STANDARD.decode(&file.salt).ok()? != salt {
+            return None;
+        }
+
+        let bytes = Zeroizing::new(BASE64_STANDARD.decode(&file.key).ok()?);
+        let mut key = Zeroizing::new([0u8; 32]);
+        if bytes.len() != key.len() {
+            return None;
+        }
+        key.copy_from_slice(&bytes);
+        Some(key)
+    }
+
+    /// When the cached unlock expires, if there is a live one.
+    #[must_use]
+    pub fn expires_at(&self) -> Option<u64> {
+        let content = Zeroizing::new(fs::read_to_string(&self.path).ok()?);
+        let file: CacheFile = serde_json::from_str(&content).ok()?;
+        (file.expires_at > now_secs()).then_some(file.expires_at)
+    }
+
+    /// Remove the cache file.
+    ///
+    /// Returns `true` if there was one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be removed.
+    pub fn clear(&self) -> Result<bool> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlock_cache_roundtrip() {
+        let tmp_dir = tempfile::tempdir().expect("temp dir");
+        let cache = UnlockCache::new(tmp_dir.path().join("run/identity-unlock.json"));
+        let device_id = Uuid::new_v4();
+        let key = [7u8; 32];
+
+        assert!(cache.load(device_id, b"salt").is_none());
+        cache
+            .store(device_id, b"salt", &key, Duration::from_secs(60))
+            .expect("store");
+
+        assert_eq!(*cache.load(device_id, b"salt").expect("cached"), key);
+        assert!(cache.load(device_id, b"other salt").is_none());
+        assert!(cache.load(Uuid::new_v4(), b"salt").is_none());
+        assert!(cache.expires_at().is_some());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(cache.path())
+                .expect("metadata")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(cache.clear().expect("clear"));
+        assert!(!cache.clear().expect("clear"));
+    }
+
+    #[test]
+    fn test_unlock_cache_expiry() {
+        let tmp_dir = tempfile::tempdir().expect("temp dir");
+        let cache = UnlockCache::new(tmp_dir.path().join("identity-unlock.json"));
+        let device_id = Uuid::new_v4();
+
+        cache
+            .store(device_id, b"salt", &[1u8; 32], Duration::ZERO)
+            .expect("store");
+        assert!(cache.load(device_id, b"salt").is_none());
+        assert!(!cache.path().exists());
+    }
+}
@@ -0,0 +1,90 @@
+//! Verification helpers for discovery packets.
+//!
+//! Device beacons are signed with the device's Ed25519 key and share
+//! announcements carry an HMAC keyed from the share code. Both include a
+//! timestamp and a random nonce: listeners drop packets outside
+//! [`MAX_PACKET_AGE`] and packets whose nonce they have already seen.
+//!
+//! The share code travels in the announcement itself, so its HMAC only
+//! catches corrupted, truncated or stale packets; anyone who can see an
+//! announcement can forge one. Only beacon signatures from keys in the trust
+//! store say who sent a packet.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use base64::prelude::*;
+
+/// How far a packet timestamp may differ from the local clock.
+pub const MAX_PACKET_AGE: Duration = Duration::from_secs(120);
+
+/// Current Unix time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Generate a random base64-encoded nonce.
+pub fn new_nonce() -> String {
+    BASE64_STANDARD.encode(crate::crypto::random_bytes::<16>())
+}
+
+/// Check whether `timestamp` is within [`MAX_PACKET_AGE`] of now, in either
+/// direction to tolerate clock skew between devices.
+pub fn is_fresh(timestamp: u64) -> bool {
+    unix_now().abs_diff(timestamp) <= MAX_PACKET_AGE.as_secs()
+}
+
+/// Remembers recently seen nonces to reject replayed packets.
+///
+/// Each listener has its own guard and it does not look at the sender's
+/// address: it stops one listener from acting on the same packet twice, but
+/// a listener started later accepts a captured packet replayed within
+/// [`MAX_PACKET_AGE`], from any address.
+#[derive(Debug, Default)]
+pub struct ReplayGuard {
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl ReplayGuard {
+    /// Record `nonce`, returning `false` if it was already seen.
+    pub fn check(&self, nonce: &str) -> bool {
+        let now = Instant::now();
+        let mut seen = self
+            .seen
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        seen.retain(|_, at| now.duration_since(*at) <= MAX_PACKET_AGE * 2);
+        if seen.contains_key(nonce) {
+            return false;
+        }
+        seen.insert(nonce.to_string(), now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_fresh() {
+        let now = unix_now();
+        assert!(is_fresh(now));
+        assert!(is_fresh(now + 30));
+        assert!(!is_fresh(now - MAX_PACKET_AGE.as_secs() - 1));
+        assert!(!is_fresh(0));
+    }
+
+    #[test]
+    fn test_replay_guard() {
+        let guard = ReplayGuard::default();
+        let nonce = new_nonce();
+        assert!(guard.check(&nonce));
+        assert!(!guard.check(&nonce));
+        assert!(guard.check(&new_nonce()));
+    }
+}
@@ -18,20 +18,43 @@
 //!
 //! This is an on-demand system - beacons are only broadcast when needed,
 //! not continuously.
+//!
+//! ## Signing
+//!
+//! Every beacon is signed with the sender's Ed25519 identity key, re-signed
+//! with a fresh timestamp and nonce on each broadcast. Listeners drop beacons
+//! that are unsigned, stale, or replayed, and beacons claiming the
+//! `device_id` of a trusted device must be signed by the key in the trust
+//! store, so a device cannot be impersonated by copying its id.
+//!
+//! ## Privacy Mode
+//!
+//! A [private](DeviceBeacon::into_private) beacon carries a rotating
+//! resolvable identifier instead of the device ID, and no name or public
+//! key. Listeners resolve it with the resolving keys of the devices they
+//! paired with and report it under the trusted device's identity; beacons
+//! nobody can resolve are dropped.
 
 use std::collections::HashMap;
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use base64::prelude::*;
 use serde::{Deserialize, Serialize};
 use tokio::net::UdpSocket;
 use tokio::sync::{broadcast, Mutex};
 use uuid::Uuid;
 
+use crate::crypto::DeviceIdentity;
 use crate::error::{Error, Result};
+use crate::trust::TrustedDevice;
 
-use super::DEFAULT_DISCOVERY_PORT;
+use super::auth::{self, ReplayGuard};
+use super::interfaces::SourceFilter;
+use super::privacy;
+use super::sweep::{self, QueryKind, SweepOptions};
+use super::{socket, DiscoveryOptions, InterfaceFilter, MulticastOptions, DEFAULT_DISCOVERY_PORT};
 
 /// Device beacon broadcast by devices announcing availability.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +80,15 @@ pub struct DeviceBeacon {
     pub ready_to_receive: bool,
     /// Timestamp for deduplication and freshness
     pub timestamp: u64,
+    /// Random nonce against replay
+    #[serde(default)]
+    pub nonce: String,
+    /// Whether `device_id` is a rotating resolvable identifier (privacy mode)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub private: bool,
+    /// Base64-encoded Ed25519 signature over all other fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
 }
 
 impl DeviceBeacon {
@@ -80,12 +112,28 @@ impl DeviceBeacon {
             transfer_port,
             looking_for: None,
             ready_to_receive: false,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map_or(0, |d| d.as_secs()),
+            timestamp: auth::unix_now(),
+            nonce: String::new(),
+            private: false,
+            signature: None,
         }
     }
 
+    /// Convert into a privacy-mode beacon.
+    ///
+    /// Drops the device name, public key and `looking_for` target. The
+    /// device ID is replaced with a fresh resolvable identifier each time the
+    /// beacon is [signed](DeviceBeacon::sign).
+    #[must_use]
+    pub fn into_private(mut self) -> Self {
+        self.device_id = Uuid::nil();
+        self.device_name = String::new();
+        self.public_key = String::new();
+        self.looking_for = None;
+        self.private = true;
+        self
+    }
+
     /// Set the target device this beacon is looking for.
     #[must_use]
     pub fn looking_for(mut self, device_id: Uuid) -> Self {
@@ -114,9 +162,62 @@ impl DeviceBeacon {
 
     /// Update the timestamp.
     pub fn refresh_timestamp(&mut self) {
-        self.timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_or(0, |d| d.as_secs());
+        self.timestamp = auth::unix_now();
+    }
+
+    /// Sign the beacon with a fresh nonce.
+    ///
+    /// Private beacons also get a fresh resolvable identifier. Any later
+    /// change to the beacon, including [`refresh_timestamp`], invalidates
+    /// the signature.
+    ///
+    /// [`refresh_timestamp`]: DeviceBeacon::refresh_timestamp
+    pub fn sign(&mut self, identity: &DeviceIdentity) {
+        if self.private {
+            self.device_id = privacy::resolvable_id(&identity.resolving_key());
+        }
+        self.nonce = auth::new_nonce();
+        self.signature = Some(BASE64_STANDARD.encode(identity.sign(&self.signed_bytes())));
+    }
+
+    /// Check that the beacon carries `public_key` and is signed by it.
+    ///
+    /// Private beacons carry no key, so only the signature is checked. This
+    /// does not check freshness.
+    #[must_use]
+    pub fn verify_signature(&self, public_key: &str) -> bool {
+        if !self.private && self.public_key != public_key {
+            return false;
+        }
+        let Some(signature) = self
+            .signature
+            .as_deref()
+            .and_then(|s| BASE64_STANDARD.decode(s).ok())
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        else {
+            return false;
+        };
+        DeviceIdentity::verify_base64(public_key, &self.signed_bytes(), &signature)
+    }
+
+    fn signed_bytes(&self) -> Vec<u8> {
+        let fields = (
+            &self.beacon_type,
+            &self.protocol,
+            &self.version,
+            self.device_id,
+            &self.device_name,
+            &self.public_key,
+            self.transfer_port,
+            self.looking_for,
+            self.ready_to_receive,
+            self.timestamp,
+            &self.nonce,
+            self.private,
+        );
+        let mut data = b"yoop:beacon:".to_vec();
+        data.extend(serde_json::to_vec(&fields).unwrap_or_default());
+        data
     }
 }
 
@@ -133,13 +234,13 @@ pub struct DiscoveredDevice {
 
 impl DiscoveredDevice {
     /// Get the transfer address for this device.
+    ///
+    /// IPv6 sources keep their scope id, which link-local addresses need.
     #[must_use]
     pub fn transfer_addr(&self) -> SocketAddr {
-        let ip = match self.source {
-            SocketAddr::V4(addr) => *addr.ip(),
-            SocketAddr::V6(_) => Ipv4Addr::LOCALHOST,
-        };
-        SocketAddr::V4(SocketAddrV4::new(ip, self.beacon.transfer_port))
+        let mut addr = self.source;
+        addr.set_port(self.beacon.transfer_port);
+        addr
     }
 }
 
@@ -148,6 +249,14 @@ impl DiscoveredDevice {
 pub struct BeaconBroadcaster {
     /// UDP socket for broadcasting
     socket: Arc<UdpSocket>,
+    /// UDP socket for IPv6 multicast, if available
+    socket_v6: Option<Arc<UdpSocket>>,
+    /// Interfaces to announce on
+    interfaces: InterfaceFilter,
+    /// Broadcast private beacons
+    privacy: bool,
+    /// IPv4 multicast settings
+    multicast: MulticastOptions,
     /// Discovery port
     port: u16,
     /// Shutdown signal sender
@@ -163,30 +272,26 @@ impl BeaconBroadcaster {
     ///
     /// Returns an error if the socket cannot be created.
     pub async fn new(port: u16) -> Result<Self> {
-        let socket = socket2::Socket::new(
-            socket2::Domain::IPV4,
-            socket2::Type::DGRAM,
-            Some(socket2::Protocol::UDP),
-        )?;
-
-        socket.set_broadcast(true)?;
-        socket.set_reuse_address(true)?;
-
-        #[cfg(target_os = "macos")]
-        socket.set_reuse_port(true)?;
-
-        let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0);
-        socket.bind(&addr.into())?;
-
-        socket.set_nonblocking(true)?;
+        Self::with_options(port, &DiscoveryOptions::default()).await
+    }
 
-        let std_socket: std::net::UdpSocket = socket.into();
-        let socket = UdpSocket::from_std(std_socket)?;
+    /// Create a new beacon broadcaster with explicit IPv6 and interface options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the IPv4 socket cannot be created.
+    pub async fn with_options(port: u16, options: &DiscoveryOptions) -> Result<Self> {
+        let socket = socket::ipv4_sender(options.multicast.ttl)?;
+        let socket_v6 = socket::optional_ipv6(options.ipv6, socket::ipv6_sender);
 
         let (shutdown_tx, _) = broadcast::channel(1);
 
         Ok(Self {
             socket: Arc::new(socket),
+            socket_v6: socket_v6.map(Arc::new),
+            interfaces: options.interfaces.clone(),
+            privacy: options.privacy,
+            multicast: options.multicast.clone(),
             port,
             shutdown_tx,
             is_active: Arc::new(Mutex::new(false)),
@@ -204,15 +309,25 @@ impl BeaconBroadcaster {
 
     /// Start broadcasting a device beacon.
     ///
+    /// In privacy mode the beacon is converted with
+    /// [`DeviceBeacon::into_private`] first. Sweep queries for devices are
+    /// answered with a freshly signed copy until the broadcaster stops.
+    ///
     /// # Arguments
     ///
     /// * `beacon` - The device beacon to broadcast
+    /// * `identity` - Identity used to sign each broadcast
     /// * `interval` - How often to broadcast
     ///
     /// # Errors
     ///
     /// Returns an error if broadcasting fails.
-    pub async fn start(&self, beacon: DeviceBeacon, interval: Duration) -> Result<()> {
+    pub async fn start(
+        &self,
+        beacon: DeviceBeacon,
+        identity: &DeviceIdentity,
+        interval: Duration,
+    ) -> Result<()> {
         let mut is_active = self.is_active.lock().await;
         if *is_active {
             return Ok(());
@@ -221,16 +336,38 @@ impl BeaconBroadcaster {
         drop(is_active);
 
         let socket = Arc::clone(&self.socket);
+        let socket_v6 = self.socket_v6.clone();
+        let interfaces = self.interfaces.clone();
+        let multicast = self.multicast.clone();
         let port = self.port;
+        let identity = identity.clone();
         let is_active = Arc::clone(&self.is_active);
         let mut shutdown_rx = self.shutdown_tx.subscribe();
 
-        tokio::spawn(async move {
-            let broadcast_addr = SocketAddrV4::new(Ipv4Addr::BROADCAST, port);
-            let mut current_beacon = beacon;
+        let mut current_beacon = if self.privacy {
+            beacon.into_private()
+        } else {
+            beacon
+        };
 
+        let reply = current_beacon.clone();
+        let reply_identity = identity.clone();
+        sweep::spawn_responder(
+            port,
+            QueryKind::Device,
+            self.shutdown_tx.subscribe(),
+            move || {
+                let mut reply = reply.clone();
+                reply.refresh_timestamp();
+                reply.sign(&reply_identity);
+                serde_json::to_vec(&reply).ok()
+            },
+        );
+
+        tokio::spawn(async move {
             loop {
                 current_beacon.refresh_timestamp();
+                current_beacon.sign(&identity);
 
                 let json = match serde_json::to_vec(&current_beacon) {
                     Ok(json) => json,
@@ -240,8 +377,11 @@ impl BeaconBroadcaster {
                     }
                 };
 
-                if let Err(e) = socket.send_to(&json, broadcast_addr).await {
-                    tracing::warn!("Failed to send beacon: {}", e);
+                socket::send_ipv4_broadcast(&socket, &json, port, &interfaces).await;
+                socket::send_ipv4_multicast(&socket, &json, port, &multicast, &interfaces).await;
+
+                if let Some(ref socket_v6) = socket_v6 {
+                    socket::send_ipv6_multicast(socket_v6, &json, port, &interfaces).await;
                 }
 
                 tokio::select! {
@@ -278,6 +418,20 @@ impl BeaconBroadcaster {
 pub struct BeaconListener {
     /// UDP socket for receiving beacons
     socket: Arc<UdpSocket>,
+    /// UDP socket for receiving IPv6 multicast, if available
+    socket_v6: Option<Arc<UdpSocket>>,
+    /// Drops packets from interfaces excluded by the filter
+    sources: SourceFilter,
+    /// Trusted devices, by device ID
+    trusted: HashMap<Uuid, TrustedDevice>,
+    /// Drops beacons whose nonce was already seen
+    replay: ReplayGuard,
+    /// Discovery port queried on swept hosts
+    port: u16,
+    /// Sweep settings used when passive discovery finds nothing
+    sweep: SweepOptions,
+    /// Interfaces whose subnets are swept
+    interfaces: InterfaceFilter,
 }
 
 impl BeaconListener {
@@ -287,28 +441,142 @@ impl BeaconListener {
     ///
     /// Returns an error if the socket cannot be created.
     pub async fn new(port: u16) -> Result<Self> {
-        let socket = socket2::Socket::new(
-            socket2::Domain::IPV4,
-            socket2::Type::DGRAM,
-            Some(socket2::Protocol::UDP),
-        )?;
+        Self::with_options(port, &DiscoveryOptions::default()).await
+    }
+
+    /// Create a new beacon listener with explicit IPv6 and interface options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the IPv4 socket cannot be created.
+    pub async fn with_options(port: u16, options: &DiscoveryOptions) -> Result<Self> {
+        let socket = socket::ipv4_listener(port)?;
+        socket::join_ipv4_multicast(&socket, &options.multicast, &options.interfaces);
+        let socket_v6 = socket::optional_ipv6(options.ipv6, || {
+            socket::ipv6_listener(port, &options.interfaces)
+        });
+
+        Ok(Self {
+            socket: Arc::new(socket),
+            socket_v6: socket_v6.map(Arc::new),
+            sources: SourceFilter::new(&options.interfaces),
+            trusted: HashMap::new(),
+            replay: ReplayGuard::default(),
+            port,
+            sweep: options.sweep.clone(),
+            interfaces: options.interfaces.clone(),
+        })
+    }
+
+    /// Require beacons claiming one of these devices to be signed by its
+    /// stored public key, and resolve their private beacons.
+    ///
+    /// Beacons from other devices only need a valid signature from the key
+    /// they carry.
+    #[must_use]
+    pub fn with_trusted<'a>(
+        mut self,
+        devices: impl IntoIterator<Item = &'a TrustedDevice>,
+    ) -> Self {
+        self.trusted.extend(
+            devices
+                .into_iter()
+                .map(|device| (device.device_id, device.clone())),
+        );
+        self
+    }
 
-        socket.set_reuse_address(true)?;
+    /// Find the trusted device a private beacon's identifier resolves to.
+    fn resolve(&self, id: Uuid) -> Option<&TrustedDevice> {
+        self.trusted.values().find(|device| {
+            device
+                .resolving_key
+                .as_deref()
+                .and_then(privacy::decode_key)
+                .is_some_and(|key| privacy::resolves(&key, id))
+        })
+    }
+
+    /// Query every swept host for device beacons.
+    ///
+    /// Queries are sent from the listening socket, so replies are received
+    /// and checked like broadcast beacons.
+    async fn send_sweep(&self) {
+        let targets = sweep::sweep_targets(&self.sweep, &self.interfaces);
+        sweep::send_queries(&self.socket, QueryKind::Device, &targets, self.port).await;
+    }
 
-        #[cfg(target_os = "macos")]
-        socket.set_reuse_port(true)?;
+    /// Receive the next packet from a selected interface.
+    async fn recv_from(&self, buf: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+        loop {
+            let (len, source) =
+                socket::recv_from(&self.socket, self.socket_v6.as_deref(), buf).await?;
+            if self.sources.accepts(&source) {
+                return Ok((len, source));
+            }
+            tracing::trace!("Ignoring discovery packet from filtered source {source}");
+        }
+    }
 
-        let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port);
-        socket.bind(&addr.into())?;
+    /// Parse a beacon, dropping forged, stale or replayed ones.
+    fn accept_beacon(&self, data: &[u8], source: SocketAddr) -> Option<DeviceBeacon> {
+        let beacon = serde_json::from_slice::<DeviceBeacon>(data).ok()?;
+        if !beacon.is_valid() {
+            return None;
+        }
 
-        socket.set_nonblocking(true)?;
+        let beacon = self.authenticate(beacon, source)?;
+        if !auth::is_fresh(beacon.timestamp) {
+            tracing::debug!(
+                "Ignoring stale beacon for {} from {source}",
+                beacon.device_id
+            );
+            return None;
+        }
+        if !self.replay.check(&beacon.nonce) {
+            tracing::trace!("Ignoring replayed beacon from {source}");
+            return None;
+        }
+        Some(beacon)
+    }
 
-        let std_socket: std::net::UdpSocket = socket.into();
-        let socket = UdpSocket::from_std(std_socket)?;
+    /// Check a beacon's signature against the trust store, resolving
+    /// private beacons to the trusted device they belong to.
+    ///
+    /// This does not check freshness or replay, so it also suits beacons
+    /// rebuilt from cached mDNS records.
+    pub(super) fn authenticate(
+        &self,
+        mut beacon: DeviceBeacon,
+        source: SocketAddr,
+    ) -> Option<DeviceBeacon> {
+        let trusted = if beacon.private {
+            let Some(device) = self.resolve(beacon.device_id) else {
+                tracing::trace!("Ignoring private beacon from {source}: not a trusted device");
+                return None;
+            };
+            Some(device)
+        } else {
+            self.trusted.get(&beacon.device_id)
+        };
 
-        Ok(Self {
-            socket: Arc::new(socket),
-        })
+        let expected_key = trusted.map_or(beacon.public_key.as_str(), |device| {
+            device.public_key.as_str()
+        });
+        if !beacon.verify_signature(expected_key) {
+            tracing::debug!(
+                "Ignoring beacon for {} from {source}: signature does not match",
+                beacon.device_id
+            );
+            return None;
+        }
+
+        if let Some(device) = trusted.filter(|_| beacon.private) {
+            beacon.device_id = device.device_id;
+            beacon.device_name.clone_from(&device.device_name);
+            beacon.public_key.clone_from(&device.public_key);
+        }
+        Some(beacon)
     }
 
     /// Create a new beacon listener on the default port.
@@ -322,6 +590,9 @@ impl BeaconListener {
 
     /// Wait for a device with the given device_id.
     ///
+    /// With sweeping enabled, listens passively for half the timeout, then
+    /// sweeps and waits for replies for the rest.
+    ///
     /// # Arguments
     ///
     /// * `device_id` - The device ID to look for
@@ -334,6 +605,24 @@ impl BeaconListener {
         &self,
         device_id: Uuid,
         timeout: Duration,
+    ) -> Result<DiscoveredDevice> {
+        if !self.sweep.enabled {
+            return self.find_device_passive(device_id, timeout).await;
+        }
+
+        let passive = timeout / 2;
+        if let Ok(device) = self.find_device_passive(device_id, passive).await {
+            return Ok(device);
+        }
+        self.send_sweep().await;
+        self.find_device_passive(device_id, timeout.saturating_sub(passive))
+            .await
+    }
+
+    async fn find_device_passive(
+        &self,
+        device_id: Uuid,
+        timeout: Duration,
     ) -> Result<DiscoveredDevice> {
         let deadline = Instant::now() + timeout;
 
@@ -344,12 +633,12 @@ impl BeaconListener {
             }
 
             let mut buf = [0u8; 4096];
-            let result = tokio::time::timeout(remaining, self.socket.recv_from(&mut buf)).await;
+            let result = tokio::time::timeout(remaining, self.recv_from(&mut buf)).await;
 
             match result {
                 Ok(Ok((len, source))) => {
-                    if let Ok(beacon) = serde_json::from_slice::<DeviceBeacon>(&buf[..len]) {
-                        if beacon.is_valid() && beacon.device_id == device_id {
+                    if let Some(beacon) = self.accept_beacon(&buf[..len], source) {
+                        if beacon.device_id == device_id {
                             return Ok(DiscoveredDevice {
                                 beacon,
                                 source,
@@ -370,6 +659,8 @@ impl BeaconListener {
 
     /// Wait for a device with the given name.
     ///
+    /// Sweeps like [`find_device`](BeaconListener::find_device) when enabled.
+    ///
     /// # Arguments
     ///
     /// * `device_name` - The device name to look for (case-insensitive)
@@ -382,6 +673,24 @@ impl BeaconListener {
         &self,
         device_name: &str,
         timeout: Duration,
+    ) -> Result<DiscoveredDevice> {
+        if !self.sweep.enabled {
+            return self.find_by_name_passive(device_name, timeout).await;
+        }
+
+        let passive = timeout / 2;
+        if let Ok(device) = self.find_by_name_passive(device_name, passive).await {
+            return Ok(device);
+        }
+        self.send_sweep().await;
+        self.find_by_name_passive(device_name, timeout.saturating_sub(passive))
+            .await
+    }
+
+    async fn find_by_name_passive(
+        &self,
+        device_name: &str,
+        timeout: Duration,
     ) -> Result<DiscoveredDevice> {
         let deadline = Instant::now() + timeout;
         let name_lower = device_name.to_lowercase();
@@ -393,12 +702,12 @@ impl BeaconListener {
             }
 
             let mut buf = [0u8; 4096];
-            let result = tokio::time::timeout(remaining, self.socket.recv_from(&mut buf)).await;
+            let result = tokio::time::timeout(remaining, self.recv_from(&mut buf)).await;
 
             match result {
                 Ok(Ok((len, source))) => {
-                    if let Ok(beacon) = serde_json::from_slice::<DeviceBeacon>(&buf[..len]) {
-                        if beacon.is_valid() && beacon.device_name.to_lowercase() == name_lower {
+                    if let Some(beacon) = self.accept_beacon(&buf[..len], source) {
+                        if beacon.device_name.to_lowercase() == name_lower {
                             return Ok(DiscoveredDevice {
                                 beacon,
                                 source,
@@ -441,12 +750,12 @@ impl BeaconListener {
             }
 
             let mut buf = [0u8; 4096];
-            let result = tokio::time::timeout(remaining, self.socket.recv_from(&mut buf)).await;
+            let result = tokio::time::timeout(remaining, self.recv_from(&mut buf)).await;
 
             match result {
                 Ok(Ok((len, source))) => {
-                    if let Ok(beacon) = serde_json::from_slice::<DeviceBeacon>(&buf[..len]) {
-                        if beacon.is_valid() && beacon.is_looking_for(our_device_id) {
+                    if let Some(beacon) = self.accept_beacon(&buf[..len], source) {
+                        if beacon.is_looking_for(our_device_id) {
                             return Ok(DiscoveredDevice {
                                 beacon,
                                 source,
@@ -467,6 +776,9 @@ impl BeaconListener {
 
     /// List all devices broadcasting beacons on the network.
     ///
+    /// With sweeping enabled and nothing heard in the first half of
+    /// `duration`, sweeps and collects replies for the rest.
+    ///
     /// # Arguments
     ///
     /// * `duration` - How long to listen for beacons
@@ -475,6 +787,20 @@ impl BeaconListener {
     ///
     /// A list of discovered devices, deduplicated by device_id.
     pub async fn scan(&self, duration: Duration) -> Vec<DiscoveredDevice> {
+        if !self.sweep.enabled {
+            return self.scan_passive(duration).await;
+        }
+
+        let passive = duration / 2;
+        let devices = self.scan_passive(passive).await;
+        if !devices.is_empty() {
+            return devices;
+        }
+        self.send_sweep().await;
+        self.scan_passive(duration.saturating_sub(passive)).await
+    }
+
+    async fn scan_passive(&self, duration: Duration) -> Vec<DiscoveredDevice> {
         let deadline = Instant::now() + duration;
         let mut devices: HashMap<Uuid, DiscoveredDevice> = HashMap::new();
 
@@ -485,22 +811,20 @@ impl BeaconListener {
             }
 
             let mut buf = [0u8; 4096];
-            let result = tokio::time::timeout(remaining, self.socket.recv_from(&mut buf)).await;
+            let result = tokio::time::timeout(remaining, self.recv_from(&mut buf)).await;
 
             match result {
                 Ok(Ok((len, source))) => {
-                    if let Ok(beacon) = serde_json::from_slice::<DeviceBeacon>(&buf[..len]) {
-                        if beacon.is_valid() {
-                            let device_id = beacon.device_id;
-                            devices.insert(
-                                device_id,
-                                DiscoveredDevice {
-                                    beacon,
-                                    source,
-                                    discovered_at: Instant::now(),
-                                },
-                            );
-                        }
+                    if let Some(beacon) = self.accept_beacon(&buf[..len], source) {
+                        let device_id = beacon.device_id;
+                        devices.insert(
+                            device_id,
+                            DiscoveredDevice {
+                                beacon,
+                                source,
+                                discovered_at: Instant::now(),
+                            },
+                        );
                     }
                 }
                 Ok(Err(e)) => {
@@ -519,6 +843,7 @@ impl BeaconListener {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::net::{Ipv4Addr, SocketAddrV4};
 
     #[test]
     fn test_beacon_creation() {
@@ -582,6 +907,161 @@ mod tests {
         assert!(!json.contains("looking_for"));
     }
 
+    fn signed_beacon(identity: &DeviceIdentity) -> DeviceBeacon {
+        let mut beacon = DeviceBeacon::new(
+            identity.device_id(),
+            "Test Device",
+            &identity.public_key_base64(),
+            52530,
+        );
+        beacon.sign(identity);
+        beacon
+    }
+
+    #[test]
+    fn test_beacon_signature() {
+        let identity = DeviceIdentity::generate().expect("generate identity");
+        let beacon = signed_beacon(&identity);
+        assert!(beacon.verify_signature(&identity.public_key_base64()));
+
+        let json = serde_json::to_vec(&beacon).expect("serialize");
+        let decoded: DeviceBeacon = serde_json::from_slice(&json).expect("deserialize");
+        assert!(decoded.verify_signature(&identity.public_key_base64()));
+
+        let mut tampered = beacon.clone();
+        tampered.transfer_port = 4444;
+        assert!(!tampered.verify_signature(&identity.public_key_base64()));
+
+        let other = DeviceIdentity::generate().expect("generate identity");
+        assert!(!beacon.verify_signature(&other.public_key_base64()));
+
+        let unsigned = DeviceBeacon::new(identity.device_id(), "Test", "key", 52530);
+        assert!(!unsigned.verify_signature("key"));
+    }
+
+    #[tokio::test]
+    async fn test_listener_rejects_impersonated_trusted_device() {
+        let genuine = DeviceIdentity::generate().expect("generate identity");
+        let attacker = DeviceIdentity::generate().expect("generate identity");
+        let trusted = TrustedDevice::new(
+            genuine.device_id(),
+            "Genuine".to_string(),
+            genuine.public_key_base64(),
+        );
+
+        let listener = BeaconListener::with_options(
+            0,
+            &DiscoveryOptions {
+                ipv6: false,
+                ..DiscoveryOptions::default()
+            },
+        )
+        .await
+        .expect("create listener")
+        .with_trusted([&trusted]);
+        let target = SocketAddr::new(
+            Ipv4Addr::LOCALHOST.into(),
+            listener.socket.local_addr().expect("addr").port(),
+        );
+        let sender = UdpSocket::bind("127.0.0.1:0").await.expect("bind sender");
+
+        let mut forged = DeviceBeacon::new(
+            genuine.device_id(),
+            "Genuine",
+            &attacker.public_key_base64(),
+            4444,
+        );
+        forged.sign(&attacker);
+        let mut stale = signed_beacon(&genuine);
+        stale.timestamp = 1;
+        stale.sign(&genuine);
+        let genuine_beacon = signed_beacon(&genuine);
+
+        for beacon in [&forged, &stale, &genuine_beacon, &genuine_beacon] {
+            let json = serde_json::to_vec(beacon).expect("serialize");
+            sender.send_to(&json, target).await.expect("send");
+        }
+
+        let devices = listener.scan(Duration::from_millis(300)).await;
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].beacon.transfer_port, 52530);
+        assert_eq!(devices[0].beacon.nonce, genuine_beacon.nonce);
+    }
+
+    #[test]
+    fn test_private_beacon_hides_identity() {
+        let identity = DeviceIdentity::generate().expect("generate identity");
+        let mut beacon = DeviceBeacon::new(
+            identity.device_id(),
+            "Marcus-Laptop",
+            &identity.public_key_base64(),
+            52530,
+        )
+        .looking_for(Uuid::new_v4())
+        .into_private();
+
+        beacon.sign(&identity);
+        let first_id = beacon.device_id;
+        let json = serde_json::to_string(&beacon).expect("serialize");
+        assert!(!json.contains("Marcus-Laptop"));
+        assert!(!json.contains(&identity.public_key_base64()));
+        assert!(!json.contains(&identity.device_id().to_string()));
+        assert!(beacon.looking_for.is_none());
+        assert!(beacon.verify_signature(&identity.public_key_base64()));
+
+        beacon.sign(&identity);
+        assert_ne!(beacon.device_id, first_id, "Identifier should rotate");
+    }
+
+    #[tokio::test]
+    async fn test_listener_resolves_private_beacons() {
+        let trusted_identity = DeviceIdentity::generate().expect("generate identity");
+        let stranger = DeviceIdentity::generate().expect("generate identity");
+        let trusted = TrustedDevice::new(
+            trusted_identity.device_id(),
+            "Laptop".to_string(),
+            trusted_identity.public_key_base64(),
+        )
+        .with_resolving_key(Some(trusted_identity.resolving_key_base64()));
+
+        let listener = BeaconListener::with_options(
+            0,
+            &DiscoveryOptions {
+                ipv6: false,
+                ..DiscoveryOptions::default()
+            },
+        )
+        .await
+        .expect("create listener")
+        .with_trusted([&trusted]);
+        let target = SocketAddr::new(
+            Ipv4Addr::LOCALHOST.into(),
+            listener.socket.local_addr().expect("addr").port(),
+        );
+        let sender = UdpSocket::bind("127.0.0.1:0").await.expect("bind sender");
+
+        for identity in [&stranger, &trusted_identity] {
+            let mut beacon = DeviceBeacon::new(
+                identity.device_id(),
+                "Private",
+                &identity.public_key_base64(),
+                52530,
+            )
+            .into_private();
+            beacon.sign(identity);
+            let json = serde_json::to_vec(&beacon).expect("serialize");
+            sender.send_to(&json, target).await.expect("send");
+        }
+
+        let found = listener
+            .find_device(trusted.device_id, Duration::from_secs(2))
+            .await
+            .expect("resolve private beacon");
+        assert_eq!(found.beacon.device_name, "Laptop");
+        assert_eq!(found.beacon.public_key, trusted.public_key);
+        assert!(found.beacon.private);
+    }
+
     #[tokio::test]
     async fn test_broadcaster_creation() {
         let broadcaster = BeaconBroadcaster::new(0).await;
@@ -599,9 +1079,10 @@ mod tests {
         let broadcaster = BeaconBroadcaster::new(0).await.expect("create broadcaster");
         let device_id = Uuid::new_v4();
         let beacon = DeviceBeacon::new(device_id, "Test Device", "key", 52530);
+        let identity = DeviceIdentity::generate().expect("generate identity");
 
         broadcaster
-            .start(beacon, Duration::from_millis(100))
+            .start(beacon, &identity, Duration::from_millis(100))
             .await
             .expect("start broadcasting");
 
@@ -630,4 +1111,31 @@ mod tests {
             SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 100), 52530))
         );
     }
+
+    #[test]
+    fn test_discovered_device_transfer_addr_ipv6() {
+        let beacon = DeviceBeacon::new(Uuid::new_v4(), "Test", "key", 52530);
+        let source = SocketAddr::V6(std::net::SocketAddrV6::new(
+            "fe80::1".parse().expect("ip"),
+            52525,
+            0,
+            2,
+        ));
+
+        let discovered = DiscoveredDevice {
+            beacon,
+            source,
+            discovered_at: Instant::now(),
+        };
+
+        let SocketAddr::V6(addr) = discovered.transfer_addr() else {
+            panic!("expected IPv6 address");
+        };
+        assert_eq!(
+            addr.ip(),
+            &"fe80::1".parse::<std::net::Ipv6Addr>().expect("ip")
+        );
+        assert_eq!(addr.port(), 52530);
+        assert_eq!(addr.scope_id(), 2);
+    }
 }
@@ -5,13 +5,18 @@
 //!
 //! ## Strategy
 //!
-//! - For broadcasting: Announce via both UDP broadcast and mDNS simultaneously
+//! - For broadcasting: Announce via UDP broadcast, IPv4 multicast (when
+//!   enabled) and mDNS simultaneously
 //! - For discovery: Race UDP and mDNS, return first successful result
 //!
 //! This approach ensures discovery works in:
 //! - Home networks (UDP broadcast works)
+//! - Networks that rate-limit broadcast (IPv4 multicast)
 //! - Corporate networks (mDNS may work better)
 //! - Networks with broadcast disabled (mDNS fallback)
+//!
+//! Shares found via mDNS are only returned if their MAC verifies against the
+//! share code, the same integrity check the UDP listener applies.
 
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -19,8 +24,9 @@ use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
-use super::{Broadcaster, DiscoveredShare, DiscoveryPacket, Listener};
+use super::{Broadcaster, DiscoveredShare, DiscoveryOptions, DiscoveryPacket, Listener};
 use crate::code::ShareCode;
+use crate::crypto;
 use crate::error::{Error, Result};
 
 #[cfg(feature = "mdns")]
@@ -33,6 +39,8 @@ pub struct HybridBroadcaster {
     /// mDNS broadcaster (if feature enabled)
     #[cfg(feature = "mdns")]
     mdns: Option<MdnsBroadcaster>,
+    /// Resolving key to redact announcements with, in privacy mode
+    privacy_key: Option<[u8; 32]>,
     /// Whether currently broadcasting
     is_active: Arc<Mutex<bool>>,
 }
@@ -48,11 +56,20 @@ impl HybridBroadcaster {
     ///
     /// Returns an error if either broadcaster cannot be created.
     pub async fn new(port: u16) -> Result<Self> {
-        let udp = Broadcaster::new(port).await?;
+        Self::with_options(port, &DiscoveryOptions::default()).await
+    }
+
+    /// Create a new hybrid broadcaster with explicit IPv6 and interface options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the UDP broadcaster cannot be created.
+    pub async fn with_options(port: u16, options: &DiscoveryOptions) -> Result<Self> {
+        let udp = Broadcaster::with_options(port, options).await?;
 
         #[cfg(feature = "mdns")]
-        let mdns = match MdnsBroadcaster::new() {
-            Ok(m) => Some(m),
+        let mdns = match MdnsBroadcaster::with_interfaces(&options.interfaces) {
+            Ok(m) => Some(m.hide_hostname(options.privacy)),
             Err(e) => {
                 tracing::warn!("Failed to create mDNS broadcaster, continuing with UDP only: {e}");
                 None
@@ -63,10 +80,22 @@ impl HybridBroadcaster {
             udp,
             #[cfg(feature = "mdns")]
             mdns,
+            privacy_key: options.privacy.then(crypto::random_bytes),
             is_active: Arc::new(Mutex::new(false)),
         })
     }
 
+    /// Redact announcements with this device's resolving key in privacy
+    /// mode; see [`Broadcaster::with_resolving_key`].
+    #[must_use]
+    pub fn with_resolving_key(mut self, key: [u8; 32]) -> Self {
+        if self.privacy_key.is_some() {
+            self.privacy_key = Some(key);
+        }
+        self.udp = self.udp.with_resolving_key(key);
+        self
+    }
+
     /// Start broadcasting a share.
     ///
     /// # Arguments
@@ -85,18 +114,28 @@ impl HybridBroadcaster {
         *is_active = true;
         drop(is_active);
 
+        let mut packet = packet;
+        if let Some(key) = &self.privacy_key {
+            packet.redact(key);
+        }
+
         self.udp.start(packet.clone(), interval).await?;
 
         #[cfg(feature = "mdns")]
         if let Some(ref mdns) = self.mdns {
+            let mut sealed = packet.clone();
+            sealed.seal();
             let properties = MdnsProperties {
-                code: packet.code.clone(),
-                device_name: packet.device_name.clone(),
-                device_id: packet.device_id,
-                transfer_port: packet.transfer_port,
-                file_count: packet.file_count,
-                total_size: packet.total_size,
-                protocol_version: packet.version.clone(),
+                code: sealed.code,
+                device_name: sealed.device_name,
+                device_id: sealed.device_id,
+                transfer_port: sealed.transfer_port,
+                file_count: sealed.file_count,
+                total_size: sealed.total_size,
+                protocol_version: sealed.version,
+                timestamp: sealed.timestamp,
+                nonce: sealed.nonce,
+                mac: sealed.mac,
             };
 
             if let Err(e) = mdns.register(properties).await {
@@ -177,10 +216,19 @@ impl HybridListener {
     ///
     /// Returns an error if either listener cannot be created.
     pub async fn new(port: u16) -> Result<Self> {
-        let udp = Listener::new(port).await?;
+        Self::with_options(port, &DiscoveryOptions::default()).await
+    }
+
+    /// Create a new hybrid listener with explicit IPv6 and interface options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the UDP listener cannot be created.
+    pub async fn with_options(port: u16, options: &DiscoveryOptions) -> Result<Self> {
+        let udp = Listener::with_options(port, options).await?;
 
         #[cfg(feature = "mdns")]
-        let mdns = match MdnsListener::new() {
+        let mdns = match MdnsListener::with_interfaces(&options.interfaces) {
             Ok(m) => Some(m),
             Err(e) => {
                 tracing::warn!("Failed to create mDNS listener, continuing with UDP only: {e}");
@@ -229,8 +277,10 @@ impl HybridListener {
                     mdns_result = mdns_future => {
                         match mdns_result {
                             Ok(mdns_share) => {
-                                tracing::debug!(code = %code, "Found share via mDNS");
-                                return Ok(mdns_to_discovered(mdns_share));
+                                if let Some(share) = verified(mdns_share) {
+                                    tracing::debug!(code = %code, "Found share via mDNS");
+                                    return Ok(share);
+                                }
                             }
                             Err(e) => {
                                 tracing::debug!("mDNS discovery failed: {e}");
@@ -272,18 +322,19 @@ impl HybridListener {
         {
             if let Some(ref mdns) = self.mdns {
                 if prefer_mdns {
-                    if let Ok(mdns_share) = mdns.find(code, half_timeout).await {
+                    if let Some(share) = mdns.find(code, half_timeout).await.ok().and_then(verified)
+                    {
                         tracing::debug!(code = %code, "Found share via mDNS");
-                        return Ok(mdns_to_discovered(mdns_share));
+                        return Ok(share);
                     }
                     return self.udp.find(code, half_timeout).await;
                 }
                 if let Ok(share) = self.udp.find(code, half_timeout).await {
                     return Ok(share);
                 }
-                if let Ok(mdns_share) = mdns.find(code, half_timeout).await {
+                if let Some(share) = mdns.find(code, half_timeout).await.ok().and_then(verified) {
                     tracing::debug!(code = %code, "Found share via mDNS fallback");
-                    return Ok(mdns_to_discovered(mdns_share));
+                    return Ok(share);
                 }
                 return Err(Error::CodeNotFound(code.to_string()));
             }
@@ -294,8 +345,9 @@ impl HybridListener {
 
     /// Find a share by code with fallback to stored IP addresses.
     ///
-    /// First tries regular discovery (UDP + mDNS), then falls back to trying
-    /// stored IP addresses from the trust store if discovery fails.
+    /// First tries regular discovery (UDP + mDNS, plus a subnet sweep when
+    /// enabled), then falls back to trying stored IP addresses from the trust
+    /// store if discovery fails.
     ///
     /// # Arguments
     ///
@@ -364,8 +416,8 @@ impl HybridListener {
                     shares.insert(share.packet.device_id, share);
                 }
 
-                for mdns_share in mdns_shares {
-                    shares.insert(mdns_share.device_id, mdns_to_discovered(mdns_share));
+                for share in mdns_shares.into_iter().filter_map(verified) {
+                    shares.insert(share.packet.device_id, share);
                 }
 
                 return shares.into_values().collect();
@@ -415,12 +467,29 @@ fn mdns_to_discovered(mdns_share: MdnsDiscoveredShare) -> DiscoveredShare {
             file_count: mdns_share.file_count,
             total_size: mdns_share.total_size,
             preview_available: true,
+            timestamp: mdns_share.timestamp,
+            nonce: mdns_share.nonce,
+            mac: mdns_share.mac,
         },
         source: mdns_share.address,
         discovered_at: Instant::now(),
     }
 }
 
+/// Convert an mDNS share, dropping it if its MAC does not verify.
+///
+/// mDNS records are registered once per share and cached by resolvers, so
+/// only the MAC is checked, not the timestamp.
+#[cfg(feature = "mdns")]
+fn verified(mdns_share: MdnsDiscoveredShare) -> Option<DiscoveredShare> {
+    let share = mdns_to_discovered(mdns_share);
+    if share.packet.verify_mac() {
+        return Some(share);
+    }
+    tracing::debug!("Ignoring mDNS share with invalid MAC from {}", share.source);
+    None
+}
+
 /// Attempt to probe a direct address to check if a share is available.
 ///
 /// This function tries to connect directly to the given address and check
@@ -452,6 +521,9 @@ async fn try_direct_probe(
         file_count: 0,
         total_size: 0,
         preview_available: false,
+        timestamp: 0,
+        nonce: String::new(),
+        mac: None,
     };
 
     Ok(DiscoveredShare {
@@ -511,6 +583,37 @@ mod tests {
         assert!(result.is_err(), "Should timeout for non-existent code");
     }
 
+    #[cfg(feature = "mdns")]
+    #[test]
+    fn test_mdns_share_requires_valid_mac() {
+        let code = generate_code();
+        let mut packet = DiscoveryPacket::new(&code, "Test Device", Uuid::new_v4(), 52530, 1, 1);
+        packet.seal();
+
+        let mdns_share = MdnsDiscoveredShare {
+            code: packet.code.clone(),
+            device_name: packet.device_name.clone(),
+            device_id: packet.device_id,
+            address: "192.168.1.10:52530".parse().expect("addr"),
+            transfer_port: packet.transfer_port,
+            file_count: packet.file_count,
+            total_size: packet.total_size,
+            protocol_version: packet.version.clone(),
+            timestamp: packet.timestamp,
+            nonce: packet.nonce.clone(),
+            mac: packet.mac.clone(),
+        };
+        assert!(verified(mdns_share.clone()).is_some());
+
+        let mut forged = mdns_share.clone();
+        forged.transfer_port = 4444;
+        assert!(verified(forged).is_none());
+
+        let mut unsigned = mdns_share;
+        unsigned.mac = None;
+        assert!(verified(unsigned).is_none());
+    }
+
     #[tokio::test]
     async fn test_hybrid_scan_empty() {
         let listener = HybridListener::new(0).await.expect("create listener");
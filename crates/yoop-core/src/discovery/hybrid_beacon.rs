@@ -0,0 +1,487 @@
+//! Hybrid device presence combining UDP beacons and mDNS.
+//!
+//! The beacon counterpart of [`super::hybrid`]: presence beacons are both
+//! broadcast over UDP and registered as a `_yoop-device._tcp` mDNS service,
+//! and lookups race the two, so trusted sends work on networks that filter
+//! broadcast but pass mDNS.
+//!
+//! Only presence is advertised over mDNS. Beacons with a `looking_for` target
+//! are transient requests and stay UDP-only.
+//!
+//! Devices found via mDNS go through the same signature and trust-store
+//! checks as UDP beacons. As with shares, the registered record is cached by
+//! resolvers, so its timestamp is not checked.
+
+#[cfg(feature = "mdns")]
+use std::collections::HashMap;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use super::{BeaconBroadcaster, BeaconListener, DeviceBeacon, DiscoveredDevice, DiscoveryOptions};
+use crate::crypto::DeviceIdentity;
+use crate::error::Result;
+use crate::trust::TrustedDevice;
+
+#[cfg(feature = "mdns")]
+use super::mdns::{MdnsBroadcaster, MdnsDeviceProperties, MdnsDiscoveredDevice, MdnsListener};
+
+/// Hybrid broadcaster that announces device presence via UDP and mDNS.
+pub struct HybridBeaconBroadcaster {
+    /// UDP beacon broadcaster
+    udp: BeaconBroadcaster,
+    /// mDNS broadcaster (if feature enabled)
+    #[cfg(feature = "mdns")]
+    mdns: Option<MdnsBroadcaster>,
+    /// Advertise private beacons
+    #[cfg(feature = "mdns")]
+    privacy: bool,
+}
+
+impl HybridBeaconBroadcaster {
+    /// Create a new hybrid beacon broadcaster with explicit options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the UDP broadcaster cannot be created.
+    pub async fn with_options(port: u16, options: &DiscoveryOptions) -> Result<Self> {
+        let udp = BeaconBroadcaster::with_options(port, options).await?;
+
+        #[cfg(feature = "mdns")]
+        let mdns = match MdnsBroadcaster::with_interfaces(&options.interfaces) {
+            Ok(m) => Some(m.hide_hostname(options.privacy)),
+            Err(e) => {
+                tracing::warn!("Failed to create mDNS broadcaster, continuing with UDP only: {e}");
+                None
+            }
+        };
+
+        Ok(Self {
+            udp,
+            #[cfg(feature = "mdns")]
+            mdns,
+            #[cfg(feature = "mdns")]
+            privacy: options.privacy,
+        })
+    }
+
+    /// Start broadcasting a device beacon.
+    ///
+    /// Presence beacons (without a `looking_for` target) are also registered
+    /// via mDNS, signed once at registration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if UDP broadcasting fails to start.
+    pub async fn start(
+        &self,
+        beacon: DeviceBeacon,
+        identity: &DeviceIdentity,
+        interval: Duration,
+    ) -> Result<()> {
+        #[cfg(feature = "mdns")]
+        let presence = beacon.looking_for.is_none().then(|| beacon.clone());
+
+        self.udp.start(beacon, identity, interval).await?;
+
+        #[cfg(feature = "mdns")]
+        if let (Some(mdns), Some(presence)) = (self.mdns.as_ref(), presence) {
+            let mut presence = if self.privacy {
+                presence.into_private()
+            } else {
+                presence
+            };
+            presence.refresh_timestamp();
+            presence.sign(identity);
+
+            if let Err(e) = mdns.register_device(beacon_to_mdns(presence)).await {
+                tracing::warn!("Failed to register mDNS device presence: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop broadcasting.
+    pub async fn stop(&self) {
+        self.udp.stop().await;
+
+        #[cfg(feature = "mdns")]
+        if let Some(ref mdns) = self.mdns {
+            if let Err(e) = mdns.unregister().await {
+                tracing::warn!("Failed to unregister mDNS device presence: {e}");
+            }
+        }
+    }
+
+    /// Check if currently broadcasting.
+    pub async fn is_broadcasting(&self) -> bool {
+        self.udp.is_broadcasting().await
+    }
+
+    /// Shutdown the broadcaster and release resources.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the mDNS shutdown fails.
+    #[cfg(feature = "mdns")]
+    pub fn shutdown(self) -> Result<()> {
+        if let Some(mdns) = self.mdns {
+            mdns.shutdown()?;
+        }
+        Ok(())
+    }
+
+    /// Shutdown the broadcaster and release resources.
+    ///
+    /// # Errors
+    ///
+    /// This variant always succeeds when mDNS is disabled.
+    #[cfg(not(feature = "mdns"))]
+    pub fn shutdown(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Hybrid listener that discovers devices via UDP beacons and mDNS presence.
+pub struct HybridBeaconListener {
+    /// UDP beacon listener, which also holds the trusted devices
+    udp: BeaconListener,
+    /// mDNS presence listener (if feature enabled)
+    #[cfg(feature = "mdns")]
+    mdns: Option<MdnsListener>,
+}
+
+impl HybridBeaconListener {
+    /// Create a new hybrid beacon listener with explicit options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the UDP listener cannot be created.
+    pub async fn with_options(port: u16, options: &DiscoveryOptions) -> Result<Self> {
+        let udp = BeaconListener::with_options(port, options).await?;
+
+        #[cfg(feature = "mdns")]
+        let mdns = match MdnsListener::for_devices(&options.interfaces) {
+            Ok(m) => Some(m),
+            Err(e) => {
+                tracing::warn!("Failed to create mDNS listener, continuing with UDP only: {e}");
+                None
+            }
+        };
+
+        Ok(Self {
+            udp,
+            #[cfg(feature = "mdns")]
+            mdns,
+        })
+    }
+
+    /// Require and resolve these trusted devices, as
+    /// [`BeaconListener::with_trusted`] does.
+    #[must_use]
+    pub fn with_trusted<'a>(
+        mut self,
+        devices: impl IntoIterator<Item = &'a TrustedDevice>,
+    ) -> Self {
+        self.udp = self.udp.with_trusted(devices);
+        self
+    }
+
+    /// Wait for a device with the given device_id.
+    ///
+    /// Races UDP beacons and mDNS presence, returning the first match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device is not found within the timeout.
+    pub async fn find_device(
+        &self,
+        device_id: Uuid,
+        timeout: Duration,
+    ) -> Result<DiscoveredDevice> {
+        #[cfg(feature = "mdns")]
+        if let Some(ref mdns) = self.mdns {
+            let mdns_future = mdns.find_device(timeout, |device| {
+                self.authenticated(device)
+                    .filter(|found| found.beacon.device_id == device_id)
+            });
+
+            tokio::select! {
+                udp_result = self.udp.find_device(device_id, timeout) => {
+                    if udp_result.is_ok() {
+                        tracing::debug!(%device_id, "Found device via UDP");
+                    }
+                    return udp_result;
+                }
+                Some(found) = mdns_future => {
+                    tracing::debug!(%device_id, "Found device via mDNS");
+                    return Ok(found);
+                }
+            }
+        }
+
+        self.udp.find_device(device_id, timeout).await
+    }
+
+    /// Wait for a device with the given name (case-insensitive).
+    ///
+    /// Races UDP beacons and mDNS presence, returning the first match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device is not found within the timeout.
+    pub async fn find_by_name(
+        &self,
+        device_name: &str,
+        timeout: Duration,
+    ) -> Result<DiscoveredDevice> {
+        #[cfg(feature = "mdns")]
+        if let Some(ref mdns) = self.mdns {
+            let mdns_future = mdns.find_device(timeout, |device| {
+                self.authenticated(device)
+                    .filter(|found| found.beacon.device_name.eq_ignore_ascii_case(device_name))
+            });
+
+            tokio::select! {
+                udp_result = self.udp.find_by_name(device_name, timeout) => return udp_result,
+                Some(found) = mdns_future => {
+                    tracing::debug!(device = %device_name, "Found device via mDNS");
+                    return Ok(found);
+                }
+            }
+        }
+
+        self.udp.find_by_name(device_name, timeout).await
+    }
+
+    /// Wait for any device that is looking for us.
+    ///
+    /// `looking_for` requests are only sent over UDP.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no device is found within the timeout.
+    pub async fn find_looking_for_us(
+        &self,
+        our_device_id: Uuid,
+        timeout: Duration,
+    ) -> Result<DiscoveredDevice> {
+        self.udp.find_looking_for_us(our_device_id, timeout).await
+    }
+
+    /// List all devices announcing themselves via UDP or mDNS.
+    ///
+    /// # Returns
+    ///
+    /// A list of discovered devices, deduplicated by device_id.
+    pub async fn scan(&self, duration: Duration) -> Vec<DiscoveredDevice> {
+        #[cfg(feature = "mdns")]
+        if let Some(ref mdns) = self.mdns {
+            let (udp_devices, mdns_devices) =
+                tokio::join!(self.udp.scan(duration), mdns.scan_devices(duration));
+
+            let mut devices: HashMap<Uuid, DiscoveredDevice> = HashMap::new();
+            for device in udp_devices {
+                devices.insert(device.beacon.device_id, device);
+            }
+            for device in mdns_devices
+                .into_iter()
+                .filter_map(|d| self.authenticated(d))
+            {
+                devices.entry(device.beacon.device_id).or_insert(device);
+            }
+            return devices.into_values().collect();
+        }
+
+        self.udp.scan(duration).await
+    }
+
+    /// Shutdown the listener and release resources.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the mDNS shutdown fails.
+    #[cfg(feature = "mdns")]
+    pub fn shutdown(self) -> Result<()> {
+        if let Some(mdns) = self.mdns {
+            mdns.shutdown()?;
+        }
+        Ok(())
+    }
+
+    /// Shutdown the listener and release resources.
+    ///
+    /// # Errors
+    ///
+    /// This variant always succeeds when mDNS is disabled.
+    #[cfg(not(feature = "mdns"))]
+    pub fn shutdown(self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Convert an mDNS device, dropping it if its signature does not verify.
+    #[cfg(feature = "mdns")]
+    fn authenticated(&self, device: MdnsDiscoveredDevice) -> Option<DiscoveredDevice> {
+        let source = device.address;
+        let beacon = mdns_to_beacon(device.properties);
+        if !beacon.is_valid() {
+            return None;
+        }
+        let beacon = self.udp.authenticate(beacon, source)?;
+        Some(DiscoveredDevice {
+            beacon,
+            source,
+            discovered_at: std::time::Instant::now(),
+        })
+    }
+}
+
+/// Flatten a signed presence beacon into mDNS properties.
+#[cfg(feature = "mdns")]
+fn beacon_to_mdns(beacon: DeviceBeacon) -> MdnsDeviceProperties {
+    MdnsDeviceProperties {
+        device_id: beacon.device_id,
+        device_name: beacon.device_name,
+        public_key: beacon.public_key,
+        transfer_port: beacon.transfer_port,
+        ready_to_receive: beacon.ready_to_receive,
+        protocol_version: beacon.version,
+        timestamp: beacon.timestamp,
+        nonce: beacon.nonce,
+        private: beacon.private,
+        signature: beacon.signature,
+    }
+}
+
+/// Rebuild the signed beacon from mDNS properties.
+#[cfg(feature = "mdns")]
+fn mdns_to_beacon(properties: MdnsDeviceProperties) -> DeviceBeacon {
+    DeviceBeacon {
+        beacon_type: "device".to_string(),
+        protocol: "yoop".to_string(),
+        version: properties.protocol_version,
+        device_id: properties.device_id,
+        device_name: properties.device_name,
+        public_key: properties.public_key,
+        transfer_port: properties.transfer_port,
+        looking_for: None,
+        ready_to_receive: properties.ready_to_receive,
+        timestamp: properties.timestamp,
+        nonce: properties.nonce,
+        private: properties.private,
+        signature: properties.signature,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> DiscoveryOptions {
+        DiscoveryOptions {
+            ipv6: false,
+            ..DiscoveryOptions::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_beacon_broadcaster_start_stop() {
+        let broadcaster = HybridBeaconBroadcaster::with_options(0, &options())
+            .await
+            .expect("create broadcaster");
+        let identity = DeviceIdentity::generate().expect("generate identity");
+        let beacon = DeviceBeacon::new(
+            identity.device_id(),
+            "Test Device",
+            &identity.public_key_base64(),
+            52530,
+        );
+
+        broadcaster
+            .start(beacon, &identity, Duration::from_millis(100))
+            .await
+            .expect("start broadcasting");
+        assert!(broadcaster.is_broadcasting().await);
+
+        broadcaster.stop().await;
+        assert!(!broadcaster.is_broadcasting().await);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_beacon_find_timeout() {
+        let listener = HybridBeaconListener::with_options(0, &options())
+            .await
+            .expect("create listener");
+        let result = listener
+            .find_device(Uuid::new_v4(), Duration::from_millis(100))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "mdns")]
+    #[tokio::test]
+    async fn test_mdns_presence_requires_trusted_signature() {
+        let genuine = DeviceIdentity::generate().expect("generate identity");
+        let attacker = DeviceIdentity::generate().expect("generate identity");
+        let trusted = TrustedDevice::new(
+            genuine.device_id(),
+            "Laptop".to_string(),
+            genuine.public_key_base64(),
+        )
+        .with_resolving_key(Some(genuine.resolving_key_base64()));
+        let listener = HybridBeaconListener::with_options(0, &options())
+            .await
+            .expect("create listener")
+            .with_trusted([&trusted]);
+        let address = "192.168.1.10:52530".parse().expect("addr");
+
+        let mut beacon = DeviceBeacon::new(
+            genuine.device_id(),
+            "Laptop",
+            &genuine.public_key_base64(),
+            52530,
+        )
+        .ready_to_receive(true);
+        beacon.sign(&genuine);
+        let found = listener
+            .authenticated(MdnsDiscoveredDevice {
+                properties: beacon_to_mdns(beacon),
+                address,
+            })
+            .expect("genuine presence accepted");
+        assert_eq!(found.beacon.device_id, genuine.device_id());
+        assert!(found.beacon.ready_to_receive);
+
+        let mut forged = DeviceBeacon::new(
+            genuine.device_id(),
+            "Laptop",
+            &attacker.public_key_base64(),
+            4444,
+        );
+        forged.sign(&attacker);
+        assert!(listener
+            .authenticated(MdnsDiscoveredDevice {
+                properties: beacon_to_mdns(forged),
+                address,
+            })
+            .is_none());
+
+        let mut private = DeviceBeacon::new(
+            genuine.device_id(),
+            "Laptop",
+            &genuine.public_key_base64(),
+            52530,
+        )
+        .into_private();
+        private.sign(&genuine);
+        let resolved = listener
+            .authenticated(MdnsDiscoveredDevice {
+                properties: beacon_to_mdns(private),
+                address,
+            })
+            .expect("private presence resolved");
+        assert_eq!(resolved.beacon.device_id, genuine.device_id());
+        assert_eq!(resolved.beacon.device_name, "Laptop");
+    }
+}
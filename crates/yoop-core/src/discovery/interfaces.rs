@@ -0,0 +1,333 @@
+//! Network interface selection for discovery.
+//!
+//! A limited broadcast to `255.255.255.255` leaves through whichever interface
+//! the OS routes it to, which on multi-homed hosts (Docker bridges, VPNs,
+//! Wi-Fi plus Ethernet) is often the wrong one. Instead, announcements are
+//! sent as directed broadcasts (e.g. `192.168.1.255`) on every selected
+//! interface, and IPv6 multicast is sent once per selected interface index.
+//!
+//! Selection is controlled by [`InterfaceFilter`]:
+//! - `interface`: `"auto"` for all interfaces, or a single interface name
+//! - `allow`: if non-empty, only these interfaces are used
+//! - `deny`: these interfaces are never used
+//!
+//! Allow and deny entries are interface names, optionally ending in `*` to
+//! match a prefix (e.g. `docker*`, `veth*`).
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use serde::Serialize;
+
+use super::socket::MulticastOptions;
+use super::sweep::SweepOptions;
+
+/// Which network interfaces discovery may use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceFilter {
+    /// `"auto"` for all interfaces, or the name of a single interface
+    pub interface: String,
+    /// Interfaces to use; empty means all
+    pub allow: Vec<String>,
+    /// Interfaces to never use
+    pub deny: Vec<String>,
+}
+
+impl Default for InterfaceFilter {
+    fn default() -> Self {
+        Self {
+            interface: "auto".to_string(),
+            allow: Vec::new(),
+            deny: Vec::new(),
+        }
+    }
+}
+
+impl InterfaceFilter {
+    /// Check whether discovery may use the named interface.
+    #[must_use]
+    pub fn allows(&self, name: &str) -> bool {
+        if !self.is_auto() && self.interface != name {
+            return false;
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|p| pattern_matches(p, name)) {
+            return false;
+        }
+        !self.deny.iter().any(|p| pattern_matches(p, name))
+    }
+
+    /// Whether the filter excludes anything at all.
+    #[must_use]
+    pub fn is_restrictive(&self) -> bool {
+        !self.is_auto() || !self.allow.is_empty() || !self.deny.is_empty()
+    }
+
+    fn is_auto(&self) -> bool {
+        self.interface.is_empty() || self.interface.eq_ignore_ascii_case("auto")
+    }
+}
+
+/// Options shared by all discovery broadcasters and listeners.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveryOptions {
+    /// Also announce and listen over IPv6 multicast
+    pub ipv6: bool,
+    /// Which interfaces to announce on and accept announcements from
+    pub interfaces: InterfaceFilter,
+    /// Privacy mode: announce rotating identifiers instead of the device's
+    /// identity, and only what is needed to connect for code shares
+    pub privacy: bool,
+    /// Probe hosts directly when passive discovery finds nothing
+    pub sweep: SweepOptions,
+    /// IPv4 multicast alongside directed broadcast
+    pub multicast: MulticastOptions,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            ipv6: true,
+            interfaces: InterfaceFilter::default(),
+            privacy: false,
+            sweep: SweepOptions::default(),
+            multicast: MulticastOptions::default(),
+        }
+    }
+}
+
+/// A local interface address considered for discovery.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveryInterface {
+    /// Interface name (e.g. `eth0`, `en0`)
+    pub name: String,
+    /// OS interface index, used as the IPv6 scope id
+    pub index: Option<u32>,
+    /// Address assigned to the interface
+    pub addr: IpAddr,
+    /// Directed broadcast address (IPv4 only)
+    pub broadcast: Option<Ipv4Addr>,
+    /// Whether discovery uses this interface under the current filter
+    pub selected: bool,
+}
+
+/// List the non-loopback interface addresses and whether discovery uses them.
+#[must_use]
+pub fn list_interfaces(filter: &InterfaceFilter) -> Vec<DiscoveryInterface> {
+    let mut interfaces: Vec<DiscoveryInterface> = if_addrs::get_if_addrs()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|iface| !iface.is_loopback())
+        .map(|iface| {
+            let broadcast = match iface.addr {
+                if_addrs::IfAddr::V4(ref v4) => v4.broadcast,
+                if_addrs::IfAddr::V6(_) => None,
+            };
+            DiscoveryInterface {
+                selected: filter.allows(&iface.name),
+                addr: iface.ip(),
+                index: iface.index,
+                name: iface.name,
+                broadcast,
+            }
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name).then(a.addr.cmp(&b.addr)));
+    interfaces
+}
+
+/// IPv4 broadcast destinations for the selected interfaces.
+///
+/// Falls back to the limited broadcast address when no interface reports a
+/// broadcast address and the filter does not restrict interfaces.
+#[must_use]
+pub fn broadcast_targets(filter: &InterfaceFilter) -> Vec<Ipv4Addr> {
+    let mut targets: Vec<Ipv4Addr> = list_interfaces(filter)
+        .into_iter()
+        .filter(|iface| iface.selected)
+        .filter_map(|iface| iface.broadcast)
+        .collect();
+    targets.sort_unstable();
+    targets.dedup();
+
+    if targets.is_empty() && !filter.is_restrictive() {
+        targets.push(Ipv4Addr::BROADCAST);
+    }
+    targets
+}
+
+/// IPv4 addresses of the selected interfaces, used for multicast.
+#[must_use]
+pub fn ipv4_interfaces(filter: &InterfaceFilter) -> Vec<Ipv4Addr> {
+    let mut addrs: Vec<Ipv4Addr> = list_interfaces(filter)
+        .into_iter()
+        .filter(|iface| iface.selected)
+        .filter_map(|iface| match iface.addr {
+            IpAddr::V4(v4) => Some(v4),
+            IpAddr::V6(_) => None,
+        })
+        .collect();
+    addrs.sort_unstable();
+    addrs.dedup();
+    addrs
+}
+
+/// Indices of the selected interfaces that carry an IPv6 address.
+#[must_use]
+pub fn ipv6_interfaces(filter: &InterfaceFilter) -> Vec<u32> {
+    let mut indices: Vec<u32> = list_interfaces(filter)
+        .into_iter()
+        .filter(|iface| iface.selected && iface.addr.is_ipv6())
+        .filter_map(|iface| iface.index)
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+/// Accepts or rejects announcement sources based on the interface filter.
+///
+/// IPv4 sources must fall within the subnet of a selected interface; IPv6
+/// link-local sources must arrive on a selected interface index. Loopback
+/// sources are always accepted.
+#[derive(Debug, Clone, Default)]
+pub struct SourceFilter {
+    /// `None` when the filter is not restrictive and everything is accepted
+    allowed: Option<AllowedNetworks>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct AllowedNetworks {
+    v4: Vec<(Ipv4Addr, Ipv4Addr)>,
+    v6_indices: Vec<u32>,
+}
+
+impl SourceFilter {
+    /// Snapshot the selected interfaces for `filter`.
+    #[must_use]
+    pub fn new(filter: &InterfaceFilter) -> Self {
+        if !filter.is_restrictive() {
+            return Self::default();
+        }
+
+        let mut allowed = AllowedNetworks::default();
+        for iface in if_addrs::get_if_addrs().unwrap_or_default() {
+            if iface.is_loopback() || !filter.allows(&iface.name) {
+                continue;
+            }
+            match iface.addr {
+                if_addrs::IfAddr::V4(v4) => allowed.v4.push((v4.ip, v4.netmask)),
+                if_addrs::IfAddr::V6(_) => allowed.v6_indices.extend(iface.index),
+            }
+        }
+
+        Self {
+            allowed: Some(allowed),
+        }
+    }
+
+    /// Check whether an announcement from `source` should be processed.
+    #[must_use]
+    pub fn accepts(&self, source: &SocketAddr) -> bool {
+        let Some(ref allowed) = self.allowed else {
+            return true;
+        };
+        if source.ip().to_canonical().is_loopback() {
+            return true;
+        }
+
+        match source {
+            SocketAddr::V4(v4) => in_subnets(*v4.ip(), &allowed.v4),
+            SocketAddr::V6(v6) => v6.ip().to_ipv4_mapped().map_or_else(
+                || v6.scope_id() == 0 || allowed.v6_indices.contains(&v6.scope_id()),
+                |mapped| in_subnets(mapped, &allowed.v4),
+            ),
+        }
+    }
+}
+
+fn in_subnets(ip: Ipv4Addr, subnets: &[(Ipv4Addr, Ipv4Addr)]) -> bool {
+    let ip = u32::from(ip);
+    subnets.iter().any(|(addr, mask)| {
+        let mask = u32::from(*mask);
+        ip & mask == u32::from(*addr) & mask
+    })
+}
+
+fn pattern_matches(pattern: &str, name: &str) -> bool {
+    pattern
+        .strip_suffix('*')
+        .map_or(pattern == name, |prefix| name.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(interface: &str, allow: &[&str], deny: &[&str]) -> InterfaceFilter {
+        InterfaceFilter {
+            interface: interface.to_string(),
+            allow: allow.iter().map(ToString::to_string).collect(),
+            deny: deny.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_default_filter_allows_everything() {
+        let f = InterfaceFilter::default();
+        assert!(f.allows("eth0"));
+        assert!(f.allows("docker0"));
+        assert!(!f.is_restrictive());
+    }
+
+    #[test]
+    fn test_named_interface() {
+        let f = filter("wlan0", &[], &[]);
+        assert!(f.allows("wlan0"));
+        assert!(!f.allows("eth0"));
+        assert!(f.is_restrictive());
+    }
+
+    #[test]
+    fn test_allow_and_deny_patterns() {
+        let f = filter("auto", &["en*", "wlan0"], &["enx*"]);
+        assert!(f.allows("en0"));
+        assert!(f.allows("wlan0"));
+        assert!(!f.allows("enx001122"));
+        assert!(!f.allows("docker0"));
+
+        let f = filter("auto", &[], &["docker*", "veth*"]);
+        assert!(f.allows("eth0"));
+        assert!(!f.allows("docker0"));
+        assert!(!f.allows("veth12ab"));
+    }
+
+    #[test]
+    fn test_broadcast_targets_fallback() {
+        let targets = broadcast_targets(&InterfaceFilter::default());
+        assert!(!targets.is_empty());
+
+        let none = filter("no-such-interface", &[], &[]);
+        assert!(broadcast_targets(&none).is_empty());
+        assert!(ipv6_interfaces(&none).is_empty());
+    }
+
+    #[test]
+    fn test_source_filter() {
+        let open = SourceFilter::default();
+        assert!(open.accepts(&"10.1.2.3:52525".parse().unwrap()));
+
+        let restricted = SourceFilter {
+            allowed: Some(AllowedNetworks {
+                v4: vec![(
+                    Ipv4Addr::new(192, 168, 1, 20),
+                    Ipv4Addr::new(255, 255, 255, 0),
+                )],
+                v6_indices: vec![2],
+            }),
+        };
+        assert!(restricted.accepts(&"192.168.1.77:52525".parse().unwrap()));
+        assert!(!restricted.accepts(&"172.17.0.2:52525".parse().unwrap()));
+        assert!(restricted.accepts(&"127.0.0.1:52525".parse().unwrap()));
+        assert!(restricted.accepts(&"[fe80::1%2]:52525".parse().unwrap()));
+        assert!(!restricted.accepts(&"[fe80::1%3]:52525".parse().unwrap()));
+    }
+}
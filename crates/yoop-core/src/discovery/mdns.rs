@@ -6,23 +6,36 @@
 //! ## Service Type
 //!
 //! Yoop registers as `_yoop._tcp.local.` with TXT records containing
-//! the share code and transfer metadata.
+//! the share code and transfer metadata, plus the same timestamp, nonce and
+//! MAC as the UDP discovery packet so listeners can apply the same checks.
+//!
+//! Device presence is advertised separately as `_yoop-device._tcp.local.`.
+//! Its TXT record holds just enough of a signed [device beacon] to verify it:
+//! the device ID, name and public key (all omitted or replaced in privacy
+//! mode), the ready flag, and the timestamp, nonce and signature.
+//!
+//! [device beacon]: super::DeviceBeacon
 
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::Duration;
 
-use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use mdns_sd::{IfKind, ServiceDaemon, ServiceEvent, ServiceInfo};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
 use crate::code::ShareCode;
 use crate::error::{Error, Result};
 
+use super::interfaces::{self, InterfaceFilter};
+
 /// mDNS service type for Yoop.
 pub const SERVICE_TYPE: &str = "_yoop._tcp.local.";
 
+/// mDNS service type for trusted-device presence.
+pub const DEVICE_SERVICE_TYPE: &str = "_yoop-device._tcp.local.";
+
 /// TXT record keys for service properties.
 pub mod txt_keys {
     /// Share code key
@@ -37,6 +50,20 @@ pub mod txt_keys {
     pub const TOTAL_SIZE: &str = "total_size";
     /// Protocol version key
     pub const VERSION: &str = "version";
+    /// Timestamp the MAC was computed at
+    pub const TIMESTAMP: &str = "timestamp";
+    /// Nonce covered by the MAC
+    pub const NONCE: &str = "nonce";
+    /// Base64-encoded HMAC keyed from the share code
+    pub const MAC: &str = "mac";
+    /// Base64-encoded Ed25519 public key (device presence)
+    pub const PUBLIC_KEY: &str = "pk";
+    /// Whether the device is ready to receive (device presence)
+    pub const READY: &str = "ready";
+    /// Whether the device ID is a rotating identifier (device presence)
+    pub const PRIVATE: &str = "private";
+    /// Base64-encoded Ed25519 beacon signature (device presence)
+    pub const SIGNATURE: &str = "sig";
 }
 
 /// Properties for mDNS service registration.
@@ -56,20 +83,34 @@ pub struct MdnsProperties {
     pub total_size: u64,
     /// Protocol version
     pub protocol_version: String,
+    /// Timestamp the MAC was computed at
+    pub timestamp: u64,
+    /// Nonce covered by the MAC
+    pub nonce: String,
+    /// Base64-encoded HMAC keyed from the share code
+    pub mac: Option<String>,
 }
 
 impl MdnsProperties {
     /// Convert to TXT record properties.
+    ///
+    /// The timestamp, nonce and MAC are only included for sealed shares.
     #[must_use]
     pub fn to_txt_properties(&self) -> Vec<(&str, String)> {
-        vec![
+        let mut properties = vec![
             (txt_keys::CODE, self.code.clone()),
             (txt_keys::DEVICE_NAME, self.device_name.clone()),
             (txt_keys::DEVICE_ID, self.device_id.to_string()),
             (txt_keys::FILE_COUNT, self.file_count.to_string()),
             (txt_keys::TOTAL_SIZE, self.total_size.to_string()),
             (txt_keys::VERSION, self.protocol_version.clone()),
-        ]
+        ];
+        if let Some(ref mac) = self.mac {
+            properties.push((txt_keys::TIMESTAMP, self.timestamp.to_string()));
+            properties.push((txt_keys::NONCE, self.nonce.clone()));
+            properties.push((txt_keys::MAC, mac.clone()));
+        }
+        properties
     }
 }
 
@@ -92,6 +133,12 @@ pub struct MdnsDiscoveredShare {
     pub total_size: u64,
     /// Protocol version
     pub protocol_version: String,
+    /// Timestamp the MAC was computed at
+    pub timestamp: u64,
+    /// Nonce covered by the MAC
+    pub nonce: String,
+    /// Base64-encoded HMAC keyed from the share code, if advertised
+    pub mac: Option<String>,
 }
 
 impl MdnsDiscoveredShare {
@@ -112,32 +159,168 @@ impl MdnsDiscoveredShare {
             .and_then(|s| s.parse().ok())
             .unwrap_or(0);
         let protocol_version = get_str(txt_keys::VERSION).unwrap_or_else(|| "1.0".to_string());
+        let timestamp = get_str(txt_keys::TIMESTAMP)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let nonce = get_str(txt_keys::NONCE).unwrap_or_default();
+        let mac = get_str(txt_keys::MAC);
 
-        let addresses = info.get_addresses();
-        let ip = addresses.iter().find(|addr| addr.is_ipv4())?;
+        let ip = preferred_address(info)?;
         let port = info.get_port();
 
         Some(Self {
             code,
             device_name,
             device_id,
-            address: SocketAddr::new(*ip, port),
+            address: SocketAddr::new(ip, port),
             transfer_port: port,
             file_count,
             total_size,
             protocol_version,
+            timestamp,
+            nonce,
+            mac,
         })
     }
 }
 
+/// Properties for mDNS device presence registration.
+///
+/// These mirror the signed fields of a [`DeviceBeacon`](super::DeviceBeacon)
+/// without a `looking_for` target.
+#[derive(Debug, Clone)]
+pub struct MdnsDeviceProperties {
+    /// Device ID, or a rotating identifier in privacy mode
+    pub device_id: Uuid,
+    /// Device name (empty in privacy mode)
+    pub device_name: String,
+    /// Base64-encoded public key (empty in privacy mode)
+    pub public_key: String,
+    /// Transfer port
+    pub transfer_port: u16,
+    /// Whether the device is ready to receive files
+    pub ready_to_receive: bool,
+    /// Protocol version
+    pub protocol_version: String,
+    /// Timestamp the signature was computed at
+    pub timestamp: u64,
+    /// Nonce covered by the signature
+    pub nonce: String,
+    /// Whether `device_id` is a rotating identifier
+    pub private: bool,
+    /// Base64-encoded Ed25519 signature
+    pub signature: Option<String>,
+}
+
+impl MdnsDeviceProperties {
+    /// Convert to TXT record properties.
+    ///
+    /// Empty names and keys are left out to keep the record small.
+    #[must_use]
+    pub fn to_txt_properties(&self) -> Vec<(&str, String)> {
+        let mut properties = vec![
+            (txt_keys::DEVICE_ID, self.device_id.to_string()),
+            (txt_keys::VERSION, self.protocol_version.clone()),
+            (txt_keys::READY, self.ready_to_receive.to_string()),
+            (txt_keys::TIMESTAMP, self.timestamp.to_string()),
+            (txt_keys::NONCE, self.nonce.clone()),
+        ];
+        if !self.device_name.is_empty() {
+            properties.push((txt_keys::DEVICE_NAME, self.device_name.clone()));
+        }
+        if !self.public_key.is_empty() {
+            properties.push((txt_keys::PUBLIC_KEY, self.public_key.clone()));
+        }
+        if self.private {
+            properties.push((txt_keys::PRIVATE, "true".to_string()));
+        }
+        if let Some(ref signature) = self.signature {
+            properties.push((txt_keys::SIGNATURE, signature.clone()));
+        }
+        properties
+    }
+}
+
+/// Information about a device found via mDNS presence.
+#[derive(Debug, Clone)]
+pub struct MdnsDiscoveredDevice {
+    /// The advertised properties
+    pub properties: MdnsDeviceProperties,
+    /// Socket address for transfer
+    pub address: SocketAddr,
+}
+
+impl MdnsDiscoveredDevice {
+    /// Parse from a resolved ServiceInfo.
+    fn from_service_info(info: &ServiceInfo) -> Option<Self> {
+        let properties = info.get_properties();
+
+        let get_str =
+            |key: &str| -> Option<String> { properties.get(key).map(|p| p.val_str().to_string()) };
+
+        let device_id = get_str(txt_keys::DEVICE_ID).and_then(|s| Uuid::parse_str(&s).ok())?;
+        let ip = preferred_address(info)?;
+        let port = info.get_port();
+
+        Some(Self {
+            properties: MdnsDeviceProperties {
+                device_id,
+                device_name: get_str(txt_keys::DEVICE_NAME).unwrap_or_default(),
+                public_key: get_str(txt_keys::PUBLIC_KEY).unwrap_or_default(),
+                transfer_port: port,
+                ready_to_receive: get_str(txt_keys::READY).is_some_and(|s| s == "true"),
+                protocol_version: get_str(txt_keys::VERSION).unwrap_or_else(|| "1.0".to_string()),
+                timestamp: get_str(txt_keys::TIMESTAMP)
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0),
+                nonce: get_str(txt_keys::NONCE).unwrap_or_default(),
+                private: get_str(txt_keys::PRIVATE).is_some_and(|s| s == "true"),
+                signature: get_str(txt_keys::SIGNATURE),
+            },
+            address: SocketAddr::new(ip, port),
+        })
+    }
+}
+
+/// Pick the address to connect to from a resolved service.
+///
+/// Prefers IPv4; mDNS does not carry a scope id, so link-local IPv6 is unusable.
+fn preferred_address(info: &ServiceInfo) -> Option<IpAddr> {
+    let addresses = info.get_addresses();
+    addresses
+        .iter()
+        .find(|addr| addr.is_ipv4())
+        .or_else(|| {
+            addresses.iter().find(|addr| match addr {
+                IpAddr::V6(v6) => !v6.is_unicast_link_local(),
+                IpAddr::V4(_) => false,
+            })
+        })
+        .copied()
+}
+
+/// Qualify a host name with `.local.` as mDNS requires.
+fn local_hostname(raw_hostname: String) -> String {
+    if raw_hostname.ends_with(".local.") {
+        raw_hostname
+    } else if raw_hostname.to_lowercase().ends_with(".local") {
+        format!("{raw_hostname}.")
+    } else {
+        format!("{raw_hostname}.local.")
+    }
+}
+
 /// mDNS service broadcaster.
 ///
-/// Registers a Yoop share as an mDNS service on the local network.
+/// Registers a Yoop share, or this device's presence, as an mDNS service on
+/// the local network.
 pub struct MdnsBroadcaster {
     /// The mDNS daemon (wrapped in Option to support Drop)
     daemon: Option<ServiceDaemon>,
-    /// Instance name for the registered service
+    /// Full name (`instance.service-type`) of the registered service
     instance_name: Arc<Mutex<Option<String>>>,
+    /// Advertise a host name derived from the share code instead of ours
+    hide_hostname: bool,
 }
 
 impl MdnsBroadcaster {
@@ -147,15 +330,32 @@ impl MdnsBroadcaster {
     ///
     /// Returns an error if the mDNS daemon cannot be created.
     pub fn new() -> Result<Self> {
-        let daemon =
-            ServiceDaemon::new().map_err(|e| Error::Internal(format!("mDNS daemon error: {e}")))?;
+        Self::with_interfaces(&InterfaceFilter::default())
+    }
+
+    /// Create a new mDNS broadcaster limited to the selected interfaces.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the mDNS daemon cannot be created.
+    pub fn with_interfaces(filter: &InterfaceFilter) -> Result<Self> {
+        let daemon = create_daemon(filter)?;
 
         Ok(Self {
             daemon: Some(daemon),
             instance_name: Arc::new(Mutex::new(None)),
+            hide_hostname: false,
         })
     }
 
+    /// Advertise a host name derived from the share code rather than the
+    /// machine's host name, for privacy mode.
+    #[must_use]
+    pub fn hide_hostname(mut self, hide: bool) -> Self {
+        self.hide_hostname = hide;
+        self
+    }
+
     /// Register a share as an mDNS service.
     ///
     /// # Arguments
@@ -170,36 +370,26 @@ impl MdnsBroadcaster {
 
         let txt_props: Vec<_> = properties.to_txt_properties();
 
-        let raw_hostname = hostname::get().map_or_else(
-            |_| "localhost".to_string(),
-            |h| h.to_string_lossy().to_string(),
-        );
-
-        let hostname = if raw_hostname.ends_with(".local.") {
-            raw_hostname
-        } else if raw_hostname.to_lowercase().ends_with(".local") {
-            format!("{raw_hostname}.")
+        let raw_hostname = if self.hide_hostname {
+            format!("yoop-{}", properties.code.to_lowercase())
         } else {
-            format!("{raw_hostname}.local.")
+            hostname::get().map_or_else(
+                |_| "localhost".to_string(),
+                |h| h.to_string_lossy().to_string(),
+            )
         };
 
         let service_info = ServiceInfo::new(
             SERVICE_TYPE,
             &instance_name,
-            &hostname,
+            &local_hostname(raw_hostname),
             (),
             properties.transfer_port,
             txt_props.as_slice(),
         )
         .map_err(|e| Error::Internal(format!("Failed to create mDNS service info: {e}")))?;
 
-        self.daemon
-            .as_ref()
-            .ok_or_else(|| Error::Internal("mDNS daemon already shutdown".to_string()))?
-            .register(service_info)
-            .map_err(|e| Error::Internal(format!("Failed to register mDNS service: {e}")))?;
-
-        *self.instance_name.lock().await = Some(instance_name.clone());
+        self.register_service(service_info).await?;
 
         tracing::info!(
             code = %properties.code,
@@ -210,6 +400,58 @@ impl MdnsBroadcaster {
         Ok(())
     }
 
+    /// Register this device's presence as an mDNS service.
+    ///
+    /// The instance name is derived from the advertised device ID, so in
+    /// privacy mode it carries only the rotating identifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if registration fails.
+    pub async fn register_device(&self, properties: MdnsDeviceProperties) -> Result<()> {
+        let short_id = properties.device_id.simple().to_string()[..12].to_string();
+        let instance_name = format!("Yoop-Device-{short_id}");
+
+        let raw_hostname = if self.hide_hostname {
+            format!("yoop-{short_id}")
+        } else {
+            hostname::get().map_or_else(
+                |_| "localhost".to_string(),
+                |h| h.to_string_lossy().to_string(),
+            )
+        };
+
+        let txt_props = properties.to_txt_properties();
+        let service_info = ServiceInfo::new(
+            DEVICE_SERVICE_TYPE,
+            &instance_name,
+            &local_hostname(raw_hostname),
+            (),
+            properties.transfer_port,
+            txt_props.as_slice(),
+        )
+        .map_err(|e| Error::Internal(format!("Failed to create mDNS service info: {e}")))?;
+
+        self.register_service(service_info).await?;
+
+        tracing::info!(instance = %instance_name, "Registered mDNS device presence");
+
+        Ok(())
+    }
+
+    async fn register_service(&self, service_info: ServiceInfo) -> Result<()> {
+        let full_name = service_info.get_fullname().to_string();
+
+        self.daemon
+            .as_ref()
+            .ok_or_else(|| Error::Internal("mDNS daemon already shutdown".to_string()))?
+            .register(service_info)
+            .map_err(|e| Error::Internal(format!("Failed to register mDNS service: {e}")))?;
+
+        *self.instance_name.lock().await = Some(full_name);
+        Ok(())
+    }
+
     /// Unregister the mDNS service.
     ///
     /// # Errors
@@ -218,7 +460,7 @@ impl MdnsBroadcaster {
     pub async fn unregister(&self) -> Result<()> {
         let instance_name = self.instance_name.lock().await.take();
         if let Some(instance_name) = instance_name {
-            let full_name = format!("{instance_name}.{SERVICE_TYPE}");
+            let full_name = instance_name.clone();
 
             let receiver = self
                 .daemon
@@ -302,12 +544,15 @@ impl Drop for MdnsBroadcaster {
 
 /// mDNS service listener.
 ///
-/// Discovers Yoop shares advertised via mDNS on the local network.
+/// Discovers Yoop shares, or device presence, advertised via mDNS on the
+/// local network.
 pub struct MdnsListener {
     /// The mDNS daemon (wrapped in Option to support Drop)
     daemon: Option<ServiceDaemon>,
     /// Receiver for service events
     receiver: flume::Receiver<ServiceEvent>,
+    /// Service type being browsed
+    service_type: &'static str,
 }
 
 impl MdnsListener {
@@ -317,16 +562,39 @@ impl MdnsListener {
     ///
     /// Returns an error if the mDNS daemon cannot be created.
     pub fn new() -> Result<Self> {
-        let daemon =
-            ServiceDaemon::new().map_err(|e| Error::Internal(format!("mDNS daemon error: {e}")))?;
+        Self::with_interfaces(&InterfaceFilter::default())
+    }
+
+    /// Create a new mDNS listener limited to the selected interfaces.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the mDNS daemon cannot be created.
+    pub fn with_interfaces(filter: &InterfaceFilter) -> Result<Self> {
+        Self::browse(filter, SERVICE_TYPE)
+    }
+
+    /// Create a listener for device presence, limited to the selected
+    /// interfaces.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the mDNS daemon cannot be created.
+    pub fn for_devices(filter: &InterfaceFilter) -> Result<Self> {
+        Self::browse(filter, DEVICE_SERVICE_TYPE)
+    }
+
+    fn browse(filter: &InterfaceFilter, service_type: &'static str) -> Result<Self> {
+        let daemon = create_daemon(filter)?;
 
         let receiver = daemon
-            .browse(SERVICE_TYPE)
+            .browse(service_type)
             .map_err(|e| Error::Internal(format!("Failed to browse mDNS services: {e}")))?;
 
         Ok(Self {
             daemon: Some(daemon),
             receiver,
+            service_type,
         })
     }
 
@@ -412,12 +680,81 @@ impl MdnsListener {
         discovered.into_values().collect()
     }
 
+    /// Wait for a device whose presence `accept` maps to a result.
+    ///
+    /// Only meaningful on a listener created with
+    /// [`for_devices`](MdnsListener::for_devices). `accept` is where callers
+    /// authenticate the advertisement and match it against what they want.
+    pub async fn find_device<T>(
+        &self,
+        timeout: Duration,
+        mut accept: impl FnMut(MdnsDiscoveredDevice) -> Option<T>,
+    ) -> Option<T> {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                return None;
+            }
+
+            let result =
+                tokio::time::timeout(remaining, async { self.receiver.recv_async().await }).await;
+
+            match result {
+                Ok(Ok(event)) => {
+                    if let ServiceEvent::ServiceResolved(info) = event {
+                        if let Some(found) =
+                            MdnsDiscoveredDevice::from_service_info(&info).and_then(&mut accept)
+                        {
+                            return Some(found);
+                        }
+                    }
+                }
+                Ok(Err(_)) | Err(_) => return None,
+            }
+        }
+    }
+
+    /// Scan for all advertised devices.
+    ///
+    /// Only meaningful on a listener created with
+    /// [`for_devices`](MdnsListener::for_devices). Results are not
+    /// authenticated.
+    pub async fn scan_devices(&self, duration: Duration) -> Vec<MdnsDiscoveredDevice> {
+        let mut discovered = HashMap::new();
+        let deadline = tokio::time::Instant::now() + duration;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+
+            let result =
+                tokio::time::timeout(remaining, async { self.receiver.recv_async().await }).await;
+
+            match result {
+                Ok(Ok(event)) => {
+                    if let ServiceEvent::ServiceResolved(info) = event {
+                        if let Some(device) = MdnsDiscoveredDevice::from_service_info(&info) {
+                            discovered.insert(device.properties.device_id, device);
+                        }
+                    }
+                }
+                Ok(Err(_)) | Err(_) => break,
+            }
+        }
+
+        discovered.into_values().collect()
+    }
+
     /// Stop browsing for services.
     ///
     /// This should be called before shutdown to properly clean up the browse operation.
     fn stop_browsing(&self) {
         if let Some(ref daemon) = self.daemon {
-            if let Err(e) = daemon.stop_browse(SERVICE_TYPE) {
+            if let Err(e) = daemon.stop_browse(self.service_type) {
                 tracing::debug!("Failed to stop mDNS browse: {e}");
             }
         }
@@ -456,7 +793,7 @@ impl Drop for MdnsListener {
     #[allow(clippy::cognitive_complexity)]
     fn drop(&mut self) {
         if let Some(ref daemon) = self.daemon {
-            if let Err(e) = daemon.stop_browse(SERVICE_TYPE) {
+            if let Err(e) = daemon.stop_browse(self.service_type) {
                 tracing::debug!("Failed to stop mDNS browse during drop: {e}");
             }
         }
@@ -483,6 +820,30 @@ impl Drop for MdnsListener {
     }
 }
 
+/// Create an mDNS daemon with excluded interfaces disabled.
+fn create_daemon(filter: &InterfaceFilter) -> Result<ServiceDaemon> {
+    let daemon =
+        ServiceDaemon::new().map_err(|e| Error::Internal(format!("mDNS daemon error: {e}")))?;
+
+    if filter.is_restrictive() {
+        let mut excluded: Vec<String> = interfaces::list_interfaces(filter)
+            .into_iter()
+            .filter(|iface| !iface.selected)
+            .map(|iface| iface.name)
+            .collect();
+        excluded.dedup();
+
+        if !excluded.is_empty() {
+            let kinds: Vec<IfKind> = excluded.into_iter().map(IfKind::Name).collect();
+            daemon
+                .disable_interface(kinds)
+                .map_err(|e| Error::Internal(format!("mDNS interface filter error: {e}")))?;
+        }
+    }
+
+    Ok(daemon)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -497,6 +858,9 @@ mod tests {
             file_count: 5,
             total_size: 1_024_000,
             protocol_version: "1.0".to_string(),
+            timestamp: 0,
+            nonce: String::new(),
+            mac: None,
         };
 
         let txt = props.to_txt_properties();
@@ -507,9 +871,40 @@ mod tests {
         assert_eq!(code_prop.unwrap().1, "TEST-123");
     }
 
+    #[test]
+    fn test_device_properties_to_txt() {
+        let mut props = MdnsDeviceProperties {
+            device_id: Uuid::nil(),
+            device_name: "Laptop".to_string(),
+            public_key: "key".to_string(),
+            transfer_port: 52530,
+            ready_to_receive: true,
+            protocol_version: "1.0".to_string(),
+            timestamp: 1,
+            nonce: "nonce".to_string(),
+            private: false,
+            signature: Some("sig".to_string()),
+        };
+
+        let txt = props.to_txt_properties();
+        assert!(txt.contains(&(txt_keys::PUBLIC_KEY, "key".to_string())));
+        assert!(txt.contains(&(txt_keys::READY, "true".to_string())));
+        assert!(!txt.iter().any(|(k, _)| *k == txt_keys::PRIVATE));
+
+        props.device_name = String::new();
+        props.public_key = String::new();
+        props.private = true;
+        let txt = props.to_txt_properties();
+        assert!(!txt.iter().any(|(k, _)| *k == txt_keys::DEVICE_NAME));
+        assert!(!txt.iter().any(|(k, _)| *k == txt_keys::PUBLIC_KEY));
+        assert!(txt.contains(&(txt_keys::PRIVATE, "true".to_string())));
+    }
+
     #[test]
     fn test_service_type_format() {
         assert!(SERVICE_TYPE.ends_with(".local."));
         assert!(SERVICE_TYPE.starts_with("_yoop._tcp"));
+        assert!(DEVICE_SERVICE_TYPE.ends_with("._tcp.local."));
+        assert_ne!(DEVICE_SERVICE_TYPE, SERVICE_TYPE);
     }
 }
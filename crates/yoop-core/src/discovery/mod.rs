@@ -24,38 +24,107 @@
 //!   "supports": ["tcp", "quic"],
 //!   "file_count": 3,
 //!   "total_size": 157286400,
-//!   "preview_available": true
+//!   "preview_available": true,
+//!   "timestamp": 1699899000,
+//!   "nonce": "base64",
+//!   "mac": "base64"
 //! }
 //! ```
 //!
+//! ## Authentication
+//!
+//! Device beacons are signed with the device's Ed25519 key; beacons from
+//! devices in the trust store must be signed by the stored key. Share
+//! announcements carry an HMAC-SHA256 keyed from the share code. The code is
+//! broadcast in the same packet (so `yoop scan` can list it), which makes
+//! this an integrity check rather than authentication: it drops corrupted
+//! and truncated packets, but anyone on the network can forge an
+//! announcement. Both include a timestamp and nonce, and listeners drop
+//! packets that are stale or repeat a nonce they have already seen.
+//!
+//! ## Privacy Mode
+//!
+//! With [`DiscoveryOptions::privacy`] set, beacons replace the device ID,
+//! name and public key with a rotating identifier that only paired devices
+//! can resolve, and share announcements replace the device name, ID and file
+//! summary with placeholders, leaving just the code and transfer port.
+//!
 //! ## mDNS Discovery
 //!
 //! When the `mdns` feature is enabled, Yoop also advertises and discovers
 //! shares via mDNS/DNS-SD using the service type `_yoop._tcp.local.`.
 //! This provides better reliability in networks where UDP broadcast is blocked.
+//!
+//! Presence beacons are also registered under `_yoop-device._tcp.local.` by
+//! [`HybridBeaconBroadcaster`], and [`HybridBeaconListener`] races them
+//! against UDP beacons. Their TXT records carry only what a signed beacon
+//! does, and they are verified against the trust store the same way.
+//!
+//! ## Subnet Sweep
+//!
+//! With [`SweepOptions::enabled`] set, listeners that hear nothing passively
+//! send a [`DiscoveryQuery`] to each host in the local subnets (or configured
+//! CIDR ranges), and sharers and beacon broadcasters answer it by unicast.
+//! This finds peers on networks that drop both broadcast and mDNS.
+//!
+//! ## IPv4 Multicast
+//!
+//! Since routers and access points often rate-limit or drop broadcast,
+//! announcements and beacons are also sent to the IPv4 multicast group in
+//! [`MulticastOptions`] (default `239.255.79.70`, TTL 1) on each selected
+//! interface, and listeners join the group there. Duplicates arriving by
+//! both paths are dropped by the replay check. [`check_delivery`] reports
+//! which mechanisms actually deliver packets.
+//!
+//! ## IPv6
+//!
+//! IPv4 announcements are sent as directed broadcasts on each selected
+//! interface (see [`InterfaceFilter`]). When IPv6 is enabled they are also sent to the link-local multicast group `ff02::7970` on each
+//! IPv6-capable interface, so shares are found on IPv6-only networks. The
+//! IPv6 path is best-effort and silently disabled on hosts without IPv6.
 
 #[cfg(feature = "mdns")]
 pub mod mdns;
 
+mod auth;
 mod beacon;
 mod hybrid;
+mod hybrid_beacon;
+mod interfaces;
+mod privacy;
+mod socket;
+mod sweep;
 
 pub use beacon::{BeaconBroadcaster, BeaconListener, DeviceBeacon, DiscoveredDevice};
 pub use hybrid::{HybridBroadcaster, HybridListener};
+pub use hybrid_beacon::{HybridBeaconBroadcaster, HybridBeaconListener};
+pub use interfaces::{
+    broadcast_targets, ipv4_interfaces, ipv6_interfaces, list_interfaces, DiscoveryInterface,
+    DiscoveryOptions, InterfaceFilter,
+};
+pub use socket::{
+    check_delivery, DeliveryReport, MulticastOptions, IPV4_MULTICAST_GROUP, IPV6_MULTICAST_GROUP,
+};
+pub use sweep::{sweep_targets, DiscoveryQuery, QueryKind, SweepOptions, MAX_SWEEP_HOSTS};
 
 use std::collections::HashMap;
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use base64::prelude::*;
 use serde::{Deserialize, Serialize};
 use tokio::net::UdpSocket;
 use tokio::sync::{broadcast, Mutex};
 use uuid::Uuid;
 
 use crate::code::ShareCode;
+use crate::crypto;
 use crate::error::{Error, Result};
 
+use auth::ReplayGuard;
+use interfaces::SourceFilter;
+
 /// Default discovery port.
 pub const DEFAULT_DISCOVERY_PORT: u16 = 52525;
 
@@ -84,6 +153,15 @@ pub struct DiscoveryPacket {
     pub total_size: u64,
     /// Whether previews are available
     pub preview_available: bool,
+    /// Unix timestamp when the packet was sealed
+    #[serde(default)]
+    pub timestamp: u64,
+    /// Random nonce against replay
+    #[serde(default)]
+    pub nonce: String,
+    /// Base64-encoded HMAC-SHA256 over the packet, keyed from the share code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mac: Option<String>,
 }
 
 impl DiscoveryPacket {
@@ -109,6 +187,9 @@ impl DiscoveryPacket {
             file_count,
             total_size,
             preview_available: true,
+            timestamp: 0,
+            nonce: String::new(),
+            mac: None,
         }
     }
 
@@ -117,6 +198,73 @@ impl DiscoveryPacket {
     pub fn is_valid(&self) -> bool {
         self.protocol == "yoop" && self.version == "1.0"
     }
+
+    /// Stamp the packet with the current time and a fresh nonce, and
+    /// compute its MAC.
+    pub fn seal(&mut self) {
+        self.timestamp = auth::unix_now();
+        self.nonce = auth::new_nonce();
+        self.mac = Some(BASE64_STANDARD.encode(self.compute_mac()));
+    }
+
+    /// Strip everything not needed to connect: the device name, device ID
+    /// and file summary.
+    ///
+    /// The device ID is replaced with one derived from the code and the
+    /// device's resolving key, so repeated announcements of the same share
+    /// still match up.
+    pub fn redact(&mut self, resolving_key: &[u8; 32]) {
+        self.device_name = String::new();
+        self.device_id = privacy::share_id(resolving_key, &self.code);
+        self.file_count = 0;
+        self.total_size = 0;
+        self.preview_available = false;
+    }
+
+    /// Check the MAC against the key derived from the packet's code.
+    ///
+    /// This does not check freshness; see [`DiscoveryPacket::is_intact`].
+    #[must_use]
+    pub fn verify_mac(&self) -> bool {
+        let Some(mac) = self
+            .mac
+            .as_deref()
+            .and_then(|mac| BASE64_STANDARD.decode(mac).ok())
+        else {
+            return false;
+        };
+        crypto::constant_time_eq(&mac, &self.compute_mac())
+    }
+
+    /// Check that the MAC is valid and the packet is recent.
+    ///
+    /// The MAC key is derived from the code the packet carries, so this
+    /// does not prove who sent it.
+    #[must_use]
+    pub fn is_intact(&self) -> bool {
+        self.verify_mac() && auth::is_fresh(self.timestamp)
+    }
+
+    /// HMAC over the fields that identify the share and where to fetch it.
+    ///
+    /// Only fields that mDNS also carries are covered, so shares found via
+    /// mDNS can be verified the same way.
+    fn compute_mac(&self) -> [u8; 32] {
+        let fields = (
+            &self.protocol,
+            &self.version,
+            &self.code,
+            &self.device_name,
+            self.device_id,
+            self.transfer_port,
+            self.file_count,
+            self.total_size,
+            self.timestamp,
+            &self.nonce,
+        );
+        let data = serde_json::to_vec(&fields).unwrap_or_default();
+        crypto::hmac_sha256(&crypto::derive_discovery_key(&self.code), &data)
+    }
 }
 
 /// A discovered share on the network.
@@ -130,11 +278,31 @@ pub struct DiscoveredShare {
     pub discovered_at: Instant,
 }
 
+impl DiscoveredShare {
+    /// Get the address to connect to for the transfer.
+    ///
+    /// IPv6 sources keep their scope id, which link-local addresses need.
+    #[must_use]
+    pub fn transfer_addr(&self) -> SocketAddr {
+        let mut addr = self.source;
+        addr.set_port(self.packet.transfer_port);
+        addr
+    }
+}
+
 /// Broadcaster for announcing shares on the network.
 #[derive(Debug)]
 pub struct Broadcaster {
     /// UDP socket for broadcasting
     socket: Arc<UdpSocket>,
+    /// UDP socket for IPv6 multicast, if available
+    socket_v6: Option<Arc<UdpSocket>>,
+    /// Interfaces to announce on
+    interfaces: InterfaceFilter,
+    /// Resolving key to redact announcements with, in privacy mode
+    privacy_key: Option<[u8; 32]>,
+    /// IPv4 multicast settings
+    multicast: MulticastOptions,
     /// Discovery port
     port: u16,
     /// Shutdown signal sender
@@ -150,38 +318,50 @@ impl Broadcaster {
     ///
     /// Returns an error if the socket cannot be created.
     pub async fn new(port: u16) -> Result<Self> {
-        let socket = socket2::Socket::new(
-            socket2::Domain::IPV4,
-            socket2::Type::DGRAM,
-            Some(socket2::Protocol::UDP),
-        )?;
-
-        socket.set_broadcast(true)?;
-        socket.set_reuse_address(true)?;
-
-        #[cfg(target_os = "macos")]
-        socket.set_reuse_port(true)?;
-
-        let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0);
-        socket.bind(&addr.into())?;
-
-        socket.set_nonblocking(true)?;
+        Self::with_options(port, &DiscoveryOptions::default()).await
+    }
 
-        let std_socket: std::net::UdpSocket = socket.into();
-        let socket = UdpSocket::from_std(std_socket)?;
+    /// Create a new broadcaster with explicit IPv6 and interface options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the IPv4 socket cannot be created.
+    pub async fn with_options(port: u16, options: &DiscoveryOptions) -> Result<Self> {
+        let socket = socket::ipv4_sender(options.multicast.ttl)?;
+        let socket_v6 = socket::optional_ipv6(options.ipv6, socket::ipv6_sender);
 
         let (shutdown_tx, _) = broadcast::channel(1);
 
         Ok(Self {
             socket: Arc::new(socket),
+            socket_v6: socket_v6.map(Arc::new),
+            interfaces: options.interfaces.clone(),
+            privacy_key: options.privacy.then(crypto::random_bytes),
+            multicast: options.multicast.clone(),
             port,
             shutdown_tx,
             is_active: Arc::new(Mutex::new(false)),
         })
     }
 
+    /// Redact announcements with this device's resolving key in privacy
+    /// mode, so paired devices can tell its shares apart.
+    ///
+    /// Without it a random key is used. Has no effect outside privacy mode.
+    #[must_use]
+    pub fn with_resolving_key(mut self, key: [u8; 32]) -> Self {
+        if self.privacy_key.is_some() {
+            self.privacy_key = Some(key);
+        }
+        self
+    }
+
     /// Start broadcasting a share.
     ///
+    /// In privacy mode the packet is [redacted](DiscoveryPacket::redact)
+    /// first. Sweep queries for shares are answered with the same packet
+    /// until the broadcaster stops.
+    ///
     /// # Arguments
     ///
     /// * `packet` - The discovery packet to broadcast
@@ -199,14 +379,34 @@ impl Broadcaster {
         drop(is_active);
 
         let socket = Arc::clone(&self.socket);
+        let socket_v6 = self.socket_v6.clone();
+        let interfaces = self.interfaces.clone();
+        let multicast = self.multicast.clone();
         let port = self.port;
         let is_active = Arc::clone(&self.is_active);
         let mut shutdown_rx = self.shutdown_tx.subscribe();
 
-        tokio::spawn(async move {
-            let broadcast_addr = SocketAddrV4::new(Ipv4Addr::BROADCAST, port);
+        let mut packet = packet;
+        if let Some(key) = &self.privacy_key {
+            packet.redact(key);
+        }
 
+        let reply = packet.clone();
+        sweep::spawn_responder(
+            port,
+            QueryKind::Share,
+            self.shutdown_tx.subscribe(),
+            move || {
+                let mut reply = reply.clone();
+                reply.seal();
+                serde_json::to_vec(&reply).ok()
+            },
+        );
+
+        tokio::spawn(async move {
             loop {
+                packet.seal();
+
                 let json = match serde_json::to_vec(&packet) {
                     Ok(json) => json,
                     Err(e) => {
@@ -215,8 +415,11 @@ impl Broadcaster {
                     }
                 };
 
-                if let Err(e) = socket.send_to(&json, broadcast_addr).await {
-                    tracing::warn!("Failed to send broadcast: {}", e);
+                socket::send_ipv4_broadcast(&socket, &json, port, &interfaces).await;
+                socket::send_ipv4_multicast(&socket, &json, port, &multicast, &interfaces).await;
+
+                if let Some(ref socket_v6) = socket_v6 {
+                    socket::send_ipv6_multicast(socket_v6, &json, port, &interfaces).await;
                 }
 
                 tokio::select! {
@@ -253,6 +456,18 @@ impl Broadcaster {
 pub struct Listener {
     /// UDP socket for receiving broadcasts
     socket: Arc<UdpSocket>,
+    /// UDP socket for receiving IPv6 multicast, if available
+    socket_v6: Option<Arc<UdpSocket>>,
+    /// Drops packets from interfaces excluded by the filter
+    sources: SourceFilter,
+    /// Drops packets whose nonce was already seen
+    replay: ReplayGuard,
+    /// Discovery port queried on swept hosts
+    port: u16,
+    /// Sweep settings used when passive discovery finds nothing
+    sweep: SweepOptions,
+    /// Interfaces whose subnets are swept
+    interfaces: InterfaceFilter,
 }
 
 impl Listener {
@@ -262,32 +477,75 @@ impl Listener {
     ///
     /// Returns an error if the socket cannot be created.
     pub async fn new(port: u16) -> Result<Self> {
-        let socket = socket2::Socket::new(
-            socket2::Domain::IPV4,
-            socket2::Type::DGRAM,
-            Some(socket2::Protocol::UDP),
-        )?;
-
-        socket.set_reuse_address(true)?;
-
-        #[cfg(target_os = "macos")]
-        socket.set_reuse_port(true)?;
-
-        let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port);
-        socket.bind(&addr.into())?;
-
-        socket.set_nonblocking(true)?;
+        Self::with_options(port, &DiscoveryOptions::default()).await
+    }
 
-        let std_socket: std::net::UdpSocket = socket.into();
-        let socket = UdpSocket::from_std(std_socket)?;
+    /// Create a new listener with explicit IPv6 and interface options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the IPv4 socket cannot be created.
+    pub async fn with_options(port: u16, options: &DiscoveryOptions) -> Result<Self> {
+        let socket = socket::ipv4_listener(port)?;
+        socket::join_ipv4_multicast(&socket, &options.multicast, &options.interfaces);
+        let socket_v6 = socket::optional_ipv6(options.ipv6, || {
+            socket::ipv6_listener(port, &options.interfaces)
+        });
 
         Ok(Self {
             socket: Arc::new(socket),
+            socket_v6: socket_v6.map(Arc::new),
+            sources: SourceFilter::new(&options.interfaces),
+            replay: ReplayGuard::default(),
+            port,
+            sweep: options.sweep.clone(),
+            interfaces: options.interfaces.clone(),
         })
     }
 
+    /// Query every swept host for shares.
+    ///
+    /// Queries are sent from the listening socket, so replies are received
+    /// and checked like broadcast announcements.
+    async fn send_sweep(&self) {
+        let targets = sweep::sweep_targets(&self.sweep, &self.interfaces);
+        sweep::send_queries(&self.socket, QueryKind::Share, &targets, self.port).await;
+    }
+
+    /// Receive the next packet from a selected interface.
+    async fn recv_from(&self, buf: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+        loop {
+            let (len, source) =
+                socket::recv_from(&self.socket, self.socket_v6.as_deref(), buf).await?;
+            if self.sources.accepts(&source) {
+                return Ok((len, source));
+            }
+            tracing::trace!("Ignoring discovery packet from filtered source {source}");
+        }
+    }
+
+    /// Parse a share announcement, dropping corrupted, stale or replayed ones.
+    fn accept_packet(&self, data: &[u8], source: SocketAddr) -> Option<DiscoveryPacket> {
+        let packet = serde_json::from_slice::<DiscoveryPacket>(data).ok()?;
+        if !packet.is_valid() {
+            return None;
+        }
+        if !packet.is_intact() {
+            tracing::debug!("Ignoring invalid or stale share announcement from {source}");
+            return None;
+        }
+        if !self.replay.check(&packet.nonce) {
+            tracing::trace!("Ignoring replayed share announcement from {source}");
+            return None;
+        }
+        Some(packet)
+    }
+
     /// Wait for a share with the given code.
     ///
+    /// With sweeping enabled, listens passively for half the timeout, then
+    /// sweeps and waits for replies for the rest.
+    ///
     /// # Arguments
     ///
     /// * `code` - The share code to look for
@@ -297,6 +555,20 @@ impl Listener {
     ///
     /// Returns an error if the code is not found within the timeout.
     pub async fn find(&self, code: &ShareCode, timeout: Duration) -> Result<DiscoveredShare> {
+        if !self.sweep.enabled {
+            return self.find_passive(code, timeout).await;
+        }
+
+        let passive = timeout / 2;
+        if let Ok(share) = self.find_passive(code, passive).await {
+            return Ok(share);
+        }
+        self.send_sweep().await;
+        self.find_passive(code, timeout.saturating_sub(passive))
+            .await
+    }
+
+    async fn find_passive(&self, code: &ShareCode, timeout: Duration) -> Result<DiscoveredShare> {
         let deadline = Instant::now() + timeout;
         let code_str = code.to_string();
 
@@ -307,12 +579,12 @@ impl Listener {
             }
 
             let mut buf = [0u8; 4096];
-            let result = tokio::time::timeout(remaining, self.socket.recv_from(&mut buf)).await;
+            let result = tokio::time::timeout(remaining, self.recv_from(&mut buf)).await;
 
             match result {
                 Ok(Ok((len, source))) => {
-                    if let Ok(packet) = serde_json::from_slice::<DiscoveryPacket>(&buf[..len]) {
-                        if packet.is_valid() && packet.code == code_str {
+                    if let Some(packet) = self.accept_packet(&buf[..len], source) {
+                        if packet.code == code_str {
                             return Ok(DiscoveredShare {
                                 packet,
                                 source,
@@ -333,6 +605,9 @@ impl Listener {
 
     /// List all active shares on the network.
     ///
+    /// With sweeping enabled and nothing heard in the first half of
+    /// `duration`, sweeps and collects replies for the rest.
+    ///
     /// # Arguments
     ///
     /// * `duration` - How long to listen for shares
@@ -341,6 +616,20 @@ impl Listener {
     ///
     /// A list of discovered shares, deduplicated by device_id.
     pub async fn scan(&self, duration: Duration) -> Vec<DiscoveredShare> {
+        if !self.sweep.enabled {
+            return self.scan_passive(duration).await;
+        }
+
+        let passive = duration / 2;
+        let shares = self.scan_passive(passive).await;
+        if !shares.is_empty() {
+            return shares;
+        }
+        self.send_sweep().await;
+        self.scan_passive(duration.saturating_sub(passive)).await
+    }
+
+    async fn scan_passive(&self, duration: Duration) -> Vec<DiscoveredShare> {
         let deadline = Instant::now() + duration;
         let mut shares: HashMap<Uuid, DiscoveredShare> = HashMap::new();
 
@@ -351,22 +640,20 @@ impl Listener {
             }
 
             let mut buf = [0u8; 4096];
-            let result = tokio::time::timeout(remaining, self.socket.recv_from(&mut buf)).await;
+            let result = tokio::time::timeout(remaining, self.recv_from(&mut buf)).await;
 
             match result {
                 Ok(Ok((len, source))) => {
-                    if let Ok(packet) = serde_json::from_slice::<DiscoveryPacket>(&buf[..len]) {
-                        if packet.is_valid() {
-                            let device_id = packet.device_id;
-                            shares.insert(
-                                device_id,
-                                DiscoveredShare {
-                                    packet,
-                                    source,
-                                    discovered_at: Instant::now(),
-                                },
-                            );
-                        }
+                    if let Some(packet) = self.accept_packet(&buf[..len], source) {
+                        let device_id = packet.device_id;
+                        shares.insert(
+                            device_id,
+                            DiscoveredShare {
+                                packet,
+                                source,
+                                discovered_at: Instant::now(),
+                            },
+                        );
                     }
                 }
                 Ok(Err(e)) => {
@@ -423,6 +710,140 @@ mod tests {
         assert_eq!(deserialized.device_id, packet.device_id);
     }
 
+    #[test]
+    fn test_discovery_packet_mac() {
+        let code = generate_code();
+        let mut packet = DiscoveryPacket::new(&code, "Test Device", Uuid::new_v4(), 52530, 1, 1);
+        assert!(!packet.verify_mac(), "Unsealed packet has no MAC");
+
+        packet.seal();
+        assert!(packet.verify_mac());
+        assert!(packet.is_intact());
+
+        let json = serde_json::to_vec(&packet).expect("serialize");
+        let decoded: DiscoveryPacket = serde_json::from_slice(&json).expect("deserialize");
+        assert!(decoded.is_intact());
+
+        let mut tampered = packet.clone();
+        tampered.transfer_port = 4444;
+        assert!(!tampered.verify_mac());
+
+        let mut stale = packet;
+        stale.timestamp -= auth::MAX_PACKET_AGE.as_secs() + 60;
+        stale.mac = Some(BASE64_STANDARD.encode(stale.compute_mac()));
+        assert!(stale.verify_mac());
+        assert!(!stale.is_intact(), "Stale packet should be rejected");
+    }
+
+    #[test]
+    fn test_discovery_packet_redact() {
+        let code = generate_code();
+        let device_id = Uuid::new_v4();
+        let mut packet = DiscoveryPacket::new(&code, "Marcus-Laptop", device_id, 52530, 3, 4096);
+        let key = [7u8; 32];
+        packet.redact(&key);
+
+        assert!(packet.device_name.is_empty());
+        assert_ne!(packet.device_id, device_id);
+        assert_eq!(packet.file_count, 0);
+        assert_eq!(packet.total_size, 0);
+        assert_eq!(packet.code, code.to_string());
+        assert_eq!(packet.transfer_port, 52530);
+
+        let once = packet.clone();
+        packet.redact(&key);
+        assert_eq!(packet.device_id, once.device_id, "Redaction is idempotent");
+
+        packet.seal();
+        let json = serde_json::to_string(&packet).expect("serialize");
+        assert!(!json.contains("Marcus-Laptop"));
+        assert!(!json.contains(&device_id.to_string()));
+    }
+
+    #[test]
+    fn test_discovery_packet_without_mac_deserializes() {
+        let json = r#"{"protocol":"yoop","version":"1.0","code":"A7K9","device_name":"Old",
+            "device_id":"00000000-0000-0000-0000-000000000000","expires_at":0,
+            "transfer_port":52530,"supports":["tcp"],"file_count":1,"total_size":1,
+            "preview_available":true}"#;
+        let packet: DiscoveryPacket = serde_json::from_str(json).expect("deserialize");
+        assert!(packet.is_valid());
+        assert!(!packet.is_intact());
+    }
+
+    #[tokio::test]
+    async fn test_listener_drops_invalid_and_replayed() {
+        let listener = Listener::with_options(
+            0,
+            &DiscoveryOptions {
+                ipv6: false,
+                ..DiscoveryOptions::default()
+            },
+        )
+        .await
+        .expect("create listener");
+        let target = SocketAddr::new(
+            std::net::Ipv4Addr::LOCALHOST.into(),
+            listener.socket.local_addr().expect("addr").port(),
+        );
+        let sender = UdpSocket::bind("127.0.0.1:0").await.expect("bind sender");
+
+        let code = generate_code();
+        let mut packet = DiscoveryPacket::new(&code, "Test Device", Uuid::new_v4(), 52530, 1, 1);
+        let unsealed = serde_json::to_vec(&packet).expect("serialize");
+        packet.seal();
+        let sealed = serde_json::to_vec(&packet).expect("serialize");
+
+        sender.send_to(&unsealed, target).await.expect("send");
+        sender.send_to(&sealed, target).await.expect("send");
+        sender.send_to(&sealed, target).await.expect("send");
+
+        let shares = listener.scan(Duration::from_millis(300)).await;
+        assert_eq!(shares.len(), 1);
+        assert!(shares[0].packet.is_intact());
+
+        sender.send_to(&sealed, target).await.expect("send");
+        let result = listener.find(&code, Duration::from_millis(200)).await;
+        assert!(result.is_err(), "Replayed packet should be dropped");
+    }
+
+    #[tokio::test]
+    async fn test_broadcaster_answers_sweep_query() {
+        use std::net::Ipv4Addr;
+
+        let port = 53000 + (std::process::id() % 100) as u16;
+        let broadcaster = Broadcaster::with_options(
+            port,
+            &DiscoveryOptions {
+                ipv6: false,
+                ..DiscoveryOptions::default()
+            },
+        )
+        .await
+        .expect("create broadcaster");
+
+        let code = generate_code();
+        let packet = DiscoveryPacket::new(&code, "Test Device", Uuid::new_v4(), 52530, 1, 1);
+        broadcaster
+            .start(packet, Duration::from_secs(60))
+            .await
+            .expect("start broadcasting");
+
+        let querier = UdpSocket::bind("127.0.0.1:0").await.expect("bind querier");
+        sweep::send_queries(&querier, QueryKind::Share, &[Ipv4Addr::LOCALHOST], port).await;
+
+        let mut buf = [0u8; 4096];
+        let (len, _) = tokio::time::timeout(Duration::from_secs(2), querier.recv_from(&mut buf))
+            .await
+            .expect("reply timeout")
+            .expect("recv");
+        broadcaster.stop().await;
+
+        let reply: DiscoveryPacket = serde_json::from_slice(&buf[..len]).expect("parse reply");
+        assert_eq!(reply.code, code.to_string());
+        assert!(reply.is_intact());
+    }
+
     #[tokio::test]
     async fn test_broadcaster_creation() {
         let broadcaster = Broadcaster::new(0).await;
@@ -483,6 +904,61 @@ mod tests {
         assert_eq!(share.packet.file_count, 3);
     }
 
+    #[tokio::test]
+    async fn test_listener_receives_ipv6() {
+        let port = 52900 + (std::process::id() % 100) as u16;
+        let listener = Listener::with_options(port, &DiscoveryOptions::default())
+            .await
+            .expect("create listener");
+        if listener.socket_v6.is_none() {
+            return;
+        }
+
+        let code = generate_code();
+        let device_id = Uuid::new_v4();
+        let mut packet = DiscoveryPacket::new(&code, "Test Device", device_id, 52530, 1, 1024);
+        packet.seal();
+        let json = serde_json::to_vec(&packet).expect("serialize");
+
+        let sender = UdpSocket::bind("[::1]:0").await.expect("bind sender");
+        let target: SocketAddr = format!("[::1]:{port}").parse().expect("addr");
+        sender.send_to(&json, target).await.expect("send");
+
+        let share = listener
+            .find(&code, Duration::from_secs(2))
+            .await
+            .expect("find share over IPv6");
+        assert_eq!(share.packet.device_id, device_id);
+        assert!(share.source.is_ipv6());
+        assert_eq!(
+            share.transfer_addr(),
+            SocketAddr::new(share.source.ip(), 52530)
+        );
+    }
+
+    #[test]
+    fn test_transfer_addr_keeps_ipv6_scope() {
+        let code = generate_code();
+        let packet = DiscoveryPacket::new(&code, "Test Device", Uuid::new_v4(), 52530, 1, 1024);
+        let source = SocketAddr::V6(std::net::SocketAddrV6::new(
+            "fe80::1".parse().expect("ip"),
+            52525,
+            0,
+            3,
+        ));
+        let share = DiscoveredShare {
+            packet,
+            source,
+            discovered_at: Instant::now(),
+        };
+
+        let SocketAddr::V6(addr) = share.transfer_addr() else {
+            panic!("expected IPv6 address");
+        };
+        assert_eq!(addr.port(), 52530);
+        assert_eq!(addr.scope_id(), 3);
+    }
+
     #[tokio::test]
     async fn test_find_timeout() {
         let port = 52700 + (std::process::id() % 100) as u16;
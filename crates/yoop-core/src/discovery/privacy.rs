@@ -0,0 +1,98 @@
+//! Rotating resolvable identifiers for privacy mode.
+//!
+//! In privacy mode a device's beacons carry neither its stable `device_id`
+//! nor its name or public key. Instead the `device_id` field holds a fresh
+//! resolvable identifier on every broadcast: 8 random bytes followed by the
+//! first 8 bytes of an HMAC over them, keyed with the device's resolving
+//! key.
+//!
+//! The resolving key is a random secret kept in the device identity. It is
+//! never broadcast or sent during code transfers; devices only exchange it
+//! when they [pair](crate::trust::pairing) and keep it in their trust store.
+//! Paired devices can therefore recognise our beacons by trying each stored
+//! key, while everyone else sees an unlinkable random identifier.
+
+use uuid::Uuid;
+
+use crate::crypto;
+
+/// Decode a base64-encoded resolving key.
+pub fn decode_key(key: &str) -> Option<[u8; 32]> {
+    use base64::prelude::*;
+
+    BASE64_STANDARD.decode(key).ok()?.try_into().ok()
+}
+
+/// Generate a fresh identifier that resolves with `key`.
+pub fn resolvable_id(key: &[u8; 32]) -> Uuid {
+    let prand = crypto::random_bytes::<8>();
+    let tag = crypto::hmac_sha256(key, &prand);
+
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&prand);
+    bytes[8..].copy_from_slice(&tag[..8]);
+    Uuid::from_bytes(bytes)
+}
+
+/// Check whether `id` was generated with `key`.
+pub fn resolves(key: &[u8; 32], id: Uuid) -> bool {
+    let bytes = id.as_bytes();
+    let tag = crypto::hmac_sha256(key, &bytes[..8]);
+    crypto::constant_time_eq(&tag[..8], &bytes[8..])
+}
+
+/// Stable per-share identifier used in place of the device ID in code-share
+/// announcements, so UDP and mDNS announcements of one share still match.
+///
+/// It is keyed with the sharing device's resolving key, so shares from one
+/// device cannot be linked by anyone it has not paired with.
+pub fn share_id(key: &[u8; 32], code: &str) -> Uuid {
+    let mut data = Vec::with_capacity(11 + code.len());
+    data.extend_from_slice(b"yoop:share:");
+    data.extend_from_slice(code.as_bytes());
+    let tag = crypto::hmac_sha256(key, &data);
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&tag[..16]);
+    Uuid::from_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::DeviceIdentity;
+
+    #[test]
+    fn test_resolvable_id_rotates_and_resolves() {
+        let identity = DeviceIdentity::generate().expect("generate identity");
+        let key = identity.resolving_key();
+
+        let first = resolvable_id(&key);
+        let second = resolvable_id(&key);
+        assert_ne!(first, second, "Identifiers should rotate");
+        assert!(resolves(&key, first));
+        assert!(resolves(&key, second));
+
+        let other = DeviceIdentity::generate().expect("generate identity");
+        assert!(!resolves(&other.resolving_key(), first));
+        assert!(!resolves(&key, identity.device_id()));
+    }
+
+    #[test]
+    fn test_decode_key() {
+        let identity = DeviceIdentity::generate().expect("generate identity");
+        assert_eq!(
+            decode_key(&identity.resolving_key_base64()),
+            Some(identity.resolving_key())
+        );
+        assert!(decode_key("not base64!").is_none());
+    }
+
+    #[test]
+    fn test_share_id_is_stable_per_code() {
+        let key = [7u8; 32];
+        assert_eq!(share_id(&key, "A7K9"), share_id(&key, "A7K9"));
+        assert_ne!(share_id(&key, "A7K9"), share_id(&key, "B2C3"));
+        assert_ne!(share_id(&key, "A7K9"), share_id(&[8u8; 32], "A7K9"));
+    }
+}
@@ -0,0 +1,433 @@
+//! Shared UDP socket setup for discovery.
+//!
+//! IPv4 discovery uses directed broadcast on each selected interface. IPv6 has
+//! no broadcast, so announcements are sent to the link-local multicast group
+//! [`IPV6_MULTICAST_GROUP`] once per interface, using the interface index as
+//! the scope id.
+//!
+//! Routers and some Wi-Fi access points rate-limit or drop broadcast, so IPv4
+//! announcements are also sent to a multicast group (see [`MulticastOptions`])
+//! on each selected interface, and listeners join that group.
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::net::UdpSocket;
+
+use super::auth;
+use super::interfaces::{self, DiscoveryOptions, InterfaceFilter};
+
+/// Link-local multicast group used for IPv6 discovery (`ff02::7970`).
+pub const IPV6_MULTICAST_GROUP: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0x7970);
+
+/// Default organization-local multicast group for IPv4 discovery.
+pub const IPV4_MULTICAST_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 79, 70);
+
+/// IPv4 multicast settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MulticastOptions {
+    /// Send and listen on the multicast group alongside broadcast
+    pub enabled: bool,
+    /// Multicast group address
+    pub group: Ipv4Addr,
+    /// Multicast TTL; 1 keeps packets on the local link
+    pub ttl: u32,
+}
+
+impl Default for MulticastOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            group: IPV4_MULTICAST_GROUP,
+            ttl: 1,
+        }
+    }
+}
+
+impl MulticastOptions {
+    /// Whether multicast is enabled with a valid group address.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.group.is_multicast()
+    }
+}
+
+/// Create the IPv4 broadcast and multicast sender socket.
+pub fn ipv4_sender(multicast_ttl: u32) -> io::Result<UdpSocket> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::IPV4,
+        socket2::Type::DGRAM,
+        Some(socket2::Protocol::UDP),
+    )?;
+
+    socket.set_broadcast(true)?;
+    socket.set_reuse_address(true)?;
+    socket.set_multicast_ttl_v4(multicast_ttl)?;
+    socket.set_multicast_loop_v4(true)?;
+
+    #[cfg(target_os = "macos")]
+    socket.set_reuse_port(true)?;
+
+    let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0);
+    socket.bind(&addr.into())?;
+
+    into_tokio(socket)
+}
+
+/// Create the IPv4 listener socket bound to `port`.
+pub fn ipv4_listener(port: u16) -> io::Result<UdpSocket> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::IPV4,
+        socket2::Type::DGRAM,
+        Some(socket2::Protocol::UDP),
+    )?;
+
+    socket.set_reuse_address(true)?;
+
+    #[cfg(target_os = "macos")]
+    socket.set_reuse_port(true)?;
+
+    let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port);
+    socket.bind(&addr.into())?;
+
+    into_tokio(socket)
+}
+
+/// Join the IPv4 multicast group on every selected interface.
+///
+/// Interfaces that refuse the membership are skipped.
+pub fn join_ipv4_multicast(
+    socket: &UdpSocket,
+    options: &MulticastOptions,
+    filter: &InterfaceFilter,
+) {
+    if !options.is_active() {
+        return;
+    }
+    let socket = socket2::SockRef::from(socket);
+    for addr in interfaces::ipv4_interfaces(filter) {
+        if let Err(e) = socket.join_multicast_v4(&options.group, &addr) {
+            tracing::debug!(
+                "Failed to join IPv4 discovery group {} on {addr}: {e}",
+                options.group
+            );
+        }
+    }
+}
+
+/// Create the IPv6 multicast sender socket.
+pub fn ipv6_sender() -> io::Result<UdpSocket> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::IPV6,
+        socket2::Type::DGRAM,
+        Some(socket2::Protocol::UDP),
+    )?;
+
+    socket.set_only_v6(true)?;
+    socket.set_multicast_hops_v6(1)?;
+    socket.set_multicast_loop_v6(true)?;
+
+    let addr = SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, 0, 0, 0);
+    socket.bind(&addr.into())?;
+
+    into_tokio(socket)
+}
+
+/// Create the IPv6 listener socket bound to `port`.
+///
+/// The socket joins [`IPV6_MULTICAST_GROUP`] on every selected IPv6-capable
+/// interface. It fails only if the socket cannot be bound; interfaces that
+/// refuse the membership are skipped.
+pub fn ipv6_listener(port: u16, filter: &InterfaceFilter) -> io::Result<UdpSocket> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::IPV6,
+        socket2::Type::DGRAM,
+        Some(socket2::Protocol::UDP),
+    )?;
+
+    socket.set_only_v6(true)?;
+    socket.set_reuse_address(true)?;
+
+    #[cfg(target_os = "macos")]
+    socket.set_reuse_port(true)?;
+
+    let addr = SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, port, 0, 0);
+    socket.bind(&addr.into())?;
+
+    for index in interfaces::ipv6_interfaces(filter) {
+        if let Err(e) = socket.join_multicast_v6(&IPV6_MULTICAST_GROUP, index) {
+            tracing::debug!("Failed to join IPv6 discovery group on interface {index}: {e}");
+        }
+    }
+
+    into_tokio(socket)
+}
+
+/// Send `payload` as a directed broadcast on every selected interface.
+pub async fn send_ipv4_broadcast(
+    socket: &UdpSocket,
+    payload: &[u8],
+    port: u16,
+    filter: &InterfaceFilter,
+) {
+    for target in interfaces::broadcast_targets(filter) {
+        if let Err(e) = socket
+            .send_to(payload, SocketAddrV4::new(target, port))
+            .await
+        {
+            tracing::warn!("Failed to send broadcast to {target}: {e}");
+        }
+    }
+}
+
+/// Send `payload` to the IPv4 multicast group on every selected interface.
+pub async fn send_ipv4_multicast(
+    socket: &UdpSocket,
+    payload: &[u8],
+    port: u16,
+    options: &MulticastOptions,
+    filter: &InterfaceFilter,
+) {
+    if !options.is_active() {
+        return;
+    }
+    let target = SocketAddrV4::new(options.group, port);
+    for addr in interfaces::ipv4_interfaces(filter) {
+        if let Err(e) = socket2::SockRef::from(socket).set_multicast_if_v4(&addr) {
+            tracing::debug!("Failed to select {addr} for IPv4 multicast: {e}");
+            continue;
+        }
+        if let Err(e) = socket.send_to(payload, target).await {
+            tracing::debug!("Failed to send IPv4 multicast from {addr}: {e}");
+        }
+    }
+}
+
+/// Send `payload` to the IPv6 discovery group on every selected interface.
+pub async fn send_ipv6_multicast(
+    socket: &UdpSocket,
+    payload: &[u8],
+    port: u16,
+    filter: &InterfaceFilter,
+) {
+    for index in interfaces::ipv6_interfaces(filter) {
+        let target = SocketAddrV6::new(IPV6_MULTICAST_GROUP, port, 0, index);
+        if let Err(e) = socket.send_to(payload, target).await {
+            tracing::debug!("Failed to send IPv6 multicast on interface {index}: {e}");
+        }
+    }
+}
+
+/// Receive from whichever of the IPv4 and IPv6 sockets has data first.
+pub async fn recv_from(
+    v4: &UdpSocket,
+    v6: Option<&UdpSocket>,
+    buf: &mut [u8],
+) -> io::Result<(usize, SocketAddr)> {
+    let Some(v6) = v6 else {
+        return v4.recv_from(buf).await;
+    };
+
+    loop {
+        let socket = tokio::select! {
+            ready = v4.readable() => { ready?; v4 }
+            ready = v6.readable() => { ready?; v6 }
+        };
+
+        match socket.try_recv_from(buf) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            result => return result,
+        }
+    }
+}
+
+/// Create the optional IPv6 socket, logging instead of failing.
+///
+/// IPv6 discovery is best-effort: hosts without IPv6 keep working over IPv4.
+pub fn optional_ipv6(
+    enabled: bool,
+    create: impl FnOnce() -> io::Result<UdpSocket>,
+) -> Option<UdpSocket> {
+    if !enabled {
+        return None;
+    }
+
+    match create() {
+        Ok(socket) => Some(socket),
+        Err(e) => {
+            tracing::debug!("IPv6 discovery unavailable: {e}");
+            None
+        }
+    }
+}
+
+/// Which discovery mechanisms delivered a test packet back to this host.
+///
+/// `None` means the mechanism is disabled in the options.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DeliveryReport {
+    /// Directed IPv4 broadcast
+    pub broadcast: bool,
+    /// IPv4 multicast group
+    pub multicast: Option<bool>,
+    /// IPv6 link-local multicast group
+    pub ipv6: Option<bool>,
+}
+
+/// Send a test packet over each enabled mechanism and report which arrive.
+///
+/// Packets are sent to a temporary port through the selected interfaces and
+/// received by a listener set up like a discovery listener, so a mechanism
+/// that fails here cannot work for discovery either.
+///
+/// # Errors
+///
+/// Returns an error if the IPv4 sockets cannot be created.
+pub async fn check_delivery(
+    options: &DiscoveryOptions,
+    timeout: Duration,
+) -> io::Result<DeliveryReport> {
+    let listener = ipv4_listener(0)?;
+    let port = listener.local_addr()?.port();
+    join_ipv4_multicast(&listener, &options.multicast, &options.interfaces);
+    let listener_v6 = optional_ipv6(options.ipv6, || ipv6_listener(port, &options.interfaces));
+
+    let nonce = auth::new_nonce();
+    let payload = |mechanism: &str| format!("yoop-delivery-check:{nonce}:{mechanism}");
+
+    let sender = ipv4_sender(options.multicast.ttl)?;
+    send_ipv4_broadcast(
+        &sender,
+        payload("broadcast").as_bytes(),
+        port,
+        &options.interfaces,
+    )
+    .await;
+    let multicast = options.multicast.is_active();
+    if multicast {
+        send_ipv4_multicast(
+            &sender,
+            payload("multicast").as_bytes(),
+            port,
+            &options.multicast,
+            &options.interfaces,
+        )
+        .await;
+    }
+    if listener_v6.is_some() {
+        if let Ok(sender_v6) = ipv6_sender() {
+            send_ipv6_multicast(
+                &sender_v6,
+                payload("ipv6").as_bytes(),
+                port,
+                &options.interfaces,
+            )
+            .await;
+        }
+    }
+
+    let mut report = DeliveryReport {
+        broadcast: false,
+        multicast: multicast.then_some(false),
+        ipv6: listener_v6.as_ref().map(|_| false),
+    };
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut buf = [0u8; 128];
+    while let Ok(Ok((len, _))) = tokio::time::timeout_at(
+        deadline,
+        recv_from(&listener, listener_v6.as_ref(), &mut buf),
+    )
+    .await
+    {
+        let received = String::from_utf8_lossy(&buf[..len]);
+        if received == payload("broadcast") {
+            report.broadcast = true;
+        } else if received == payload("multicast") {
+            report.multicast = Some(true);
+        } else if received == payload("ipv6") {
+            report.ipv6 = Some(true);
+        }
+    }
+    Ok(report)
+}
+
+fn into_tokio(socket: socket2::Socket) -> io::Result<UdpSocket> {
+    socket.set_nonblocking(true)?;
+    let std_socket: std::net::UdpSocket = socket.into();
+    UdpSocket::from_std(std_socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multicast_group_is_link_local() {
+        assert!(IPV6_MULTICAST_GROUP.is_multicast());
+        assert_eq!(IPV6_MULTICAST_GROUP.segments()[0], 0xff02);
+    }
+
+    #[tokio::test]
+    async fn test_recv_from_either_family() {
+        let v4 = UdpSocket::bind("127.0.0.1:0").await.expect("bind v4");
+        let Ok(v6) = UdpSocket::bind("[::1]:0").await else {
+            return;
+        };
+
+        let sender = UdpSocket::bind("[::1]:0").await.expect("bind sender");
+        sender
+            .send_to(b"hello", v6.local_addr().expect("addr"))
+            .await
+            .expect("send");
+
+        let mut buf = [0u8; 16];
+        let (len, source) = tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            recv_from(&v4, Some(&v6), &mut buf),
+        )
+        .await
+        .expect("timeout")
+        .expect("recv");
+
+        assert_eq!(&buf[..len], b"hello");
+        assert!(source.is_ipv6());
+    }
+
+    #[test]
+    fn test_multicast_options() {
+        let options = MulticastOptions::default();
+        assert!(options.is_active());
+        assert!(IPV4_MULTICAST_GROUP.is_multicast());
+
+        let invalid = MulticastOptions {
+            group: Ipv4Addr::new(192, 168, 1, 1),
+            ..MulticastOptions::default()
+        };
+        assert!(!invalid.is_active());
+    }
+
+    #[tokio::test]
+    async fn test_check_delivery_disabled_mechanisms() {
+        let options = DiscoveryOptions {
+            ipv6: false,
+            multicast: MulticastOptions {
+                enabled: false,
+                ..MulticastOptions::default()
+            },
+            ..DiscoveryOptions::default()
+        };
+        let report = check_delivery(&options, Duration::from_millis(200))
+            .await
+            .expect("check delivery");
+        assert_eq!(report.multicast, None);
+        assert_eq!(report.ipv6, None);
+    }
+
+    #[test]
+    fn test_optional_ipv6_disabled() {
+        let socket = optional_ipv6(false, || unreachable!("should not be called"));
+        assert!(socket.is_none());
+    }
+}
@@ -0,0 +1,464 @@
+//! Unicast subnet sweep for networks that drop broadcast and multicast.
+//!
+//! Many managed Wi-Fi networks filter both UDP broadcast and mDNS, so neither
+//! announcements nor beacons ever arrive. With sweeping enabled, a listener
+//! that hears nothing passively sends a small [`DiscoveryQuery`] to every host
+//! in the local subnets (or the configured CIDR ranges) on the discovery port.
+//! Sharers and beacon broadcasters answer a query by unicasting their current
+//! announcement back to the querying socket, where it goes through the same
+//! authentication and replay checks as a broadcast one.
+//!
+//! Only IPv4 is swept; IPv6 subnets are far too large to enumerate. Local
+//! subnets wider than a `/22` are narrowed to the `/22` around our address,
+//! and a sweep never covers more than [`MAX_SWEEP_HOSTS`] hosts.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError, Weak};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use super::interfaces::InterfaceFilter;
+use super::socket;
+
+/// Upper bound on the number of hosts probed by one sweep.
+pub const MAX_SWEEP_HOSTS: usize = 4096;
+
+/// Widest local subnet swept as a whole.
+const WIDEST_LOCAL_PREFIX: u8 = 22;
+
+/// Queries sent back to back before pausing, so the local queue is not flooded.
+const QUERY_BURST: usize = 64;
+
+/// Pause between bursts of queries.
+const QUERY_BURST_DELAY: Duration = Duration::from_millis(2);
+
+/// Minimum time between replies to the same host, against reflection abuse.
+const REPLY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Active sweep settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SweepOptions {
+    /// Probe hosts when passive discovery finds nothing
+    pub enabled: bool,
+    /// CIDR ranges to probe (e.g. `10.20.0.0/24`); empty means the subnets
+    /// of the selected interfaces
+    pub ranges: Vec<String>,
+}
+
+/// What a sweep query is looking for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryKind {
+    /// Active code shares, answered with a discovery packet
+    Share,
+    /// Device beacons, answered with a signed beacon
+    Device,
+}
+
+/// Query sent to each swept host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryQuery {
+    /// Protocol identifier
+    pub protocol: String,
+    /// Protocol version
+    pub version: String,
+    /// What is being looked for
+    pub query: QueryKind,
+}
+
+impl DiscoveryQuery {
+    /// Create a new query.
+    #[must_use]
+    pub fn new(query: QueryKind) -> Self {
+        Self {
+            protocol: "yoop".to_string(),
+            version: "1.0".to_string(),
+            query,
+        }
+    }
+
+    /// Check if this is a valid Yoop query.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.protocol == "yoop" && self.version == "1.0"
+    }
+}
+
+/// Hosts to probe for `options`, excluding our own addresses.
+///
+/// Invalid ranges are logged and skipped.
+#[must_use]
+pub fn sweep_targets(options: &SweepOptions, filter: &InterfaceFilter) -> Vec<Ipv4Addr> {
+    let local: Vec<(Ipv4Addr, Ipv4Addr)> = if_addrs::get_if_addrs()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|iface| !iface.is_loopback())
+        .filter_map(|iface| match iface.addr {
+            if_addrs::IfAddr::V4(v4) => Some((iface.name, v4)),
+            if_addrs::IfAddr::V6(_) => None,
+        })
+        .filter(|(name, _)| filter.allows(name))
+        .map(|(_, v4)| (v4.ip, v4.netmask))
+        .collect();
+
+    let networks: Vec<(Ipv4Addr, u8)> = if options.ranges.is_empty() {
+        local
+            .iter()
+            .map(|(ip, mask)| {
+                let prefix = u8::try_from(u32::from(*mask).leading_ones()).unwrap_or(32);
+                (*ip, prefix.max(WIDEST_LOCAL_PREFIX))
+            })
+            .collect()
+    } else {
+        options
+            .ranges
+            .iter()
+            .filter_map(|range| {
+                let parsed = parse_cidr(range);
+                if parsed.is_none() {
+                    tracing::warn!("Ignoring invalid sweep range '{range}'");
+                }
+                parsed
+            })
+            .collect()
+    };
+
+    let mut targets: Vec<Ipv4Addr> = networks
+        .into_iter()
+        .flat_map(|(addr, prefix)| hosts(addr, prefix))
+        .filter(|ip| !local.iter().any(|(own, _)| own == ip))
+        .collect();
+    targets.sort_unstable();
+    targets.dedup();
+
+    if targets.len() > MAX_SWEEP_HOSTS {
+        tracing::warn!(
+            "Sweep covers {} hosts, probing only the first {MAX_SWEEP_HOSTS}",
+            targets.len()
+        );
+        targets.truncate(MAX_SWEEP_HOSTS);
+    }
+    targets
+}
+
+/// Send a query for `kind` to every target on `port`.
+///
+/// Replies arrive on `socket` like ordinary announcements.
+pub async fn send_queries(socket: &UdpSocket, kind: QueryKind, targets: &[Ipv4Addr], port: u16) {
+    let Ok(payload) = serde_json::to_vec(&DiscoveryQuery::new(kind)) else {
+        return;
+    };
+
+    tracing::debug!("Sweeping {} hosts for {kind:?}", targets.len());
+    for (i, target) in targets.iter().enumerate() {
+        if i > 0 && i % QUERY_BURST == 0 {
+            tokio::time::sleep(QUERY_BURST_DELAY).await;
+        }
+        if let Err(e) = socket
+            .send_to(&payload, SocketAddrV4::new(*target, port))
+            .await
+        {
+            tracing::trace!("Failed to send sweep query to {target}: {e}");
+        }
+    }
+}
+
+/// Answer sweep queries for `kind` on `port` until `shutdown_rx` fires.
+///
+/// `reply` builds a fresh payload for each answer. All responders for one
+/// port in a process share a single socket that dispatches on the query's
+/// [`QueryKind`]; with several sockets on the port, the kernel would hand
+/// each unicast query to just one of them. Answering is best-effort: if the
+/// port cannot be bound, sweeps simply do not find this device.
+pub fn spawn_responder<F>(
+    port: u16,
+    kind: QueryKind,
+    mut shutdown_rx: broadcast::Receiver<()>,
+    reply: F,
+) where
+    F: Fn() -> Option<Vec<u8>> + Send + 'static,
+{
+    let responder = match Responder::shared(port) {
+        Ok(responder) => responder,
+        Err(e) => {
+            tracing::debug!("Not answering sweep queries: {e}");
+            return;
+        }
+    };
+    let id = responder.add(kind, Box::new(reply));
+
+    tokio::spawn(async move {
+        let _ = shutdown_rx.recv().await;
+        responder.remove(id);
+    });
+}
+
+/// Builds the payload answering a query.
+type ReplyFn = Box<dyn Fn() -> Option<Vec<u8>> + Send>;
+
+/// Per-process responders, one per port.
+static RESPONDERS: LazyLock<Mutex<HashMap<u16, Weak<Responder>>>> = LazyLock::new(Mutex::default);
+
+/// Socket answering sweep queries on one port for every registered reply.
+///
+/// Kept alive by its registrations; the receive task stops once the last
+/// one is removed.
+struct Responder {
+    /// Registered replies, by registration ID
+    replies: Mutex<HashMap<u64, (QueryKind, ReplyFn)>>,
+    /// Next registration ID
+    next_id: AtomicU64,
+    /// Task receiving queries
+    task: JoinHandle<()>,
+}
+
+impl Responder {
+    /// Get the responder for `port`, binding it if none is running.
+    fn shared(port: u16) -> std::io::Result<Arc<Self>> {
+        let mut responders = RESPONDERS.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(responder) = responders.get(&port).and_then(Weak::upgrade) {
+            if !responder.task.is_finished() {
+                return Ok(responder);
+            }
+        }
+
+        let socket = socket::ipv4_listener(port)?;
+        let responder = Arc::new_cyclic(|weak| Self {
+            replies: Mutex::default(),
+            next_id: AtomicU64::new(0),
+            task: tokio::spawn(Self::run(socket, weak.clone())),
+        });
+        responders.insert(port, Arc::downgrade(&responder));
+        drop(responders);
+        Ok(responder)
+    }
+
+    fn add(&self, kind: QueryKind, reply: ReplyFn) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.replies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, (kind, reply));
+        id
+    }
+
+    fn remove(&self, id: u64) {
+        self.replies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&id);
+    }
+
+    /// Build the answers to a query for `kind`.
+    fn payloads(&self, kind: QueryKind) -> Vec<Vec<u8>> {
+        self.replies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .filter(|(reply_kind, _)| *reply_kind == kind)
+            .filter_map(|(_, reply)| reply())
+            .collect()
+    }
+
+    async fn run(socket: UdpSocket, responder: Weak<Self>) {
+        let mut last_reply: HashMap<(IpAddr, QueryKind), Instant> = HashMap::new();
+        let mut buf = [0u8; 512];
+
+        loop {
+            let (len, source) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    tracing::debug!("Sweep responder receive error: {e}");
+                    continue;
+                }
+            };
+
+            let Some(kind) = parse_query(&buf[..len]) else {
+                continue;
+            };
+            let Some(payloads) = responder.upgrade().map(|r| r.payloads(kind)) else {
+                break;
+            };
+            if payloads.is_empty() || !should_reply(&mut last_reply, source, kind) {
+                continue;
+            }
+            for payload in payloads {
+                if let Err(e) = socket.send_to(&payload, source).await {
+                    tracing::debug!("Failed to answer sweep query from {source}: {e}");
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Responder {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn parse_query(data: &[u8]) -> Option<QueryKind> {
+    serde_json::from_slice::<DiscoveryQuery>(data)
+        .ok()
+        .filter(DiscoveryQuery::is_valid)
+        .map(|query| query.query)
+}
+
+/// Rate-limit replies per host and query kind, so spoofed queries cannot
+/// turn us into a traffic reflector.
+fn should_reply(
+    last_reply: &mut HashMap<(IpAddr, QueryKind), Instant>,
+    source: SocketAddr,
+    kind: QueryKind,
+) -> bool {
+    let now = Instant::now();
+    last_reply.retain(|_, at| now.duration_since(*at) < REPLY_INTERVAL);
+    let key = (source.ip(), kind);
+    if last_reply.contains_key(&key) {
+        return false;
+    }
+    last_reply.insert(key, now);
+    true
+}
+
+/// Parse `a.b.c.d/n`, or a bare address as a single host.
+fn parse_cidr(range: &str) -> Option<(Ipv4Addr, u8)> {
+    let (addr, prefix) = range
+        .trim()
+        .split_once('/')
+        .unwrap_or_else(|| (range.trim(), "32"));
+    let addr: Ipv4Addr = addr.parse().ok()?;
+    let prefix: u8 = prefix.parse().ok()?;
+    (prefix <= 32).then_some((addr, prefix))
+}
+
+/// Usable host addresses of the network containing `addr`.
+///
+/// Network and broadcast addresses are skipped for prefixes up to `/30`.
+fn hosts(addr: Ipv4Addr, prefix: u8) -> impl Iterator<Item = Ipv4Addr> {
+    let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+    let network = u32::from(addr) & mask;
+    let broadcast = network | !mask;
+    let (first, last) = if prefix >= 31 {
+        (network, broadcast)
+    } else {
+        (network + 1, broadcast - 1)
+    };
+    (first..=last).take(MAX_SWEEP_HOSTS + 1).map(Ipv4Addr::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cidr() {
+        assert_eq!(
+            parse_cidr("10.20.0.0/24"),
+            Some((Ipv4Addr::new(10, 20, 0, 0), 24))
+        );
+        assert_eq!(
+            parse_cidr("192.168.1.7"),
+            Some((Ipv4Addr::new(192, 168, 1, 7), 32))
+        );
+        assert_eq!(parse_cidr("10.0.0.0/33"), None);
+        assert_eq!(parse_cidr("not-a-range"), None);
+    }
+
+    #[test]
+    fn test_hosts() {
+        let all: Vec<Ipv4Addr> = hosts(Ipv4Addr::new(192, 168, 1, 77), 24).collect();
+        assert_eq!(all.len(), 254);
+        assert_eq!(all[0], Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(all[253], Ipv4Addr::new(192, 168, 1, 254));
+
+        let single: Vec<Ipv4Addr> = hosts(Ipv4Addr::new(10, 0, 0, 5), 32).collect();
+        assert_eq!(single, vec![Ipv4Addr::new(10, 0, 0, 5)]);
+
+        assert_eq!(hosts(Ipv4Addr::UNSPECIFIED, 0).count(), MAX_SWEEP_HOSTS + 1);
+    }
+
+    #[test]
+    fn test_sweep_targets_from_ranges() {
+        let options = SweepOptions {
+            enabled: true,
+            ranges: vec![
+                "10.99.0.0/30".to_string(),
+                "10.99.0.2".to_string(),
+                "bogus".to_string(),
+            ],
+        };
+        let targets = sweep_targets(&options, &InterfaceFilter::default());
+        assert_eq!(
+            targets,
+            vec![Ipv4Addr::new(10, 99, 0, 1), Ipv4Addr::new(10, 99, 0, 2)]
+        );
+
+        let options = SweepOptions {
+            enabled: true,
+            ranges: vec!["10.0.0.0/8".to_string()],
+        };
+        assert_eq!(
+            sweep_targets(&options, &InterfaceFilter::default()).len(),
+            MAX_SWEEP_HOSTS
+        );
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let share = serde_json::to_vec(&DiscoveryQuery::new(QueryKind::Share)).expect("serialize");
+        assert_eq!(parse_query(&share), Some(QueryKind::Share));
+        let device =
+            serde_json::to_vec(&DiscoveryQuery::new(QueryKind::Device)).expect("serialize");
+        assert_eq!(parse_query(&device), Some(QueryKind::Device));
+        assert_eq!(parse_query(br#"{"protocol":"yoop","version":"1.0"}"#), None);
+    }
+
+    #[test]
+    fn test_reply_rate_limit() {
+        let mut last_reply = HashMap::new();
+        let source: SocketAddr = "192.168.1.20:52525".parse().expect("addr");
+        assert!(should_reply(&mut last_reply, source, QueryKind::Share));
+        assert!(!should_reply(&mut last_reply, source, QueryKind::Share));
+        assert!(should_reply(&mut last_reply, source, QueryKind::Device));
+        assert!(should_reply(
+            &mut last_reply,
+            "192.168.1.21:52525".parse().expect("addr"),
+            QueryKind::Share
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_one_responder_answers_every_kind() {
+        let port = 53100 + (std::process::id() % 100) as u16;
+        let (shutdown_tx, _) = broadcast::channel(1);
+        spawn_responder(port, QueryKind::Share, shutdown_tx.subscribe(), || {
+            Some(b"share".to_vec())
+        });
+        spawn_responder(port, QueryKind::Device, shutdown_tx.subscribe(), || {
+            Some(b"device".to_vec())
+        });
+
+        let querier = UdpSocket::bind("127.0.0.1:0").await.expect("bind querier");
+        let mut buf = [0u8; 64];
+        for (kind, expected) in [
+            (QueryKind::Share, &b"share"[..]),
+            (QueryKind::Device, &b"device"[..]),
+        ] {
+            send_queries(&querier, kind, &[Ipv4Addr::LOCALHOST], port).await;
+            let (len, _) =
+                tokio::time::timeout(Duration::from_secs(2), querier.recv_from(&mut buf))
+                    .await
+                    .expect("reply timeout")
+                    .expect("recv");
+            assert_eq!(&buf[..len], expected);
+        }
+        let _ = shutdown_tx.send(());
+    }
+}
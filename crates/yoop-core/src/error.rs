@@ -151,6 +151,23 @@ pub enum Error {
     #[error("trust verification failed: {0}")]
     TrustError(String),
 
+    /// Trusted device lacks the permission for an action
+    #[error("device '{device}' is not permitted to {action}")]
+    CapabilityDenied {
+        /// Device name
+        device: String,
+        /// What the device tried to do
+        action: String,
+    },
+
+    /// Device identity is encrypted and no passphrase is available
+    #[error("device identity is locked")]
+    IdentityLocked,
+
+    /// Wrong passphrase for the device identity
+    #[error("incorrect identity passphrase")]
+    InvalidPassphrase,
+
     /// Preview generation failed
     #[error("failed to generate preview for '{file}': {reason}")]
     PreviewFailed {
@@ -261,6 +278,56 @@ pub enum Error {
     /// Invalid input provided
     #[error("invalid input: {0}")]
     InvalidInput(String),
+
+    /// An incoming transfer exceeds a configured receive limit
+    #[error("transfer exceeds receive limit: {limit} is {actual}, maximum is {max}")]
+    ReceiveLimitExceeded {
+        /// Which limit was exceeded
+        limit: &'static str,
+        /// Value offered by the sender
+        actual: u64,
+        /// Configured maximum
+        max: u64,
+    },
+
+    /// The sender offered a path outside the output directory
+    #[error("unsafe path from sender: {0}")]
+    UnsafePath(String),
+
+    /// The sender offered a symlink the receive policy does not allow
+    #[error("symlink not allowed: {0}")]
+    SymlinkNotAllowed(String),
+
+    /// A received file does not match the SHA-256 reported by the sender
+    #[error("SHA-256 mismatch for received file '{0}'")]
+    FileHashMismatch(String),
+
+    /// The configured scanner rejected a received file
+    #[error("received file '{file}' rejected by scanner: {reason}")]
+    FileQuarantined {
+        /// The rejected file
+        file: String,
+        /// The scanner's verdict
+        reason: String,
+    },
+
+    /// An event hook could not be run or failed
+    #[error("{event} hook failed: {reason}")]
+    HookFailed {
+        /// The event the hook ran for
+        event: &'static str,
+        /// Why the hook failed
+        reason: String,
+    },
+
+    /// Every port in the transfer port range is in use
+    #[error("no free port in transfer port range {start}-{end}")]
+    NoFreePort {
+        /// First port of the range
+        start: u16,
+        /// Last port of the range
+        end: u16,
+    },
 }
 
 impl Error {
@@ -297,6 +364,33 @@ impl Error {
         )
     }
 
+    /// Returns whether this error means the peer connection dropped.
+    ///
+    /// Local I/O failures (disk full, permission denied) are not counted,
+    /// since reconnecting would not fix them.
+    #[must_use]
+    pub fn is_connection_loss(&self) -> bool {
+        use io::ErrorKind;
+
+        match self {
+            Self::ConnectionLost(_) | Self::Timeout(_) | Self::KeepAliveFailed(_) => true,
+            Self::Io(e) => matches!(
+                e.kind(),
+                ErrorKind::UnexpectedEof
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::NotConnected
+                    | ErrorKind::TimedOut
+                    | ErrorKind::NetworkUnreachable
+                    | ErrorKind::NetworkDown
+                    | ErrorKind::HostUnreachable
+                    | ErrorKind::AddrNotAvailable
+            ),
+            _ => false,
+        }
+    }
+
     /// Returns a helpful suggestion for resolving the error, if applicable.
     #[must_use]
     pub fn suggestion(&self) -> Option<&'static str> {
@@ -328,6 +422,39 @@ impl Error {
                 "Failed to rollback. You may need to manually reinstall:\n\
                    npm install -g yoop",
             ),
+            Self::IdentityLocked => Some(
+                "Unlock the identity for this session:\n\
+                   yoop identity unlock\n\
+                 Or set YOOP_IDENTITY_PASSPHRASE for non-interactive use",
+            ),
+            Self::CapabilityDenied { .. } => Some(
+                "Review the device's permissions:\n\
+                   yoop trust list\n\
+                 And grant what it needs, e.g.:\n\
+                   yoop trust set <device> --allow files",
+            ),
+            Self::ReceiveLimitExceeded { .. } => Some(
+                "If you trust the sender, raise the limit, e.g.:\n\
+                   yoop config set limits.max_files 500000",
+            ),
+            Self::SymlinkNotAllowed(_) => Some(
+                "Ask the sender to share without preserving symlinks, or change the policy:\n\
+                   yoop config set limits.symlinks any",
+            ),
+            Self::FileHashMismatch(_) => Some(
+                "The file was corrupted in transit and has not been saved. Try the transfer again",
+            ),
+            Self::FileQuarantined { .. } => Some(
+                "The file has not been saved. Check the scanner configured in:\n\
+                   yoop config get quarantine.scan_command",
+            ),
+            Self::HookFailed { .. } => Some(
+                "The transfer itself was not affected. Check the [[hooks]] entries in your config",
+            ),
+            Self::NoFreePort { .. } => Some(
+                "Stop other Yoop sessions or widen the port range:\n\
+                   yoop config set transfer_port_range 52530-52560",
+            ),
             _ => None,
         }
     }
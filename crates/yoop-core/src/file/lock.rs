@@ -0,0 +1,95 @@
+//! Advisory lock files for data files shared between processes.
+//!
+//! The CLI, the outbox worker and the TUI may all update the same file. A
+//! [`LockFile`] is an empty `<file>.lock` created exclusively next to it and
+//! removed when dropped; a lock left behind by a crashed process is taken
+//! over once it is older than [`STALE_AFTER`].
+
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+/// How long to wait for another process holding the lock.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Age after which a leftover lock file is considered stale.
+const STALE_AFTER: Duration = Duration::from_secs(10);
+
+/// Exclusive lock on a file, held until dropped.
+#[derive(Debug)]
+pub struct LockFile {
+    path: PathBuf,
+}
+
+impl LockFile {
+    /// Lock `file`, waiting for another holder to release it.
+    ///
+    /// This blocks the calling thread; async code should call it from
+    /// `spawn_blocking`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is still held after the timeout or the
+    /// lock file cannot be created.
+    pub fn acquire(file: &Path) -> Result<Self> {
+        let mut path = file.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+        let deadline = Instant::now() + TIMEOUT;
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > STALE_AFTER);
+                    if stale {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(Error::Internal(format!(
+                            "timed out waiting for lock {}",
+                            path.display()
+                        )));
+                    }
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data.json");
+
+        let lock = LockFile::acquire(&file).unwrap();
+        assert!(dir.path().join("data.json.lock").exists());
+
+        let waiter = std::thread::spawn(move || LockFile::acquire(&file).map(drop));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!waiter.is_finished());
+
+        drop(lock);
+        waiter.join().unwrap().unwrap();
+        assert!(!dir.path().join("data.json.lock").exists());
+    }
+}
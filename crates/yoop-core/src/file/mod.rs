@@ -5,6 +5,7 @@
 //! - Chunking files for transfer
 //! - Metadata preservation
 //! - Path sanitization
+//! - Staging received files until they are verified
 //!
 //! ## Metadata Preservation
 //!
@@ -18,12 +19,21 @@
 //! - Unix: Full permission support (mode bits), native symlinks
 //! - Windows: No permission support, symlink fallback to copy
 
+mod lock;
+mod quarantine;
+
+pub use lock::LockFile;
+
+pub use quarantine::{
+    sha256_hex, staging_path, QuarantineConfig, DEFAULT_SCAN_TIMEOUT, STAGING_SUFFIX,
+};
+
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use serde::{Deserialize, Serialize};
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 
 /// Get Unix file permissions from metadata.
 ///
@@ -75,6 +85,45 @@ pub fn apply_permissions(_path: &Path, _permissions: Option<u32>) -> Result<()>
     Ok(())
 }
 
+/// Atomically replace `path` with `contents`, readable only by the owner.
+///
+/// The contents go to a temporary file created with mode `0600` in the same
+/// directory, which is synced and then renamed over `path`, so a crash
+/// leaves either the old file or the new one and never exposes the contents
+/// to other users.
+///
+/// # Errors
+///
+/// Returns an error if the temporary file cannot be written or renamed.
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::InvalidPath(path.display().to_string()))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let result = (|| {
+        let mut file = options.open(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
 /// How to handle symlinks during file enumeration and transfer.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -494,6 +543,19 @@ impl FileChunker {
     }
 }
 
+/// Compute the SHA-256 of a file from its chunks, as lowercase hex.
+///
+/// Senders report this so the receiver can verify the whole file before
+/// releasing it.
+#[must_use]
+pub fn chunks_sha256(chunks: &[FileChunk]) -> String {
+    let mut hasher = sha2::Sha256::new();
+    for chunk in chunks {
+        hasher.update(&chunk.data);
+    }
+    sha256_hex(&hasher.finalize().into())
+}
+
 /// Sanitize a path to prevent directory traversal attacks.
 ///
 /// # Arguments
@@ -540,18 +602,34 @@ pub fn format_size(bytes: u64) -> String {
 }
 
 /// Writer for receiving file chunks and assembling them.
+///
+/// Data is written to a hidden staging file next to `output_path` (see
+/// [`staging_path`]) and only renamed to `output_path` by
+/// [`finalize`](Self::finalize) once the file has been verified. A writer
+/// dropped before that removes its staging file, unless it was opened with
+/// [`new_resumable`](Self::new_resumable).
 #[derive(Debug)]
 pub struct FileWriter {
     /// Output file path
     pub output_path: PathBuf,
     /// Expected total file size
     pub expected_size: u64,
+    /// Path the data is written to until the file is released
+    staging_path: PathBuf,
     /// File handle
     file: Option<tokio::fs::File>,
     /// Bytes written so far
     pub bytes_written: u64,
     /// SHA-256 hasher for final verification
     sha256_hasher: sha2::Sha256,
+    /// SHA-256 (hex) reported by the sender
+    expected_sha256: Option<String>,
+    /// Checks run before the file is released
+    quarantine: QuarantineConfig,
+    /// Keep the staging file when dropped, so the transfer can resume
+    retain_partial: bool,
+    /// Whether the file has been moved to `output_path`
+    released: bool,
 }
 
 impl FileWriter {
@@ -572,22 +650,29 @@ impl FileWriter {
             tokio::fs::create_dir_all(parent).await?;
         }
 
-        let file = File::create(&output_path).await?;
+        let staging_path = staging_path(&output_path);
+        let file = File::create(&staging_path).await?;
 
         Ok(Self {
             output_path,
             expected_size,
+            staging_path,
             file: Some(file),
             bytes_written: 0,
             sha256_hasher: sha2::Sha256::new(),
+            expected_sha256: None,
+            quarantine: QuarantineConfig::default(),
+            retain_partial: false,
+            released: false,
         })
     }
 
     /// Create a new file writer for resuming a transfer.
     ///
-    /// Opens an existing file for appending/writing at arbitrary positions.
+    /// Opens an existing staging file for appending/writing at arbitrary positions.
     /// The file will be pre-allocated to the expected size if it doesn't already exist
-    /// or is smaller than expected.
+    /// or is smaller than expected. The staging file is kept if the writer is
+    /// dropped without being finalized; use [`discard`](Self::discard) to remove it.
     ///
     /// # Arguments
     ///
@@ -609,12 +694,13 @@ impl FileWriter {
             tokio::fs::create_dir_all(parent).await?;
         }
 
+        let staging_path = staging_path(&output_path);
         let file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
             .truncate(false)
-            .open(&output_path)
+            .open(&staging_path)
             .await?;
 
         let metadata = file.metadata().await?;
@@ -625,12 +711,37 @@ impl FileWriter {
         Ok(Self {
             output_path,
             expected_size,
+            staging_path,
             file: Some(file),
             bytes_written: resume_offset,
             sha256_hasher: sha2::Sha256::new(),
+            expected_sha256: None,
+            quarantine: QuarantineConfig::default(),
+            retain_partial: true,
+            released: false,
         })
     }
 
+    /// Run the checks in `quarantine` before releasing the file.
+    #[must_use]
+    pub fn with_quarantine(mut self, quarantine: QuarantineConfig) -> Self {
+        self.quarantine = quarantine;
+        self
+    }
+
+    /// Set the SHA-256 (hex) the sender reported for this file.
+    ///
+    /// The file is only released if its content hashes to this value.
+    pub fn set_expected_sha256(&mut self, sha256: String) {
+        self.expected_sha256 = Some(sha256);
+    }
+
+    /// Get the path the data is staged at until the file is released.
+    #[must_use]
+    pub fn staging_path(&self) -> &Path {
+        &self.staging_path
+    }
+
     /// Write a chunk to the file.
     ///
     /// Verifies the xxHash64 checksum before writing.
@@ -700,13 +811,14 @@ impl FileWriter {
         Ok(())
     }
 
-    /// Finalize the file and return the SHA-256 hash.
+    /// Finalize the file, release it to `output_path` and return the SHA-256 hash.
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be synced.
+    /// Returns an error if the file cannot be synced, does not match the
+    /// expected hash or is rejected by the scanner. The staging file is
+    /// removed in the latter two cases.
     pub async fn finalize(mut self) -> Result<[u8; 32]> {
-        use sha2::Digest;
         use tokio::io::AsyncWriteExt;
 
         if let Some(ref mut file) = self.file {
@@ -715,19 +827,22 @@ impl FileWriter {
         }
         self.file = None;
 
-        Ok(self.sha256_hasher.finalize().into())
+        let hash = self.sha256_hasher.finalize_reset().into();
+        self.release(&hash).await?;
+        Ok(hash)
     }
 
     /// Finalize the file and compute SHA-256 hash from the complete file.
     ///
     /// This is used for resumed transfers where chunks may have been written
-    /// out of order, making the incremental hash invalid.
+    /// out of order, making the incremental hash invalid. The file is
+    /// released like with [`finalize`](Self::finalize).
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be read or synced.
+    /// Returns an error if the file cannot be read or synced, does not match
+    /// the expected hash or is rejected by the scanner.
     pub async fn finalize_with_full_hash(mut self) -> Result<[u8; 32]> {
-        use sha2::Digest;
         use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
         if let Some(ref mut file) = self.file {
@@ -737,7 +852,7 @@ impl FileWriter {
         self.file = None;
 
         let mut hasher = sha2::Sha256::new();
-        let mut file = tokio::fs::File::open(&self.output_path).await?;
+        let mut file = tokio::fs::File::open(&self.staging_path).await?;
         let mut buffer = vec![0u8; 64 * 1024];
 
         loop {
@@ -747,8 +862,46 @@ impl FileWriter {
             }
             hasher.update(&buffer[..n]);
         }
+        drop(file);
 
-        Ok(hasher.finalize().into())
+        let hash = hasher.finalize().into();
+        self.release(&hash).await?;
+        Ok(hash)
+    }
+
+    /// Abandon the file and remove its staging file.
+    pub async fn discard(mut self) {
+        self.file = None;
+        remove_staged(
+            &self.staging_path,
+            tokio::fs::remove_file(&self.staging_path).await,
+        );
+    }
+
+    /// Verify the staged file and atomically move it to `output_path`.
+    async fn release(&mut self, hash: &[u8; 32]) -> Result<()> {
+        let name = self.output_path.display().to_string();
+
+        if let Err(e) = self.check(hash, &name).await {
+            tracing::warn!("Not releasing {}: {}", name, e);
+            remove_staged(
+                &self.staging_path,
+                tokio::fs::remove_file(&self.staging_path).await,
+            );
+            return Err(e);
+        }
+
+        tokio::fs::rename(&self.staging_path, &self.output_path).await?;
+        self.released = true;
+        Ok(())
+    }
+
+    /// Check the staged file against the expected hash and the scanner.
+    async fn check(&self, hash: &[u8; 32], name: &str) -> Result<()> {
+        if let Some(ref expected) = self.expected_sha256 {
+            quarantine::verify_sha256(hash, expected, name)?;
+        }
+        self.quarantine.scan(&self.staging_path, name).await
     }
 
     /// Get the current bytes written count.
@@ -770,6 +923,25 @@ impl FileWriter {
     }
 }
 
+impl Drop for FileWriter {
+    fn drop(&mut self) {
+        if self.released || self.retain_partial {
+            return;
+        }
+        self.file = None;
+        remove_staged(&self.staging_path, std::fs::remove_file(&self.staging_path));
+    }
+}
+
+/// Log the outcome of removing a staging file.
+fn remove_staged(path: &Path, result: std::io::Result<()>) {
+    match result {
+        Ok(()) => tracing::debug!("Removed staged file {}", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => tracing::warn!("Failed to remove staged file {}: {}", path.display(), e),
+    }
+}
+
 use sha2::Digest;
 
 #[cfg(test)]
@@ -814,6 +986,24 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn test_write_private_replaces_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("secret.json");
+        std::fs::write(&path, "old").unwrap();
+
+        write_private(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
     #[tokio::test]
     async fn test_chunk_empty_file() {
         let temp_dir = TempDir::new().expect("create temp dir");
@@ -967,6 +1157,88 @@ mod tests {
         assert!(result.is_err(), "Should fail on checksum mismatch");
     }
 
+    #[tokio::test]
+    async fn test_file_writer_stages_until_verified() {
+        let temp_dir = TempDir::new().expect("create temp dir");
+        let output_path = temp_dir.path().join("output.txt");
+        let content = b"Test content for staging";
+        let chunk = FileChunk {
+            file_index: 0,
+            chunk_index: 0,
+            data: content.to_vec(),
+            checksum: crate::crypto::xxhash64(content),
+            is_last: true,
+        };
+
+        let mut writer = FileWriter::new(output_path.clone(), content.len() as u64)
+            .await
+            .expect("create writer");
+        writer.set_expected_sha256(chunks_sha256(std::slice::from_ref(&chunk)));
+        writer.write_chunk(&chunk).await.expect("write chunk");
+
+        let staged = writer.staging_path().to_path_buf();
+        assert!(staged.exists(), "Data should be staged");
+        assert!(!output_path.exists(), "File should not be visible yet");
+
+        writer.finalize().await.expect("finalize");
+        assert!(!staged.exists());
+        assert_eq!(std::fs::read(&output_path).expect("read file"), content);
+    }
+
+    #[tokio::test]
+    async fn test_file_writer_rejects_hash_mismatch() {
+        let temp_dir = TempDir::new().expect("create temp dir");
+        let output_path = temp_dir.path().join("output.txt");
+        let content = b"Tampered content";
+        let chunk = FileChunk {
+            file_index: 0,
+            chunk_index: 0,
+            data: content.to_vec(),
+            checksum: crate::crypto::xxhash64(content),
+            is_last: true,
+        };
+
+        let mut writer = FileWriter::new(output_path.clone(), content.len() as u64)
+            .await
+            .expect("create writer");
+        writer.set_expected_sha256(sha256_hex(&crate::crypto::sha256(b"Original content")));
+        writer.write_chunk(&chunk).await.expect("write chunk");
+        let staged = writer.staging_path().to_path_buf();
+
+        let result = writer.finalize().await;
+        assert!(matches!(
+            result,
+            Err(crate::error::Error::FileHashMismatch(_))
+        ));
+        assert!(!staged.exists(), "Rejected file should be removed");
+        assert!(
+            !output_path.exists(),
+            "Rejected file should not be released"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_writer_drop_removes_staged_file() {
+        let temp_dir = TempDir::new().expect("create temp dir");
+        let output_path = temp_dir.path().join("output.txt");
+
+        let writer = FileWriter::new(output_path.clone(), 100)
+            .await
+            .expect("create writer");
+        let staged = writer.staging_path().to_path_buf();
+        assert!(staged.exists());
+
+        drop(writer);
+        assert!(!staged.exists(), "Abandoned file should be removed");
+        assert!(!output_path.exists());
+
+        let writer = FileWriter::new_resumable(output_path.clone(), 100, 0)
+            .await
+            .expect("create resumable writer");
+        drop(writer);
+        assert!(staged.exists(), "Resumable file should be kept");
+    }
+
     #[tokio::test]
     #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
     async fn test_roundtrip_chunking() {
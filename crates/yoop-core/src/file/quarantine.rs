@@ -0,0 +1,213 @@
+//! Staging of received files.
+//!
+//! A [`FileWriter`](super::FileWriter) never writes to a file's final path.
+//! Data goes to a hidden staging file in the same directory, so it lives on
+//! the same filesystem and can be moved into place with one atomic rename.
+//! Other programs and sync watchers therefore never see a partially received
+//! file: it appears under its real name only after its SHA-256 matched the
+//! sender's and the optional external scanner accepted it.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Suffix of staging files, so they can be recognised and excluded.
+pub const STAGING_SUFFIX: &str = ".yoop-partial";
+
+/// Default time the scanner gets per file.
+pub const DEFAULT_SCAN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Longest file name that gets a readable staging name. Longer names are
+/// replaced by a hash so the staging name stays within filesystem limits.
+const MAX_READABLE_NAME: usize = 200;
+
+/// How received files are checked before they are released.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuarantineConfig {
+    /// Scanner run on each received file before it is released, as a
+    /// program followed by its arguments. The staged file's path is appended
+    /// as the last argument. Exit status 0 accepts the file; any other
+    /// status, a failure to start or a timeout rejects it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_command: Option<Vec<String>>,
+    /// How long the scanner may run on one file
+    #[serde(with = "crate::config::humantime_serde")]
+    pub scan_timeout: Duration,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        Self {
+            scan_command: None,
+            scan_timeout: DEFAULT_SCAN_TIMEOUT,
+        }
+    }
+}
+
+impl QuarantineConfig {
+    /// Run the configured scanner on the staged file at `path`.
+    ///
+    /// `name` identifies the file in the error. Succeeds immediately when no
+    /// scanner is configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FileQuarantined`] if the scanner rejects the file,
+    /// cannot be started or does not finish within the timeout.
+    pub async fn scan(&self, path: &Path, name: &str) -> Result<()> {
+        let Some((program, args)) = self
+            .scan_command
+            .as_deref()
+            .and_then(<[String]>::split_first)
+        else {
+            return Ok(());
+        };
+
+        let rejected = |reason: String| Error::FileQuarantined {
+            file: name.to_string(),
+            reason,
+        };
+
+        let output = tokio::process::Command::new(program)
+            .args(args)
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output();
+
+        let output = match tokio::time::timeout(self.scan_timeout, output).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => return Err(rejected(format!("failed to run {program}: {e}"))),
+            Err(_) => {
+                return Err(rejected(format!(
+                    "scanner did not finish within {}s",
+                    self.scan_timeout.as_secs()
+                )))
+            }
+        };
+
+        if output.status.success() {
+            tracing::debug!("Scanner accepted {}", name);
+            return Ok(());
+        }
+
+        let reason = [&output.stdout, &output.stderr]
+            .into_iter()
+            .find_map(|out| {
+                String::from_utf8_lossy(out)
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .map(String::from)
+            })
+            .unwrap_or_else(|| output.status.to_string());
+        Err(rejected(reason))
+    }
+}
+
+/// Get the staging path used while receiving the file at `final_path`.
+#[must_use]
+pub fn staging_path(final_path: &Path) -> PathBuf {
+    let name = final_path.file_name().unwrap_or_default();
+
+    let mut staged = OsString::from(".");
+    if name.len() > MAX_READABLE_NAME {
+        staged.push(format!(
+            "{:016x}",
+            crate::crypto::xxhash64(name.as_encoded_bytes())
+        ));
+    } else {
+        staged.push(name);
+    }
+    staged.push(STAGING_SUFFIX);
+
+    final_path.with_file_name(staged)
+}
+
+/// Encode a SHA-256 hash as lowercase hex.
+#[must_use]
+pub fn sha256_hex(hash: &[u8; 32]) -> String {
+    use std::fmt::Write;
+
+    hash.iter().fold(String::with_capacity(64), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    })
+}
+
+/// Check a received file's hash against the one the sender reported.
+pub(super) fn verify_sha256(actual: &[u8; 32], expected: &str, name: &str) -> Result<()> {
+    if sha256_hex(actual).eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(Error::FileHashMismatch(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staging_path_is_hidden_sibling() {
+        let staged = staging_path(Path::new("/downloads/photos/cat.jpg"));
+        assert_eq!(staged, Path::new("/downloads/photos/.cat.jpg.yoop-partial"));
+
+        let long_name = "a".repeat(250);
+        let staged = staging_path(&Path::new("/downloads").join(&long_name));
+        assert_eq!(staged.parent(), Some(Path::new("/downloads")));
+        assert!(staged.file_name().unwrap().len() < 40);
+    }
+
+    #[test]
+    fn test_verify_sha256() {
+        let hash = crate::crypto::sha256(b"hello");
+        let hex = sha256_hex(&hash);
+
+        assert!(verify_sha256(&hash, &hex, "hello.txt").is_ok());
+        assert!(verify_sha256(&hash, &hex.to_uppercase(), "hello.txt").is_ok());
+        assert!(matches!(
+            verify_sha256(&hash, &sha256_hex(&[0; 32]), "hello.txt"),
+            Err(Error::FileHashMismatch(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_scan_verdict() {
+        let path = Path::new("/dev/null");
+        let config = |command: &[&str]| QuarantineConfig {
+            scan_command: Some(command.iter().map(ToString::to_string).collect()),
+            ..QuarantineConfig::default()
+        };
+
+        assert!(QuarantineConfig::default().scan(path, "f").await.is_ok());
+        assert!(config(&["true"]).scan(path, "f").await.is_ok());
+
+        let err = config(&["sh", "-c", "echo 'Eicar FOUND'; exit 1", "scan"])
+            .scan(path, "f")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::FileQuarantined { ref reason, .. } if reason == "Eicar FOUND")
+        );
+
+        let timeout = QuarantineConfig {
+            scan_timeout: Duration::from_millis(100),
+            ..config(&["sleep", "5"])
+        };
+        assert!(timeout.scan(path, "f").await.is_err());
+        assert!(config(&["/nonexistent/scanner"])
+            .scan(path, "f")
+            .await
+            .is_err());
+    }
+}
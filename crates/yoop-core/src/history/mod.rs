@@ -143,7 +143,9 @@ impl TransferHistoryEntry {
     /// Add files to the entry.
     #[must_use]
     pub fn with_files(mut self, files: Vec<HistoryFileEntry>) -> Self {
-        self.total_bytes = files.iter().map(|f| f.size).sum();
+        self.total_bytes = files
+            .iter()
+            .fold(0, |total, f| total.saturating_add(f.size));
         self.files = files;
         self
     }
@@ -0,0 +1,485 @@
+//! Event hooks for scripting.
+//!
+//! Hooks run a user command when something happens in a session: a share
+//! starts or completes, a transfer or a single file is received, a synced
+//! file changes or conflicts, or clipboard content arrives. They are
+//! configured as `[[hooks]]` entries naming an event and a command.
+//!
+//! ## Running
+//!
+//! Sessions report events through [`emit`], which starts every hook enabled
+//! with [`enable`] for that event in the background. A hook gets the event's
+//! details in `YOOP_*` environment variables and the whole [`HookEvent`] as
+//! JSON on stdin. A hook that cannot be started, exits with a non-zero
+//! status or outlives its timeout is logged and killed; it never affects the
+//! session that triggered it. [`wait`] lets a process finish running hooks
+//! before it exits.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinHandle;
+
+use crate::error::{Error, Result};
+
+/// Default time a hook may run.
+pub const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+static ACTIVE_HOOKS: Mutex<Vec<Hook>> = Mutex::new(Vec::new());
+
+static RUNNING: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// Run `hooks` for events emitted for the rest of the process.
+pub fn enable(hooks: Vec<Hook>) {
+    if let Ok(mut active) = ACTIVE_HOOKS.lock() {
+        *active = hooks;
+    }
+}
+
+/// Stop running hooks.
+pub fn disable() {
+    if let Ok(mut active) = ACTIVE_HOOKS.lock() {
+        active.clear();
+    }
+}
+
+/// Start the enabled hooks for `event` in the background.
+///
+/// Failures are logged and otherwise ignored.
+pub fn emit(event: HookEvent) {
+    tracing::debug!("Hook event: {}", event.event);
+    let hooks: Vec<Hook> = ACTIVE_HOOKS
+        .lock()
+        .map(|active| {
+            active
+                .iter()
+                .filter(|hook| hook.event == event.event)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    if hooks.is_empty() {
+        return;
+    }
+
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        tracing::warn!("Cannot run hooks for {} outside a runtime", event.event);
+        return;
+    };
+
+    let Ok(mut running) = RUNNING.lock() else {
+        return;
+    };
+    running.retain(|task| !task.is_finished());
+    let event = Arc::new(event);
+    for hook in hooks {
+        let event = Arc::clone(&event);
+        running.push(runtime.spawn(async move {
+            if let Err(e) = run(&hook, &event).await {
+                tracing::warn!("{}", e);
+            }
+        }));
+    }
+}
+
+/// Wait for hooks that are still running.
+pub async fn wait() {
+    let tasks = RUNNING
+        .lock()
+        .map(|mut running| std::mem::take(&mut *running))
+        .unwrap_or_default();
+    for task in tasks {
+        let _ = task.await;
+    }
+}
+
+/// Run a single hook for `event` and wait for it to finish.
+///
+/// # Errors
+///
+/// Returns [`Error::HookFailed`] if the command cannot be started, exits
+/// with a non-zero status or does not finish within the hook's timeout.
+pub async fn run(hook: &Hook, event: &HookEvent) -> Result<()> {
+    let failed = |reason: String| Error::HookFailed {
+        event: event.event.as_str(),
+        reason,
+    };
+
+    let Some((program, args)) = hook.command.split_first() else {
+        return Err(failed("no command configured".to_string()));
+    };
+
+    let input = serde_json::to_vec(event)
+        .map_err(|e| Error::Serialization(format!("Failed to serialize hook event: {e}")))?;
+
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .envs(event.env())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| failed(format!("failed to run {program}: {e}")))?;
+
+    // the timeout covers writing the event too, since a hook that neither
+    // reads its input nor exits blocks the write once the pipe is full
+    let stdin = child.stdin.take();
+    let finished = async move {
+        if let Some(mut stdin) = stdin {
+            // the hook may exit without reading its input
+            if let Err(e) = stdin.write_all(&input).await {
+                tracing::debug!("Hook {} did not read the event: {}", program, e);
+            }
+        }
+        child.wait_with_output().await
+    };
+
+    let output = match tokio::time::timeout(hook.timeout, finished).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Err(failed(format!("{program}: {e}"))),
+        Err(_) => {
+            return Err(failed(format!(
+                "{program} did not finish within {}s",
+                hook.timeout.as_secs()
+            )))
+        }
+    };
+
+    if output.status.success() {
+        tracing::debug!("Hook {} for {} finished", program, event.event);
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map_or_else(
+            || format!("{program} exited with {}", output.status),
+            |line| format!("{program} exited with {}: {line}", output.status),
+        );
+    Err(failed(reason))
+}
+
+/// A command run when an event occurs (`[[hooks]]`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hook {
+    /// Event that triggers the hook
+    pub event: HookEventKind,
+    /// Program followed by its arguments
+    pub command: Vec<String>,
+    /// How long the command may run before it is killed
+    #[serde(default = "default_timeout", with = "crate::config::humantime_serde")]
+    pub timeout: Duration,
+}
+
+const fn default_timeout() -> Duration {
+    DEFAULT_HOOK_TIMEOUT
+}
+
+/// Kind of event a hook can run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookEventKind {
+    /// A share is waiting for a receiver, or a send to a trusted device began
+    ShareStarted,
+    /// A share was received by the other side
+    ShareCompleted,
+    /// All files of a transfer were received
+    ReceiveCompleted,
+    /// A single file was received and released
+    FileReceived,
+    /// A file was created or updated by sync
+    SyncFileReceived,
+    /// A file was deleted by sync
+    SyncFileDeleted,
+    /// Sync found a file changed on both sides
+    SyncConflict,
+    /// Clipboard content was received
+    ClipboardReceived,
+}
+
+impl HookEventKind {
+    /// All event kinds.
+    pub const ALL: [Self; 8] = [
+        Self::ShareStarted,
+        Self::ShareCompleted,
+        Self::ReceiveCompleted,
+        Self::FileReceived,
+        Self::SyncFileReceived,
+        Self::SyncFileDeleted,
+        Self::SyncConflict,
+        Self::ClipboardReceived,
+    ];
+
+    /// Name used in the configuration and `YOOP_EVENT`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ShareStarted => "share-started",
+            Self::ShareCompleted => "share-completed",
+            Self::ReceiveCompleted => "receive-completed",
+            Self::FileReceived => "file-received",
+            Self::SyncFileReceived => "sync-file-received",
+            Self::SyncFileDeleted => "sync-file-deleted",
+            Self::SyncConflict => "sync-conflict",
+            Self::ClipboardReceived => "clipboard-received",
+        }
+    }
+}
+
+impl fmt::Display for HookEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HookEventKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| Error::InvalidInput(format!("unknown hook event: {s}")))
+    }
+}
+
+/// An event passed to hooks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HookEvent {
+    /// What happened
+    pub event: HookEventKind,
+    /// Unix timestamp of the event
+    pub timestamp: u64,
+    /// Name of the peer device
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer: Option<String>,
+    /// Share code of the transfer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// File or directory concerned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Number of files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<u64>,
+    /// Size in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    /// Additional detail, such as a conflict resolution or content type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl HookEvent {
+    /// Create an event of the given kind.
+    #[must_use]
+    pub fn new(event: HookEventKind) -> Self {
+        Self {
+            event,
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            peer: None,
+            code: None,
+            path: None,
+            files: None,
+            bytes: None,
+            detail: None,
+        }
+    }
+
+    /// Set the name of the peer device.
+    #[must_use]
+    pub fn with_peer(mut self, peer: impl Into<String>) -> Self {
+        self.peer = Some(peer.into());
+        self
+    }
+
+    /// Set the share code.
+    #[must_use]
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Set the file or directory concerned.
+    #[must_use]
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Set the number of files.
+    #[must_use]
+    pub const fn with_files(mut self, files: u64) -> Self {
+        self.files = Some(files);
+        self
+    }
+
+    /// Set the size in bytes.
+    #[must_use]
+    pub const fn with_bytes(mut self, bytes: u64) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    /// Set a human-readable detail.
+    #[must_use]
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Environment variables describing the event.
+    #[must_use]
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("YOOP_EVENT", self.event.as_str().to_string()),
+            ("YOOP_TIMESTAMP", self.timestamp.to_string()),
+        ];
+        let optional = [
+            ("YOOP_PEER", self.peer.clone()),
+            ("YOOP_CODE", self.code.clone()),
+            (
+                "YOOP_PATH",
+                self.path.as_ref().map(|p| p.display().to_string()),
+            ),
+            ("YOOP_FILES", self.files.map(|n| n.to_string())),
+            ("YOOP_BYTES", self.bytes.map(|n| n.to_string())),
+            ("YOOP_DETAIL", self.detail.clone()),
+        ];
+        env.extend(
+            optional
+                .into_iter()
+                .filter_map(|(name, value)| value.map(|value| (name, value))),
+        );
+        env
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(command: &[&str]) -> Hook {
+        Hook {
+            event: HookEventKind::FileReceived,
+            command: command.iter().map(ToString::to_string).collect(),
+            timeout: DEFAULT_HOOK_TIMEOUT,
+        }
+    }
+
+    fn event() -> HookEvent {
+        HookEvent::new(HookEventKind::FileReceived)
+            .with_peer("Laptop")
+            .with_path("/downloads/report.pdf")
+            .with_bytes(1024)
+    }
+
+    #[test]
+    fn test_event_kind_names_round_trip() {
+        for kind in HookEventKind::ALL {
+            assert_eq!(kind.as_str().parse::<HookEventKind>().unwrap(), kind);
+        }
+        assert!("transfer-done".parse::<HookEventKind>().is_err());
+    }
+
+    #[test]
+    fn test_hook_config_parsing() {
+        #[derive(Deserialize)]
+        struct Hooks {
+            hooks: Vec<Hook>,
+        }
+
+        let config: Hooks = toml::from_str(
+            r#"
+            [[hooks]]
+            event = "receive-completed"
+            command = ["notify-send", "Yoop"]
+
+            [[hooks]]
+            event = "sync-conflict"
+            command = ["/usr/local/bin/on-conflict"]
+            timeout = "5s"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.hooks[0].event, HookEventKind::ReceiveCompleted);
+        assert_eq!(config.hooks[0].timeout, DEFAULT_HOOK_TIMEOUT);
+        assert_eq!(config.hooks[1].event, HookEventKind::SyncConflict);
+        assert_eq!(config.hooks[1].timeout, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_event_env() {
+        let env = event().env();
+        let get = |name: &str| {
+            env.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        assert_eq!(get("YOOP_EVENT"), Some("file-received"));
+        assert_eq!(get("YOOP_PEER"), Some("Laptop"));
+        assert_eq!(get("YOOP_PATH"), Some("/downloads/report.pdf"));
+        assert_eq!(get("YOOP_BYTES"), Some("1024"));
+        assert_eq!(get("YOOP_CODE"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_passes_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let script = format!(
+            "cat > {0}.json && echo \"$YOOP_EVENT $YOOP_PEER\" > {0}",
+            out.display()
+        );
+
+        run(&hook(&["sh", "-c", &script]), &event()).await.unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(out.with_extension("json")).unwrap()).unwrap();
+        assert_eq!(json["event"], "file-received");
+        assert_eq!(json["path"], "/downloads/report.pdf");
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap().trim(),
+            "file-received Laptop"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_reports_failures() {
+        let err = run(&hook(&["sh", "-c", "echo boom >&2; exit 3"]), &event())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("boom"));
+
+        let slow = Hook {
+            timeout: Duration::from_millis(100),
+            ..hook(&["sleep", "5"])
+        };
+        assert!(run(&slow, &event()).await.is_err());
+
+        // more input than a pipe holds, to a hook that never reads it
+        let large = event().with_detail("x".repeat(1024 * 1024));
+        let result = tokio::time::timeout(Duration::from_secs(5), run(&slow, &large))
+            .await
+            .expect("hook timeout should cover writing the event");
+        assert!(result.is_err());
+        assert!(run(&hook(&["/nonexistent/hook"]), &event()).await.is_err());
+        assert!(run(&hook(&[]), &event()).await.is_err());
+    }
+}
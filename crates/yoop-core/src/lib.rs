@@ -12,17 +12,22 @@
 //!
 //! ## Modules
 //!
+//! - [`audit`] - Tamper-evident security audit log
+//! - [`bench`] - Throughput and latency benchmarking
 //! - [`clipboard`] - Clipboard sharing (one-shot and live sync)
 //! - [`code`] - Share code generation and validation
-//! - [`compression`] - Transfer compression using zstd
+//! - [`compression`] - Transfer compression using zstd or LZ4
 //! - [`config`] - Configuration management
 //! - [`crypto`] - Cryptographic primitives (TLS, hashing, signatures)
 //! - [`discovery`] - Network discovery via UDP broadcast and mDNS
 //! - [`mod@file`] - File operations, chunking, and metadata
 //! - [`history`] - Transfer history tracking and persistence
+//! - [`hooks`] - Event hooks for scripting
+//! - [`outbox`] - Store-and-forward queue for offline trusted devices
 //! - [`preview`] - File preview generation (thumbnails, text snippets)
 //! - [`protocol`] - LDRP wire protocol implementation
 //! - [`qr`] - QR code generation for share codes
+//! - [`relay`] - Rendezvous and relay server for shares across networks
 //! - [`transfer`] - File transfer engine
 //! - [`trust`] - Trusted devices management
 //! - [`web`] - Embedded web server for browser-based access
@@ -55,6 +60,8 @@
 #![allow(clippy::cast_precision_loss)]
 #![allow(clippy::unused_self)]
 
+pub mod audit;
+pub mod bench;
 pub mod clipboard;
 pub mod code;
 pub mod compression;
@@ -65,9 +72,12 @@ pub mod discovery;
 pub mod error;
 pub mod file;
 pub mod history;
+pub mod hooks;
+pub mod outbox;
 pub mod preview;
 pub mod protocol;
 pub mod qr;
+pub mod relay;
 pub mod sync;
 pub mod transfer;
 pub mod trust;
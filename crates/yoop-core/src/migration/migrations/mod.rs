@@ -4,3 +4,6 @@
 
 #[cfg(feature = "update")]
 pub mod v0_1_to_v0_2;
+
+#[cfg(feature = "update")]
+pub mod v0_2_to_v0_3;
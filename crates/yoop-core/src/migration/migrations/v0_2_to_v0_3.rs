@@ -0,0 +1,120 @@
+//! Migration from version 0.2.x to 0.3.x.
+//!
+//! This migration rewrites the identity file in the version 2 format,
+//! records the resolving key used for private beacons, and restricts the
+//! file to its owner. If `YOOP_IDENTITY_PASSPHRASE` is set, the key
+//! is encrypted with it as part of the upgrade; otherwise encryption stays
+//! opt-in through `yoop identity encrypt`.
+//!
+//! The identity is not included in pre-migration backups, so that they
+//! never hold a plaintext copy of the key.
+
+use std::path::Path;
+
+use crate::crypto::{unlock, DeviceIdentity};
+use crate::error::Result;
+use crate::migration::Migration;
+use crate::update::SchemaVersion;
+
+/// Migration from v0.2.x to v0.3.x upgrading the identity file format.
+#[allow(non_camel_case_types)]
+pub struct V0_2ToV0_3;
+
+impl Migration for V0_2ToV0_3 {
+    fn from_version(&self) -> SchemaVersion {
+        SchemaVersion::new(0, 2, 0)
+    }
+
+    fn to_version(&self) -> SchemaVersion {
+        SchemaVersion::new(0, 3, 0)
+    }
+
+    fn description(&self) -> &'static str {
+        "Upgrade identity file and optionally encrypt its key"
+    }
+
+    fn up(&self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join("identity.json");
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let mut identity = DeviceIdentity::load_from(path)?;
+        if !identity.is_encrypted() {
+            if let Ok(passphrase) = std::env::var(unlock::PASSPHRASE_ENV) {
+                identity.set_passphrase(Some(&passphrase))?;
+            }
+        }
+        identity.save()
+    }
+
+    fn down(&self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join("identity.json");
+        if !path.exists() {
+            return Ok(());
+        }
+
+        // older versions read version 2 files as long as the key is in the clear
+        let mut identity = DeviceIdentity::load_from(path)?;
+        if identity.is_encrypted() {
+            identity.set_passphrase(None)?;
+            identity.save()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_v0_2_to_v0_3_up() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("identity.json");
+
+        let expected = DeviceIdentity::generate().unwrap().with_path(path.clone());
+        expected.save().unwrap();
+        let mut v1: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        v1["version"] = 1.into();
+        fs::write(&path, v1.to_string()).unwrap();
+
+        V0_2ToV0_3.up(temp_dir.path()).unwrap();
+
+        let upgraded: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(upgraded["version"], 2);
+        let loaded = DeviceIdentity::load_from(path.clone()).unwrap();
+        assert_eq!(loaded.public_key_base64(), expected.public_key_base64());
+        assert_eq!(upgraded["resolving_key"], loaded.resolving_key_base64());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        V0_2ToV0_3.down(temp_dir.path()).unwrap();
+        assert!(!DeviceIdentity::load_from(path).unwrap().is_encrypted());
+    }
+
+    #[test]
+    fn test_v0_2_to_v0_3_no_identity() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(V0_2ToV0_3.up(temp_dir.path()).is_ok());
+        assert!(V0_2ToV0_3.down(temp_dir.path()).is_ok());
+    }
+
+    #[test]
+    fn test_migration_metadata() {
+        let migration = V0_2ToV0_3;
+
+        assert_eq!(migration.from_version(), SchemaVersion::new(0, 2, 0));
+        assert_eq!(migration.to_version(), SchemaVersion::new(0, 3, 0));
+        assert_eq!(migration.id(), "0_2_to_0_3");
+    }
+}
@@ -118,7 +118,10 @@ impl MigrationManager {
 
     #[cfg(feature = "update")]
     fn register_migrations() -> Vec<Box<dyn Migration>> {
-        vec![Box::new(migrations::v0_1_to_v0_2::V0_1ToV0_2)]
+        vec![
+            Box::new(migrations::v0_1_to_v0_2::V0_1ToV0_2),
+            Box::new(migrations::v0_2_to_v0_3::V0_2ToV0_3),
+        ]
     }
 
     #[cfg(not(feature = "update"))]
@@ -0,0 +1,569 @@
+//! Store-and-forward outbox for trusted devices.
+//!
+//! Sends to a trusted device that is not currently reachable can be queued
+//! here instead of failing. A background worker listens for device beacons
+//! and delivers queued sends once the target comes online.
+//!
+//! ## Features
+//!
+//! - Queued sends persisted to a JSON file next to the transfer history;
+//!   every change re-reads the file under a lock, so the worker and CLI
+//!   commands running at the same time don't lose each other's updates, and
+//!   the file is replaced atomically so plain loads never see a partial write
+//! - Exponential backoff between failed delivery attempts
+//! - Entries expire after a configurable time
+//! - Files are read at delivery time, not when queued
+
+use std::fs;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::crypto::DeviceIdentity;
+use crate::error::{Error, Result};
+use crate::file::{self, FileMetadata, LockFile};
+use crate::transfer::{TransferConfig, TrustedSendSession};
+use crate::trust::TrustedDevice;
+
+/// Default time a queued send is kept before it expires (7 days).
+pub const DEFAULT_EXPIRY: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Delay before the first retry after a failed delivery.
+const RETRY_BASE_SECS: u64 = 30;
+
+/// Upper bound for the delay between delivery attempts.
+const RETRY_MAX_SECS: u64 = 15 * 60;
+
+/// A send waiting for its target device to come online.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    /// Unique identifier for this queued send
+    pub id: Uuid,
+    /// ID of the target device
+    pub device_id: Uuid,
+    /// Name of the target device (at the time of queueing)
+    pub device_name: String,
+    /// Absolute paths of the files and folders to send
+    pub paths: Vec<PathBuf>,
+    /// Number of files (at the time of queueing)
+    pub file_count: usize,
+    /// Total size in bytes (at the time of queueing)
+    pub total_bytes: u64,
+    /// Unix timestamp when the send was queued
+    pub created_at: u64,
+    /// Unix timestamp after which the send is dropped
+    pub expires_at: u64,
+    /// Number of delivery attempts so far
+    pub attempts: u32,
+    /// Unix timestamp of the last delivery attempt
+    pub last_attempt_at: Option<u64>,
+    /// Unix timestamp before which no new attempt is made
+    pub next_attempt_at: u64,
+    /// Error from the last failed attempt
+    pub last_error: Option<String>,
+}
+
+impl OutboxEntry {
+    /// Create a new entry expiring after `expires_in`.
+    #[must_use]
+    pub fn new(
+        device_id: Uuid,
+        device_name: String,
+        paths: Vec<PathBuf>,
+        expires_in: Duration,
+    ) -> Self {
+        let now = now_secs();
+
+        Self {
+            id: Uuid::new_v4(),
+            device_id,
+            device_name,
+            paths,
+            file_count: 0,
+            total_bytes: 0,
+            created_at: now,
+            expires_at: now.saturating_add(expires_in.as_secs()),
+            attempts: 0,
+            last_attempt_at: None,
+            next_attempt_at: now,
+            last_error: None,
+        }
+    }
+
+    /// Set the file count and total size shown in listings.
+    #[must_use]
+    pub const fn with_totals(mut self, file_count: usize, total_bytes: u64) -> Self {
+        self.file_count = file_count;
+        self.total_bytes = total_bytes;
+        self
+    }
+
+    /// Check if the entry has expired at `now` (Unix seconds).
+    #[must_use]
+    pub const fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Check if a delivery attempt may be made at `now` (Unix seconds).
+    #[must_use]
+    pub const fn is_due(&self, now: u64) -> bool {
+        !self.is_expired(now) && now >= self.next_attempt_at
+    }
+
+    /// Short form of the ID used in listings and on the command line.
+    #[must_use]
+    pub fn short_id(&self) -> String {
+        self.id.simple().to_string()[..8].to_string()
+    }
+
+    fn record_failure(&mut self, now: u64, error: String) {
+        self.attempts = self.attempts.saturating_add(1);
+        self.last_attempt_at = Some(now);
+        self.last_error = Some(error);
+
+        let shift = self.attempts.saturating_sub(1).min(16);
+        let delay = (RETRY_BASE_SECS << shift).min(RETRY_MAX_SECS);
+        self.next_attempt_at = now.saturating_add(delay);
+    }
+}
+
+/// Serializable wrapper for the outbox database.
+#[derive(Debug, Serialize, Deserialize)]
+struct OutboxDatabase {
+    /// Version of the outbox database format
+    version: u32,
+    /// Queued sends (oldest first)
+    entries: Vec<OutboxEntry>,
+}
+
+/// Persistent queue of sends to trusted devices.
+#[derive(Debug)]
+pub struct Outbox {
+    /// Path to the outbox database file
+    path: PathBuf,
+    /// Queued sends (oldest first)
+    entries: Vec<OutboxEntry>,
+}
+
+impl Outbox {
+    /// Load the outbox from the default location.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the outbox cannot be loaded.
+    pub fn load() -> Result<Self> {
+        let path = Self::default_path().unwrap_or_else(|| PathBuf::from("outbox.json"));
+        Self::load_from(path)
+    }
+
+    /// Load from a specific path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the outbox cannot be loaded.
+    pub fn load_from(path: PathBuf) -> Result<Self> {
+        let entries = Self::read_entries(&path)?;
+        Ok(Self { path, entries })
+    }
+
+    fn read_entries(path: &Path) -> Result<Vec<OutboxEntry>> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file = fs::File::open(path).map_err(|e| {
+            Error::ConfigError(format!(
+                "Failed to open outbox at {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let reader = BufReader::new(file);
+        let db: OutboxDatabase = serde_json::from_reader(reader).map_err(|e| {
+            Error::ConfigError(format!(
+                "Failed to parse outbox at {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        Ok(db.entries)
+    }
+
+    /// Get the default outbox path.
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("com", "yoop", "Yoop")
+            .map(|dirs| dirs.data_dir().join("outbox.json"))
+    }
+
+    /// Get the path this outbox is stored at.
+    #[must_use]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Save the outbox.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the outbox cannot be saved.
+    pub fn save(&self) -> Result<()> {
+        let _lock = self.lock()?;
+        self.write()
+    }
+
+    /// Apply `change` to the entries currently on disk and save them.
+    ///
+    /// Holds the outbox lock from reading to writing so concurrent updates
+    /// from other processes are kept. `change` returns its result and
+    /// whether it modified the entries.
+    fn update<T>(&mut self, change: impl FnOnce(&mut Vec<OutboxEntry>) -> (T, bool)) -> Result<T> {
+        let _lock = self.lock()?;
+        self.entries = Self::read_entries(&self.path)?;
+        let (result, changed) = change(&mut self.entries);
+        if changed {
+            self.write()?;
+        }
+        Ok(result)
+    }
+
+    fn lock(&self) -> Result<LockFile> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                Error::ConfigError(format!(
+                    "Failed to create outbox directory {}: {}",
+                    parent.display(),
+                    e
+                ))
+            })?;
+        }
+        LockFile::acquire(&self.path)
+    }
+
+    fn write(&self) -> Result<()> {
+        let db = OutboxDatabase {
+            version: 1,
+            entries: self.entries.clone(),
+        };
+
+        let json = serde_json::to_vec_pretty(&db).map_err(|e| {
+            Error::ConfigError(format!(
+                "Failed to serialize outbox at {}: {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+
+        // Replaced atomically so readers that don't take the lock never see a
+        // partly written file.
+        file::write_private(&self.path, &json).map_err(|e| {
+            Error::ConfigError(format!(
+                "Failed to write outbox at {}: {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Queue a send.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the outbox cannot be saved.
+    pub fn enqueue(&mut self, entry: OutboxEntry) -> Result<()> {
+        self.update(|entries| {
+            entries.push(entry);
+            ((), true)
+        })
+    }
+
+    /// List queued sends (oldest first).
+    #[must_use]
+    pub fn list(&self) -> &[OutboxEntry] {
+        &self.entries
+    }
+
+    /// Find a queued send by ID or unambiguous ID prefix.
+    #[must_use]
+    pub fn find(&self, id_prefix: &str) -> Option<&OutboxEntry> {
+        let prefix = id_prefix.to_lowercase().replace('-', "");
+        if prefix.is_empty() {
+            return None;
+        }
+
+        let mut matches = self
+            .entries
+            .iter()
+            .filter(|e| e.id.simple().to_string().starts_with(&prefix));
+
+        match (matches.next(), matches.next()) {
+            (Some(entry), None) => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// Queued sends for a device that may be attempted at `now`.
+    #[must_use]
+    pub fn due_for(&self, device_id: &Uuid, now: u64) -> Vec<OutboxEntry> {
+        self.entries
+            .iter()
+            .filter(|e| &e.device_id == device_id && e.is_due(now))
+            .cloned()
+            .collect()
+    }
+
+    /// Remove a queued send (after delivery or cancellation).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the outbox cannot be saved.
+    pub fn remove(&mut self, id: &Uuid) -> Result<Option<OutboxEntry>> {
+        self.update(|entries| {
+            let removed = entries
+                .iter()
+                .position(|e| &e.id == id)
+                .map(|pos| entries.remove(pos));
+            let changed = removed.is_some();
+            (removed, changed)
+        })
+    }
+
+    /// Record a failed delivery attempt and schedule the next one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the outbox cannot be saved.
+    pub fn record_failure(&mut self, id: &Uuid, error: String) -> Result<()> {
+        self.update(|entries| {
+            let entry = entries.iter_mut().find(|e| &e.id == id);
+            let changed = entry.is_some();
+            if let Some(entry) = entry {
+                entry.record_failure(now_secs(), error);
+            }
+            ((), changed)
+        })
+    }
+
+    /// Make a queued send due immediately, clearing its backoff.
+    ///
+    /// Returns `false` if no entry with this ID exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the outbox cannot be saved.
+    pub fn retry(&mut self, id: &Uuid) -> Result<bool> {
+        self.update(|entries| {
+            let entry = entries.iter_mut().find(|e| &e.id == id);
+            let found = entry.is_some();
+            if let Some(entry) = entry {
+                entry.next_attempt_at = now_secs();
+            }
+            (found, found)
+        })
+    }
+
+    /// Remove and return all entries that expired at `now`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the outbox cannot be saved.
+    pub fn remove_expired(&mut self, now: u64) -> Result<Vec<OutboxEntry>> {
+        self.update(|entries| {
+            let (expired, kept): (Vec<_>, Vec<_>) = std::mem::take(entries)
+                .into_iter()
+                .partition(|e| e.is_expired(now));
+            *entries = kept;
+            let changed = !expired.is_empty();
+            (expired, changed)
+        })
+    }
+
+    /// Get the number of queued sends.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the outbox is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Deliver a queued send to a device seen at `addr`.
+///
+/// Returns the files that were sent.
+///
+/// # Errors
+///
+/// Returns an error if the files can no longer be read or the transfer fails.
+pub async fn deliver(
+    entry: &OutboxEntry,
+    device: TrustedDevice,
+    addr: SocketAddr,
+    identity: DeviceIdentity,
+    config: TransferConfig,
+) -> Result<Vec<FileMetadata>> {
+    let mut session = TrustedSendSession::new(device, identity, &entry.paths, config).await?;
+    session.set_direct_address(addr);
+    session.send().await?;
+    Ok(session.files().to_vec())
+}
+
+/// Current time as Unix seconds.
+#[must_use]
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(device_id: Uuid) -> OutboxEntry {
+        OutboxEntry::new(
+            device_id,
+            "Laptop".to_string(),
+            vec![PathBuf::from("/tmp/file.txt")],
+            DEFAULT_EXPIRY,
+        )
+        .with_totals(1, 1024)
+    }
+
+    #[test]
+    fn test_enqueue_and_reload() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("outbox.json");
+
+        let mut outbox = Outbox::load_from(path.clone()).unwrap();
+        assert!(outbox.is_empty());
+
+        let queued = entry(Uuid::new_v4());
+        let id = queued.id;
+        outbox.enqueue(queued).unwrap();
+
+        let reloaded = Outbox::load_from(path).unwrap();
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded.list()[0].id, id);
+        assert_eq!(reloaded.list()[0].total_bytes, 1024);
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("outbox.json");
+
+        let mut worker = Outbox::load_from(path.clone()).unwrap();
+        let mut cli = Outbox::load_from(path.clone()).unwrap();
+
+        let stale = entry(Uuid::new_v4());
+        let stale_id = stale.id;
+        worker.enqueue(stale).unwrap();
+        cli.enqueue(entry(Uuid::new_v4())).unwrap();
+        worker
+            .record_failure(&stale_id, "offline".to_string())
+            .unwrap();
+
+        let reloaded = Outbox::load_from(path).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.list()[0].attempts, 1);
+    }
+
+    #[test]
+    fn test_find_by_prefix() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut outbox = Outbox::load_from(temp_dir.path().join("outbox.json")).unwrap();
+
+        let queued = entry(Uuid::new_v4());
+        let id = queued.id;
+        let short = queued.short_id();
+        outbox.enqueue(queued).unwrap();
+
+        assert_eq!(outbox.find(&short).unwrap().id, id);
+        assert_eq!(outbox.find(&id.to_string()).unwrap().id, id);
+        assert!(outbox.find("").is_none());
+        assert!(outbox.find("zzzz").is_none());
+    }
+
+    #[test]
+    fn test_backoff_and_retry() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut outbox = Outbox::load_from(temp_dir.path().join("outbox.json")).unwrap();
+
+        let device_id = Uuid::new_v4();
+        let queued = entry(device_id);
+        let id = queued.id;
+        outbox.enqueue(queued).unwrap();
+
+        let now = now_secs();
+        assert_eq!(outbox.due_for(&device_id, now).len(), 1);
+        assert!(outbox.due_for(&Uuid::new_v4(), now).is_empty());
+
+        outbox
+            .record_failure(&id, "connection refused".to_string())
+            .unwrap();
+        let failed = &outbox.list()[0];
+        assert_eq!(failed.attempts, 1);
+        assert!(failed.next_attempt_at >= now + RETRY_BASE_SECS);
+        assert!(outbox.due_for(&device_id, now).is_empty());
+
+        assert!(outbox.retry(&id).unwrap());
+        assert_eq!(outbox.due_for(&device_id, now_secs()).len(), 1);
+        assert!(!outbox.retry(&Uuid::new_v4()).unwrap());
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let mut queued = entry(Uuid::new_v4());
+        for _ in 0..40 {
+            queued.record_failure(1000, "timeout".to_string());
+        }
+        assert_eq!(queued.next_attempt_at, 1000 + RETRY_MAX_SECS);
+    }
+
+    #[test]
+    fn test_remove_expired() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut outbox = Outbox::load_from(temp_dir.path().join("outbox.json")).unwrap();
+
+        let device_id = Uuid::new_v4();
+        let mut stale = entry(device_id);
+        stale.expires_at = now_secs() - 1;
+        let stale_id = stale.id;
+        outbox.enqueue(stale).unwrap();
+        outbox.enqueue(entry(device_id)).unwrap();
+
+        let now = now_secs();
+        assert_eq!(outbox.due_for(&device_id, now).len(), 1);
+
+        let expired = outbox.remove_expired(now).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, stale_id);
+        assert_eq!(outbox.len(), 1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut outbox = Outbox::load_from(temp_dir.path().join("outbox.json")).unwrap();
+
+        let queued = entry(Uuid::new_v4());
+        let id = queued.id;
+        outbox.enqueue(queued).unwrap();
+
+        assert!(outbox.remove(&id).unwrap().is_some());
+        assert!(outbox.remove(&id).unwrap().is_none());
+        assert!(outbox.is_empty());
+    }
+}
@@ -113,6 +113,20 @@ pub enum MessageType {
     SyncComplete = 0x78,
     /// Sync: Status update
     SyncStatus = 0x79,
+    /// Relay: Register a share with a relay server
+    RelayRegister = 0x80,
+    /// Relay: Ask a relay server to connect to a registered share
+    RelayConnect = 0x81,
+    /// Relay: Both peers are connected and the stream is now spliced
+    RelayPaired = 0x82,
+    /// Pairing: Joining device's key and nonce commitment
+    PairRequest = 0x90,
+    /// Pairing: Hosting device's key and nonce
+    PairResponse = 0x91,
+    /// Pairing: Joining device reveals its committed nonce
+    PairReveal = 0x92,
+    /// Pairing: User's verdict on the short authentication string
+    PairConfirm = 0x93,
     /// Error message
     Error = 0xFF,
 }
@@ -157,6 +171,13 @@ impl MessageType {
             0x77 => Some(Self::SyncChunkAck),
             0x78 => Some(Self::SyncComplete),
             0x79 => Some(Self::SyncStatus),
+            0x80 => Some(Self::RelayRegister),
+            0x81 => Some(Self::RelayConnect),
+            0x82 => Some(Self::RelayPaired),
+            0x90 => Some(Self::PairRequest),
+            0x91 => Some(Self::PairResponse),
+            0x92 => Some(Self::PairReveal),
+            0x93 => Some(Self::PairConfirm),
             0xFF => Some(Self::Error),
             _ => None,
         }
@@ -237,20 +258,36 @@ pub struct HelloPayload {
 }
 
 /// Code verification payload.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Sent twice by the connecting side: first with its key exchange message,
+/// then with its key confirmation; see [`crate::code::prove_code`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CodeVerifyPayload {
-    /// HMAC of the code
-    pub code_hmac: Vec<u8>,
+    /// SPAKE2 message derived from the code
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pake_message: Vec<u8>,
+    /// Key confirmation bound to the TLS session
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub confirmation: Vec<u8>,
 }
 
 /// Code verification acknowledgment payload.
+///
+/// The first acknowledgment carries the accepting side's key exchange
+/// message and confirmation; the second reports the result.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeVerifyAckPayload {
-    /// Whether verification succeeded
+    /// Whether verification succeeded (or may continue)
     pub success: bool,
     /// Error message if failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// SPAKE2 message derived from the code
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pake_message: Vec<u8>,
+    /// Key confirmation bound to the TLS session
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub confirmation: Vec<u8>,
 }
 
 /// File list payload.
@@ -260,6 +297,9 @@ pub struct FileListPayload {
     pub files: Vec<crate::file::FileMetadata>,
     /// Total size
     pub total_size: u64,
+    /// Transfer ID, used by the receiver to resume after a reconnect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer_id: Option<uuid::Uuid>,
 }
 
 /// File list acknowledgment payload.
@@ -281,6 +321,10 @@ pub struct ChunkStartPayload {
     pub chunk_index: u64,
     /// Total chunks for this file
     pub total_chunks: u64,
+    /// SHA-256 (hex) of the whole file, checked before the receiver
+    /// releases it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
 }
 
 /// Chunk data payload (binary).
@@ -418,6 +462,9 @@ pub struct TrustedHelloPayload {
     pub nonce: String,
     /// Ed25519 signature of the nonce using sender's private key (base64-encoded)
     pub nonce_signature: String,
+    /// Signed key rotations, so peers holding an older key can update it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_rotations: Vec<crate::crypto::KeyRotation>,
 }
 
 /// Trusted device hello acknowledgment payload.
@@ -445,6 +492,9 @@ pub struct TrustedHelloAckPayload {
     /// Trust level of the sender in receiver's store
     #[serde(skip_serializing_if = "Option::is_none")]
     pub trust_level: Option<String>,
+    /// Signed key rotations, so peers holding an older key can update it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_rotations: Vec<crate::crypto::KeyRotation>,
 }
 
 /// Trusted device verification challenge payload.
@@ -647,6 +697,65 @@ pub struct SyncStatusPayload {
     pub message: Option<String>,
 }
 
+/// Relay: Register or connect request payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayRequestPayload {
+    /// Base64 rendezvous tag derived from the share code
+    pub tag: String,
+}
+
+/// Pairing: Request payload sent by the joining device.
+///
+/// The nonce is only committed to here, so the host has to pick its own
+/// nonce before it can know the joiner's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairRequestPayload {
+    /// Device name
+    pub device_name: String,
+    /// Protocol version string
+    pub protocol_version: String,
+    /// Unique device identifier
+    pub device_id: uuid::Uuid,
+    /// Base64-encoded Ed25519 public key
+    pub public_key: String,
+    /// SHA-256 of the joiner's nonce (base64-encoded)
+    pub commitment: String,
+}
+
+/// Pairing: Response payload sent by the hosting device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairResponsePayload {
+    /// Device name
+    pub device_name: String,
+    /// Unique device identifier
+    pub device_id: uuid::Uuid,
+    /// Base64-encoded Ed25519 public key
+    pub public_key: String,
+    /// Random nonce (32 bytes, base64-encoded)
+    pub nonce: String,
+}
+
+/// Pairing: Reveal payload opening the joiner's commitment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairRevealPayload {
+    /// Random nonce (32 bytes, base64-encoded)
+    pub nonce: String,
+}
+
+/// Pairing: Confirmation payload sent by both devices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairConfirmPayload {
+    /// Whether the user confirmed that the codes match
+    pub accepted: bool,
+    /// Ed25519 signature of the pairing transcript (base64-encoded, if accepted)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// The sender's resolving key for private beacons (base64-encoded, if
+    /// accepted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolving_key: Option<String>,
+}
+
 /// Encode a message payload to JSON bytes.
 ///
 /// # Errors
@@ -1055,6 +1164,7 @@ mod tests {
             public_key: "base64_public_key".to_string(),
             nonce: "base64_nonce".to_string(),
             nonce_signature: "base64_signature".to_string(),
+            key_rotations: Vec::new(),
         };
 
         let encoded = encode_payload(&payload).expect("encode");
@@ -1065,6 +1175,10 @@ mod tests {
         assert_eq!(decoded.public_key, payload.public_key);
         assert_eq!(decoded.nonce, payload.nonce);
         assert_eq!(decoded.nonce_signature, payload.nonce_signature);
+        assert!(decoded.key_rotations.is_empty());
+        assert!(!String::from_utf8(encoded)
+            .expect("utf8")
+            .contains("key_rotations"));
     }
 
     #[test]
@@ -1078,6 +1192,7 @@ mod tests {
             nonce_signature: Some("nonce_sig".to_string()),
             error: None,
             trust_level: Some("Full".to_string()),
+            key_rotations: Vec::new(),
         };
 
         let encoded = encode_payload(&payload).expect("encode");
@@ -1099,6 +1214,7 @@ mod tests {
             nonce_signature: None,
             error: Some("Device not in trust store".to_string()),
             trust_level: None,
+            key_rotations: Vec::new(),
         };
 
         let encoded = encode_payload(&payload).expect("encode");
@@ -1187,6 +1303,21 @@ mod tests {
         assert_eq!(MessageType::from_byte(0x79), Some(MessageType::SyncStatus));
     }
 
+    #[test]
+    fn test_relay_message_types() {
+        assert_eq!(
+            MessageType::from_byte(0x80),
+            Some(MessageType::RelayRegister)
+        );
+        assert_eq!(
+            MessageType::from_byte(0x81),
+            Some(MessageType::RelayConnect)
+        );
+        assert_eq!(MessageType::from_byte(0x82), Some(MessageType::RelayPaired));
+        assert_eq!(MessageType::from_byte(0x90), Some(MessageType::PairRequest));
+        assert_eq!(MessageType::from_byte(0x93), Some(MessageType::PairConfirm));
+    }
+
     #[test]
     fn test_sync_init_payload_serialization() {
         let payload = SyncInitPayload {
@@ -32,6 +32,9 @@ pub struct QrConfig {
     pub scheme: String,
     /// Error correction level (default: Medium)
     pub error_correction: EcLevel,
+    /// SHA-256 fingerprint of the serving certificate, added to the link so
+    /// the scanning device can verify it (default: none)
+    pub fingerprint: Option<String>,
 }
 
 impl Default for QrConfig {
@@ -39,6 +42,7 @@ impl Default for QrConfig {
         Self {
             scheme: "yoop".to_string(),
             error_correction: EcLevel::M,
+            fingerprint: None,
         }
     }
 }
@@ -52,7 +56,8 @@ impl Default for QrConfig {
 ///
 /// # Returns
 ///
-/// A deep link URL (e.g., "yoop://A7K9")
+/// A deep link URL (e.g., "yoop://A7K9"), with the certificate fingerprint
+/// as an `fp` parameter when one is configured (colons removed).
 ///
 /// # Example
 ///
@@ -61,10 +66,20 @@ impl Default for QrConfig {
 ///
 /// let link = create_deep_link("A7K9", &QrConfig::default());
 /// assert_eq!(link, "yoop://A7K9");
+///
+/// let config = QrConfig {
+///     fingerprint: Some("AB:CD:EF".to_string()),
+///     ..QrConfig::default()
+/// };
+/// assert_eq!(create_deep_link("A7K9", &config), "yoop://A7K9?fp=ABCDEF");
 /// ```
 #[must_use]
 pub fn create_deep_link(code: &str, config: &QrConfig) -> String {
-    format!("{}://{}", config.scheme, code.to_uppercase())
+    let link = format!("{}://{}", config.scheme, code.to_uppercase());
+    match &config.fingerprint {
+        Some(fingerprint) => format!("{link}?fp={}", fingerprint.replace(':', "")),
+        None => link,
+    }
 }
 
 /// Generate ASCII art QR code for terminal display.
@@ -88,9 +103,28 @@ pub fn create_deep_link(code: &str, config: &QrConfig) -> String {
 /// println!("{}", qr);
 /// ```
 pub fn generate_ascii(code: &str) -> Result<String> {
-    let deep_link = create_deep_link(code, &QrConfig::default());
+    generate_ascii_text(&create_deep_link(code, &QrConfig::default()))
+}
 
-    let qr_code = QrCode::with_error_correction_level(&deep_link, EcLevel::M)
+/// Generate ASCII art QR code for arbitrary text.
+///
+/// Used for data other than share codes, such as key fingerprints shown
+/// while pairing devices.
+///
+/// # Errors
+///
+/// Returns an error if QR code generation fails.
+///
+/// # Example
+///
+/// ```
+/// use yoop_core::qr::generate_ascii_text;
+///
+/// let qr = generate_ascii_text("yoop-fingerprint:3F2A91BC").unwrap();
+/// assert!(!qr.is_empty());
+/// ```
+pub fn generate_ascii_text(text: &str) -> Result<String> {
+    let qr_code = QrCode::with_error_correction_level(text, EcLevel::M)
         .map_err(|e| Error::Internal(format!("Failed to generate QR code: {e}")))?;
 
     let rendered = qr_code
@@ -124,9 +158,17 @@ pub fn generate_ascii(code: &str) -> Result<String> {
 /// assert!(svg.contains("</svg>"));
 /// ```
 pub fn generate_svg(code: &str) -> Result<String> {
-    let deep_link = create_deep_link(code, &QrConfig::default());
+    generate_svg_text(&create_deep_link(code, &QrConfig::default()))
+}
 
-    let qr_code = QrCode::with_error_correction_level(&deep_link, EcLevel::M)
+/// Generate SVG QR code for arbitrary text, such as a deep link built with
+/// a custom [`QrConfig`].
+///
+/// # Errors
+///
+/// Returns an error if QR code generation fails.
+pub fn generate_svg_text(text: &str) -> Result<String> {
+    let qr_code = QrCode::with_error_correction_level(text, EcLevel::M)
         .map_err(|e| Error::Internal(format!("Failed to generate QR code: {e}")))?;
 
     let svg_string = qr_code
@@ -210,6 +252,16 @@ mod tests {
         assert_eq!(link, "localdrop://A7K9");
     }
 
+    #[test]
+    fn test_create_deep_link_with_fingerprint() {
+        let config = QrConfig {
+            fingerprint: Some("3F:2A:91:BC".to_string()),
+            ..Default::default()
+        };
+        let link = create_deep_link("a7k9", &config);
+        assert_eq!(link, "yoop://A7K9?fp=3F2A91BC");
+    }
+
     #[test]
     fn test_generate_ascii_not_empty() {
         let qr = generate_ascii("A7K9").unwrap();
@@ -273,10 +325,12 @@ mod tests {
         let config_low = QrConfig {
             scheme: "yoop".to_string(),
             error_correction: EcLevel::L,
+            fingerprint: None,
         };
         let config_high = QrConfig {
             scheme: "yoop".to_string(),
             error_correction: EcLevel::H,
+            fingerprint: None,
         };
 
         assert_eq!(config_low.error_correction, EcLevel::L);
@@ -0,0 +1,427 @@
+//! Self-hostable rendezvous and relay server.
+//!
+//! Discovery only works within a broadcast domain, so shares cannot be found
+//! across office VLANs or between two home networks. A relay server, reachable
+//! from both sides, bridges that gap:
+//!
+//! 1. The sharer connects to the relay and sends `RelayRegister` with the
+//!    share code's [rendezvous tag](rendezvous_tag), then waits.
+//! 2. A receiver that cannot find the share directly connects to the relay and
+//!    sends `RelayConnect` with the same tag.
+//! 3. The relay answers both with `RelayPaired` and from then on copies bytes
+//!    between the two connections.
+//!
+//! The peers then run the normal transfer protocol over the spliced
+//! connection: TLS, handshake and code verification happen end to end, so the
+//! relay only ever sees the tag and TLS ciphertext.
+//!
+//! The tag is 128 bits of a domain-separated hash of the code, so concurrent
+//! shares on one relay do not collide and a receiver is never paired with
+//! someone else's share. Share codes are short, though, so the relay can
+//! recover a code from its tag by hashing every possible code, and with the
+//! code it could intercept the transfer: only use a relay you run or trust.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use yoop_core::relay::{RelayServer, DEFAULT_RELAY_PORT};
+//!
+//! let server = RelayServer::new(DEFAULT_RELAY_PORT, true).await?;
+//! println!("Relay listening on {}", server.local_addr()?);
+//! server.run().await?;
+//! ```
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use base64::prelude::*;
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+use crate::code::ShareCode;
+use crate::connection;
+use crate::crypto;
+use crate::error::{Error, Result};
+use crate::protocol::{self, ErrorPayload, MessageType, RelayRequestPayload};
+
+/// Default TCP port for [`RelayServer`].
+pub const DEFAULT_RELAY_PORT: u16 = crate::DEFAULT_TRANSFER_PORT_END + 2;
+
+/// Timeout for the initial request and the relay's reply.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Error code sent when no share is registered under the requested tag.
+const ERROR_NOT_FOUND: &str = "not_found";
+
+/// Error code sent when a share is already registered under the tag.
+const ERROR_IN_USE: &str = "in_use";
+
+/// Error code sent when a request carries a malformed tag.
+const ERROR_BAD_TAG: &str = "bad_tag";
+
+/// Length of a rendezvous tag in bytes.
+pub const TAG_LEN: usize = 16;
+
+/// Domain separator for rendezvous tags.
+const TAG_CONTEXT: &[u8] = b"yoop:relay-tag:v1:";
+
+/// Shares waiting for a receiver, keyed by rendezvous tag.
+type Waiting = Arc<Mutex<HashMap<[u8; TAG_LEN], oneshot::Sender<TcpStream>>>>;
+
+/// Derive the tag a relay pairs peers by from a share code.
+#[must_use]
+pub fn rendezvous_tag(code: &ShareCode) -> [u8; TAG_LEN] {
+    let mut data = Vec::with_capacity(TAG_CONTEXT.len() + code.as_str().len());
+    data.extend_from_slice(TAG_CONTEXT);
+    data.extend_from_slice(code.as_str().as_bytes());
+    let mut tag = [0u8; TAG_LEN];
+    tag.copy_from_slice(&crypto::sha256(&data)[..TAG_LEN]);
+    tag
+}
+
+/// A relay server pairing sharers and receivers by rendezvous tag.
+#[derive(Debug)]
+pub struct RelayServer {
+    /// TCP listener
+    listener: TcpListener,
+    /// Registered shares waiting for a receiver
+    waiting: Waiting,
+}
+
+impl RelayServer {
+    /// Create a relay server listening on the given port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the port cannot be bound.
+    pub async fn new(port: u16, ipv6: bool) -> Result<Self> {
+        let listener = connection::bind_listener(port, ipv6)?;
+
+        Ok(Self {
+            listener,
+            waiting: Arc::default(),
+        })
+    }
+
+    /// Get the local address the server is bound to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be determined.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accept and relay connections until an accept error occurs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if accepting a connection fails.
+    pub async fn run(&self) -> Result<()> {
+        loop {
+            let (stream, peer_addr) = self.listener.accept().await?;
+            let peer_addr = connection::canonical_addr(peer_addr);
+            let waiting = Arc::clone(&self.waiting);
+
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, peer_addr, waiting).await {
+                    tracing::debug!("Relay connection from {} ended: {}", peer_addr, e);
+                }
+            });
+        }
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    peer_addr: SocketAddr,
+    waiting: Waiting,
+) -> Result<()> {
+    let _ = stream.set_nodelay(true);
+
+    let (header, payload) = protocol::read_frame_with_timeout(&mut stream, REQUEST_TIMEOUT).await?;
+    let request: RelayRequestPayload = protocol::decode_payload(&payload)?;
+    let Some(tag) = BASE64_STANDARD
+        .decode(&request.tag)
+        .ok()
+        .and_then(|tag| <[u8; TAG_LEN]>::try_from(tag).ok())
+    else {
+        return send_error(&mut stream, ERROR_BAD_TAG, "malformed rendezvous tag").await;
+    };
+
+    match header.message_type {
+        MessageType::RelayRegister => {
+            let rx = {
+                let mut waiting = waiting.lock().unwrap_or_else(PoisonError::into_inner);
+                waiting.retain(|_, tx| !tx.is_closed());
+                match waiting.entry(tag) {
+                    Entry::Occupied(_) => None,
+                    Entry::Vacant(entry) => {
+                        let (tx, rx) = oneshot::channel();
+                        entry.insert(tx);
+                        Some(rx)
+                    }
+                }
+            };
+            let Some(rx) = rx else {
+                return send_error(&mut stream, ERROR_IN_USE, "share already registered").await;
+            };
+            tracing::info!("Share registered from {}", peer_addr);
+
+            // Watch the idle connection so a sharer that goes away frees its
+            // registration instead of being paired with a receiver later.
+            let mut probe = [0u8; 1];
+            let receiver = tokio::select! {
+                receiver = rx => receiver.ok(),
+                _ = stream.read(&mut probe) => None,
+            };
+            let Some(mut receiver) = receiver else {
+                tracing::info!("Share from {} unregistered", peer_addr);
+                return Ok(());
+            };
+
+            protocol::write_frame(&mut stream, MessageType::RelayPaired, &[]).await?;
+            protocol::write_frame(&mut receiver, MessageType::RelayPaired, &[]).await?;
+            tracing::info!("Relaying share from {}", peer_addr);
+
+            let (up, down) = tokio::io::copy_bidirectional(&mut stream, &mut receiver).await?;
+            tracing::info!(
+                "Relay for share from {} closed ({} bytes up, {} bytes down)",
+                peer_addr,
+                up,
+                down
+            );
+            Ok(())
+        }
+        MessageType::RelayConnect => {
+            let tx = waiting
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(&tag);
+            let mut stream = match tx {
+                Some(tx) => match tx.send(stream) {
+                    Ok(()) => {
+                        tracing::info!("Receiver {} paired with a share", peer_addr);
+                        return Ok(());
+                    }
+                    Err(stream) => stream,
+                },
+                None => stream,
+            };
+            send_error(&mut stream, ERROR_NOT_FOUND, "no share registered").await
+        }
+        other => {
+            send_error(&mut stream, "unexpected", "expected a relay request").await?;
+            Err(Error::UnexpectedMessage {
+                expected: "RelayRegister or RelayConnect".to_string(),
+                actual: format!("{other:?}"),
+            })
+        }
+    }
+}
+
+async fn send_error(stream: &mut TcpStream, code: &str, message: &str) -> Result<()> {
+    let error = ErrorPayload {
+        code: code.to_string(),
+        message: message.to_string(),
+    };
+    let payload = protocol::encode_payload(&error)?;
+    protocol::write_frame_with_timeout(stream, MessageType::Error, &payload, REQUEST_TIMEOUT).await
+}
+
+/// Connect to a relay, appending [`DEFAULT_RELAY_PORT`] if `relay` has no port.
+async fn connect_relay(relay: &str) -> Result<TcpStream> {
+    let relay = relay.trim();
+    let has_port = match relay.rsplit_once(':') {
+        Some((host, port)) => {
+            port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']'))
+        }
+        None => false,
+    };
+
+    let stream = if has_port {
+        TcpStream::connect(relay).await?
+    } else {
+        let host = relay.trim_start_matches('[').trim_end_matches(']');
+        TcpStream::connect((host, DEFAULT_RELAY_PORT)).await?
+    };
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+async fn send_request(
+    stream: &mut TcpStream,
+    message_type: MessageType,
+    code: &ShareCode,
+) -> Result<()> {
+    let request = RelayRequestPayload {
+        tag: BASE64_STANDARD.encode(rendezvous_tag(code)),
+    };
+    let payload = protocol::encode_payload(&request)?;
+    protocol::write_frame_with_timeout(stream, message_type, &payload, REQUEST_TIMEOUT).await
+}
+
+fn check_paired(header: &protocol::FrameHeader, payload: &[u8], code: &ShareCode) -> Result<()> {
+    match header.message_type {
+        MessageType::RelayPaired => Ok(()),
+        MessageType::Error => {
+            let error: ErrorPayload = protocol::decode_payload(payload)?;
+            if error.code == ERROR_NOT_FOUND {
+                Err(Error::CodeNotFound(code.to_string()))
+            } else {
+                Err(Error::ProtocolError(format!("relay: {}", error.message)))
+            }
+        }
+        other => Err(Error::UnexpectedMessage {
+            expected: "RelayPaired".to_string(),
+            actual: format!("{other:?}"),
+        }),
+    }
+}
+
+/// Register a share with a relay and wait for a receiver.
+///
+/// Resolves once a receiver has connected through the relay, returning the
+/// spliced stream. Dropping the future unregisters the share.
+///
+/// # Errors
+///
+/// Returns an error if the relay is unreachable or refuses the registration.
+pub async fn register(relay: &str, code: &ShareCode) -> Result<TcpStream> {
+    let mut stream = connect_relay(relay).await?;
+    send_request(&mut stream, MessageType::RelayRegister, code).await?;
+
+    let (header, payload) = protocol::read_frame(&mut stream).await?;
+    check_paired(&header, &payload, code)?;
+    Ok(stream)
+}
+
+/// Connect to a share registered with a relay.
+///
+/// # Errors
+///
+/// Returns `CodeNotFound` if no share is registered under `code`, or another
+/// error if the relay is unreachable.
+pub async fn connect(relay: &str, code: &ShareCode) -> Result<TcpStream> {
+    let mut stream = connect_relay(relay).await?;
+    send_request(&mut stream, MessageType::RelayConnect, code).await?;
+
+    let (header, payload) = protocol::read_frame_with_timeout(&mut stream, REQUEST_TIMEOUT).await?;
+    check_paired(&header, &payload, code)?;
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    async fn start_server() -> String {
+        let server = RelayServer::new(0, false).await.expect("bind relay");
+        let port = server.local_addr().expect("local addr").port();
+        tokio::spawn(async move { server.run().await });
+        format!("127.0.0.1:{port}")
+    }
+
+    fn code(s: &str) -> ShareCode {
+        ShareCode::parse(s).expect("valid code")
+    }
+
+    #[test]
+    fn test_rendezvous_tag() {
+        assert_eq!(rendezvous_tag(&code("A7K9")), rendezvous_tag(&code("A7K9")));
+
+        let tags: std::collections::HashSet<[u8; TAG_LEN]> =
+            ["A7K9", "B2C3", "XYZ2", "QRST", "2345"]
+                .iter()
+                .map(|c| rendezvous_tag(&code(c)))
+                .collect();
+        assert_eq!(tags.len(), 5, "Tags should differ between codes");
+    }
+
+    #[tokio::test]
+    async fn test_relay_pairs_and_splices() {
+        let relay = start_server().await;
+        let share_code = code("A7K9");
+
+        let sharer = {
+            let relay = relay.clone();
+            let share_code = share_code.clone();
+            tokio::spawn(async move { register(&relay, &share_code).await })
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut receiver = connect(&relay, &share_code).await.expect("connect");
+        let mut sharer = sharer.await.expect("join").expect("register");
+
+        receiver.write_all(b"ping").await.expect("write");
+        let mut buf = [0u8; 4];
+        sharer.read_exact(&mut buf).await.expect("read");
+        assert_eq!(&buf, b"ping");
+
+        sharer.write_all(b"pong").await.expect("write");
+        receiver.read_exact(&mut buf).await.expect("read");
+        assert_eq!(&buf, b"pong");
+    }
+
+    #[tokio::test]
+    async fn test_relay_pairs_concurrent_shares() {
+        let relay = start_server().await;
+        let codes = [code("A7K9"), code("B2C3")];
+
+        let sharers: Vec<_> = codes
+            .iter()
+            .map(|share_code| {
+                let relay = relay.clone();
+                let share_code = share_code.clone();
+                tokio::spawn(async move { register(&relay, &share_code).await })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut receivers = Vec::new();
+        for share_code in codes.iter().rev() {
+            receivers.push(connect(&relay, share_code).await.expect("connect"));
+        }
+        receivers.reverse();
+
+        for (index, (sharer, mut receiver)) in sharers.into_iter().zip(receivers).enumerate() {
+            let mut sharer = sharer.await.expect("join").expect("register");
+            let sent = [u8::try_from(index).unwrap(); 4];
+            sharer.write_all(&sent).await.expect("write");
+            let mut buf = [0u8; 4];
+            receiver.read_exact(&mut buf).await.expect("read");
+            assert_eq!(buf, sent);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relay_unknown_code() {
+        let relay = start_server().await;
+
+        let result = connect(&relay, &code("A7K9")).await;
+        assert!(matches!(result, Err(Error::CodeNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_relay_drops_abandoned_registration() {
+        let relay = start_server().await;
+        let share_code = code("A7K9");
+
+        let sharer = {
+            let relay = relay.clone();
+            let share_code = share_code.clone();
+            tokio::spawn(async move { register(&relay, &share_code).await })
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        sharer.abort();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let result = connect(&relay, &share_code).await;
+        assert!(matches!(result, Err(Error::CodeNotFound(_))));
+    }
+}
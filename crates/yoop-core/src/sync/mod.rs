@@ -75,6 +75,7 @@ impl Default for SyncConfig {
                 "Thumbs.db".into(),
                 "*.swp".into(),
                 "*.tmp".into(),
+                format!("*{}", crate::file::STAGING_SUFFIX),
             ],
             follow_symlinks: false,
             sync_deletions: true,
@@ -6,6 +6,7 @@
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
 
@@ -23,10 +24,13 @@ use super::watcher::{FileEvent, FileEventKind, FileWatcher};
 use super::{FileKind, RelativePath, SyncConfig, SyncOp, SyncStats};
 use base64::prelude::*;
 
+use crate::audit::{self, AuditEvent, AuditEventKind};
 use crate::code::{CodeGenerator, ShareCode};
+use crate::connection;
 use crate::crypto::{self, DeviceIdentity, TlsConfig};
 use crate::discovery::{DiscoveryPacket, HybridBroadcaster, HybridListener};
 use crate::file::{FileChunk, FileChunker, FileWriter};
+use crate::hooks::{self, HookEvent, HookEventKind};
 use crate::protocol::{
     decode_payload, decode_sync_chunk, encode_payload, encode_sync_chunk, read_frame, write_frame,
     HelloPayload, MessageType, SyncCapabilities, SyncChunkAckPayload, SyncChunkPayload,
@@ -105,6 +109,34 @@ pub enum SyncEvent {
     },
 }
 
+/// Wrap `event_callback` so that file changes and conflicts also run the
+/// sync hooks.
+fn with_hooks<F>(peer: String, root: PathBuf, mut event_callback: F) -> impl FnMut(SyncEvent)
+where
+    F: FnMut(SyncEvent),
+{
+    move |event| {
+        let hook = match &event {
+            SyncEvent::FileReceived { path } => {
+                Some(HookEvent::new(HookEventKind::SyncFileReceived).with_path(root.join(path)))
+            }
+            SyncEvent::FileDeleted { path } => {
+                Some(HookEvent::new(HookEventKind::SyncFileDeleted).with_path(root.join(path)))
+            }
+            SyncEvent::Conflict { path, resolution } => Some(
+                HookEvent::new(HookEventKind::SyncConflict)
+                    .with_path(root.join(path))
+                    .with_detail(resolution.as_str()),
+            ),
+            _ => None,
+        };
+        if let Some(hook) = hook {
+            hooks::emit(hook.with_peer(peer.as_str()));
+        }
+        event_callback(event);
+    }
+}
+
 /// A pending sync host session waiting for peer connection.
 ///
 /// This struct represents the first phase of hosting a sync session.
@@ -130,6 +162,17 @@ impl SyncHostSession {
         &self.code
     }
 
+    /// Get the local address the transfer listener is bound to.
+    ///
+    /// The port is the first free one in the configured transfer port range.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be determined.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
     /// Wait for a peer to connect and complete the handshake.
     ///
     /// This method blocks until a peer connects, then performs the TLS
@@ -144,6 +187,7 @@ impl SyncHostSession {
         tracing::info!("Waiting for connection on {}", local_addr);
 
         let (tcp_stream, peer_addr) = self.listener.accept().await?;
+        let peer_addr = connection::canonical_addr(peer_addr);
         tracing::info!("Connection from {}", peer_addr);
 
         configure_tcp_keepalive(&tcp_stream)?;
@@ -172,6 +216,7 @@ impl SyncHostSession {
             op_id_counter: 0,
             tls_stream: Some(tokio_rustls::TlsStream::Server(tls_stream)),
             session_start: Instant::now(),
+            trusted_device: None,
         })
     }
 }
@@ -190,6 +235,8 @@ pub struct SyncSession {
     tls_stream: Option<TlsStream<TcpStream>>,
     #[allow(dead_code)]
     session_start: Instant,
+    /// The peer's trust record, whose permissions bound what it may send
+    trusted_device: Option<TrustedDevice>,
 }
 
 impl std::fmt::Debug for SyncSession {
@@ -243,8 +290,11 @@ impl SyncSession {
             .ok_or_else(|| Error::Internal("server config not available".to_string()))?;
         let acceptor = TlsAcceptor::from(Arc::new(server_config.clone()));
 
-        let listener =
-            TcpListener::bind(format!("0.0.0.0:{}", transfer_config.transfer_port)).await?;
+        let listener = connection::bind_listener_in_range(
+            transfer_config.transfer_port,
+            transfer_config.transfer_port_end,
+            transfer_config.ipv6,
+        )?;
         let local_addr = listener.local_addr()?;
 
         let device_name = hostname::get().map_or_else(
@@ -261,7 +311,11 @@ impl SyncSession {
             local_index.len(),
             local_index.total_size(),
         );
-        let broadcaster = HybridBroadcaster::new(transfer_config.discovery_port).await?;
+        let broadcaster = HybridBroadcaster::with_options(
+            transfer_config.discovery_port,
+            &transfer_config.discovery_options(),
+        )
+        .await?;
         broadcaster
             .start(packet, transfer_config.broadcast_interval)
             .await?;
@@ -342,20 +396,18 @@ impl SyncSession {
             tracing::info!("Connecting directly to {}", addr);
             addr
         } else {
-            let listener = HybridListener::new(transfer_config.discovery_port).await?;
+            let listener = HybridListener::with_options(
+                transfer_config.discovery_port,
+                &transfer_config.discovery_options(),
+            )
+            .await?;
 
             let share_code = ShareCode::parse(code)?;
             let announcement = listener
                 .find(&share_code, transfer_config.discovery_timeout)
                 .await?;
 
-            let addr: SocketAddr = format!(
-                "{}:{}",
-                announcement.source.ip(),
-                announcement.packet.transfer_port
-            )
-            .parse()
-            .map_err(|e| Error::Internal(format!("Invalid peer address: {e}")))?;
+            let addr = announcement.transfer_addr();
 
             tracing::info!("Found peer at {}", addr);
             addr
@@ -399,6 +451,7 @@ impl SyncSession {
             op_id_counter: 0,
             tls_stream: Some(tokio_rustls::TlsStream::Client(tls_stream)),
             session_start: Instant::now(),
+            trusted_device: None,
         })
     }
 
@@ -409,12 +462,18 @@ impl SyncSession {
     ///
     /// # Errors
     ///
-    /// Returns an error if connection fails or trust verification fails.
+    /// Returns an error if connection fails, trust verification fails, or
+    /// the device is not permitted to sync this directory.
     pub async fn connect_trusted(
         device: &TrustedDevice,
         config: SyncConfig,
         transfer_config: TransferConfig,
     ) -> Result<Self> {
+        device
+            .permissions
+            .check_sync_folder(&device.device_name, &config.sync_root)
+            .inspect_err(|e| capability_denied(e, device))?;
+
         let (ip, port) = device.address().ok_or_else(|| {
             Error::ConfigError(format!(
                 "Device '{}' has no stored address. Connect with --host first.",
@@ -461,7 +520,8 @@ impl SyncSession {
             &local_index,
             &config,
         )
-        .await?;
+        .await
+        .inspect_err(|e| audit::handshake_failed(e, transfer_addr, device))?;
 
         Ok(Self {
             config,
@@ -474,6 +534,7 @@ impl SyncSession {
             op_id_counter: 0,
             tls_stream: Some(tokio_rustls::TlsStream::Client(tls_stream)),
             session_start: Instant::now(),
+            trusted_device: Some(device.clone()),
         })
     }
 
@@ -505,12 +566,6 @@ impl SyncSession {
                     )));
                 }
 
-                if hello.public_key != expected_device.public_key {
-                    return Err(Error::TrustError(
-                        "Public key mismatch - device may have been reinstalled".to_string(),
-                    ));
-                }
-
                 let nonce_bytes = BASE64_STANDARD
                     .decode(&hello.nonce)
                     .map_err(|e| Error::ProtocolError(format!("Invalid nonce: {e}")))?;
@@ -527,6 +582,17 @@ impl SyncSession {
                     return Err(Error::TrustError("Invalid signature".to_string()));
                 }
 
+                if !crate::trust::accept_rotated_key(
+                    expected_device,
+                    &hello.public_key,
+                    &hello.key_rotations,
+                    None,
+                )? {
+                    return Err(Error::TrustError(
+                        "Public key mismatch - device may have been reinstalled".to_string(),
+                    ));
+                }
+
                 let identity = DeviceIdentity::load_or_generate()?;
                 let response_signature = identity.sign(&nonce_bytes);
 
@@ -538,6 +604,7 @@ impl SyncSession {
                     nonce_signature: Some(BASE64_STANDARD.encode(response_signature)),
                     error: None,
                     trust_level: Some("Full".to_string()),
+                    key_rotations: identity.rotations().to_vec(),
                 };
 
                 let ack_payload = encode_payload(&ack)?;
@@ -822,7 +889,19 @@ impl SyncSession {
     /// # Errors
     ///
     /// Returns an error if reconciliation or file operations fail.
-    pub async fn run_initial_sync<F>(&mut self, mut event_callback: F) -> Result<()>
+    pub async fn run_initial_sync<F>(&mut self, event_callback: F) -> Result<()>
+    where
+        F: FnMut(SyncEvent),
+    {
+        let event_callback = with_hooks(
+            self.peer_name().to_string(),
+            self.config.sync_root.clone(),
+            event_callback,
+        );
+        self.initial_sync(event_callback).await
+    }
+
+    async fn initial_sync<F>(&mut self, mut event_callback: F) -> Result<()>
     where
         F: FnMut(SyncEvent),
     {
@@ -859,6 +938,20 @@ impl SyncSession {
             tracing::debug!("Applied {} conflict resolutions", resolutions.len());
         }
 
+        if let Some(device) = &self.trusted_device {
+            let incoming = local_ops
+                .iter()
+                .filter_map(|op| match op {
+                    SyncOp::Create { size, .. } | SyncOp::Modify { size, .. } => Some(*size),
+                    _ => None,
+                })
+                .fold(0u64, u64::saturating_add);
+            device
+                .permissions
+                .check_size(&device.device_name, incoming)
+                .inspect_err(|e| capability_denied(e, device))?;
+        }
+
         let local_plan = SyncPlan::from_ops(local_ops);
         let remote_plan = SyncPlan::from_ops(remote_ops);
 
@@ -1333,13 +1426,18 @@ impl SyncSession {
     /// # Errors
     ///
     /// Returns an error if the sync session cannot be started or a fatal error occurs.
-    pub async fn run<F>(&mut self, mut event_callback: F) -> Result<SyncStats>
+    pub async fn run<F>(&mut self, event_callback: F) -> Result<SyncStats>
     where
         F: FnMut(SyncEvent) + Send + 'static,
     {
         let start_time = Instant::now();
 
-        self.run_initial_sync(&mut event_callback).await?;
+        let mut event_callback = with_hooks(
+            self.peer_name().to_string(),
+            self.config.sync_root.clone(),
+            event_callback,
+        );
+        self.initial_sync(&mut event_callback).await?;
 
         let tls_stream = self
             .tls_stream
@@ -1376,6 +1474,7 @@ impl SyncSession {
             Arc::new(Mutex::new(self.stats.clone())),
             Arc::new(Mutex::new(self.local_index.clone())),
             self.config.clone(),
+            self.trusted_device.clone(),
             event_callback,
         );
 
@@ -1492,6 +1591,7 @@ impl SyncSession {
         stats: Arc<Mutex<SyncStats>>,
         #[allow(unused_variables)] local_index: Arc<Mutex<FileIndex>>,
         config: SyncConfig,
+        trusted_device: Option<TrustedDevice>,
         mut event_callback: F,
     ) -> JoinHandle<Result<()>>
     where
@@ -1514,6 +1614,7 @@ impl SyncSession {
                                             &mut *stream_guard,
                                             op_payload,
                                             &config,
+                                            trusted_device.as_ref(),
                                             &stats,
                                             &mut event_callback,
                                         )
@@ -1795,6 +1896,7 @@ impl SyncSession {
         stream: &mut S,
         payload: SyncOpPayload,
         config: &SyncConfig,
+        trusted_device: Option<&TrustedDevice>,
         stats: &Arc<Mutex<SyncStats>>,
         event_callback: &mut F,
     ) -> Result<()>
@@ -1807,6 +1909,15 @@ impl SyncSession {
         match payload.op_type {
             SyncOpType::Create | SyncOpType::Modify => {
                 if let Some(size) = payload.size {
+                    let chunked = size > 0 && payload.chunk_count.unwrap_or(0) > 0;
+                    if let Some(device) = trusted_device {
+                        if let Err(e) = device.permissions.check_size(&device.device_name, size) {
+                            capability_denied(&e, device);
+                            Self::refuse_sync_op(stream, op_id, chunked, &e).await?;
+                            return Err(e);
+                        }
+                    }
+
                     let path = RelativePath::new(&payload.path);
                     let abs_path = path.to_path(&config.sync_root);
 
@@ -1819,7 +1930,7 @@ impl SyncSession {
                         size,
                     });
 
-                    if size > 0 && payload.chunk_count.unwrap_or(0) > 0 {
+                    if chunked {
                         Self::receive_file_chunks_simple(stream, op_id, &abs_path, size).await?;
                     } else {
                         tokio::fs::File::create(&abs_path).await?;
@@ -1879,6 +1990,45 @@ impl SyncSession {
         Ok(())
     }
 
+    /// Turn down an incoming file operation without reading its content.
+    ///
+    /// The sender is waiting for the acknowledgement of its first chunk, or
+    /// of the whole operation when there is no content.
+    async fn refuse_sync_op<S>(
+        stream: &mut S,
+        op_id: u64,
+        chunked: bool,
+        error: &Error,
+    ) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        if chunked {
+            let (header, chunk_data) = read_frame(stream).await?;
+            if header.message_type != MessageType::SyncChunk {
+                return Err(Error::UnexpectedMessage {
+                    expected: "SyncChunk".to_string(),
+                    actual: format!("{:?}", header.message_type),
+                });
+            }
+            let chunk_payload = decode_sync_chunk(&chunk_data)?;
+            let ack = SyncChunkAckPayload {
+                op_id,
+                chunk_index: chunk_payload.chunk_index,
+                success: false,
+            };
+            write_frame(stream, MessageType::SyncChunkAck, &encode_payload(&ack)?).await
+        } else {
+            let ack = SyncOpAckPayload {
+                op_id,
+                success: false,
+                error: Some(error.to_string()),
+                content_hash: None,
+            };
+            write_frame(stream, MessageType::SyncOpAck, &encode_payload(&ack)?).await
+        }
+    }
+
     /// Receive file chunks (simplified version).
     async fn receive_file_chunks_simple<S>(
         stream: &mut S,
@@ -1908,7 +2058,11 @@ impl SyncSession {
                     }
 
                     let computed_checksum = xxhash64(&chunk_payload.data);
-                    if computed_checksum != chunk_payload.checksum {
+                    let oversized = writer
+                        .bytes_written
+                        .saturating_add(chunk_payload.data.len() as u64)
+                        > expected_size;
+                    if oversized || computed_checksum != chunk_payload.checksum {
                         let ack = SyncChunkAckPayload {
                             op_id,
                             chunk_index: chunk_payload.chunk_index,
@@ -1916,6 +2070,12 @@ impl SyncSession {
                         };
                         write_frame(stream, MessageType::SyncChunkAck, &encode_payload(&ack)?)
                             .await?;
+                        if oversized {
+                            return Err(Error::ProtocolError(format!(
+                                "{} is larger than the announced {expected_size} bytes",
+                                output_path.display()
+                            )));
+                        }
                         return Err(Error::ChecksumMismatch {
                             file: output_path.display().to_string(),
                             chunk: u64::from(chunk_payload.chunk_index),
@@ -1957,6 +2117,15 @@ impl SyncSession {
     }
 }
 
+/// Record that a trusted peer tried to sync beyond its permissions.
+fn capability_denied(error: &Error, device: &TrustedDevice) {
+    audit::record(
+        AuditEvent::new(AuditEventKind::CapabilityDenied)
+            .with_device(device.device_id, device.device_name.clone())
+            .with_detail(error.to_string()),
+    );
+}
+
 /// Helper to configure TCP keepalive on a socket.
 fn configure_tcp_keepalive(stream: &TcpStream) -> Result<()> {
     use socket2::{SockRef, TcpKeepalive};
@@ -1998,6 +2167,7 @@ mod tests {
             op_id_counter: 0,
             tls_stream: None,
             session_start: Instant::now(),
+            trusted_device: None,
         };
 
         let debug_str = format!("{session:?}");
@@ -2023,6 +2193,7 @@ mod tests {
             op_id_counter: 0,
             tls_stream: None,
             session_start: Instant::now(),
+            trusted_device: None,
         };
 
         assert_eq!(session.peer_name(), "TestPeer");
@@ -2047,6 +2218,7 @@ mod tests {
             op_id_counter: 0,
             tls_stream: None,
             session_start: Instant::now(),
+            trusted_device: None,
         };
 
         assert_eq!(session.peer_name(), "Unknown");
@@ -2071,6 +2243,7 @@ mod tests {
             op_id_counter: 0,
             tls_stream: None,
             session_start: Instant::now(),
+            trusted_device: None,
         };
 
         let stats = session.stats();
@@ -2097,6 +2270,7 @@ mod tests {
             op_id_counter: 0,
             tls_stream: None,
             session_start: Instant::now(),
+            trusted_device: None,
         };
 
         assert_eq!(session.next_op_id(), 0);
@@ -2113,4 +2287,63 @@ mod tests {
         assert!(debug_str.contains("Connected"));
         assert!(debug_str.contains("TestPeer"));
     }
+
+    #[tokio::test]
+    async fn test_sync_op_over_size_limit_is_refused() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = SyncConfig {
+            sync_root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let permissions = crate::trust::DevicePermissions {
+            max_transfer_size: Some(16),
+            ..Default::default()
+        };
+        let device = TrustedDevice::new(uuid::Uuid::new_v4(), "Peer".to_string(), String::new())
+            .with_permissions(permissions);
+
+        let (mut ours, mut theirs) = tokio::io::duplex(64 * 1024);
+        let payload = SyncOpPayload {
+            op_id: 7,
+            op_type: SyncOpType::Create,
+            path: "big.bin".to_string(),
+            from_path: None,
+            kind: FileKind::File as u8,
+            size: Some(32),
+            content_hash: None,
+            chunk_count: Some(1),
+        };
+        let data = vec![0u8; 32];
+        let chunk = SyncChunkPayload {
+            op_id: 7,
+            chunk_index: 0,
+            checksum: crate::crypto::xxhash64(&data),
+            data,
+        };
+        write_frame(
+            &mut theirs,
+            MessageType::SyncChunk,
+            &encode_sync_chunk(&chunk),
+        )
+        .await
+        .unwrap();
+
+        let stats = Arc::new(Mutex::new(SyncStats::new()));
+        let result = SyncSession::receive_sync_op(
+            &mut ours,
+            payload,
+            &config,
+            Some(&device),
+            &stats,
+            &mut |_| {},
+        )
+        .await;
+        assert!(matches!(result, Err(Error::CapabilityDenied { .. })));
+
+        let (header, ack) = read_frame(&mut theirs).await.unwrap();
+        assert_eq!(header.message_type, MessageType::SyncChunkAck);
+        let ack: SyncChunkAckPayload = decode_payload(&ack).unwrap();
+        assert!(!ack.success);
+        assert!(!temp_dir.path().join("big.bin").exists());
+    }
 }
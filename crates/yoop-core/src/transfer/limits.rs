@@ -0,0 +1,454 @@
+//! Receive-side limits for incoming file lists.
+//!
+//! The file list comes from the sender and is not trusted. Before a
+//! transfer is offered to the user it is checked against [`ReceiveLimits`]:
+//! file count, total size, path length and depth, and the symlink policy.
+//! Every path must also be a plain relative path, so that neither files nor
+//! directory entries can be placed outside the output directory, and
+//! [`output_path`] re-checks this against the file system when writing.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::file::FileMetadata;
+
+/// Which symlink entries a receiver accepts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkPolicy {
+    /// Accept symlinks whose target stays inside the output directory
+    #[default]
+    Contained,
+    /// Refuse transfers containing any symlink
+    Reject,
+    /// Accept any symlink target
+    Any,
+}
+
+impl std::fmt::Display for SymlinkPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Contained => write!(f, "contained"),
+            Self::Reject => write!(f, "reject"),
+            Self::Any => write!(f, "any"),
+        }
+    }
+}
+
+impl std::str::FromStr for SymlinkPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "contained" => Ok(Self::Contained),
+            "reject" => Ok(Self::Reject),
+            "any" => Ok(Self::Any),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown symlink policy '{s}' (expected contained, reject or any)"
+            ))),
+        }
+    }
+}
+
+/// Limits applied to incoming file lists.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReceiveLimits {
+    /// Maximum number of entries (files and directories; None = unlimited)
+    pub max_files: Option<u64>,
+    /// Maximum total size in bytes (None = unlimited)
+    pub max_total_size: Option<u64>,
+    /// Maximum length of a relative path in bytes
+    pub max_path_length: u64,
+    /// Maximum number of components in a relative path
+    pub max_path_depth: u64,
+    /// Which symlink entries to accept
+    pub symlinks: SymlinkPolicy,
+}
+
+impl Default for ReceiveLimits {
+    fn default() -> Self {
+        Self {
+            max_files: Some(100_000),
+            max_total_size: None,
+            max_path_length: 4096,
+            max_path_depth: 64,
+            symlinks: SymlinkPolicy::Contained,
+        }
+    }
+}
+
+impl ReceiveLimits {
+    /// Check an incoming file list against these limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReceiveLimitExceeded`] when a limit is exceeded,
+    /// [`Error::UnsafePath`] for a path that could leave the output
+    /// directory and [`Error::SymlinkNotAllowed`] for a symlink the policy
+    /// refuses.
+    pub fn check(&self, files: &[FileMetadata]) -> Result<()> {
+        let count = files.len() as u64;
+        if let Some(max) = self.max_files.filter(|max| count > *max) {
+            return Err(Error::ReceiveLimitExceeded {
+                limit: "file count",
+                actual: count,
+                max,
+            });
+        }
+
+        if let Some(max) = self.max_total_size {
+            let total = total_size(files);
+            if total > max {
+                return Err(Error::ReceiveLimitExceeded {
+                    limit: "total size",
+                    actual: total,
+                    max,
+                });
+            }
+        }
+
+        let links: HashSet<&Path> = files
+            .iter()
+            .filter(|f| f.is_symlink)
+            .map(|f| f.relative_path.as_path())
+            .collect();
+        for file in files {
+            self.check_entry(file, &links)?;
+        }
+        Ok(())
+    }
+
+    fn check_entry(&self, file: &FileMetadata, links: &HashSet<&Path>) -> Result<()> {
+        let path = &file.relative_path;
+        validate_relative_path(path)?;
+
+        let length = path.as_os_str().len() as u64;
+        if length > self.max_path_length {
+            return Err(Error::ReceiveLimitExceeded {
+                limit: "path length",
+                actual: length,
+                max: self.max_path_length,
+            });
+        }
+
+        let depth = path.components().count() as u64;
+        if depth > self.max_path_depth {
+            return Err(Error::ReceiveLimitExceeded {
+                limit: "path depth",
+                actual: depth,
+                max: self.max_path_depth,
+            });
+        }
+
+        if !file.is_symlink {
+            return Ok(());
+        }
+        let target = file
+            .symlink_target
+            .as_deref()
+            .unwrap_or_else(|| Path::new(""));
+        match self.symlinks {
+            SymlinkPolicy::Any => Ok(()),
+            SymlinkPolicy::Reject => Err(Error::SymlinkNotAllowed(path.display().to_string())),
+            SymlinkPolicy::Contained if symlink_stays_inside(path, target, links) => Ok(()),
+            SymlinkPolicy::Contained => Err(Error::SymlinkNotAllowed(format!(
+                "'{}' points outside the output directory ({})",
+                path.display(),
+                target.display()
+            ))),
+        }
+    }
+}
+
+/// Check that `path` is a non-empty relative path made only of normal
+/// components.
+///
+/// # Errors
+///
+/// Returns [`Error::UnsafePath`] for absolute paths, `..`, `.` and empty
+/// paths.
+pub fn validate_relative_path(path: &Path) -> Result<()> {
+    if path.as_os_str().is_empty() {
+        return Err(Error::UnsafePath("empty path".to_string()));
+    }
+    if path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        Ok(())
+    } else {
+        Err(Error::UnsafePath(path.display().to_string()))
+    }
+}
+
+/// Whether a symlink at `link` (relative to the output directory) pointing
+/// to `target` resolves inside the output directory.
+///
+/// The check is lexical, so it only holds if no directory on the way is
+/// itself one of the symlinks in `links`; a path that goes through one is
+/// refused, since it resolves from wherever that link points.
+fn symlink_stays_inside(link: &Path, target: &Path, links: &HashSet<&Path>) -> bool {
+    if target.as_os_str().is_empty() {
+        return false;
+    }
+
+    let parent = link.parent().unwrap_or_else(|| Path::new(""));
+    let mut components = parent.components().chain(target.components()).peekable();
+    let mut resolved = PathBuf::new();
+    while let Some(component) = components.next() {
+        match component {
+            Component::Normal(name) => {
+                resolved.push(name);
+                if components.peek().is_some() && links.contains(resolved.as_path()) {
+                    return false;
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return false;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+/// Where to write an incoming entry, after checking it cannot escape
+/// `output_dir`.
+///
+/// Besides validating the path itself, the nearest existing ancestor is
+/// resolved on disk, so a symlink already inside the output directory
+/// cannot redirect the write elsewhere.
+///
+/// # Errors
+///
+/// Returns [`Error::UnsafePath`] if the path is invalid or resolves outside
+/// `output_dir`.
+pub fn output_path(output_dir: &Path, relative: &Path) -> Result<PathBuf> {
+    validate_relative_path(relative)?;
+    let path = output_dir.join(relative);
+
+    let Ok(root) = output_dir.canonicalize() else {
+        return Ok(path);
+    };
+    // a dangling symlink exists but does not resolve, and is refused too
+    let existing = path
+        .ancestors()
+        .take_while(|ancestor| ancestor.starts_with(output_dir))
+        .find(|ancestor| ancestor.symlink_metadata().is_ok());
+    match existing.map(Path::canonicalize) {
+        Some(Ok(resolved)) if !resolved.starts_with(&root) => Err(unsafe_output(relative)),
+        Some(Err(_)) => Err(unsafe_output(relative)),
+        _ => Ok(path),
+    }
+}
+
+fn unsafe_output(relative: &Path) -> Error {
+    Error::UnsafePath(format!(
+        "{} resolves outside the output directory",
+        relative.display()
+    ))
+}
+
+/// Total size of a file list, saturating instead of overflowing on sizes a
+/// hostile sender made up.
+#[must_use]
+pub fn total_size(files: &[FileMetadata]) -> u64 {
+    files
+        .iter()
+        .fold(0u64, |total, f| total.saturating_add(f.size))
+}
+
+/// Look up the file a sender's message refers to.
+///
+/// # Errors
+///
+/// Returns [`Error::ProtocolError`] for an index outside the file list.
+pub(crate) fn file_entry(files: &[FileMetadata], index: usize) -> Result<&FileMetadata> {
+    files
+        .get(index)
+        .ok_or_else(|| Error::ProtocolError(format!("file index {index} out of range")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, size: u64) -> FileMetadata {
+        FileMetadata {
+            relative_path: PathBuf::from(path),
+            size,
+            mime_type: None,
+            created: None,
+            modified: None,
+            permissions: None,
+            is_symlink: false,
+            symlink_target: None,
+            is_directory: false,
+            preview: None,
+        }
+    }
+
+    fn symlink(path: &str, target: &str) -> FileMetadata {
+        FileMetadata {
+            is_symlink: true,
+            symlink_target: Some(PathBuf::from(target)),
+            ..entry(path, 0)
+        }
+    }
+
+    #[test]
+    fn test_accepts_ordinary_list() {
+        let files = vec![
+            entry("photos", 0),
+            entry("photos/a.jpg", 1024),
+            symlink("photos/latest.jpg", "a.jpg"),
+            symlink("docs/link", "../photos/a.jpg"),
+        ];
+        assert!(ReceiveLimits::default().check(&files).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_traversal_paths() {
+        for path in ["../evil", "a/../../evil", "/etc/passwd", "./a", ""] {
+            let result = ReceiveLimits::default().check(&[entry(path, 1)]);
+            assert!(
+                matches!(result, Err(Error::UnsafePath(_))),
+                "{path:?} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_escaping_symlinks() {
+        for target in ["/etc/passwd", "../../outside", "a/../../../x", ""] {
+            let result = ReceiveLimits::default().check(&[symlink("dir/link", target)]);
+            assert!(
+                matches!(result, Err(Error::SymlinkNotAllowed(_))),
+                "{target:?} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_symlink_chains() {
+        let chains = [
+            [symlink("l2", "."), symlink("l3", "l2/../x")],
+            [symlink("l2", "."), symlink("l2/l3", "../x")],
+        ];
+        for files in chains {
+            assert!(
+                matches!(
+                    ReceiveLimits::default().check(&files),
+                    Err(Error::SymlinkNotAllowed(_))
+                ),
+                "{files:?} was accepted"
+            );
+        }
+
+        let to_link = [symlink("l2", "a"), symlink("l3", "l2")];
+        assert!(ReceiveLimits::default().check(&to_link).is_ok());
+    }
+
+    #[test]
+    fn test_symlink_policies() {
+        let files = [symlink("link", "/etc/passwd")];
+        let any = ReceiveLimits {
+            symlinks: SymlinkPolicy::Any,
+            ..ReceiveLimits::default()
+        };
+        assert!(any.check(&files).is_ok());
+
+        let reject = ReceiveLimits {
+            symlinks: SymlinkPolicy::Reject,
+            ..ReceiveLimits::default()
+        };
+        assert!(matches!(
+            reject.check(&[symlink("link", "target")]),
+            Err(Error::SymlinkNotAllowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_count_and_size_limits() {
+        let limits = ReceiveLimits {
+            max_files: Some(2),
+            max_total_size: Some(100),
+            ..ReceiveLimits::default()
+        };
+
+        let too_many: Vec<_> = (0..3).map(|i| entry(&format!("f{i}"), 1)).collect();
+        assert!(matches!(
+            limits.check(&too_many),
+            Err(Error::ReceiveLimitExceeded {
+                limit: "file count",
+                actual: 3,
+                max: 2
+            })
+        ));
+
+        let overflow = [entry("a", u64::MAX), entry("b", u64::MAX)];
+        assert!(matches!(
+            limits.check(&overflow),
+            Err(Error::ReceiveLimitExceeded {
+                limit: "total size",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_path_length_and_depth_limits() {
+        let limits = ReceiveLimits {
+            max_path_length: 10,
+            max_path_depth: 3,
+            ..ReceiveLimits::default()
+        };
+
+        assert!(matches!(
+            limits.check(&[entry("averylongfilename", 1)]),
+            Err(Error::ReceiveLimitExceeded {
+                limit: "path length",
+                ..
+            })
+        ));
+        assert!(matches!(
+            limits.check(&[entry("a/b/c/d", 1)]),
+            Err(Error::ReceiveLimitExceeded {
+                limit: "path depth",
+                ..
+            })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_path_rejects_symlinked_directory() {
+        let outside = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("escape")).unwrap();
+
+        std::os::unix::fs::symlink(outside.path().join("missing"), root.path().join("dangling"))
+            .unwrap();
+
+        assert!(output_path(root.path(), Path::new("dir/file.txt")).is_ok());
+        assert!(matches!(
+            output_path(root.path(), Path::new("dangling")),
+            Err(Error::UnsafePath(_))
+        ));
+        assert!(matches!(
+            output_path(root.path(), Path::new("escape/file.txt")),
+            Err(Error::UnsafePath(_))
+        ));
+        assert!(matches!(
+            output_path(root.path(), Path::new("../file.txt")),
+            Err(Error::UnsafePath(_))
+        ));
+    }
+}
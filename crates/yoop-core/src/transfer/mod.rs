@@ -13,13 +13,33 @@
 //! - Adaptive sizing based on network conditions
 //! - Parallel chunks: Up to 4 concurrent streams
 //! - Checksum: xxHash64 per chunk, SHA-256 for complete file
+//!
+//! ## Roaming
+//!
+//! If the connection drops mid-transfer (for example when a laptop moves
+//! from Ethernet to Wi-Fi), both ends keep the transfer alive for
+//! [`TransferConfig::reconnect_grace`]. The receiver finds the sender again
+//! at its last address or through discovery, repeats the handshake and code
+//! verification, and sends a resume request listing the chunks it already
+//! has. The sender then continues from there.
+//!
+//! ## Relay
+//!
+//! With [`TransferConfig::relay`] set, the sender also registers its code with
+//! that [`relay`](crate::relay) server and accepts receivers arriving through
+//! it. A receiver that cannot find or reach the sender directly connects
+//! through the relay instead. TLS and code verification run end to end over
+//! the relayed connection.
 
+pub mod limits;
 pub mod resume;
 pub mod trusted;
 
+pub use limits::{ReceiveLimits, SymlinkPolicy};
 pub use resume::ResumeManager;
-pub use trusted::{SenderInfo, TrustedReceiveSession, TrustedSendSession};
+pub use trusted::{discover_devices, SenderInfo, TrustedReceiveSession, TrustedSendSession};
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -33,21 +53,26 @@ use tokio::task::JoinHandle;
 use tokio_rustls::{TlsAcceptor, TlsConnector};
 use uuid::Uuid;
 
-use crate::code::{CodeGenerator, ShareCode};
-use crate::compression::CompressionAlgorithm;
-use crate::crypto::{self, TlsConfig};
+use crate::audit::{self, AuditEvent, AuditEventKind};
+use crate::code::{self, CodeGenerator, ShareCode};
+use crate::compression::{CompressionAlgorithm, CompressionStats};
+use crate::connection;
+use crate::crypto::{self, ChannelBinding, TlsConfig};
 use crate::discovery::{
-    DiscoveryPacket, HybridBroadcaster, HybridListener, DEFAULT_DISCOVERY_PORT,
+    DiscoveryOptions, DiscoveryPacket, HybridBroadcaster, HybridListener, InterfaceFilter,
+    MulticastOptions, SweepOptions, DEFAULT_DISCOVERY_PORT,
 };
 use crate::error::{Error, Result};
 use crate::file::{
-    enumerate_files, EnumerateOptions, FileChunk, FileChunker, FileMetadata, FileWriter,
+    chunks_sha256, enumerate_files, EnumerateOptions, FileChunk, FileChunker, FileMetadata,
+    FileWriter, QuarantineConfig,
 };
+use crate::hooks::{self, HookEvent, HookEventKind};
 use crate::protocol::{
-    self, ChunkAckPayload, ChunkDataPayload, ChunkStartPayload, CodeVerifyAckPayload,
-    CodeVerifyPayload, FileListAckPayload, FileListPayload, HelloPayload, MessageType,
-    TrustedHelloAckPayload, TrustedHelloPayload,
+    self, ChunkAckPayload, ChunkDataPayload, ChunkStartPayload, FileListAckPayload,
+    FileListPayload, HelloPayload, MessageType, TrustedHelloAckPayload, TrustedHelloPayload,
 };
+use crate::relay;
 use crate::trust::TrustedDevice;
 
 use base64::prelude::*;
@@ -55,6 +80,23 @@ use base64::prelude::*;
 /// Default transfer port.
 pub const DEFAULT_TRANSFER_PORT: u16 = 52530;
 
+/// Default grace period for a dropped peer to reconnect and resume.
+pub const DEFAULT_RECONNECT_GRACE: Duration = Duration::from_secs(60);
+
+/// How long the sender waits for a chunk acknowledgment before treating
+/// the connection as dead.
+const CHUNK_ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a reconnect attempt waits on the peer's last known address
+/// before falling back to discovery.
+const RECONNECT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay between failed reconnect attempts.
+const RECONNECT_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Delay before registering with an unreachable relay again.
+const RELAY_RETRY_DELAY: Duration = Duration::from_secs(5);
+
 /// Configure TCP keep-alive on a socket.
 ///
 /// This enables OS-level TCP keep-alive to prevent network equipment
@@ -79,6 +121,17 @@ fn configure_tcp_keepalive(stream: &TcpStream) -> Result<()> {
     Ok(())
 }
 
+/// Run the `file-received` hooks for a file released to `path`.
+fn emit_file_received(sender: Option<&str>, path: PathBuf, bytes: u64) {
+    let mut event = HookEvent::new(HookEventKind::FileReceived)
+        .with_path(path)
+        .with_bytes(bytes);
+    if let Some(sender) = sender {
+        event = event.with_peer(sender);
+    }
+    hooks::emit(event);
+}
+
 /// Transfer direction.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TransferDirection {
@@ -99,6 +152,8 @@ pub enum TransferState {
     Connected,
     /// Transferring files
     Transferring,
+    /// Connection lost, waiting for the peer to reconnect
+    Reconnecting,
     /// Transfer completed successfully
     Completed,
     /// Transfer was cancelled
@@ -173,7 +228,7 @@ pub struct TransferConfig {
     pub parallel_streams: usize,
     /// Bandwidth limit (bytes per second)
     pub bandwidth_limit: Option<u64>,
-    /// Compression mode (auto, always, never)
+    /// Compression mode (auto, always, never, adaptive)
     pub compression: crate::compression::CompressionMode,
     /// Compression level (1-3, lower = faster)
     pub compression_level: u8,
@@ -181,12 +236,32 @@ pub struct TransferConfig {
     pub verify_checksums: bool,
     /// Discovery port
     pub discovery_port: u16,
-    /// Transfer port
+    /// Transfer port (the first one tried)
     pub transfer_port: u16,
+    /// Last transfer port to try when earlier ones are in use
+    pub transfer_port_end: u16,
     /// Discovery timeout
     pub discovery_timeout: Duration,
     /// Broadcast interval for discovery announcements
     pub broadcast_interval: Duration,
+    /// How long to wait for a dropped peer to reconnect (zero disables)
+    pub reconnect_grace: Duration,
+    /// Use IPv6 (dual-stack listeners and multicast discovery) alongside IPv4
+    pub ipv6: bool,
+    /// Network interfaces used for discovery
+    pub interfaces: InterfaceFilter,
+    /// Privacy mode for discovery announcements
+    pub privacy_mode: bool,
+    /// Subnet sweep used when passive discovery finds nothing
+    pub sweep: SweepOptions,
+    /// IPv4 multicast discovery settings
+    pub multicast: MulticastOptions,
+    /// Relay server (`host[:port]`) used when direct connection fails
+    pub relay: Option<String>,
+    /// Limits checked against incoming file lists before accepting
+    pub limits: ReceiveLimits,
+    /// Checks received files must pass before they are released
+    pub quarantine: QuarantineConfig,
 }
 
 impl Default for TransferConfig {
@@ -200,8 +275,32 @@ impl Default for TransferConfig {
             verify_checksums: true,
             discovery_port: DEFAULT_DISCOVERY_PORT,
             transfer_port: DEFAULT_TRANSFER_PORT,
+            transfer_port_end: crate::DEFAULT_TRANSFER_PORT_END,
             discovery_timeout: Duration::from_secs(30),
             broadcast_interval: Duration::from_secs(2),
+            reconnect_grace: DEFAULT_RECONNECT_GRACE,
+            ipv6: true,
+            interfaces: InterfaceFilter::default(),
+            privacy_mode: false,
+            sweep: SweepOptions::default(),
+            multicast: MulticastOptions::default(),
+            relay: None,
+            limits: ReceiveLimits::default(),
+            quarantine: QuarantineConfig::default(),
+        }
+    }
+}
+
+impl TransferConfig {
+    /// Discovery options derived from this configuration.
+    #[must_use]
+    pub fn discovery_options(&self) -> DiscoveryOptions {
+        DiscoveryOptions {
+            ipv6: self.ipv6,
+            interfaces: self.interfaces.clone(),
+            privacy: self.privacy_mode,
+            sweep: self.sweep.clone(),
+            multicast: self.multicast.clone(),
         }
     }
 }
@@ -210,6 +309,8 @@ impl Default for TransferConfig {
 pub struct ShareSession {
     /// Share code
     code: ShareCode,
+    /// Transfer ID (identifies this transfer across reconnects)
+    transfer_id: Uuid,
     /// Files being shared
     files: Vec<FileMetadata>,
     /// File paths (for reading)
@@ -220,8 +321,6 @@ pub struct ShareSession {
     device_name: String,
     /// Device identity (for trust feature)
     identity: crypto::DeviceIdentity,
-    /// Session key for HMAC verification
-    session_key: [u8; 32],
     /// Progress sender
     progress_tx: watch::Sender<TransferProgress>,
     /// Progress receiver (for cloning to observers)
@@ -242,6 +341,10 @@ pub struct ShareSession {
     receiver_addr: Option<SocketAddr>,
     /// Negotiated compression algorithm (None = no compression)
     negotiated_compression: Option<CompressionAlgorithm>,
+    /// All algorithms both peers support (used by adaptive mode)
+    common_compression: Vec<CompressionAlgorithm>,
+    /// Compression statistics (captured after transfer)
+    compression_stats: CompressionStats,
 }
 
 impl std::fmt::Debug for ShareSession {
@@ -300,11 +403,13 @@ impl ShareSession {
         let progress = TransferProgress::new(files.len(), total_bytes);
         let (progress_tx, progress_rx) = watch::channel(progress);
 
-        let session_key = crypto::derive_session_key(code.as_str());
-
         let tls_config = TlsConfig::server()?;
 
-        let listener = TcpListener::bind(format!("0.0.0.0:{}", config.transfer_port)).await?;
+        let listener = connection::bind_listener_in_range(
+            config.transfer_port,
+            config.transfer_port_end,
+            config.ipv6,
+        )?;
         let local_addr = listener.local_addr()?;
 
         let device_name = hostname::get().map_or_else(
@@ -312,11 +417,14 @@ impl ShareSession {
             |h| h.to_string_lossy().to_string(),
         );
 
-        let broadcaster = HybridBroadcaster::new(config.discovery_port).await?;
-
         let identity = crypto::DeviceIdentity::load_or_generate()?;
         let device_id = identity.device_id();
 
+        let broadcaster =
+            HybridBroadcaster::with_options(config.discovery_port, &config.discovery_options())
+                .await?
+                .with_resolving_key(identity.resolving_key());
+
         let packet = DiscoveryPacket::new(
             &code,
             &device_name,
@@ -328,14 +436,21 @@ impl ShareSession {
 
         broadcaster.start(packet, config.broadcast_interval).await?;
 
+        hooks::emit(
+            HookEvent::new(HookEventKind::ShareStarted)
+                .with_code(code.to_string())
+                .with_files(files.len() as u64)
+                .with_bytes(total_bytes),
+        );
+
         Ok(Self {
             code,
+            transfer_id: Uuid::new_v4(),
             files,
             file_paths,
             config,
             device_name,
             identity,
-            session_key,
             progress_tx,
             progress_rx,
             listener,
@@ -346,6 +461,8 @@ impl ShareSession {
             receiver_name: None,
             receiver_addr: None,
             negotiated_compression: None,
+            common_compression: Vec::new(),
+            compression_stats: CompressionStats::new(),
         })
     }
 
@@ -361,6 +478,17 @@ impl ShareSession {
         &self.files
     }
 
+    /// Get the local address the transfer listener is bound to.
+    ///
+    /// The port is the first free one in the configured transfer port range.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be determined.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
     /// Get a progress receiver.
     #[must_use]
     pub fn progress(&self) -> watch::Receiver<TransferProgress> {
@@ -391,6 +519,12 @@ impl ShareSession {
         self.receiver_addr
     }
 
+    /// Get compression statistics (after transfer completes).
+    #[must_use]
+    pub const fn compression_stats(&self) -> &CompressionStats {
+        &self.compression_stats
+    }
+
     /// Wait for a receiver to connect and complete the transfer.
     ///
     /// # Errors
@@ -399,21 +533,7 @@ impl ShareSession {
     pub async fn wait(&mut self) -> Result<()> {
         self.update_state(TransferState::Waiting);
 
-        let (stream, peer_addr) = self.listener.accept().await?;
-        tracing::info!("Connection from {}", peer_addr);
-
-        configure_tcp_keepalive(&stream)?;
-
-        let acceptor = TlsAcceptor::from(Arc::new(
-            self.tls_config
-                .server_config()
-                .ok_or_else(|| Error::TlsError("no server config".to_string()))?
-                .clone(),
-        ));
-        let mut tls_stream = acceptor
-            .accept(stream)
-            .await
-            .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
+        let (mut tls_stream, peer_addr) = self.accept_connection().await?;
 
         self.update_state(TransferState::Connected);
 
@@ -422,16 +542,21 @@ impl ShareSession {
         self.receiver_name = Some(receiver_name);
         self.receiver_device_id = receiver_device_id;
         self.receiver_public_key = receiver_public_key;
-        self.receiver_addr = Some(peer_addr);
+        self.receiver_addr = peer_addr;
 
-        self.negotiated_compression = match (self.compression_capabilities(), receiver_compression)
-        {
-            (Some(our_caps), Some(their_caps)) => our_caps.negotiate(&their_caps),
-            _ => None,
+        self.common_compression = match (self.compression_capabilities(), receiver_compression) {
+            (Some(our_caps), Some(their_caps)) => our_caps.common_algorithms(&their_caps),
+            _ => Vec::new(),
         };
-        tracing::debug!("Negotiated compression: {:?}", self.negotiated_compression);
+        self.negotiated_compression = self.common_compression.first().copied();
+        tracing::debug!(
+            "Negotiated compression: {:?} (common: {:?})",
+            self.negotiated_compression,
+            self.common_compression
+        );
 
-        self.do_code_verification(&mut tls_stream).await?;
+        self.do_code_verification(&mut tls_stream, peer_addr)
+            .await?;
 
         let accepted = self.do_file_list_exchange(&mut tls_stream).await?;
         if !accepted {
@@ -441,15 +566,194 @@ impl ShareSession {
 
         self.update_state(TransferState::Transferring);
 
-        self.do_transfer(&mut tls_stream).await?;
+        let mut completed = HashMap::new();
+        let mut stats = CompressionStats::new();
+        loop {
+            match self
+                .do_transfer(&mut tls_stream, &completed, &mut stats)
+                .await
+            {
+                Ok(()) => break,
+                Err(e) if e.is_connection_loss() && !self.config.reconnect_grace.is_zero() => {
+                    tracing::warn!("Connection to receiver lost: {}", e);
+                    self.update_state(TransferState::Reconnecting);
+                    let (stream, request) = self.await_reconnect(e).await?;
+                    tls_stream = stream;
+                    completed = request.completed_chunks;
+                    self.update_state(TransferState::Transferring);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        self.compression_stats = stats;
+        tracing::debug!(
+            "Compression: {} ({})",
+            self.compression_stats.savings_display(),
+            self.compression_stats.algorithm_display()
+        );
 
         self.broadcaster.stop().await;
 
         self.update_state(TransferState::Completed);
 
+        let mut event = HookEvent::new(HookEventKind::ShareCompleted)
+            .with_code(self.code.to_string())
+            .with_files(self.files.len() as u64)
+            .with_bytes(self.files.iter().map(|f| f.size).sum());
+        if let Some(name) = &self.receiver_name {
+            event = event.with_peer(name.as_str());
+        }
+        hooks::emit(event);
+
         Ok(())
     }
 
+    /// Accept a receiver, directly or through the relay, and complete the
+    /// TLS handshake.
+    ///
+    /// Returns the receiver's address, or `None` if it came through the relay.
+    async fn accept_connection(&self) -> Result<(ServerTlsStream, Option<SocketAddr>)> {
+        let (stream, peer_addr) = tokio::select! {
+            accepted = self.listener.accept() => {
+                let (stream, peer_addr) = accepted?;
+                let peer_addr = connection::canonical_addr(peer_addr);
+                tracing::info!("Connection from {}", peer_addr);
+                (stream, Some(peer_addr))
+            }
+            stream = self.accept_relayed() => {
+                tracing::info!("Connection through relay");
+                (stream, None)
+            }
+        };
+
+        configure_tcp_keepalive(&stream)?;
+
+        let acceptor = TlsAcceptor::from(Arc::new(
+            self.tls_config
+                .server_config()
+                .ok_or_else(|| Error::TlsError("no server config".to_string()))?
+                .clone(),
+        ));
+        let tls_stream = acceptor
+            .accept(stream)
+            .await
+            .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
+
+        Ok((tls_stream, peer_addr))
+    }
+
+    /// Wait for a receiver through the configured relay.
+    ///
+    /// Never resolves without a relay. Relay errors are logged and retried so
+    /// that direct connections keep working while the relay is unreachable.
+    async fn accept_relayed(&self) -> TcpStream {
+        let Some(relay) = self.config.relay.as_deref() else {
+            return std::future::pending().await;
+        };
+
+        loop {
+            match relay::register(relay, &self.code).await {
+                Ok(stream) => return stream,
+                Err(e) => {
+                    tracing::warn!("Relay {} unavailable: {}", relay, e);
+                    tokio::time::sleep(RELAY_RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+
+    /// Wait for the receiver to reconnect after the connection dropped.
+    ///
+    /// Connections that fail the handshake or code verification are dropped
+    /// and the wait continues, so a stray or hostile connection cannot end an
+    /// interrupted transfer. Each such connection costs the caller one guess
+    /// at the code and is recorded in the audit log. Returns `error` (as
+    /// `ConnectionLost`) once the grace period runs out.
+    async fn await_reconnect(
+        &mut self,
+        error: Error,
+    ) -> Result<(ServerTlsStream, protocol::ResumeRequestPayload)> {
+        let deadline = Instant::now() + self.config.reconnect_grace;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let attempt = tokio::time::timeout(remaining, async {
+                let (mut stream, peer_addr) = self.accept_connection().await?;
+                let request = self.do_resume_handshake(&mut stream, peer_addr).await?;
+                Ok::<_, Error>((stream, peer_addr, request))
+            })
+            .await;
+
+            match attempt {
+                Ok(Ok((stream, peer_addr, request))) => {
+                    if let Some(peer_addr) = peer_addr {
+                        tracing::info!("Receiver reconnected from {}", peer_addr);
+                        self.receiver_addr = Some(peer_addr);
+                    } else {
+                        tracing::info!("Receiver reconnected through relay");
+                    }
+                    return Ok((stream, request));
+                }
+                Ok(Err(e)) => tracing::warn!("Rejected reconnection attempt: {}", e),
+                Err(_) => {
+                    return Err(self.receiver_addr.map_or(error, Error::ConnectionLost));
+                }
+            }
+        }
+    }
+
+    /// Re-authenticate a reconnecting receiver and read its resume request.
+    async fn do_resume_handshake<S>(
+        &self,
+        stream: &mut S,
+        peer_addr: Option<SocketAddr>,
+    ) -> Result<protocol::ResumeRequestPayload>
+    where
+        S: AsyncRead + AsyncWrite + ChannelBinding + Unpin,
+    {
+        let (_name, device_id, public_key, _compression) = self.do_handshake(stream).await?;
+        if device_id != self.receiver_device_id
+            || public_key.as_deref() != self.receiver_public_key.as_deref()
+        {
+            return Err(Error::TrustError(
+                "reconnecting device does not match the original receiver".to_string(),
+            ));
+        }
+
+        self.do_code_verification(stream, peer_addr).await?;
+
+        if !self.do_file_list_exchange(stream).await? {
+            return Err(Error::TransferRejected);
+        }
+
+        let (header, payload) = protocol::read_frame(stream).await?;
+        if header.message_type != MessageType::ResumeRequest {
+            return Err(Error::UnexpectedMessage {
+                expected: "ResumeRequest".to_string(),
+                actual: format!("{:?}", header.message_type),
+            });
+        }
+        let request: protocol::ResumeRequestPayload = protocol::decode_payload(&payload)?;
+
+        let accepted = request.transfer_id == self.transfer_id;
+        let ack = protocol::ResumeAckPayload {
+            accepted,
+            retransfer_files: None,
+            retransfer_chunks: None,
+            reason: (!accepted).then(|| "unknown transfer".to_string()),
+        };
+        let ack_payload = protocol::encode_payload(&ack)?;
+        protocol::write_frame(stream, MessageType::ResumeAck, &ack_payload).await?;
+
+        if !accepted {
+            return Err(Error::ResumeMismatch(
+                "resume request is for a different transfer".to_string(),
+            ));
+        }
+
+        Ok(request)
+    }
+
     /// Cancel the share session.
     pub async fn cancel(&mut self) {
         self.broadcaster.stop().await;
@@ -466,11 +770,10 @@ impl ShareSession {
         match self.config.compression {
             crate::compression::CompressionMode::Never => None,
             crate::compression::CompressionMode::Auto
-            | crate::compression::CompressionMode::Always => {
-                Some(crate::compression::CompressionCapabilities::with_zstd(
-                    self.config.compression_level,
-                ))
-            }
+            | crate::compression::CompressionMode::Always
+            | crate::compression::CompressionMode::Adaptive => Some(
+                crate::compression::CompressionCapabilities::all(self.config.compression_level),
+            ),
         }
     }
 
@@ -513,40 +816,31 @@ impl ShareSession {
         ))
     }
 
-    async fn do_code_verification<S>(&self, stream: &mut S) -> Result<()>
+    async fn do_code_verification<S>(
+        &self,
+        stream: &mut S,
+        peer_addr: Option<SocketAddr>,
+    ) -> Result<()>
     where
-        S: AsyncRead + AsyncWrite + Unpin,
+        S: AsyncRead + AsyncWrite + ChannelBinding + Unpin,
     {
-        let (header, payload) = protocol::read_frame(stream).await?;
-        if header.message_type != MessageType::CodeVerify {
-            return Err(Error::UnexpectedMessage {
-                expected: "CodeVerify".to_string(),
-                actual: format!("{:?}", header.message_type),
-            });
-        }
-
-        let verify: CodeVerifyPayload = protocol::decode_payload(&payload)?;
-
-        let expected_hmac = crypto::hmac_sha256(&self.session_key, self.code.as_str().as_bytes());
-
-        let success = crypto::constant_time_eq(&verify.code_hmac, &expected_hmac);
-
-        let ack = CodeVerifyAckPayload {
-            success,
-            error: if success {
-                None
-            } else {
-                Some("Invalid code".to_string())
-            },
+        let channel_binding = stream.channel_binding()?;
+        let result = match code::check_code(stream, &self.code, &channel_binding).await {
+            Ok(true) => return Ok(()),
+            Ok(false) => Err(Error::CodeNotFound(self.code.to_string())),
+            Err(e) => Err(e),
         };
-        let ack_payload = protocol::encode_payload(&ack)?;
-        protocol::write_frame(stream, MessageType::CodeVerifyAck, &ack_payload).await?;
 
-        if !success {
-            return Err(Error::CodeNotFound(self.code.to_string()));
+        let mut event = AuditEvent::new(AuditEventKind::CodeVerificationFailed)
+            .with_detail(format!("share {}", self.code));
+        if let Some(addr) = peer_addr {
+            event = event.with_peer(addr);
         }
-
-        Ok(())
+        if let (Some(id), Some(name)) = (self.receiver_device_id, &self.receiver_name) {
+            event = event.with_device(id, name.clone());
+        }
+        audit::record(event);
+        result
     }
 
     async fn do_file_list_exchange<S>(&self, stream: &mut S) -> Result<bool>
@@ -556,6 +850,7 @@ impl ShareSession {
         let file_list = FileListPayload {
             files: self.files.clone(),
             total_size: self.files.iter().map(|f| f.size).sum(),
+            transfer_id: Some(self.transfer_id),
         };
         let payload = protocol::encode_payload(&file_list)?;
         protocol::write_frame(stream, MessageType::FileList, &payload).await?;
@@ -583,16 +878,36 @@ impl ShareSession {
     }
 
     #[allow(clippy::too_many_lines)]
-    async fn do_transfer<S>(&self, stream: &mut S) -> Result<()>
+    /// Send all files, skipping chunks listed in `completed`.
+    ///
+    /// `completed` maps file index to the chunks the receiver already has
+    /// (empty on the first connection, filled from a resume request after a
+    /// reconnect).
+    async fn do_transfer<S>(
+        &self,
+        stream: &mut S,
+        completed: &HashMap<usize, Vec<u64>>,
+        stats: &mut CompressionStats,
+    ) -> Result<()>
     where
         S: AsyncRead + AsyncWrite + Unpin,
     {
         use crate::compression::{
-            should_compress_file, CompressionAlgorithm, CompressionDecision, CompressionMode,
+            should_compress_file, AdaptiveCompressor, CompressionAlgorithm, CompressionChoice,
+            CompressionDecision, CompressionMode,
         };
 
         let chunker = FileChunker::new(self.config.chunk_size);
-        let compression_level = i32::from(self.config.compression_level);
+
+        let mut adaptive = (self.config.compression == CompressionMode::Adaptive
+            && !self.common_compression.is_empty())
+        .then(|| AdaptiveCompressor::new(&self.common_compression, self.config.compression_level));
+        let static_choice =
+            self.negotiated_compression
+                .map_or(CompressionChoice::NONE, |algorithm| CompressionChoice {
+                    algorithm,
+                    level: self.config.compression_level,
+                });
 
         for (file_index, file) in self.files.iter().enumerate() {
             {
@@ -604,16 +919,24 @@ impl ShareSession {
                 let _ = self.progress_tx.send(progress);
             }
 
+            let done = completed.get(&file_index);
+
             if file.is_directory {
+                if done.is_some() {
+                    continue;
+                }
+
                 let start = ChunkStartPayload {
                     file_index,
                     chunk_index: 0,
                     total_chunks: 0,
+                    sha256: None,
                 };
                 let start_payload = protocol::encode_payload(&start)?;
                 protocol::write_frame(stream, MessageType::ChunkStart, &start_payload).await?;
 
-                let (header, ack_payload) = protocol::read_frame(stream).await?;
+                let (header, ack_payload) =
+                    protocol::read_frame_with_timeout(stream, CHUNK_ACK_TIMEOUT).await?;
                 if header.message_type != MessageType::ChunkAck {
                     return Err(Error::UnexpectedMessage {
                         expected: "ChunkAck".to_string(),
@@ -641,17 +964,24 @@ impl ShareSession {
 
             let chunks = chunker.read_chunks(&file_path, file_index).await?;
             let total_chunks = chunks.len() as u64;
+            let sha256 = chunks_sha256(&chunks);
 
             if chunks.is_empty() {
+                if done.is_some() {
+                    continue;
+                }
+
                 let start = ChunkStartPayload {
                     file_index,
                     chunk_index: 0,
                     total_chunks: 0,
+                    sha256: None,
                 };
                 let start_payload = protocol::encode_payload(&start)?;
                 protocol::write_frame(stream, MessageType::ChunkStart, &start_payload).await?;
 
-                let (header, ack_payload) = protocol::read_frame(stream).await?;
+                let (header, ack_payload) =
+                    protocol::read_frame_with_timeout(stream, CHUNK_ACK_TIMEOUT).await?;
                 if header.message_type != MessageType::ChunkAck {
                     return Err(Error::UnexpectedMessage {
                         expected: "ChunkAck".to_string(),
@@ -701,34 +1031,62 @@ impl ShareSession {
             );
 
             for chunk in chunks {
+                if done.is_some_and(|done| done.contains(&chunk.chunk_index)) {
+                    continue;
+                }
+
                 let start = ChunkStartPayload {
                     file_index,
                     chunk_index: chunk.chunk_index,
                     total_chunks,
+                    sha256: Some(sha256.clone()),
                 };
                 let start_payload = protocol::encode_payload(&start)?;
                 protocol::write_frame(stream, MessageType::ChunkStart, &start_payload).await?;
 
+                let choice = if !file_should_compress {
+                    CompressionChoice::NONE
+                } else if let Some(ref mut selector) = adaptive {
+                    selector.choose()
+                } else {
+                    static_choice
+                };
+
                 #[allow(clippy::cast_possible_truncation)]
-                let (wire_data, compression_algo, original_size) = if file_should_compress {
-                    match crate::compression::compress(&chunk.data, compression_level) {
-                        Ok(compressed) => {
-                            if compressed.len() < chunk.data.len() {
-                                let orig_len = chunk.data.len() as u32;
-                                (compressed, CompressionAlgorithm::Zstd, Some(orig_len))
-                            } else {
+                let (wire_data, compression_algo, original_size) =
+                    if choice.algorithm == CompressionAlgorithm::None {
+                        (chunk.data.clone(), CompressionAlgorithm::None, None)
+                    } else {
+                        let compress_start = Instant::now();
+                        match crate::compression::compress_chunk(
+                            choice.algorithm,
+                            &chunk.data,
+                            choice.level,
+                        ) {
+                            Ok(compressed) => {
+                                if let Some(ref mut selector) = adaptive {
+                                    selector.record_compression(
+                                        choice,
+                                        chunk.data.len(),
+                                        compressed.len(),
+                                        compress_start.elapsed(),
+                                    );
+                                }
+                                if compressed.len() < chunk.data.len() {
+                                    let orig_len = chunk.data.len() as u32;
+                                    (compressed, choice.algorithm, Some(orig_len))
+                                } else {
+                                    (chunk.data.clone(), CompressionAlgorithm::None, None)
+                                }
+                            }
+                            Err(e) => {
+                                tracing::warn!("Compression failed, sending uncompressed: {}", e);
                                 (chunk.data.clone(), CompressionAlgorithm::None, None)
                             }
                         }
-                        Err(e) => {
-                            tracing::warn!("Compression failed, sending uncompressed: {}", e);
-                            (chunk.data.clone(), CompressionAlgorithm::None, None)
-                        }
-                    }
-                } else {
-                    (chunk.data.clone(), CompressionAlgorithm::None, None)
-                };
+                    };
 
+                let wire_len = wire_data.len();
                 let wire_checksum = xxhash_rust::xxh64::xxh64(&wire_data, 0);
 
                 let data = ChunkDataPayload {
@@ -740,9 +1098,11 @@ impl ShareSession {
                     original_size,
                 };
                 let data_payload = protocol::encode_chunk_data(&data);
+                let send_start = Instant::now();
                 protocol::write_frame(stream, MessageType::ChunkData, &data_payload).await?;
 
-                let (header, ack_payload) = protocol::read_frame(stream).await?;
+                let (header, ack_payload) =
+                    protocol::read_frame_with_timeout(stream, CHUNK_ACK_TIMEOUT).await?;
                 if header.message_type != MessageType::ChunkAck {
                     return Err(Error::UnexpectedMessage {
                         expected: "ChunkAck".to_string(),
@@ -758,6 +1118,11 @@ impl ShareSession {
                     });
                 }
 
+                if let Some(ref mut selector) = adaptive {
+                    selector.record_link(wire_len, send_start.elapsed());
+                }
+                stats.add_chunk(compression_algo, chunk.data.len() as u64, wire_len as u64);
+
                 {
                     let mut progress = self.progress_rx.borrow().clone();
                     progress.file_bytes_transferred += chunk.data.len() as u64;
@@ -819,6 +1184,9 @@ const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
 /// Timeout for Pong response (10 seconds)
 const KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Type alias for the TLS stream used by ShareSession
+type ServerTlsStream = tokio_rustls::server::TlsStream<TcpStream>;
+
 /// Type alias for the TLS stream used by ReceiveSession
 type ClientTlsStream = tokio_rustls::client::TlsStream<TcpStream>;
 
@@ -830,10 +1198,33 @@ struct KeepAliveHandle {
     task_handle: JoinHandle<Result<ClientTlsStream>>,
 }
 
+/// Receiver-side transfer state that survives a reconnect.
+#[derive(Default)]
+struct ReceiveState {
+    /// Writer for the file currently being received
+    writer: Option<FileWriter>,
+    /// Index of the file currently being received
+    file_index: Option<usize>,
+    /// Chunks written so far (file_index -> chunk_indices)
+    completed_chunks: HashMap<usize, Vec<u64>>,
+}
+
+impl ReceiveState {
+    fn mark_completed(&mut self, file_index: usize, chunk_index: u64) {
+        self.completed_chunks
+            .entry(file_index)
+            .or_default()
+            .push(chunk_index);
+    }
+}
+
 /// A receive session (receiver side).
 pub struct ReceiveSession {
     /// Sender information
     sender_addr: SocketAddr,
+    /// Whether the sender is reached through the relay (`sender_addr` is
+    /// then the relay's address)
+    relayed: bool,
     /// Sender device name
     sender_name: String,
     /// Sender device ID (for trust feature)
@@ -844,12 +1235,14 @@ pub struct ReceiveSession {
     files: Vec<FileMetadata>,
     /// Output directory
     output_dir: PathBuf,
-    /// Transfer configuration (reserved for future use)
-    _config: TransferConfig,
+    /// Transfer configuration
+    config: TransferConfig,
     /// Share code used for this transfer session
     code: ShareCode,
-    /// Session key for HMAC verification (reserved for future use)
-    _session_key: [u8; 32],
+    /// Sender's transfer ID (None if the sender cannot resume after a reconnect)
+    transfer_id: Option<Uuid>,
+    /// Files accepted in the `FileListAck` (None = all), repeated on reconnect
+    accepted_files: Option<Vec<usize>>,
     /// Progress sender
     progress_tx: watch::Sender<TransferProgress>,
     /// Progress receiver
@@ -864,6 +1257,7 @@ impl std::fmt::Debug for ReceiveSession {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ReceiveSession")
             .field("sender_addr", &self.sender_addr)
+            .field("relayed", &self.relayed)
             .field("sender_name", &self.sender_name)
             .field("files", &self.files)
             .field("output_dir", &self.output_dir)
@@ -902,7 +1296,9 @@ impl ReceiveSession {
     /// Connect to a file share with optional direct address.
     ///
     /// When `direct_addr` is provided, discovery is bypassed and connection
-    /// is made directly to the specified address.
+    /// is made directly to the specified address. If the sender cannot be
+    /// reached and a relay is configured, the connection goes through the
+    /// relay instead.
     ///
     /// # Errors
     ///
@@ -913,27 +1309,16 @@ impl ReceiveSession {
         direct_addr: Option<SocketAddr>,
         config: TransferConfig,
     ) -> Result<Self> {
-        let transfer_addr = if let Some(addr) = direct_addr {
-            tracing::info!("Connecting directly to {}", addr);
-            addr
-        } else {
-            let listener = HybridListener::new(config.discovery_port).await?;
-            let discovered = listener.find(code, config.discovery_timeout).await?;
-
-            if let Err(e) = listener.shutdown() {
-                tracing::debug!("Listener shutdown: {e}");
-            }
-
-            tracing::info!(
-                "Found share from {} at {} (via hybrid discovery)",
-                discovered.packet.device_name,
-                discovered.source
-            );
-
-            SocketAddr::new(discovered.source.ip(), discovered.packet.transfer_port)
-        };
-
-        let stream = TcpStream::connect(transfer_addr).await?;
+        let (stream, transfer_addr, relayed) =
+            match Self::connect_direct(code, direct_addr, &config).await {
+                Ok((stream, addr)) => (stream, addr, false),
+                Err(e) if config.relay.is_none() => return Err(e),
+                Err(e) => {
+                    tracing::info!("Direct connection failed ({}), trying relay", e);
+                    let (stream, addr) = Self::connect_relayed(code, &config).await?;
+                    (stream, addr, true)
+                }
+            };
 
         configure_tcp_keepalive(&stream)?;
 
@@ -949,29 +1334,30 @@ impl ReceiveSession {
             .await
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
-        let session_key = crypto::derive_session_key(code.as_str());
-
         let (sender_name, sender_device_id, sender_public_key, _sender_compression) =
             Self::do_handshake(&mut tls_stream).await?;
 
-        Self::do_code_verification(&mut tls_stream, code, &session_key).await?;
+        Self::do_code_verification(&mut tls_stream, code).await?;
 
-        let files = Self::receive_file_list(&mut tls_stream).await?;
+        let file_list = Self::receive_file_list(&mut tls_stream, &config.limits).await?;
+        let files = file_list.files;
 
-        let total_bytes: u64 = files.iter().map(|f| f.size).sum();
+        let total_bytes: u64 = limits::total_size(&files);
         let progress = TransferProgress::new(files.len(), total_bytes);
         let (progress_tx, progress_rx) = watch::channel(progress);
 
         Ok(Self {
             sender_addr: transfer_addr,
+            relayed,
             sender_name,
             sender_device_id,
             sender_public_key,
             files,
             output_dir,
-            _config: config,
+            config,
             code: code.clone(),
-            _session_key: session_key,
+            transfer_id: file_list.transfer_id,
+            accepted_files: None,
             progress_tx,
             progress_rx,
             tls_stream: Some(tls_stream),
@@ -979,6 +1365,55 @@ impl ReceiveSession {
         })
     }
 
+    /// Open a TCP connection to the sender at `direct_addr` or found through
+    /// discovery.
+    async fn connect_direct(
+        code: &ShareCode,
+        direct_addr: Option<SocketAddr>,
+        config: &TransferConfig,
+    ) -> Result<(TcpStream, SocketAddr)> {
+        let transfer_addr = if let Some(addr) = direct_addr {
+            tracing::info!("Connecting directly to {}", addr);
+            addr
+        } else {
+            let listener =
+                HybridListener::with_options(config.discovery_port, &config.discovery_options())
+                    .await?;
+            let discovered = listener.find(code, config.discovery_timeout).await;
+
+            if let Err(e) = listener.shutdown() {
+                tracing::debug!("Listener shutdown: {e}");
+            }
+            let discovered = discovered?;
+
+            tracing::info!(
+                "Found share from {} at {} (via hybrid discovery)",
+                discovered.packet.device_name,
+                discovered.source
+            );
+
+            discovered.transfer_addr()
+        };
+
+        let stream = TcpStream::connect(transfer_addr).await?;
+        Ok((stream, transfer_addr))
+    }
+
+    /// Open a connection to the sender through the configured relay.
+    async fn connect_relayed(
+        code: &ShareCode,
+        config: &TransferConfig,
+    ) -> Result<(TcpStream, SocketAddr)> {
+        let relay = config
+            .relay
+            .as_deref()
+            .ok_or_else(|| Error::CodeNotFound(code.to_string()))?;
+        let stream = relay::connect(relay, code).await?;
+        let relay_addr = connection::canonical_addr(stream.peer_addr()?);
+        tracing::info!("Connected to share through relay {}", relay_addr);
+        Ok((stream, relay_addr))
+    }
+
     /// Connect to a trusted device for file receive (codeless).
     ///
     /// Uses TrustedHello handshake with signature verification instead of a share code.
@@ -1029,27 +1464,32 @@ impl ReceiveSession {
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
         let (sender_name, sender_device_id, sender_public_key) =
-            Self::do_trusted_handshake(&mut tls_stream, device).await?;
+            Self::do_trusted_handshake(&mut tls_stream, device)
+                .await
+                .inspect_err(|e| audit::handshake_failed(e, transfer_addr, device))?;
 
-        let files = Self::receive_file_list(&mut tls_stream).await?;
+        let files = Self::receive_file_list(&mut tls_stream, &config.limits)
+            .await?
+            .files;
 
-        let total_bytes: u64 = files.iter().map(|f| f.size).sum();
+        let total_bytes: u64 = limits::total_size(&files);
         let progress = TransferProgress::new(files.len(), total_bytes);
         let (progress_tx, progress_rx) = watch::channel(progress);
 
         let dummy_code = ShareCode::parse("XXXX")?;
-        let dummy_session_key = [0u8; 32];
 
         Ok(Self {
             sender_addr: transfer_addr,
+            relayed: false,
             sender_name,
             sender_device_id: Some(sender_device_id),
             sender_public_key: Some(sender_public_key),
             files,
             output_dir,
-            _config: config,
+            config,
             code: dummy_code,
-            _session_key: dummy_session_key,
+            transfer_id: None,
+            accepted_files: None,
             progress_tx,
             progress_rx,
             tls_stream: Some(tls_stream),
@@ -1063,6 +1503,14 @@ impl ReceiveSession {
         (&self.sender_addr, &self.sender_name)
     }
 
+    /// Whether the sender is reached through a relay.
+    ///
+    /// The address returned by [`sender`](Self::sender) is then the relay's.
+    #[must_use]
+    pub const fn is_relayed(&self) -> bool {
+        self.relayed
+    }
+
     /// Get sender device ID (if available, for trust feature).
     #[must_use]
     pub fn sender_device_id(&self) -> Option<Uuid> {
@@ -1224,9 +1672,10 @@ impl ReceiveSession {
 
         self.update_state(TransferState::Transferring);
 
-        self.do_receive(&mut stream).await?;
+        self.receive_with_reconnect(stream).await?;
 
         self.update_state(TransferState::Completed);
+        self.emit_received();
 
         Ok(())
     }
@@ -1248,22 +1697,175 @@ impl ReceiveSession {
             .take()
             .ok_or_else(|| Error::Internal("no TLS stream".to_string()))?;
 
+        self.accepted_files = Some(indices.to_vec());
         let ack = FileListAckPayload {
             accepted: true,
-            accepted_files: Some(indices.to_vec()),
+            accepted_files: self.accepted_files.clone(),
         };
         let ack_payload = protocol::encode_payload(&ack)?;
         protocol::write_frame(&mut stream, MessageType::FileListAck, &ack_payload).await?;
 
         self.update_state(TransferState::Transferring);
 
-        self.do_receive(&mut stream).await?;
+        self.receive_with_reconnect(stream).await?;
 
         self.update_state(TransferState::Completed);
+        self.emit_received();
 
         Ok(())
     }
 
+    /// Receive files, reconnecting to the sender if the connection drops.
+    ///
+    /// Reconnecting is only attempted when the sender advertised a transfer
+    /// ID and the grace period is non-zero; otherwise the first connection
+    /// error is returned.
+    async fn receive_with_reconnect(&mut self, mut stream: ClientTlsStream) -> Result<()> {
+        let mut state = ReceiveState::default();
+
+        loop {
+            match self.do_receive(&mut stream, &mut state).await {
+                Ok(()) => return Ok(()),
+                Err(e)
+                    if e.is_connection_loss()
+                        && self.transfer_id.is_some()
+                        && !self.config.reconnect_grace.is_zero() =>
+                {
+                    tracing::warn!("Connection to sender lost: {}", e);
+                    self.update_state(TransferState::Reconnecting);
+                    stream = self.reconnect(&state).await?;
+                    self.update_state(TransferState::Transferring);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Find the sender again and resume the transfer within the grace period.
+    async fn reconnect(&mut self, state: &ReceiveState) -> Result<ClientTlsStream> {
+        let deadline = Instant::now() + self.config.reconnect_grace;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match tokio::time::timeout(remaining, self.try_reconnect(state)).await {
+                Ok(Ok(stream)) => {
+                    tracing::info!("Reconnected to sender at {}", self.sender_addr);
+                    return Ok(stream);
+                }
+                Ok(Err(e @ (Error::ResumeRejected(_) | Error::ResumeMismatch(_)))) => {
+                    return Err(e);
+                }
+                Ok(Err(e)) => {
+                    tracing::debug!("Reconnect attempt failed: {}", e);
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    tokio::time::sleep(RECONNECT_RETRY_DELAY.min(remaining)).await;
+                }
+                Err(_) => return Err(Error::ConnectionLost(self.sender_addr)),
+            }
+        }
+    }
+
+    /// Make one attempt to reach the sender, re-authenticate and resume.
+    ///
+    /// Tries the last known address first, then falls back to discovery in
+    /// case the sender's address changed. Relayed sessions go through the
+    /// relay again.
+    async fn try_reconnect(&mut self, state: &ReceiveState) -> Result<ClientTlsStream> {
+        let last_addr = self.sender_addr;
+        let (stream, addr) = if self.relayed {
+            Self::connect_relayed(&self.code, &self.config).await?
+        } else if let Ok(Ok(stream)) =
+            tokio::time::timeout(RECONNECT_CONNECT_TIMEOUT, TcpStream::connect(last_addr)).await
+        {
+            (stream, last_addr)
+        } else {
+            let listener = HybridListener::with_options(
+                self.config.discovery_port,
+                &self.config.discovery_options(),
+            )
+            .await?;
+            let discovered = listener
+                .find(&self.code, self.config.discovery_timeout)
+                .await;
+            if let Err(e) = listener.shutdown() {
+                tracing::debug!("Listener shutdown: {e}");
+            }
+            let discovered = discovered?;
+
+            let addr = discovered.transfer_addr();
+            tracing::info!("Found sender again at {}", addr);
+            (TcpStream::connect(addr).await?, addr)
+        };
+
+        configure_tcp_keepalive(&stream)?;
+
+        let tls_config = TlsConfig::client()?;
+        let connector = TlsConnector::from(Arc::new(
+            tls_config
+                .client_config()
+                .ok_or_else(|| Error::TlsError("no client config".to_string()))?
+                .clone(),
+        ));
+        let mut tls_stream = connector
+            .connect("localhost".try_into().unwrap(), stream)
+            .await
+            .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
+
+        let (_name, device_id, public_key, _compression) =
+            Self::do_handshake(&mut tls_stream).await?;
+        if device_id != self.sender_device_id
+            || public_key.as_deref() != self.sender_public_key.as_deref()
+        {
+            return Err(Error::TrustError(
+                "reconnected device does not match the original sender".to_string(),
+            ));
+        }
+
+        Self::do_code_verification(&mut tls_stream, &self.code).await?;
+
+        let file_list = Self::receive_file_list(&mut tls_stream, &self.config.limits).await?;
+        let Some(transfer_id) = file_list
+            .transfer_id
+            .filter(|id| Some(*id) == self.transfer_id)
+        else {
+            return Err(Error::ResumeMismatch(
+                "sender restarted the transfer".to_string(),
+            ));
+        };
+
+        let ack = FileListAckPayload {
+            accepted: true,
+            accepted_files: self.accepted_files.clone(),
+        };
+        let ack_payload = protocol::encode_payload(&ack)?;
+        protocol::write_frame(&mut tls_stream, MessageType::FileListAck, &ack_payload).await?;
+
+        let resume_request = protocol::ResumeRequestPayload {
+            transfer_id,
+            completed_chunks: state.completed_chunks.clone(),
+            completed_file_hashes: HashMap::new(),
+        };
+        let resume_payload = protocol::encode_payload(&resume_request)?;
+        protocol::write_frame(&mut tls_stream, MessageType::ResumeRequest, &resume_payload).await?;
+
+        let (header, payload) = protocol::read_frame(&mut tls_stream).await?;
+        if header.message_type != MessageType::ResumeAck {
+            return Err(Error::UnexpectedMessage {
+                expected: "ResumeAck".to_string(),
+                actual: format!("{:?}", header.message_type),
+            });
+        }
+
+        let resume_ack: protocol::ResumeAckPayload = protocol::decode_payload(&payload)?;
+        if !resume_ack.accepted {
+            let reason = resume_ack.reason.unwrap_or_else(|| "unknown".to_string());
+            return Err(Error::ResumeRejected(reason));
+        }
+
+        self.sender_addr = addr;
+        Ok(tls_stream)
+    }
+
     /// Decline the transfer.
     pub async fn decline(&mut self) {
         let _ = self.stop_keep_alive().await;
@@ -1313,7 +1915,9 @@ impl ReceiveSession {
     ) -> Result<Self> {
         let code = ShareCode::parse(&resume_state.code)?;
 
-        let listener = HybridListener::new(config.discovery_port).await?;
+        let listener =
+            HybridListener::with_options(config.discovery_port, &config.discovery_options())
+                .await?;
         let discovered = listener.find(&code, config.discovery_timeout).await?;
 
         tracing::info!(
@@ -1322,8 +1926,7 @@ impl ReceiveSession {
             discovered.source
         );
 
-        let transfer_addr =
-            SocketAddr::new(discovered.source.ip(), discovered.packet.transfer_port);
+        let transfer_addr = discovered.transfer_addr();
         let stream = TcpStream::connect(transfer_addr).await?;
 
         configure_tcp_keepalive(&stream)?;
@@ -1340,13 +1943,12 @@ impl ReceiveSession {
             .await
             .map_err(|e| Error::TlsError(format!("TLS handshake failed: {e}")))?;
 
-        let session_key = crypto::derive_session_key(code.as_str());
-
         let (sender_name, sender_device_id, sender_public_key, _sender_compression) =
             Self::do_handshake(&mut tls_stream).await?;
-        Self::do_code_verification(&mut tls_stream, &code, &session_key).await?;
+        Self::do_code_verification(&mut tls_stream, &code).await?;
 
-        let files = Self::receive_file_list(&mut tls_stream).await?;
+        let file_list = Self::receive_file_list(&mut tls_stream, &config.limits).await?;
+        let files = file_list.files;
 
         if files.len() != resume_state.files.len() {
             return Err(Error::ResumeMismatch(
@@ -1354,21 +1956,23 @@ impl ReceiveSession {
             ));
         }
 
-        let total_bytes: u64 = files.iter().map(|f| f.size).sum();
+        let total_bytes: u64 = limits::total_size(&files);
         let mut progress = TransferProgress::new(files.len(), total_bytes);
         progress.total_bytes_transferred = resume_state.bytes_received;
         let (progress_tx, progress_rx) = watch::channel(progress);
 
         Ok(Self {
             sender_addr: transfer_addr,
+            relayed: false,
             sender_name,
             sender_device_id,
             sender_public_key,
             files,
             output_dir: resume_state.output_dir,
-            _config: config,
+            config,
             code,
-            _session_key: session_key,
+            transfer_id: file_list.transfer_id,
+            accepted_files: None,
             progress_tx,
             progress_rx,
             tls_stream: Some(tls_stream),
@@ -1432,6 +2036,7 @@ impl ReceiveSession {
         self.do_receive_resumed(&mut stream, resume_state).await?;
 
         self.update_state(TransferState::Completed);
+        self.emit_received();
 
         Ok(())
     }
@@ -1455,11 +2060,14 @@ impl ReceiveSession {
 
                     if current_file_index != Some(start.file_index) {
                         if let Some(writer) = current_writer.take() {
+                            let (path, size) = (writer.output_path.clone(), writer.expected_size);
                             let _sha256 = writer.finalize_with_full_hash().await?;
+                            emit_file_received(Some(&self.sender_name), path, size);
                         }
 
-                        let file = &self.files[start.file_index];
-                        let output_path = self.output_dir.join(&file.relative_path);
+                        let file = limits::file_entry(&self.files, start.file_index)?;
+                        let output_path =
+                            limits::output_path(&self.output_dir, &file.relative_path)?;
 
                         if start.total_chunks == 0 || file.is_directory {
                             tokio::fs::create_dir_all(&output_path).await.map_err(|e| {
@@ -1498,7 +2106,8 @@ impl ReceiveSession {
 
                         current_writer = Some(
                             FileWriter::new_resumable(output_path, file.size, bytes_completed)
-                                .await?,
+                                .await?
+                                .with_quarantine(self.config.quarantine.clone()),
                         );
                         current_file_index = Some(start.file_index);
 
@@ -1511,10 +2120,14 @@ impl ReceiveSession {
                             let _ = self.progress_tx.send(progress);
                         }
                     }
+
+                    if let (Some(writer), Some(sha256)) = (current_writer.as_mut(), start.sha256) {
+                        if self.config.verify_checksums {
+                            writer.set_expected_sha256(sha256);
+                        }
+                    }
                 }
                 MessageType::ChunkData => {
-                    use crate::compression::CompressionAlgorithm;
-
                     let chunk_data = protocol::decode_chunk_data(&payload)?;
 
                     let wire_checksum = xxhash_rust::xxh64::xxh64(&chunk_data.data, 0);
@@ -1527,34 +2140,30 @@ impl ReceiveSession {
                         let ack_payload = protocol::encode_payload(&ack)?;
                         protocol::write_frame(stream, MessageType::ChunkAck, &ack_payload).await?;
                         return Err(Error::ChecksumMismatch {
-                            file: self.files[chunk_data.file_index].file_name().to_string(),
+                            file: limits::file_entry(&self.files, chunk_data.file_index)?
+                                .file_name()
+                                .to_string(),
                             chunk: chunk_data.chunk_index,
                         });
                     }
 
-                    let decompressed_data = match chunk_data.compression {
-                        CompressionAlgorithm::Zstd => {
-                            match crate::compression::decompress(&chunk_data.data) {
-                                Ok(data) => data,
-                                Err(e) => {
-                                    tracing::error!("Decompression failed: {}", e);
-                                    let ack = ChunkAckPayload {
-                                        file_index: chunk_data.file_index,
-                                        chunk_index: chunk_data.chunk_index,
-                                        success: false,
-                                    };
-                                    let ack_payload = protocol::encode_payload(&ack)?;
-                                    protocol::write_frame(
-                                        stream,
-                                        MessageType::ChunkAck,
-                                        &ack_payload,
-                                    )
-                                    .await?;
-                                    return Err(e);
-                                }
-                            }
+                    let decompressed_data = match crate::compression::decompress_chunk(
+                        chunk_data.compression,
+                        &chunk_data.data,
+                    ) {
+                        Ok(data) => data,
+                        Err(e) => {
+                            tracing::error!("Decompression failed: {}", e);
+                            let ack = ChunkAckPayload {
+                                file_index: chunk_data.file_index,
+                                chunk_index: chunk_data.chunk_index,
+                                success: false,
+                            };
+                            let ack_payload = protocol::encode_payload(&ack)?;
+                            protocol::write_frame(stream, MessageType::ChunkAck, &ack_payload)
+                                .await?;
+                            return Err(e);
                         }
-                        CompressionAlgorithm::None => chunk_data.data.clone(),
                     };
 
                     let decompressed_checksum = xxhash_rust::xxh64::xxh64(&decompressed_data, 0);
@@ -1585,7 +2194,9 @@ impl ReceiveSession {
 
                     if !success {
                         return Err(Error::ChecksumMismatch {
-                            file: self.files[chunk_data.file_index].file_name().to_string(),
+                            file: limits::file_entry(&self.files, chunk_data.file_index)?
+                                .file_name()
+                                .to_string(),
                             chunk: chunk_data.chunk_index,
                         });
                     }
@@ -1612,11 +2223,16 @@ impl ReceiveSession {
                 }
                 MessageType::TransferComplete => {
                     if let Some(writer) = current_writer.take() {
+                        let (path, size) = (writer.output_path.clone(), writer.expected_size);
                         let _sha256 = writer.finalize_with_full_hash().await?;
+                        emit_file_received(Some(&self.sender_name), path, size);
                     }
                     break;
                 }
                 MessageType::TransferCancel => {
+                    if let Some(writer) = current_writer.take() {
+                        writer.discard().await;
+                    }
                     return Err(Error::TransferCancelled);
                 }
                 _ => {
@@ -1637,6 +2253,26 @@ impl ReceiveSession {
         let _ = self.progress_tx.send(progress);
     }
 
+    /// Run the `receive-completed` hooks for the accepted files.
+    fn emit_received(&self) {
+        let accepted: Vec<&FileMetadata> = self.accepted_files.as_ref().map_or_else(
+            || self.files.iter().collect(),
+            |indices| indices.iter().filter_map(|&i| self.files.get(i)).collect(),
+        );
+        hooks::emit(
+            HookEvent::new(HookEventKind::ReceiveCompleted)
+                .with_peer(self.sender_name.as_str())
+                .with_code(self.code.to_string())
+                .with_path(self.output_dir.as_path())
+                .with_files(accepted.len() as u64)
+                .with_bytes(
+                    accepted
+                        .iter()
+                        .fold(0, |total, f| total.saturating_add(f.size)),
+                ),
+        );
+    }
+
     /// Returns (sender_device_name, sender_device_id, sender_public_key, sender_compression_caps) from the Hello message.
     async fn do_handshake<S>(
         stream: &mut S,
@@ -1669,7 +2305,7 @@ impl ReceiveSession {
             protocol_version: "1.0".to_string(),
             device_id: Some(identity.device_id()),
             public_key: Some(identity.public_key_base64()),
-            compression: Some(crate::compression::CompressionCapabilities::with_zstd(1)),
+            compression: Some(crate::compression::CompressionCapabilities::all(1)),
         };
         let ack_payload = protocol::encode_payload(&ack)?;
         protocol::write_frame(stream, MessageType::HelloAck, &ack_payload).await?;
@@ -1682,35 +2318,12 @@ impl ReceiveSession {
         ))
     }
 
-    async fn do_code_verification<S>(
-        stream: &mut S,
-        code: &ShareCode,
-        session_key: &[u8; 32],
-    ) -> Result<()>
+    async fn do_code_verification<S>(stream: &mut S, code: &ShareCode) -> Result<()>
     where
-        S: AsyncRead + AsyncWrite + Unpin,
+        S: AsyncRead + AsyncWrite + ChannelBinding + Unpin,
     {
-        let hmac = crypto::hmac_sha256(session_key, code.as_str().as_bytes());
-        let verify = CodeVerifyPayload {
-            code_hmac: hmac.to_vec(),
-        };
-        let payload = protocol::encode_payload(&verify)?;
-        protocol::write_frame(stream, MessageType::CodeVerify, &payload).await?;
-
-        let (header, ack_payload) = protocol::read_frame(stream).await?;
-        if header.message_type != MessageType::CodeVerifyAck {
-            return Err(Error::UnexpectedMessage {
-                expected: "CodeVerifyAck".to_string(),
-                actual: format!("{:?}", header.message_type),
-            });
-        }
-
-        let ack: CodeVerifyAckPayload = protocol::decode_payload(&ack_payload)?;
-        if !ack.success {
-            return Err(Error::CodeNotFound(code.to_string()));
-        }
-
-        Ok(())
+        let channel_binding = stream.channel_binding()?;
+        code::prove_code(stream, code, &channel_binding).await
     }
 
     /// Perform trusted handshake for file transfer (receiver side).
@@ -1739,12 +2352,6 @@ impl ReceiveSession {
                     )));
                 }
 
-                if hello.public_key != expected_device.public_key {
-                    return Err(Error::TrustError(
-                        "Public key mismatch - device may have been reinstalled".to_string(),
-                    ));
-                }
-
                 let nonce_bytes = BASE64_STANDARD
                     .decode(&hello.nonce)
                     .map_err(|e| Error::ProtocolError(format!("Invalid nonce: {e}")))?;
@@ -1765,6 +2372,17 @@ impl ReceiveSession {
                     return Err(Error::TrustError("Invalid signature".to_string()));
                 }
 
+                if !crate::trust::accept_rotated_key(
+                    expected_device,
+                    &hello.public_key,
+                    &hello.key_rotations,
+                    None,
+                )? {
+                    return Err(Error::TrustError(
+                        "Public key mismatch - device may have been reinstalled".to_string(),
+                    ));
+                }
+
                 let identity = crypto::DeviceIdentity::load_or_generate()?;
                 let device_name = hostname::get().map_or_else(
                     |_| "Unknown".to_string(),
@@ -1781,6 +2399,7 @@ impl ReceiveSession {
                     nonce_signature: Some(BASE64_STANDARD.encode(response_signature)),
                     error: None,
                     trust_level: Some("Full".to_string()),
+                    key_rotations: identity.rotations().to_vec(),
                 };
 
                 let ack_payload = protocol::encode_payload(&ack)?;
@@ -1837,7 +2456,8 @@ impl ReceiveSession {
         }
     }
 
-    async fn receive_file_list<S>(stream: &mut S) -> Result<Vec<FileMetadata>>
+    /// Read the sender's file list, declining it if it breaks `limits`.
+    async fn receive_file_list<S>(stream: &mut S, limits: &ReceiveLimits) -> Result<FileListPayload>
     where
         S: AsyncRead + AsyncWrite + Unpin,
     {
@@ -1850,26 +2470,39 @@ impl ReceiveSession {
         }
 
         let file_list: FileListPayload = protocol::decode_payload(&payload)?;
-        Ok(file_list.files)
+        if let Err(e) = limits.check(&file_list.files) {
+            tracing::warn!("Declining file list: {}", e);
+            let ack = FileListAckPayload {
+                accepted: false,
+                accepted_files: None,
+            };
+            if let Ok(ack_payload) = protocol::encode_payload(&ack) {
+                let _ = protocol::write_frame(stream, MessageType::FileListAck, &ack_payload).await;
+            }
+            return Err(e);
+        }
+        Ok(file_list)
     }
 
+    #[allow(clippy::too_many_lines)]
     async fn handle_chunk_start<S>(
         &self,
         stream: &mut S,
         start: ChunkStartPayload,
-        current_writer: &mut Option<FileWriter>,
-        current_file_index: &mut Option<usize>,
+        state: &mut ReceiveState,
     ) -> Result<()>
     where
         S: AsyncRead + AsyncWrite + Unpin,
     {
-        if *current_file_index != Some(start.file_index) {
-            if let Some(writer) = current_writer.take() {
+        if state.file_index != Some(start.file_index) {
+            if let Some(writer) = state.writer.take() {
+                let (path, size) = (writer.output_path.clone(), writer.expected_size);
                 let _sha256 = writer.finalize().await?;
+                emit_file_received(Some(&self.sender_name), path, size);
             }
 
-            let file = &self.files[start.file_index];
-            let output_path = self.output_dir.join(&file.relative_path);
+            let file = limits::file_entry(&self.files, start.file_index)?;
+            let output_path = limits::output_path(&self.output_dir, &file.relative_path)?;
 
             if file.is_directory {
                 tokio::fs::create_dir_all(&output_path).await.map_err(|e| {
@@ -1897,6 +2530,7 @@ impl ReceiveSession {
                 }
 
                 tracing::debug!("Created directory: {}", output_path.display());
+                state.mark_completed(start.file_index, 0);
 
                 let ack = ChunkAckPayload {
                     file_index: start.file_index,
@@ -1906,7 +2540,7 @@ impl ReceiveSession {
                 let ack_payload = protocol::encode_payload(&ack)?;
                 protocol::write_frame(stream, MessageType::ChunkAck, &ack_payload).await?;
 
-                *current_file_index = Some(start.file_index);
+                state.file_index = Some(start.file_index);
                 return Ok(());
             }
 
@@ -1949,6 +2583,8 @@ impl ReceiveSession {
                 }
 
                 tracing::debug!("Created empty file: {}", output_path.display());
+                state.mark_completed(start.file_index, 0);
+                emit_file_received(Some(&self.sender_name), output_path, 0);
 
                 let ack = ChunkAckPayload {
                     file_index: start.file_index,
@@ -1958,12 +2594,16 @@ impl ReceiveSession {
                 let ack_payload = protocol::encode_payload(&ack)?;
                 protocol::write_frame(stream, MessageType::ChunkAck, &ack_payload).await?;
 
-                *current_file_index = Some(start.file_index);
+                state.file_index = Some(start.file_index);
                 return Ok(());
             }
 
-            *current_writer = Some(FileWriter::new(output_path, file.size).await?);
-            *current_file_index = Some(start.file_index);
+            state.writer = Some(
+                FileWriter::new(output_path, file.size)
+                    .await?
+                    .with_quarantine(self.config.quarantine.clone()),
+            );
+            state.file_index = Some(start.file_index);
 
             let mut progress = self.progress_rx.borrow().clone();
             progress.current_file = start.file_index;
@@ -1972,6 +2612,12 @@ impl ReceiveSession {
             progress.file_total_bytes = file.size;
             let _ = self.progress_tx.send(progress);
         }
+
+        if let (Some(writer), Some(sha256)) = (state.writer.as_mut(), start.sha256) {
+            if self.config.verify_checksums {
+                writer.set_expected_sha256(sha256);
+            }
+        }
         Ok(())
     }
 
@@ -1979,13 +2625,11 @@ impl ReceiveSession {
         &self,
         stream: &mut S,
         payload: &[u8],
-        current_writer: &mut Option<FileWriter>,
+        state: &mut ReceiveState,
     ) -> Result<()>
     where
         S: AsyncRead + AsyncWrite + Unpin,
     {
-        use crate::compression::CompressionAlgorithm;
-
         let chunk_data = protocol::decode_chunk_data(payload)?;
 
         let wire_checksum = xxhash_rust::xxh64::xxh64(&chunk_data.data, 0);
@@ -1998,13 +2642,15 @@ impl ReceiveSession {
             let ack_payload = protocol::encode_payload(&ack)?;
             protocol::write_frame(stream, MessageType::ChunkAck, &ack_payload).await?;
             return Err(Error::ChecksumMismatch {
-                file: self.files[chunk_data.file_index].file_name().to_string(),
+                file: limits::file_entry(&self.files, chunk_data.file_index)?
+                    .file_name()
+                    .to_string(),
                 chunk: chunk_data.chunk_index,
             });
         }
 
-        let decompressed_data = match chunk_data.compression {
-            CompressionAlgorithm::Zstd => match crate::compression::decompress(&chunk_data.data) {
+        let decompressed_data =
+            match crate::compression::decompress_chunk(chunk_data.compression, &chunk_data.data) {
                 Ok(data) => data,
                 Err(e) => {
                     tracing::error!("Decompression failed: {}", e);
@@ -2017,9 +2663,7 @@ impl ReceiveSession {
                     protocol::write_frame(stream, MessageType::ChunkAck, &ack_payload).await?;
                     return Err(e);
                 }
-            },
-            CompressionAlgorithm::None => chunk_data.data.clone(),
-        };
+            };
 
         let decompressed_checksum = xxhash_rust::xxh64::xxh64(&decompressed_data, 0);
 
@@ -2031,11 +2675,14 @@ impl ReceiveSession {
             is_last: false,
         };
 
-        let success = if let Some(ref mut writer) = current_writer {
+        let success = if let Some(ref mut writer) = state.writer {
             writer.write_chunk(&chunk).await.is_ok()
         } else {
             false
         };
+        if success {
+            state.mark_completed(chunk_data.file_index, chunk_data.chunk_index);
+        }
 
         let ack = ChunkAckPayload {
             file_index: chunk_data.file_index,
@@ -2047,7 +2694,9 @@ impl ReceiveSession {
 
         if !success {
             return Err(Error::ChecksumMismatch {
-                file: self.files[chunk_data.file_index].file_name().to_string(),
+                file: limits::file_entry(&self.files, chunk_data.file_index)?
+                    .file_name()
+                    .to_string(),
                 chunk: chunk_data.chunk_index,
             });
         }
@@ -2070,34 +2719,26 @@ impl ReceiveSession {
         Ok(())
     }
 
-    async fn do_receive<S>(&self, stream: &mut S) -> Result<()>
+    async fn do_receive<S>(&self, stream: &mut S, state: &mut ReceiveState) -> Result<()>
     where
         S: AsyncRead + AsyncWrite + Unpin,
     {
-        let mut current_writer: Option<FileWriter> = None;
-        let mut current_file_index: Option<usize> = None;
-
         loop {
             let (header, payload) = protocol::read_frame(stream).await?;
 
             match header.message_type {
                 MessageType::ChunkStart => {
                     let start: ChunkStartPayload = protocol::decode_payload(&payload)?;
-                    self.handle_chunk_start(
-                        stream,
-                        start,
-                        &mut current_writer,
-                        &mut current_file_index,
-                    )
-                    .await?;
+                    self.handle_chunk_start(stream, start, state).await?;
                 }
                 MessageType::ChunkData => {
-                    self.handle_chunk_data(stream, &payload, &mut current_writer)
-                        .await?;
+                    self.handle_chunk_data(stream, &payload, state).await?;
                 }
                 MessageType::TransferComplete => {
-                    if let Some(writer) = current_writer.take() {
+                    if let Some(writer) = state.writer.take() {
+                        let (path, size) = (writer.output_path.clone(), writer.expected_size);
                         let _sha256 = writer.finalize().await?;
+                        emit_file_received(Some(&self.sender_name), path, size);
                     }
                     break;
                 }
@@ -2162,7 +2803,7 @@ impl ResumeState {
         sender_device_id: uuid::Uuid,
         output_dir: PathBuf,
     ) -> Self {
-        let total_bytes = files.iter().map(|f| f.size).sum();
+        let total_bytes = limits::total_size(&files);
         let now = chrono::Utc::now();
 
         Self {
@@ -18,6 +18,7 @@
 //! 3. Verify sender's signature against trust store
 //! 4. Receive files
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -31,20 +32,27 @@ use tokio::sync::watch;
 use tokio_rustls::{TlsAcceptor, TlsConnector};
 use uuid::Uuid;
 
+use crate::audit::{self, AuditEvent, AuditEventKind};
 use crate::config::TrustLevel;
+use crate::connection;
 use crate::crypto::{self, DeviceIdentity, TlsConfig};
-use crate::discovery::{BeaconBroadcaster, BeaconListener, DeviceBeacon, DiscoveredDevice};
+use crate::discovery::{
+    BeaconBroadcaster, DeviceBeacon, DiscoveredDevice, HybridBeaconBroadcaster,
+    HybridBeaconListener,
+};
 use crate::error::{Error, Result};
 use crate::file::{
-    enumerate_files, EnumerateOptions, FileChunk, FileChunker, FileMetadata, FileWriter,
+    chunks_sha256, enumerate_files, EnumerateOptions, FileChunk, FileChunker, FileMetadata,
+    FileWriter,
 };
+use crate::hooks::{self, HookEvent, HookEventKind};
 use crate::protocol::{
     self, ChunkAckPayload, ChunkDataPayload, ChunkStartPayload, FileListAckPayload,
     FileListPayload, MessageType, TrustedHelloAckPayload, TrustedHelloPayload,
 };
-use crate::trust::{TrustStore, TrustedDevice};
+use crate::trust::{Capability, DevicePermissions, TrustStore, TrustedDevice};
 
-use super::{TransferConfig, TransferProgress, TransferState};
+use super::{emit_file_received, limits, TransferConfig, TransferProgress, TransferState};
 
 /// Configure TCP keep-alive on a socket.
 fn configure_tcp_keepalive(stream: &TcpStream) -> Result<()> {
@@ -187,6 +195,12 @@ impl TrustedSendSession {
         });
     }
 
+    /// Use a device found by a discovery run shared with other sessions,
+    /// such as [`discover_devices`] for a group send.
+    pub fn set_discovered_target(&mut self, device: DiscoveredDevice) {
+        self.discovered_target = Some(device);
+    }
+
     /// Clear the discovered target, allowing for fresh discovery.
     pub fn clear_discovered_target(&mut self) {
         self.discovered_target = None;
@@ -202,8 +216,17 @@ impl TrustedSendSession {
     pub async fn discover(&mut self) -> Result<&DiscoveredDevice> {
         self.update_state(TransferState::Waiting);
 
-        let broadcaster = BeaconBroadcaster::new(self.config.discovery_port).await?;
-        let listener = BeaconListener::new(self.config.discovery_port).await?;
+        let broadcaster = BeaconBroadcaster::with_options(
+            self.config.discovery_port,
+            &self.config.discovery_options(),
+        )
+        .await?;
+        let listener = HybridBeaconListener::with_options(
+            self.config.discovery_port,
+            &self.config.discovery_options(),
+        )
+        .await?
+        .with_trusted([&self.target_device]);
 
         let beacon = DeviceBeacon::new(
             self.identity.device_id(),
@@ -214,7 +237,7 @@ impl TrustedSendSession {
         .looking_for(self.target_device.device_id);
 
         broadcaster
-            .start(beacon, self.config.broadcast_interval)
+            .start(beacon, &self.identity, self.config.broadcast_interval)
             .await?;
 
         let discovered = listener
@@ -222,6 +245,9 @@ impl TrustedSendSession {
             .await;
 
         broadcaster.stop().await;
+        if let Err(e) = listener.shutdown() {
+            tracing::debug!("Failed to shut down mDNS listener: {e}");
+        }
 
         match discovered {
             Ok(device) => {
@@ -277,7 +303,9 @@ impl TrustedSendSession {
 
         self.update_state(TransferState::Connected);
 
-        self.do_trusted_handshake(&mut tls_stream).await?;
+        self.do_trusted_handshake(&mut tls_stream)
+            .await
+            .inspect_err(|e| audit::handshake_failed(e, transfer_addr, &self.target_device))?;
 
         let accepted = self.do_file_list_exchange(&mut tls_stream).await?;
         if !accepted {
@@ -286,10 +314,12 @@ impl TrustedSendSession {
         }
 
         self.update_state(TransferState::Transferring);
+        self.emit(HookEventKind::ShareStarted);
 
         self.do_transfer(&mut tls_stream).await?;
 
         self.update_state(TransferState::Completed);
+        self.emit(HookEventKind::ShareCompleted);
 
         Ok(())
     }
@@ -300,6 +330,16 @@ impl TrustedSendSession {
         let _ = self.progress_tx.send(progress);
     }
 
+    /// Run the hooks for a share to the target device.
+    fn emit(&self, kind: HookEventKind) {
+        hooks::emit(
+            HookEvent::new(kind)
+                .with_peer(self.target_device.device_name.as_str())
+                .with_files(self.files.len() as u64)
+                .with_bytes(self.files.iter().map(|f| f.size).sum()),
+        );
+    }
+
     async fn do_trusted_handshake<S>(&self, stream: &mut S) -> Result<()>
     where
         S: AsyncRead + AsyncWrite + Unpin,
@@ -317,6 +357,7 @@ impl TrustedSendSession {
             public_key: self.identity.public_key_base64(),
             nonce: nonce_base64,
             nonce_signature: nonce_signature_base64,
+            key_rotations: self.identity.rotations().to_vec(),
         };
 
         let payload = protocol::encode_payload(&hello)?;
@@ -333,8 +374,10 @@ impl TrustedSendSession {
         let ack: TrustedHelloAckPayload = protocol::decode_payload(&ack_payload)?;
 
         if !ack.trusted {
-            let error = ack.error.unwrap_or_else(|| "not trusted".to_string());
-            return Err(Error::DeviceNotTrusted(error));
+            return Err(ack.error.map_or_else(
+                || Error::DeviceNotTrusted(self.target_device.device_name.clone()),
+                Error::TrustError,
+            ));
         }
 
         if let (Some(pub_key), Some(sig)) = (&ack.public_key, &ack.nonce_signature) {
@@ -350,6 +393,18 @@ impl TrustedSendSession {
                 return Err(Error::SignatureInvalid);
             }
 
+            if !crate::trust::accept_rotated_key(
+                &self.target_device,
+                pub_key,
+                &ack.key_rotations,
+                None,
+            )? {
+                return Err(Error::DeviceNotTrusted(format!(
+                    "public key mismatch for {}",
+                    self.target_device.device_name
+                )));
+            }
+
             tracing::debug!("Receiver signature verified successfully");
         }
 
@@ -363,6 +418,7 @@ impl TrustedSendSession {
         let file_list = FileListPayload {
             files: self.files.clone(),
             total_size: self.files.iter().map(|f| f.size).sum(),
+            transfer_id: None,
         };
         let payload = protocol::encode_payload(&file_list)?;
         protocol::write_frame(stream, MessageType::FileList, &payload).await?;
@@ -410,6 +466,7 @@ impl TrustedSendSession {
                     file_index,
                     chunk_index: 0,
                     total_chunks: 0,
+                    sha256: None,
                 };
                 let start_payload = protocol::encode_payload(&start)?;
                 protocol::write_frame(stream, MessageType::ChunkStart, &start_payload).await?;
@@ -425,12 +482,14 @@ impl TrustedSendSession {
 
             let chunks = chunker.read_chunks(&file_path, file_index).await?;
             let total_chunks = chunks.len() as u64;
+            let sha256 = chunks_sha256(&chunks);
 
             if chunks.is_empty() {
                 let start = ChunkStartPayload {
                     file_index,
                     chunk_index: 0,
                     total_chunks: 0,
+                    sha256: None,
                 };
                 let start_payload = protocol::encode_payload(&start)?;
                 protocol::write_frame(stream, MessageType::ChunkStart, &start_payload).await?;
@@ -441,6 +500,7 @@ impl TrustedSendSession {
                     file_index,
                     chunk_index: chunk.chunk_index,
                     total_chunks,
+                    sha256: Some(sha256.clone()),
                 };
                 let start_payload = protocol::encode_payload(&start)?;
                 protocol::write_frame(stream, MessageType::ChunkStart, &start_payload).await?;
@@ -527,6 +587,50 @@ impl TrustedSendSession {
     }
 }
 
+/// Look for several trusted devices with a single beacon listener.
+///
+/// Used by group sends, where discovering each member separately would bind
+/// one listener per member to the same port and repeat the scan. Listens in
+/// rounds of one beacon interval until every device has been found or
+/// `timeout` runs out, and returns what was found, keyed by device ID.
+///
+/// # Errors
+///
+/// Returns an error if the listener cannot be created.
+pub async fn discover_devices(
+    devices: &[TrustedDevice],
+    config: &TransferConfig,
+    timeout: Duration,
+) -> Result<HashMap<Uuid, DiscoveredDevice>> {
+    let listener =
+        HybridBeaconListener::with_options(config.discovery_port, &config.discovery_options())
+            .await?
+            .with_trusted(devices);
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut found = HashMap::new();
+    while found.len() < devices.len() {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        for device in listener
+            .scan(remaining.min(config.broadcast_interval))
+            .await
+        {
+            let device_id = device.beacon.device_id;
+            if devices.iter().any(|d| d.device_id == device_id) {
+                found.entry(device_id).or_insert(device);
+            }
+        }
+    }
+
+    if let Err(e) = listener.shutdown() {
+        tracing::debug!("Failed to shut down mDNS listener: {e}");
+    }
+    Ok(found)
+}
+
 /// A trusted receive session (receiver waits for trusted senders).
 pub struct TrustedReceiveSession {
     /// Our device identity
@@ -543,8 +647,8 @@ pub struct TrustedReceiveSession {
     listener: TcpListener,
     /// TLS config
     tls_config: TlsConfig,
-    /// Beacon broadcaster
-    broadcaster: BeaconBroadcaster,
+    /// Beacon broadcaster, also advertising presence via mDNS
+    broadcaster: HybridBeaconBroadcaster,
     /// Progress sender
     progress_tx: watch::Sender<TransferProgress>,
     /// Progress receiver
@@ -568,6 +672,8 @@ pub struct SenderInfo {
     pub public_key: String,
     /// Trust level in our store
     pub trust_level: TrustLevel,
+    /// Permissions granted to the sender
+    pub permissions: DevicePermissions,
     /// Sender's address
     pub address: SocketAddr,
 }
@@ -607,10 +713,18 @@ impl TrustedReceiveSession {
 
         let tls_config = TlsConfig::server()?;
 
-        let listener = TcpListener::bind(format!("0.0.0.0:{}", config.transfer_port)).await?;
+        let listener = connection::bind_listener_in_range(
+            config.transfer_port,
+            config.transfer_port_end,
+            config.ipv6,
+        )?;
         let local_addr = listener.local_addr()?;
 
-        let broadcaster = BeaconBroadcaster::new(config.discovery_port).await?;
+        let broadcaster = HybridBeaconBroadcaster::with_options(
+            config.discovery_port,
+            &config.discovery_options(),
+        )
+        .await?;
 
         let beacon = DeviceBeacon::new(
             identity.device_id(),
@@ -620,7 +734,9 @@ impl TrustedReceiveSession {
         )
         .ready_to_receive(true);
 
-        broadcaster.start(beacon, config.broadcast_interval).await?;
+        broadcaster
+            .start(beacon, &identity, config.broadcast_interval)
+            .await?;
 
         let progress = TransferProgress::new(0, 0);
         let (progress_tx, progress_rx) = watch::channel(progress);
@@ -648,6 +764,17 @@ impl TrustedReceiveSession {
         self.identity.device_id()
     }
 
+    /// Get the local address the transfer listener is bound to.
+    ///
+    /// The port is the first free one in the configured transfer port range.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be determined.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
     /// Get a progress receiver.
     #[must_use]
     pub fn progress(&self) -> watch::Receiver<TransferProgress> {
@@ -663,6 +790,7 @@ impl TrustedReceiveSession {
         self.update_state(TransferState::Waiting);
 
         let (stream, peer_addr) = self.listener.accept().await?;
+        let peer_addr = connection::canonical_addr(peer_addr);
         tracing::info!("Connection from {}", peer_addr);
 
         configure_tcp_keepalive(&stream)?;
@@ -683,11 +811,33 @@ impl TrustedReceiveSession {
         let sender_info = self
             .do_trusted_handshake(&mut tls_stream, peer_addr)
             .await?;
-        self.sender_info = Some(sender_info);
 
         self.files = self.receive_file_list(&mut tls_stream).await?;
 
-        let total_bytes: u64 = self.files.iter().map(|f| f.size).sum();
+        if let Err(e) = self.config.limits.check(&self.files) {
+            tracing::warn!("Declining file list: {}", e);
+            self.tls_stream = Some(tls_stream);
+            self.decline().await;
+            return Err(e);
+        }
+
+        let total_bytes = limits::total_size(&self.files);
+        if let Err(e) = sender_info
+            .permissions
+            .check_size(&sender_info.device_name, total_bytes)
+        {
+            audit::record(
+                AuditEvent::new(AuditEventKind::CapabilityDenied)
+                    .with_peer(sender_info.address)
+                    .with_device(sender_info.device_id, sender_info.device_name.clone())
+                    .with_detail(e.to_string()),
+            );
+            self.tls_stream = Some(tls_stream);
+            self.decline().await;
+            return Err(e);
+        }
+        self.sender_info = Some(sender_info);
+
         let mut progress = TransferProgress::new(self.files.len(), total_bytes);
         progress.state = TransferState::Connected;
         let _ = self.progress_tx.send(progress);
@@ -709,6 +859,12 @@ impl TrustedReceiveSession {
         &self.files
     }
 
+    fn sender_name(&self) -> Option<&str> {
+        self.sender_info
+            .as_ref()
+            .map(|info| info.device_name.as_str())
+    }
+
     /// Accept the transfer and receive files.
     ///
     /// # Errors
@@ -735,6 +891,15 @@ impl TrustedReceiveSession {
 
         self.update_state(TransferState::Completed);
 
+        let mut event = HookEvent::new(HookEventKind::ReceiveCompleted)
+            .with_path(self.output_dir.as_path())
+            .with_files(self.files.len() as u64)
+            .with_bytes(limits::total_size(&self.files));
+        if let Some(name) = self.sender_name() {
+            event = event.with_peer(name);
+        }
+        hooks::emit(event);
+
         Ok(())
     }
 
@@ -797,24 +962,58 @@ impl TrustedReceiveSession {
             .try_into()
             .map_err(|_| Error::ProtocolError("invalid signature length".to_string()))?;
 
+        let event = |kind| {
+            AuditEvent::new(kind)
+                .with_peer(peer_addr)
+                .with_device(hello.device_id, hello.device_name.clone())
+        };
+
         if !DeviceIdentity::verify_base64(&hello.public_key, &nonce, &signature) {
+            audit::record(event(AuditEventKind::SignatureInvalid));
             return Err(Error::SignatureInvalid);
         }
 
         tracing::debug!("Sender signature verified");
 
-        let trusted_device = self
-            .trust_store
-            .find_by_id(&hello.device_id)
-            .ok_or_else(|| Error::DeviceNotTrusted(hello.device_name.clone()))?;
+        let Some(trusted_device) = self.trust_store.find_by_id(&hello.device_id) else {
+            audit::record(event(AuditEventKind::UntrustedDevice));
+            return Err(Error::DeviceNotTrusted(hello.device_name.clone()));
+        };
 
-        if trusted_device.public_key != hello.public_key {
+        if !crate::trust::accept_rotated_key(
+            trusted_device,
+            &hello.public_key,
+            &hello.key_rotations,
+            Some(self.trust_store.path().clone()),
+        )? {
+            audit::record(event(AuditEventKind::KeyMismatch));
             return Err(Error::DeviceNotTrusted(format!(
                 "public key mismatch for {}",
                 hello.device_name
             )));
         }
 
+        let permissions = &trusted_device.permissions;
+        if let Err(e) = permissions
+            .check(&hello.device_name, Capability::ReceiveFiles)
+            .and_then(|()| permissions.check_output_dir(&hello.device_name, &self.output_dir))
+        {
+            audit::record(event(AuditEventKind::CapabilityDenied).with_detail(e.to_string()));
+            let ack = TrustedHelloAckPayload {
+                trusted: false,
+                device_name: None,
+                device_id: None,
+                public_key: None,
+                nonce_signature: None,
+                error: Some(e.to_string()),
+                trust_level: None,
+                key_rotations: Vec::new(),
+            };
+            let ack_payload = protocol::encode_payload(&ack)?;
+            protocol::write_frame(stream, MessageType::TrustedHelloAck, &ack_payload).await?;
+            return Err(e);
+        }
+
         tracing::info!(
             "Verified trusted sender: {} (trust level: {:?})",
             hello.device_name,
@@ -832,6 +1031,7 @@ impl TrustedReceiveSession {
             nonce_signature: Some(our_signature_base64),
             error: None,
             trust_level: Some(format!("{:?}", trusted_device.trust_level)),
+            key_rotations: self.identity.rotations().to_vec(),
         };
 
         let ack_payload = protocol::encode_payload(&ack)?;
@@ -842,6 +1042,7 @@ impl TrustedReceiveSession {
             device_name: hello.device_name,
             public_key: hello.public_key,
             trust_level: trusted_device.trust_level,
+            permissions: trusted_device.permissions.clone(),
             address: peer_addr,
         })
     }
@@ -870,11 +1071,13 @@ impl TrustedReceiveSession {
     ) -> Result<()> {
         if *current_file_index != Some(start.file_index) {
             if let Some(writer) = current_writer.take() {
+                let (path, size) = (writer.output_path.clone(), writer.expected_size);
                 let _sha256 = writer.finalize().await?;
+                emit_file_received(self.sender_name(), path, size);
             }
 
-            let file = &self.files[start.file_index];
-            let output_path = self.output_dir.join(&file.relative_path);
+            let file = limits::file_entry(&self.files, start.file_index)?;
+            let output_path = limits::output_path(&self.output_dir, &file.relative_path)?;
 
             if start.total_chunks == 0 || file.is_directory {
                 tokio::fs::create_dir_all(&output_path).await.map_err(|e| {
@@ -906,7 +1109,11 @@ impl TrustedReceiveSession {
                 return Ok(());
             }
 
-            *current_writer = Some(FileWriter::new(output_path, file.size).await?);
+            *current_writer = Some(
+                FileWriter::new(output_path, file.size)
+                    .await?
+                    .with_quarantine(self.config.quarantine.clone()),
+            );
             *current_file_index = Some(start.file_index);
 
             let mut progress = self.progress_rx.borrow().clone();
@@ -916,6 +1123,12 @@ impl TrustedReceiveSession {
             progress.file_total_bytes = file.size;
             let _ = self.progress_tx.send(progress);
         }
+
+        if let (Some(writer), Some(sha256)) = (current_writer.as_mut(), start.sha256) {
+            if self.config.verify_checksums {
+                writer.set_expected_sha256(sha256);
+            }
+        }
         Ok(())
     }
 
@@ -954,7 +1167,9 @@ impl TrustedReceiveSession {
 
         if !success {
             return Err(Error::ChecksumMismatch {
-                file: self.files[chunk_data.file_index].file_name().to_string(),
+                file: limits::file_entry(&self.files, chunk_data.file_index)?
+                    .file_name()
+                    .to_string(),
                 chunk: chunk_data.chunk_index,
             });
         }
@@ -999,7 +1214,9 @@ impl TrustedReceiveSession {
                 }
                 MessageType::TransferComplete => {
                     if let Some(writer) = current_writer.take() {
+                        let (path, size) = (writer.output_path.clone(), writer.expected_size);
                         let _sha256 = writer.finalize().await?;
+                        emit_file_received(self.sender_name(), path, size);
                     }
                     break;
                 }
@@ -1030,6 +1247,7 @@ mod tests {
             device_name: "Test Device".to_string(),
             public_key: "public_key".to_string(),
             trust_level: TrustLevel::Full,
+            permissions: DevicePermissions::default(),
             address: "127.0.0.1:52530".parse().unwrap(),
         };
 
@@ -16,17 +16,53 @@
 //! - Subsequent connections verify signature
 //! - Prevents impersonation of trusted devices
 //! - Trust database stored locally, never synced
+//!
+//! ## Permissions
+//!
+//! On top of the trust level, each device carries [`DevicePermissions`]:
+//! which [`Capability`]s it has (sending files, pushing or reading the
+//! clipboard, directory sync), which folders it may sync, where its files
+//! may be saved, and how large a single transfer may be. Devices trusted
+//! before permissions existed have every capability and no limits.
+//!
+//! ## Key Rotation
+//!
+//! A device that rotates its key (`yoop identity rotate`) keeps its device ID
+//! and sends the signed rotation chain in every trusted handshake. A stored
+//! key that the chain leads away from is replaced with the presented one; see
+//! [`TrustStore::apply_rotations`].
+//!
+//! ## Pairing
+//!
+//! `yoop trust pair` runs on both devices, exchanges keys and shows a short
+//! authentication string that the users compare before either side stores
+//! the other; see [`pairing`].
+//!
+//! ## Groups
+//!
+//! Devices can be collected into named groups (e.g. `team`) so a send can
+//! target all of them at once with `yoop send @team`.
+//!
+//! ## Static Peers
+//!
+//! On VPN and overlay networks, where discovery does not reach, `[[peers]]`
+//! config entries give fixed addresses for a device; see [`peers`].
+
+pub mod pairing;
+pub mod peers;
 
 use std::fs;
 use std::io::{BufReader, BufWriter};
 use std::net::IpAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::audit::{self, AuditEvent, AuditEventKind};
 use crate::config::TrustLevel;
+use crate::crypto::{self, KeyRotation};
 use crate::error::{Error, Result};
 
 /// A trusted device record.
@@ -57,6 +93,13 @@ pub struct TrustedDevice {
     /// When the address was last updated
     #[serde(skip_serializing_if = "Option::is_none")]
     pub address_updated_at: Option<SystemTime>,
+    /// What the device is allowed to do
+    #[serde(default, skip_serializing_if = "DevicePermissions::is_unrestricted")]
+    pub permissions: DevicePermissions,
+    /// Base64-encoded key resolving the device's private beacons, learned
+    /// when pairing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolving_key: Option<String>,
 }
 
 impl TrustedDevice {
@@ -76,6 +119,8 @@ impl TrustedDevice {
             last_known_ip: None,
             last_known_port: None,
             address_updated_at: None,
+            permissions: DevicePermissions::default(),
+            resolving_key: None,
         }
     }
 
@@ -86,10 +131,26 @@ impl TrustedDevice {
         self
     }
 
+    /// Set the key resolving the device's private beacons.
+    #[must_use]
+    pub fn with_resolving_key(mut self, key: Option<String>) -> Self {
+        self.resolving_key = key;
+        self
+    }
+
+    /// Set the permissions.
+    #[must_use]
+    pub fn with_permissions(mut self, permissions: DevicePermissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
     /// Set the last known address.
+    ///
+    /// IPv4-mapped IPv6 addresses are stored as plain IPv4.
     #[must_use]
     pub fn with_address(mut self, ip: IpAddr, port: u16) -> Self {
-        self.last_known_ip = Some(ip);
+        self.last_known_ip = Some(ip.to_canonical());
         self.last_known_port = Some(port);
         self.address_updated_at = Some(SystemTime::now());
         self
@@ -108,6 +169,213 @@ impl TrustedDevice {
     }
 }
 
+/// An action a trusted device can be allowed to perform on this device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Send files to this device
+    ReceiveFiles,
+    /// Write its clipboard content into this device's clipboard
+    PushClipboard,
+    /// Read this device's clipboard
+    ReadClipboard,
+    /// Synchronize directories with this device
+    Sync,
+}
+
+impl Capability {
+    /// Every capability, in display order.
+    pub const ALL: [Self; 4] = [
+        Self::ReceiveFiles,
+        Self::PushClipboard,
+        Self::ReadClipboard,
+        Self::Sync,
+    ];
+
+    /// Short name used on the command line.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ReceiveFiles => "files",
+            Self::PushClipboard => "clipboard-push",
+            Self::ReadClipboard => "clipboard-read",
+            Self::Sync => "sync",
+        }
+    }
+
+    /// Parse a capability from its short name.
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.as_str().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// What the capability lets a device do, for error messages.
+    const fn action(self) -> &'static str {
+        match self {
+            Self::ReceiveFiles => "send files",
+            Self::PushClipboard => "write to the clipboard",
+            Self::ReadClipboard => "read the clipboard",
+            Self::Sync => "sync directories",
+        }
+    }
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Capability grants and limits for a trusted device.
+///
+/// A missing folder list means "anywhere" and an empty one means
+/// "nowhere"; a missing size limit means "any size".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DevicePermissions {
+    /// Granted capabilities
+    pub capabilities: Vec<Capability>,
+    /// Local directories the device may sync (`None` = any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_folders: Option<Vec<PathBuf>>,
+    /// Largest single transfer accepted from the device, in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_transfer_size: Option<u64>,
+    /// Directories received files may be saved to (`None` = any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dirs: Option<Vec<PathBuf>>,
+}
+
+impl Default for DevicePermissions {
+    fn default() -> Self {
+        Self {
+            capabilities: Capability::ALL.to_vec(),
+            sync_folders: None,
+            max_transfer_size: None,
+            output_dirs: None,
+        }
+    }
+}
+
+impl DevicePermissions {
+    /// Whether these are the default, unrestricted permissions.
+    #[must_use]
+    pub fn is_unrestricted(&self) -> bool {
+        Capability::ALL.iter().all(|c| self.allows(*c))
+            && self.sync_folders.is_none()
+            && self.max_transfer_size.is_none()
+            && self.output_dirs.is_none()
+    }
+
+    /// Whether a capability is granted.
+    #[must_use]
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// Grant a capability.
+    pub fn grant(&mut self, capability: Capability) {
+        if !self.allows(capability) {
+            self.capabilities.push(capability);
+        }
+    }
+
+    /// Revoke a capability.
+    pub fn revoke(&mut self, capability: Capability) {
+        self.capabilities.retain(|c| *c != capability);
+    }
+
+    /// Check that `device_name` may use a capability.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::CapabilityDenied`] if the capability is not granted.
+    pub fn check(&self, device_name: &str, capability: Capability) -> Result<()> {
+        if self.allows(capability) {
+            Ok(())
+        } else {
+            Err(Error::CapabilityDenied {
+                device: device_name.to_string(),
+                action: capability.action().to_string(),
+            })
+        }
+    }
+
+    /// Check a transfer of `size` bytes against the size limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::CapabilityDenied`] if the transfer is too large.
+    pub fn check_size(&self, device_name: &str, size: u64) -> Result<()> {
+        match self.max_transfer_size {
+            Some(limit) if size > limit => Err(Error::CapabilityDenied {
+                device: device_name.to_string(),
+                action: format!("send {size} bytes at once (limit {limit})"),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that `dir` may be synced with the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::CapabilityDenied`] if sync is not granted or the
+    /// directory is outside the allowed folders.
+    pub fn check_sync_folder(&self, device_name: &str, dir: &Path) -> Result<()> {
+        self.check(device_name, Capability::Sync)?;
+        if within_any(dir, self.sync_folders.as_deref()) {
+            Ok(())
+        } else {
+            Err(Error::CapabilityDenied {
+                device: device_name.to_string(),
+                action: format!("sync {}", dir.display()),
+            })
+        }
+    }
+
+    /// Check that files from the device may be saved to `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::CapabilityDenied`] if the directory is outside the
+    /// allowed output directories.
+    pub fn check_output_dir(&self, device_name: &str, dir: &Path) -> Result<()> {
+        if within_any(dir, self.output_dirs.as_deref()) {
+            Ok(())
+        } else {
+            Err(Error::CapabilityDenied {
+                device: device_name.to_string(),
+                action: format!("save files to {}", dir.display()),
+            })
+        }
+    }
+}
+
+/// Whether `path` is inside one of `roots`; no list at all allows anything,
+/// while an empty list allows nothing.
+fn within_any(path: &Path, roots: Option<&[PathBuf]>) -> bool {
+    let Some(roots) = roots else {
+        return true;
+    };
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    roots.iter().any(|root| {
+        let root = root.canonicalize().unwrap_or_else(|_| root.clone());
+        path.starts_with(root)
+    })
+}
+
+/// A named group of trusted devices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceGroup {
+    /// Group name (without the `@` prefix)
+    pub name: String,
+    /// IDs of the member devices
+    pub members: Vec<Uuid>,
+}
+
 /// Serializable wrapper for the trust database.
 #[derive(Debug, Serialize, Deserialize)]
 struct TrustDatabase {
@@ -115,6 +383,9 @@ struct TrustDatabase {
     version: u32,
     /// List of trusted devices
     devices: Vec<TrustedDevice>,
+    /// Device groups
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<DeviceGroup>,
 }
 
 impl Default for TrustDatabase {
@@ -122,6 +393,7 @@ impl Default for TrustDatabase {
         Self {
             version: 1,
             devices: Vec::new(),
+            groups: Vec::new(),
         }
     }
 }
@@ -133,6 +405,8 @@ pub struct TrustStore {
     path: PathBuf,
     /// Trusted devices
     devices: Vec<TrustedDevice>,
+    /// Device groups
+    groups: Vec<DeviceGroup>,
 }
 
 impl TrustStore {
@@ -156,6 +430,7 @@ impl TrustStore {
             return Ok(Self {
                 path,
                 devices: Vec::new(),
+                groups: Vec::new(),
             });
         }
 
@@ -179,6 +454,7 @@ impl TrustStore {
         Ok(Self {
             path,
             devices: db.devices,
+            groups: db.groups,
         })
     }
 
@@ -208,6 +484,7 @@ impl TrustStore {
         let db = TrustDatabase {
             version: 1,
             devices: self.devices.clone(),
+            groups: self.groups.clone(),
         };
 
         let file = fs::File::create(&self.path).map_err(|e| {
@@ -252,13 +529,26 @@ impl TrustStore {
 
     /// Add a trusted device.
     ///
+    /// A record replacing an existing one keeps its resolving key unless it
+    /// brings a new one.
+    ///
     /// # Errors
     ///
     /// Returns an error if the device cannot be added.
-    pub fn add(&mut self, device: TrustedDevice) -> Result<()> {
+    pub fn add(&mut self, mut device: TrustedDevice) -> Result<()> {
+        let event = AuditEvent::new(AuditEventKind::TrustAdded)
+            .with_device(device.device_id, device.device_name.clone())
+            .with_detail(format!("trust level {:?}", device.trust_level));
+        if device.resolving_key.is_none() {
+            device.resolving_key = self
+                .find_by_id(&device.device_id)
+                .and_then(|existing| existing.resolving_key.clone());
+        }
         self.devices.retain(|d| d.device_id != device.device_id);
         self.devices.push(device);
-        self.save()
+        self.save()?;
+        audit::record(event);
+        Ok(())
     }
 
     /// Remove a trusted device by ID.
@@ -267,13 +557,20 @@ impl TrustStore {
     ///
     /// Returns an error if the store cannot be saved.
     pub fn remove(&mut self, device_id: &Uuid) -> Result<bool> {
-        let len_before = self.devices.len();
-        self.devices.retain(|d| &d.device_id != device_id);
-        let removed = self.devices.len() < len_before;
-        if removed {
-            self.save()?;
+        let Some(index) = self.devices.iter().position(|d| &d.device_id == device_id) else {
+            return Ok(false);
+        };
+        let device = self.devices.remove(index);
+        for group in &mut self.groups {
+            group.members.retain(|id| id != device_id);
         }
-        Ok(removed)
+        self.groups.retain(|g| !g.members.is_empty());
+        self.save()?;
+        audit::record(
+            AuditEvent::new(AuditEventKind::TrustRemoved)
+                .with_device(device.device_id, device.device_name),
+        );
+        Ok(true)
     }
 
     /// Update trust level for a device.
@@ -284,7 +581,34 @@ impl TrustStore {
     pub fn set_trust_level(&mut self, device_id: &Uuid, level: TrustLevel) -> Result<bool> {
         if let Some(device) = self.devices.iter_mut().find(|d| &d.device_id == device_id) {
             device.trust_level = level;
+            let event = AuditEvent::new(AuditEventKind::TrustChanged)
+                .with_device(device.device_id, device.device_name.clone())
+                .with_detail(format!("trust level {level:?}"));
             self.save()?;
+            audit::record(event);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Replace the permissions of a device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be saved.
+    pub fn set_permissions(
+        &mut self,
+        device_id: &Uuid,
+        permissions: DevicePermissions,
+    ) -> Result<bool> {
+        if let Some(device) = self.devices.iter_mut().find(|d| &d.device_id == device_id) {
+            device.permissions = permissions;
+            let event = AuditEvent::new(AuditEventKind::TrustChanged)
+                .with_device(device.device_id, device.device_name.clone())
+                .with_detail("permissions updated");
+            self.save()?;
+            audit::record(event);
             Ok(true)
         } else {
             Ok(false)
@@ -308,6 +632,40 @@ impl TrustStore {
             .is_some_and(|d| d.public_key == public_key)
     }
 
+    /// Accept a presented public key, following signed key rotations.
+    ///
+    /// Returns `true` if the stored key already matches, or if `rotations`
+    /// contains a verified chain from the stored key to `public_key`; in the
+    /// latter case the stored key is replaced and the store saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be saved.
+    pub fn apply_rotations(
+        &mut self,
+        device_id: &Uuid,
+        public_key: &str,
+        rotations: &[KeyRotation],
+    ) -> Result<bool> {
+        let Some(device) = self.devices.iter_mut().find(|d| &d.device_id == device_id) else {
+            return Ok(false);
+        };
+        if device.public_key == public_key {
+            return Ok(true);
+        }
+        if crypto::follow_rotations(*device_id, &device.public_key, rotations) != public_key {
+            return Ok(false);
+        }
+
+        tracing::info!("{} rotated its identity key", device.device_name);
+        device.public_key = public_key.to_string();
+        let event = AuditEvent::new(AuditEventKind::KeyRotated)
+            .with_device(device.device_id, device.device_name.clone());
+        self.save()?;
+        audit::record(event);
+        Ok(true)
+    }
+
     /// Update the last seen timestamp for a device.
     ///
     /// # Errors
@@ -335,8 +693,16 @@ impl TrustStore {
     ///
     /// Returns an error if the store cannot be saved.
     pub fn clear(&mut self) -> Result<()> {
-        self.devices.clear();
-        self.save()
+        let removed = std::mem::take(&mut self.devices);
+        self.groups.clear();
+        self.save()?;
+        for device in removed {
+            audit::record(
+                AuditEvent::new(AuditEventKind::TrustRemoved)
+                    .with_device(device.device_id, device.device_name),
+            );
+        }
+        Ok(())
     }
 
     /// Update the stored address for a device.
@@ -348,7 +714,7 @@ impl TrustStore {
     /// Returns an error if the store cannot be saved.
     pub fn update_address(&mut self, device_id: &Uuid, ip: IpAddr, port: u16) -> Result<bool> {
         if let Some(device) = self.devices.iter_mut().find(|d| &d.device_id == device_id) {
-            device.last_known_ip = Some(ip);
+            device.last_known_ip = Some(ip.to_canonical());
             device.last_known_port = Some(port);
             device.address_updated_at = Some(SystemTime::now());
             self.save()?;
@@ -377,6 +743,142 @@ impl TrustStore {
         devices.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
         devices
     }
+
+    /// List all device groups.
+    #[must_use]
+    pub fn groups(&self) -> &[DeviceGroup] {
+        &self.groups
+    }
+
+    /// Find a group by name (case-insensitive, with or without `@`).
+    #[must_use]
+    pub fn find_group(&self, name: &str) -> Option<&DeviceGroup> {
+        let name = name.trim_start_matches('@');
+        self.groups
+            .iter()
+            .find(|g| g.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Get the trusted devices in a group.
+    ///
+    /// Returns `None` if the group does not exist.
+    #[must_use]
+    pub fn group_members(&self, name: &str) -> Option<Vec<&TrustedDevice>> {
+        self.find_group(name).map(|group| {
+            group
+                .members
+                .iter()
+                .filter_map(|id| self.find_by_id(id))
+                .collect()
+        })
+    }
+
+    /// Add devices to a group, creating the group if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the group name is invalid, a device is not
+    /// trusted, or the store cannot be saved.
+    pub fn add_to_group(&mut self, name: &str, device_ids: &[Uuid]) -> Result<()> {
+        let name = name.trim_start_matches('@');
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(Error::InvalidInput(format!("invalid group name: '{name}'")));
+        }
+        if let Some(unknown) = device_ids.iter().find(|id| !self.is_trusted(id)) {
+            return Err(Error::DeviceNotTrusted(unknown.to_string()));
+        }
+
+        let index = if let Some(index) = self
+            .groups
+            .iter()
+            .position(|g| g.name.eq_ignore_ascii_case(name))
+        {
+            index
+        } else {
+            self.groups.push(DeviceGroup {
+                name: name.to_string(),
+                members: Vec::new(),
+            });
+            self.groups.len() - 1
+        };
+
+        let group = &mut self.groups[index];
+        for id in device_ids {
+            if !group.members.contains(id) {
+                group.members.push(*id);
+            }
+        }
+
+        self.save()
+    }
+
+    /// Remove devices from a group.
+    ///
+    /// Returns `false` if the group does not exist. Empty groups are deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be saved.
+    pub fn remove_from_group(&mut self, name: &str, device_ids: &[Uuid]) -> Result<bool> {
+        let name = name.trim_start_matches('@');
+        let Some(group) = self
+            .groups
+            .iter_mut()
+            .find(|g| g.name.eq_ignore_ascii_case(name))
+        else {
+            return Ok(false);
+        };
+
+        group.members.retain(|id| !device_ids.contains(id));
+        self.groups.retain(|g| !g.members.is_empty());
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Delete a group (its devices stay trusted).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be saved.
+    pub fn remove_group(&mut self, name: &str) -> Result<bool> {
+        let name = name.trim_start_matches('@');
+        let len_before = self.groups.len();
+        self.groups.retain(|g| !g.name.eq_ignore_ascii_case(name));
+        let removed = self.groups.len() < len_before;
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+}
+
+/// Check a trusted device's presented key against its stored record.
+///
+/// A mismatch is accepted only when `rotations` chains from the stored key to
+/// the presented one; the trust store at `store_path` (or the default store)
+/// is then updated.
+///
+/// # Errors
+///
+/// Returns an error if the trust store cannot be loaded or saved.
+pub fn accept_rotated_key(
+    device: &TrustedDevice,
+    public_key: &str,
+    rotations: &[KeyRotation],
+    store_path: Option<PathBuf>,
+) -> Result<bool> {
+    if device.public_key == public_key {
+        return Ok(true);
+    }
+    if crypto::follow_rotations(device.device_id, &device.public_key, rotations) != public_key {
+        return Ok(false);
+    }
+
+    let mut store = match store_path {
+        Some(path) => TrustStore::load_from(path)?,
+        None => TrustStore::load()?,
+    };
+    store.apply_rotations(&device.device_id, public_key, rotations)
 }
 
 #[cfg(test)]
@@ -438,6 +940,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trust_store_add_keeps_resolving_key() {
+        let tmp_dir = TempDir::new().unwrap();
+        let trust_path = tmp_dir.path().join("trust.json");
+
+        let mut store = TrustStore::load_from(trust_path.clone()).unwrap();
+        let device = create_test_device().with_resolving_key(Some("paired-key".to_string()));
+        let device_id = device.device_id;
+        store.add(device.clone()).unwrap();
+
+        store
+            .add(
+                device
+                    .with_resolving_key(None)
+                    .with_trust_level(TrustLevel::Full),
+            )
+            .unwrap();
+
+        let loaded = TrustStore::load_from(trust_path).unwrap();
+        let stored = loaded.find_by_id(&device_id).unwrap();
+        assert_eq!(stored.resolving_key.as_deref(), Some("paired-key"));
+        assert_eq!(stored.trust_level, TrustLevel::Full);
+    }
+
     #[test]
     fn test_load_nonexistent_file() {
         let tmp_dir = TempDir::new().unwrap();
@@ -500,6 +1026,43 @@ mod tests {
         assert!(updated.address_updated_at.is_some());
     }
 
+    #[test]
+    fn test_update_address_ipv6() {
+        let tmp_dir = TempDir::new().unwrap();
+        let trust_path = tmp_dir.path().join("trust.json");
+
+        let mut store = TrustStore::load_from(trust_path.clone()).unwrap();
+        let device = create_test_device();
+        let device_id = device.device_id;
+        store.add(device).unwrap();
+
+        let ip: IpAddr = "2001:db8::42".parse().unwrap();
+        assert!(store.update_address(&device_id, ip, 52530).unwrap());
+
+        let reloaded = TrustStore::load_from(trust_path).unwrap();
+        assert_eq!(reloaded.get_address(&device_id), Some((ip, 52530)));
+    }
+
+    #[test]
+    fn test_update_address_canonicalises_mapped_ipv4() {
+        let tmp_dir = TempDir::new().unwrap();
+        let trust_path = tmp_dir.path().join("trust.json");
+
+        let mut store = TrustStore::load_from(trust_path).unwrap();
+        let device = create_test_device();
+        let device_id = device.device_id;
+        store.add(device).unwrap();
+
+        let mapped: IpAddr = "::ffff:192.168.1.7".parse().unwrap();
+        assert!(store.update_address(&device_id, mapped, 52530).unwrap());
+
+        let expected: IpAddr = "192.168.1.7".parse().unwrap();
+        assert_eq!(
+            store.find_by_id(&device_id).unwrap().last_known_ip,
+            Some(expected)
+        );
+    }
+
     #[test]
     fn test_get_devices_with_addresses() {
         let tmp_dir = TempDir::new().unwrap();
@@ -542,4 +1105,159 @@ mod tests {
         assert!(loaded_device.last_known_port.is_none());
         assert!(loaded_device.address_updated_at.is_none());
     }
+
+    #[test]
+    fn test_device_groups() {
+        let tmp_dir = TempDir::new().unwrap();
+        let trust_path = tmp_dir.path().join("trust.json");
+
+        let mut store = TrustStore::load_from(trust_path.clone()).unwrap();
+        let alice = TrustedDevice::new(Uuid::new_v4(), "alice".to_string(), "key1".to_string());
+        let bob = TrustedDevice::new(Uuid::new_v4(), "bob".to_string(), "key2".to_string());
+        let (alice_id, bob_id) = (alice.device_id, bob.device_id);
+        store.add(alice).unwrap();
+        store.add(bob).unwrap();
+
+        store.add_to_group("team", &[alice_id, bob_id]).unwrap();
+        store.add_to_group("@Team", &[alice_id]).unwrap();
+        assert!(store.add_to_group("team", &[Uuid::new_v4()]).is_err());
+        assert!(store.add_to_group("", &[alice_id]).is_err());
+
+        let loaded = TrustStore::load_from(trust_path).unwrap();
+        let members = loaded.group_members("@team").unwrap();
+        assert_eq!(members.len(), 2);
+        assert!(loaded.group_members("other").is_none());
+
+        assert!(store.remove_from_group("team", &[bob_id]).unwrap());
+        assert_eq!(store.find_group("team").unwrap().members, vec![alice_id]);
+
+        store.add_to_group("laptops", &[alice_id, bob_id]).unwrap();
+        assert!(store.remove_group("laptops").unwrap());
+        assert!(!store.remove_group("laptops").unwrap());
+
+        store.remove(&alice_id).unwrap();
+        assert!(store.find_group("team").is_none());
+        assert!(store.groups().is_empty());
+    }
+
+    #[test]
+    fn test_apply_rotations() {
+        let tmp_dir = TempDir::new().unwrap();
+        let trust_path = tmp_dir.path().join("trust.json");
+
+        let mut identity = crypto::DeviceIdentity::generate().unwrap();
+        let original_key = identity.public_key_base64();
+        identity.rotate();
+        identity.rotate();
+        let device_id = identity.device_id();
+
+        let mut store = TrustStore::load_from(trust_path.clone()).unwrap();
+        store
+            .add(TrustedDevice::new(
+                device_id,
+                "laptop".to_string(),
+                original_key,
+            ))
+            .unwrap();
+        let stored = store.find_by_id(&device_id).unwrap().clone();
+
+        let other = crypto::DeviceIdentity::generate().unwrap();
+        assert!(!store
+            .apply_rotations(&device_id, &other.public_key_base64(), identity.rotations())
+            .unwrap());
+        assert!(!accept_rotated_key(
+            &stored,
+            &identity.public_key_base64(),
+            &[],
+            Some(trust_path.clone())
+        )
+        .unwrap());
+
+        assert!(accept_rotated_key(
+            &stored,
+            &identity.public_key_base64(),
+            identity.rotations(),
+            Some(trust_path.clone())
+        )
+        .unwrap());
+        let loaded = TrustStore::load_from(trust_path).unwrap();
+        assert!(loaded.verify_key(&device_id, &identity.public_key_base64()));
+    }
+
+    #[test]
+    fn test_device_permissions() {
+        let tmp_dir = TempDir::new().unwrap();
+        let trust_path = tmp_dir.path().join("trust.json");
+
+        let mut store = TrustStore::load_from(trust_path.clone()).unwrap();
+        let device = create_test_device();
+        let device_id = device.device_id;
+        assert!(device.permissions.is_unrestricted());
+        store.add(device).unwrap();
+
+        let content = fs::read_to_string(&trust_path).unwrap();
+        assert!(!content.contains("permissions"));
+
+        let allowed = tmp_dir.path().join("shared");
+        fs::create_dir_all(allowed.join("nested")).unwrap();
+        let mut permissions = DevicePermissions::default();
+        permissions.revoke(Capability::ReadClipboard);
+        permissions.sync_folders = Some(vec![allowed.clone()]);
+        permissions.output_dirs = Some(vec![allowed.clone()]);
+        permissions.max_transfer_size = Some(1024);
+        assert!(store.set_permissions(&device_id, permissions).unwrap());
+
+        let loaded = TrustStore::load_from(trust_path).unwrap();
+        let permissions = &loaded.find_by_id(&device_id).unwrap().permissions;
+        assert!(!permissions.is_unrestricted());
+        assert!(permissions.check("Test", Capability::ReceiveFiles).is_ok());
+        assert!(matches!(
+            permissions.check("Test", Capability::ReadClipboard),
+            Err(Error::CapabilityDenied { .. })
+        ));
+        assert!(permissions.check_size("Test", 1024).is_ok());
+        assert!(permissions.check_size("Test", 1025).is_err());
+        assert!(permissions
+            .check_sync_folder("Test", &allowed.join("nested"))
+            .is_ok());
+        assert!(permissions
+            .check_sync_folder("Test", tmp_dir.path())
+            .is_err());
+        assert!(permissions.check_output_dir("Test", &allowed).is_ok());
+        assert!(permissions
+            .check_output_dir("Test", tmp_dir.path())
+            .is_err());
+    }
+
+    #[test]
+    fn test_empty_folder_lists_deny() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut permissions = DevicePermissions::default();
+        assert!(permissions
+            .check_sync_folder("Test", tmp_dir.path())
+            .is_ok());
+        assert!(permissions.check_output_dir("Test", tmp_dir.path()).is_ok());
+
+        permissions.sync_folders = Some(Vec::new());
+        permissions.output_dirs = Some(Vec::new());
+        assert!(!permissions.is_unrestricted());
+        assert!(permissions
+            .check_sync_folder("Test", tmp_dir.path())
+            .is_err());
+        assert!(permissions
+            .check_output_dir("Test", tmp_dir.path())
+            .is_err());
+    }
+
+    #[test]
+    fn test_capability_names() {
+        for capability in Capability::ALL {
+            assert_eq!(Capability::parse(capability.as_str()), Some(capability));
+        }
+        assert_eq!(
+            Capability::parse("Clipboard-Read"),
+            Some(Capability::ReadClipboard)
+        );
+        assert_eq!(Capability::parse("everything"), None);
+    }
 }
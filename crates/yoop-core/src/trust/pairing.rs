@@ -0,0 +1,772 @@
+//! Explicit device pairing with short authentication string verification.
+//!
+//! Trusting a device after a code transfer only proves that someone who knew
+//! the code sent the files. Pairing (`yoop trust pair`) instead lets both
+//! users confirm that the key they are about to trust belongs to the device
+//! in front of them:
+//!
+//! 1. The host generates a code and announces it like a share; the joiner
+//!    finds it and connects over TLS.
+//! 2. The joiner sends its identity and a commitment to a random nonce.
+//! 3. The host replies with its identity and its own nonce.
+//! 4. The joiner reveals its nonce, which the host checks against the
+//!    commitment.
+//! 5. Both devices derive a [`ShortAuthString`] from the keys and nonces,
+//!    and the users compare it on both screens.
+//! 6. Each side sends its user's verdict, signed over the transcript (with
+//!    its own domain prefix, as for the short authentication string). A
+//!    confirming side also sends its resolving key, so the other can
+//!    recognise its [private beacons](crate::discovery::DeviceBeacon::into_private).
+//!    The [`TrustedDevice`] is only written once both users have confirmed.
+//!
+//! Because the joiner commits before seeing the host's nonce, and the host
+//! picks its nonce before seeing the joiner's, a man in the middle cannot
+//! search for keys that produce the same string on both sides.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use base64::prelude::*;
+use rustls::pki_types::ServerName;
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::{TlsAcceptor, TlsConnector, TlsStream};
+use uuid::Uuid;
+
+use super::{TrustStore, TrustedDevice};
+use crate::audit::{self, AuditEvent, AuditEventKind};
+use crate::code::{CodeGenerator, ShareCode};
+use crate::config::TrustLevel;
+use crate::connection;
+use crate::crypto::{self, DeviceIdentity, TlsConfig};
+use crate::discovery::{DiscoveryPacket, HybridBroadcaster, HybridListener};
+use crate::protocol::{
+    decode_payload, encode_payload, read_frame, write_frame, MessageType, PairConfirmPayload,
+    PairRequestPayload, PairResponsePayload, PairRevealPayload,
+};
+use crate::transfer::TransferConfig;
+use crate::{Error, Result, PROTOCOL_VERSION};
+
+/// Emoji shown in the short authentication string, with names for
+/// reading them out loud.
+const EMOJI: [(&str, &str); 64] = [
+    ("🐶", "Dog"),
+    ("🐱", "Cat"),
+    ("🦁", "Lion"),
+    ("🐎", "Horse"),
+    ("🦄", "Unicorn"),
+    ("🐷", "Pig"),
+    ("🐘", "Elephant"),
+    ("🐰", "Rabbit"),
+    ("🐼", "Panda"),
+    ("🐓", "Rooster"),
+    ("🐧", "Penguin"),
+    ("🐢", "Turtle"),
+    ("🐟", "Fish"),
+    ("🐙", "Octopus"),
+    ("🦋", "Butterfly"),
+    ("🌷", "Flower"),
+    ("🌳", "Tree"),
+    ("🌵", "Cactus"),
+    ("🍄", "Mushroom"),
+    ("🌏", "Globe"),
+    ("🌙", "Moon"),
+    ("☁️", "Cloud"),
+    ("🔥", "Fire"),
+    ("🍌", "Banana"),
+    ("🍎", "Apple"),
+    ("🍓", "Strawberry"),
+    ("🌽", "Corn"),
+    ("🍕", "Pizza"),
+    ("🎂", "Cake"),
+    ("❤️", "Heart"),
+    ("😀", "Smiley"),
+    ("🤖", "Robot"),
+    ("🎩", "Hat"),
+    ("👓", "Glasses"),
+    ("🔧", "Spanner"),
+    ("🎅", "Santa"),
+    ("👍", "Thumbs Up"),
+    ("☂️", "Umbrella"),
+    ("⌛", "Hourglass"),
+    ("⏰", "Clock"),
+    ("🎁", "Gift"),
+    ("💡", "Light Bulb"),
+    ("📕", "Book"),
+    ("✏️", "Pencil"),
+    ("📎", "Paperclip"),
+    ("✂️", "Scissors"),
+    ("🔒", "Lock"),
+    ("🔑", "Key"),
+    ("🔨", "Hammer"),
+    ("☎️", "Telephone"),
+    ("🏁", "Flag"),
+    ("🚂", "Train"),
+    ("🚲", "Bicycle"),
+    ("✈️", "Aeroplane"),
+    ("🚀", "Rocket"),
+    ("🏆", "Trophy"),
+    ("⚽", "Ball"),
+    ("🎸", "Guitar"),
+    ("🎺", "Trumpet"),
+    ("🔔", "Bell"),
+    ("⚓", "Anchor"),
+    ("🎧", "Headphones"),
+    ("📁", "Folder"),
+    ("📌", "Pin"),
+];
+
+/// Number of emoji in a short authentication string (36 bits).
+const EMOJI_COUNT: usize = 6;
+
+/// The short authentication string both users compare.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortAuthString {
+    /// Six decimal digits, e.g. `"042 917"`
+    pub digits: String,
+    /// Emoji and their names
+    pub emoji: Vec<(&'static str, &'static str)>,
+}
+
+impl ShortAuthString {
+    /// Derive the string from a pairing transcript hash.
+    #[must_use]
+    pub fn from_transcript(transcript: &[u8; 32]) -> Self {
+        let hash = crypto::sha256(&[b"yoop:pair:sas:v1".as_slice(), transcript].concat());
+
+        let number = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]) % 1_000_000;
+        let digits = format!("{:03} {:03}", number / 1000, number % 1000);
+
+        let bits = u64::from_be_bytes([0, 0, 0, hash[4], hash[5], hash[6], hash[7], hash[8]]);
+        let emoji = (0..EMOJI_COUNT)
+            .map(|i| {
+                let index = (bits >> (34 - 6 * i)) & 0x3F;
+                EMOJI[usize::try_from(index).unwrap_or_default()]
+            })
+            .collect();
+
+        Self { digits, emoji }
+    }
+
+    /// The emoji as a single line, e.g. `"🐶 🔑 🚀 ..."`.
+    #[must_use]
+    pub fn emoji_line(&self) -> String {
+        self.emoji
+            .iter()
+            .map(|(emoji, _)| *emoji)
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+
+    /// The emoji names as a single line, e.g. `"Dog, Key, Rocket, ..."`.
+    #[must_use]
+    pub fn names_line(&self) -> String {
+        self.emoji
+            .iter()
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Human-readable fingerprint of a base64-encoded public key.
+///
+/// The first 16 bytes of the key's SHA-256, as hex in groups of four,
+/// e.g. `"3F2A 91BC ..."`.
+#[must_use]
+pub fn fingerprint(public_key: &str) -> String {
+    let bytes = BASE64_STANDARD
+        .decode(public_key)
+        .unwrap_or_else(|_| public_key.as_bytes().to_vec());
+    let hash = crypto::sha256(&bytes);
+    hash[..16]
+        .chunks(2)
+        .map(|pair| format!("{:02X}{:02X}", pair[0], pair[1]))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Text encoded in the fingerprint QR code of a device.
+#[must_use]
+pub fn fingerprint_uri(device_id: Uuid, public_key: &str) -> String {
+    format!(
+        "yoop-fingerprint:{}:{}",
+        device_id,
+        fingerprint(public_key).replace(' ', "")
+    )
+}
+
+/// Result of a completed pairing.
+#[derive(Debug, Clone)]
+pub enum PairingOutcome {
+    /// Both users confirmed; the device was added to the trust store.
+    Paired(Box<TrustedDevice>),
+    /// The local user rejected the pairing.
+    Rejected,
+    /// The other user rejected the pairing.
+    RejectedByPeer,
+}
+
+/// A pairing host waiting for the other device to join.
+pub struct PairingHost {
+    code: ShareCode,
+    identity: DeviceIdentity,
+    device_name: String,
+    transfer_port: u16,
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    broadcaster: HybridBroadcaster,
+}
+
+impl std::fmt::Debug for PairingHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PairingHost")
+            .field("code", &self.code)
+            .field("device_name", &self.device_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PairingHost {
+    /// Generate a pairing code and start announcing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the listener or broadcaster cannot be started.
+    pub async fn start(
+        identity: DeviceIdentity,
+        device_name: String,
+        transfer_config: &TransferConfig,
+    ) -> Result<Self> {
+        let code = CodeGenerator::new().generate()?;
+
+        let tls_config = TlsConfig::server()?;
+        let server_config = tls_config
+            .server_config()
+            .ok_or_else(|| Error::Internal("server config not available".to_string()))?;
+        let acceptor = TlsAcceptor::from(Arc::new(server_config.clone()));
+
+        let listener = connection::bind_listener_in_range(
+            transfer_config.transfer_port,
+            transfer_config.transfer_port_end,
+            transfer_config.ipv6,
+        )?;
+        let local_addr = listener.local_addr()?;
+
+        let packet = DiscoveryPacket::new(
+            &code,
+            &device_name,
+            identity.device_id(),
+            local_addr.port(),
+            0,
+            0,
+        );
+        let broadcaster = HybridBroadcaster::with_options(
+            transfer_config.discovery_port,
+            &transfer_config.discovery_options(),
+        )
+        .await?
+        .with_resolving_key(identity.resolving_key());
+        broadcaster
+            .start(packet, transfer_config.broadcast_interval)
+            .await?;
+
+        tracing::info!("Pairing ready with code: {}", code);
+
+        Ok(Self {
+            code,
+            identity,
+            device_name,
+            transfer_port: transfer_config.transfer_port,
+            listener,
+            acceptor,
+            broadcaster,
+        })
+    }
+
+    /// The code the other device has to enter.
+    #[must_use]
+    pub fn code(&self) -> &ShareCode {
+        &self.code
+    }
+
+    /// Wait for the other device and run the key exchange.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection or key exchange fails.
+    pub async fn accept(self) -> Result<Pairing> {
+        let (tcp_stream, peer_addr) = self.listener.accept().await?;
+        let peer_addr = connection::canonical_addr(peer_addr);
+        tracing::info!("Pairing connection from {}", peer_addr);
+
+        let mut tls_stream = self.acceptor.accept(tcp_stream).await?;
+        self.broadcaster.stop().await;
+
+        let (peer, transcript) = exchange_host(
+            &mut tls_stream,
+            &self.identity,
+            &self.device_name,
+            self.code.as_str(),
+        )
+        .await?;
+
+        // the joiner connected from an ephemeral port; expect it to listen
+        // on the first port of the configured range
+        Ok(Pairing::new(
+            TlsStream::Server(tls_stream),
+            self.identity,
+            peer,
+            SocketAddr::new(peer_addr.ip(), self.transfer_port),
+            transcript,
+        ))
+    }
+}
+
+/// Join a pairing started on another device.
+///
+/// # Errors
+///
+/// Returns an error if the host cannot be found or the key exchange fails.
+pub async fn join(
+    code: &str,
+    identity: DeviceIdentity,
+    device_name: &str,
+    transfer_config: &TransferConfig,
+) -> Result<Pairing> {
+    let share_code = ShareCode::parse(code)?;
+    let listener = HybridListener::with_options(
+        transfer_config.discovery_port,
+        &transfer_config.discovery_options(),
+    )
+    .await?;
+    let announcement = listener
+        .find(&share_code, transfer_config.discovery_timeout)
+        .await?;
+    let peer_addr = announcement.transfer_addr();
+    tracing::info!("Found pairing host at {}", peer_addr);
+
+    let tcp_stream = TcpStream::connect(peer_addr).await?;
+
+    let tls_config = TlsConfig::client()?;
+    let client_config = tls_config
+        .client_config()
+        .ok_or_else(|| Error::Internal("client config not available".to_string()))?;
+    let connector = TlsConnector::from(Arc::new(client_config.clone()));
+    let domain = ServerName::try_from("yoop.local")
+        .map_err(|_| Error::TlsError("invalid server name".to_string()))?;
+    let mut tls_stream = connector.connect(domain, tcp_stream).await?;
+
+    let (peer, transcript) =
+        exchange_join(&mut tls_stream, &identity, device_name, share_code.as_str()).await?;
+
+    Ok(Pairing::new(
+        TlsStream::Client(tls_stream),
+        identity,
+        peer,
+        peer_addr,
+        transcript,
+    ))
+}
+
+/// Identity presented by the other device.
+#[derive(Debug, Clone)]
+struct PeerIdentity {
+    device_id: Uuid,
+    device_name: String,
+    public_key: String,
+}
+
+/// A key exchange waiting for the users to compare the codes.
+pub struct Pairing {
+    stream: TlsStream<TcpStream>,
+    identity: DeviceIdentity,
+    peer: PeerIdentity,
+    peer_addr: SocketAddr,
+    transcript: [u8; 32],
+    sas: ShortAuthString,
+}
+
+impl std::fmt::Debug for Pairing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pairing")
+            .field("peer", &self.peer)
+            .field("peer_addr", &self.peer_addr)
+            .field("sas", &self.sas)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Pairing {
+    fn new(
+        stream: TlsStream<TcpStream>,
+        identity: DeviceIdentity,
+        peer: PeerIdentity,
+        peer_addr: SocketAddr,
+        transcript: [u8; 32],
+    ) -> Self {
+        Self {
+            stream,
+            identity,
+            peer,
+            peer_addr,
+            sas: ShortAuthString::from_transcript(&transcript),
+            transcript,
+        }
+    }
+
+    /// What each side signs to confirm the pairing.
+    ///
+    /// Domain-separated so the signature cannot double as one over the raw
+    /// nonces signed in trusted handshakes.
+    fn confirmation_message(&self) -> Vec<u8> {
+        [b"yoop:pair:confirm:v1".as_slice(), &self.transcript].concat()
+    }
+
+    /// The other device's name.
+    #[must_use]
+    pub fn peer_name(&self) -> &str {
+        &self.peer.device_name
+    }
+
+    /// The other device's ID.
+    #[must_use]
+    pub fn peer_device_id(&self) -> Uuid {
+        self.peer.device_id
+    }
+
+    /// The other device's base64-encoded public key.
+    #[must_use]
+    pub fn peer_public_key(&self) -> &str {
+        &self.peer.public_key
+    }
+
+    /// The other device's address.
+    #[must_use]
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// The string both users have to compare.
+    #[must_use]
+    pub fn short_auth_string(&self) -> &ShortAuthString {
+        &self.sas
+    }
+
+    /// Exchange both users' verdicts and trust the device if both confirmed.
+    ///
+    /// The device is added to `trust_store` with `trust_level` only if
+    /// `accepted` is true, the other user confirmed too, and their
+    /// confirmation is signed by the key that was exchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the exchange fails, the peer's signature is
+    /// invalid, or the trust store cannot be saved.
+    pub async fn complete(
+        mut self,
+        accepted: bool,
+        trust_level: TrustLevel,
+        trust_store: &mut TrustStore,
+    ) -> Result<PairingOutcome> {
+        let confirm = PairConfirmPayload {
+            accepted,
+            signature: accepted
+                .then(|| BASE64_STANDARD.encode(self.identity.sign(&self.confirmation_message()))),
+            resolving_key: accepted.then(|| self.identity.resolving_key_base64()),
+        };
+        write_frame(
+            &mut self.stream,
+            MessageType::PairConfirm,
+            &encode_payload(&confirm)?,
+        )
+        .await?;
+
+        let event = |kind| {
+            AuditEvent::new(kind)
+                .with_peer(self.peer_addr)
+                .with_device(self.peer.device_id, self.peer.device_name.clone())
+        };
+        if !accepted {
+            audit::record(event(AuditEventKind::PairingRejected).with_detail("rejected here"));
+            return Ok(PairingOutcome::Rejected);
+        }
+
+        let peer_confirm: PairConfirmPayload =
+            expect_frame(&mut self.stream, MessageType::PairConfirm).await?;
+        if !peer_confirm.accepted {
+            audit::record(event(AuditEventKind::PairingRejected).with_detail("rejected by peer"));
+            return Ok(PairingOutcome::RejectedByPeer);
+        }
+
+        let verified = peer_confirm
+            .signature
+            .as_deref()
+            .and_then(|s| BASE64_STANDARD.decode(s).ok())
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .is_some_and(|signature| {
+                DeviceIdentity::verify_base64(
+                    &self.peer.public_key,
+                    &self.confirmation_message(),
+                    &signature,
+                )
+            });
+        if !verified {
+            audit::record(
+                event(AuditEventKind::SignatureInvalid).with_detail("pairing confirmation"),
+            );
+            return Err(Error::SignatureInvalid);
+        }
+        audit::record(event(AuditEventKind::PairingCompleted));
+
+        let device = TrustedDevice::new(
+            self.peer.device_id,
+            self.peer.device_name,
+            self.peer.public_key,
+        )
+        .with_trust_level(trust_level)
+        .with_address(self.peer_addr.ip(), self.peer_addr.port())
+        .with_resolving_key(peer_confirm.resolving_key);
+        trust_store.add(device.clone())?;
+
+        Ok(PairingOutcome::Paired(Box::new(device)))
+    }
+}
+
+/// Fields bound into the pairing transcript, in the order both sides hash them.
+#[derive(Serialize)]
+struct Transcript<'a> {
+    domain: &'static str,
+    code: &'a str,
+    joiner_id: Uuid,
+    joiner_key: &'a str,
+    host_id: Uuid,
+    host_key: &'a str,
+    commitment: &'a str,
+    host_nonce: &'a str,
+    joiner_nonce: &'a str,
+}
+
+impl Transcript<'_> {
+    fn hash(&self) -> Result<[u8; 32]> {
+        Ok(crypto::sha256(&encode_payload(self)?))
+    }
+}
+
+async fn exchange_host<S>(
+    stream: &mut S,
+    identity: &DeviceIdentity,
+    device_name: &str,
+    code: &str,
+) -> Result<(PeerIdentity, [u8; 32])>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let request: PairRequestPayload = expect_frame(stream, MessageType::PairRequest).await?;
+
+    let host_nonce = BASE64_STANDARD.encode(crypto::random_bytes::<32>());
+    let response = PairResponsePayload {
+        device_name: device_name.to_string(),
+        device_id: identity.device_id(),
+        public_key: identity.public_key_base64(),
+        nonce: host_nonce.clone(),
+    };
+    write_frame(
+        stream,
+        MessageType::PairResponse,
+        &encode_payload(&response)?,
+    )
+    .await?;
+
+    let reveal: PairRevealPayload = expect_frame(stream, MessageType::PairReveal).await?;
+    if commitment(&reveal.nonce) != request.commitment {
+        return Err(Error::ProtocolError(
+            "pairing nonce does not match commitment".to_string(),
+        ));
+    }
+
+    let transcript = Transcript {
+        domain: "yoop:pair:v1",
+        code,
+        joiner_id: request.device_id,
+        joiner_key: &request.public_key,
+        host_id: response.device_id,
+        host_key: &response.public_key,
+        commitment: &request.commitment,
+        host_nonce: &host_nonce,
+        joiner_nonce: &reveal.nonce,
+    }
+    .hash()?;
+
+    let peer = PeerIdentity {
+        device_id: request.device_id,
+        device_name: request.device_name,
+        public_key: request.public_key,
+    };
+    Ok((peer, transcript))
+}
+
+async fn exchange_join<S>(
+    stream: &mut S,
+    identity: &DeviceIdentity,
+    device_name: &str,
+    code: &str,
+) -> Result<(PeerIdentity, [u8; 32])>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let joiner_nonce = BASE64_STANDARD.encode(crypto::random_bytes::<32>());
+    let request = PairRequestPayload {
+        device_name: device_name.to_string(),
+        protocol_version: format!("{}.{}", PROTOCOL_VERSION.0, PROTOCOL_VERSION.1),
+        device_id: identity.device_id(),
+        public_key: identity.public_key_base64(),
+        commitment: commitment(&joiner_nonce),
+    };
+    write_frame(stream, MessageType::PairRequest, &encode_payload(&request)?).await?;
+
+    let response: PairResponsePayload = expect_frame(stream, MessageType::PairResponse).await?;
+
+    let reveal = PairRevealPayload {
+        nonce: joiner_nonce.clone(),
+    };
+    write_frame(stream, MessageType::PairReveal, &encode_payload(&reveal)?).await?;
+
+    let transcript = Transcript {
+        domain: "yoop:pair:v1",
+        code,
+        joiner_id: request.device_id,
+        joiner_key: &request.public_key,
+        host_id: response.device_id,
+        host_key: &response.public_key,
+        commitment: &request.commitment,
+        host_nonce: &response.nonce,
+        joiner_nonce: &joiner_nonce,
+    }
+    .hash()?;
+
+    let peer = PeerIdentity {
+        device_id: response.device_id,
+        device_name: response.device_name,
+        public_key: response.public_key,
+    };
+    Ok((peer, transcript))
+}
+
+fn commitment(nonce: &str) -> String {
+    BASE64_STANDARD.encode(crypto::sha256(nonce.as_bytes()))
+}
+
+async fn expect_frame<S, T>(stream: &mut S, expected: MessageType) -> Result<T>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: for<'de> serde::Deserialize<'de>,
+{
+    let (header, payload) = read_frame(stream).await?;
+    if header.message_type != expected {
+        return Err(Error::UnexpectedMessage {
+            expected: format!("{expected:?}"),
+            actual: format!("{:?}", header.message_type),
+        });
+    }
+    decode_payload(&payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_auth_string_is_deterministic() {
+        let transcript = [7u8; 32];
+        let sas = ShortAuthString::from_transcript(&transcript);
+        assert_eq!(sas, ShortAuthString::from_transcript(&transcript));
+        assert_eq!(sas.digits.len(), 7);
+        assert_eq!(sas.emoji.len(), EMOJI_COUNT);
+
+        let other = ShortAuthString::from_transcript(&[8u8; 32]);
+        assert_ne!(sas, other);
+    }
+
+    #[test]
+    fn test_fingerprint_format() {
+        let identity = DeviceIdentity::generate().expect("generate");
+        let fp = fingerprint(&identity.public_key_base64());
+        assert_eq!(fp.len(), 39);
+        assert_eq!(fp.split(' ').count(), 8);
+        assert!(
+            fingerprint_uri(identity.device_id(), &identity.public_key_base64())
+                .ends_with(&fp.replace(' ', ""))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_key_exchange_matches() {
+        let host = DeviceIdentity::generate().expect("generate");
+        let joiner = DeviceIdentity::generate().expect("generate");
+        let (mut host_stream, mut join_stream) = tokio::io::duplex(64 * 1024);
+
+        let host_task = {
+            let host = host.clone();
+            tokio::spawn(
+                async move { exchange_host(&mut host_stream, &host, "host", "A7K9").await },
+            )
+        };
+        let (host_peer, join_transcript) =
+            exchange_join(&mut join_stream, &joiner, "joiner", "A7K9")
+                .await
+                .expect("join");
+        let (join_peer, host_transcript) = host_task.await.expect("task").expect("host");
+
+        assert_eq!(host_transcript, join_transcript);
+        assert_eq!(host_peer.public_key, host.public_key_base64());
+        assert_eq!(join_peer.public_key, joiner.public_key_base64());
+        assert_eq!(join_peer.device_name, "joiner");
+    }
+
+    #[tokio::test]
+    async fn test_key_exchange_rejects_bad_reveal() {
+        let host = DeviceIdentity::generate().expect("generate");
+        let joiner = DeviceIdentity::generate().expect("generate");
+        let (mut host_stream, mut join_stream) = tokio::io::duplex(64 * 1024);
+
+        let host_task =
+            tokio::spawn(
+                async move { exchange_host(&mut host_stream, &host, "host", "A7K9").await },
+            );
+
+        let request = PairRequestPayload {
+            device_name: "joiner".to_string(),
+            protocol_version: "1.0".to_string(),
+            device_id: joiner.device_id(),
+            public_key: joiner.public_key_base64(),
+            commitment: commitment("committed"),
+        };
+        write_frame(
+            &mut join_stream,
+            MessageType::PairRequest,
+            &encode_payload(&request).unwrap(),
+        )
+        .await
+        .unwrap();
+        let _: PairResponsePayload = expect_frame(&mut join_stream, MessageType::PairResponse)
+            .await
+            .unwrap();
+        let reveal = PairRevealPayload {
+            nonce: "chosen later".to_string(),
+        };
+        write_frame(
+            &mut join_stream,
+            MessageType::PairReveal,
+            &encode_payload(&reveal).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            host_task.await.expect("task"),
+            Err(Error::ProtocolError(_))
+        ));
+    }
+}
@@ -0,0 +1,350 @@
+//! Static peers for VPN and overlay networks.
+//!
+//! UDP broadcast and mDNS do not cross Tailscale, WireGuard or ZeroTier
+//! links, and a device's `last_known_ip` goes stale whenever the overlay
+//! hands out a new address. `[[peers]]` entries in the config list hostnames
+//! or IPs for a trusted device. Each is resolved (so MagicDNS names work)
+//! and probed with a TCP connect to the device's transfer port, and the
+//! first reachable address is recorded with [`TrustStore::update_address`].
+//!
+//! ## Example
+//!
+//! ```toml
+//! [[peers]]
+//! device = "Laptop"
+//! addresses = ["laptop.tailnet.ts.net", "100.64.0.2:52531"]
+//! ```
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+use super::{TrustStore, TrustedDevice};
+use crate::config::PeerConfig;
+use crate::error::{Error, Result};
+
+/// How long a single connection attempt may take.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Outcome of probing one configured address.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerProbe {
+    /// The address as configured
+    pub address: String,
+    /// The socket address that accepted a connection
+    pub reachable: Option<SocketAddr>,
+    /// Why the address could not be reached
+    pub error: Option<String>,
+}
+
+/// Reachability of a configured peer.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerStatus {
+    /// Trusted device ID
+    pub device_id: Uuid,
+    /// Trusted device name
+    pub device_name: String,
+    /// First configured address that accepted a connection
+    pub address: Option<SocketAddr>,
+    /// Per-address results, in configured order
+    pub probes: Vec<PeerProbe>,
+}
+
+impl PeerStatus {
+    /// Whether any configured address is reachable.
+    #[must_use]
+    pub fn is_reachable(&self) -> bool {
+        self.address.is_some()
+    }
+}
+
+/// Split a configured address into host and port.
+///
+/// Accepts `host`, `host:port`, bare IPv6 literals and `[v6]:port`; entries
+/// without a port use `default_port`.
+#[must_use]
+pub fn split_host_port(entry: &str, default_port: u16) -> Option<(String, u16)> {
+    let entry = entry.trim();
+    if entry.is_empty() {
+        return None;
+    }
+    if let Ok(ip) = entry.parse::<IpAddr>() {
+        return Some((ip.to_string(), default_port));
+    }
+    if let Ok(addr) = entry.parse::<SocketAddr>() {
+        return Some((addr.ip().to_string(), addr.port()));
+    }
+    if let Some(host) = entry.strip_prefix('[').and_then(|e| e.strip_suffix(']')) {
+        return Some((host.to_string(), default_port));
+    }
+
+    match entry.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => {
+            let port = port.parse().ok()?;
+            (!host.is_empty()).then(|| (host.to_string(), port))
+        }
+        Some(_) => None,
+        None => Some((entry.to_string(), default_port)),
+    }
+}
+
+/// Resolve a configured address, looking up hostnames via DNS.
+///
+/// # Errors
+///
+/// Returns an error if the entry is malformed or the name does not resolve.
+pub async fn resolve(entry: &str, default_port: u16) -> Result<Vec<SocketAddr>> {
+    let (host, port) = split_host_port(entry, default_port)
+        .ok_or_else(|| Error::InvalidInput(format!("invalid peer address '{entry}'")))?;
+
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|e| Error::InvalidInput(format!("cannot resolve '{host}': {e}")))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(Error::InvalidInput(format!("'{host}' has no addresses")));
+    }
+    Ok(addrs)
+}
+
+/// Probe one configured address, trying each resolved address in turn.
+pub async fn probe(entry: &str, default_port: u16, timeout: Duration) -> PeerProbe {
+    let mut probe = PeerProbe {
+        address: entry.to_string(),
+        reachable: None,
+        error: None,
+    };
+
+    let addrs = match resolve(entry, default_port).await {
+        Ok(addrs) => addrs,
+        Err(e) => {
+            probe.error = Some(e.to_string());
+            return probe;
+        }
+    };
+
+    for addr in addrs {
+        match tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => {
+                probe.reachable = Some(addr);
+                return probe;
+            }
+            Ok(Err(e)) => probe.error = Some(format!("{addr}: {e}")),
+            Err(_) => probe.error = Some(format!("{addr}: timed out")),
+        }
+    }
+    probe
+}
+
+/// Probe every configured address of a trusted device.
+pub async fn check_peer(
+    device: &TrustedDevice,
+    addresses: &[String],
+    default_port: u16,
+    timeout: Duration,
+) -> PeerStatus {
+    let mut probes = Vec::with_capacity(addresses.len());
+    for entry in addresses {
+        probes.push(probe(entry, default_port, timeout).await);
+    }
+
+    PeerStatus {
+        device_id: device.device_id,
+        device_name: device.device_name.clone(),
+        address: probes.iter().find_map(|p| p.reachable),
+        probes,
+    }
+}
+
+/// Look for a trusted device at its configured addresses, then at the
+/// address it was last seen at.
+///
+/// Returns the first address that accepts a connection. Recording it is left
+/// to the caller, which usually knows whether the device answered.
+pub async fn probe_device(
+    device: &TrustedDevice,
+    addresses: &[String],
+    default_port: u16,
+) -> Option<SocketAddr> {
+    if !addresses.is_empty() {
+        let status = check_peer(device, addresses, default_port, PROBE_TIMEOUT).await;
+        if status.address.is_some() {
+            return status.address;
+        }
+    }
+
+    let (ip, port) = device.address()?;
+    let addr = SocketAddr::new(ip, port);
+    match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(addr)).await {
+        Ok(Ok(_)) => Some(addr),
+        Ok(Err(e)) => {
+            tracing::debug!(
+                "Stored address {addr} of {} unreachable: {e}",
+                device.device_name
+            );
+            None
+        }
+        Err(_) => {
+            tracing::debug!("Stored address {addr} of {} timed out", device.device_name);
+            None
+        }
+    }
+}
+
+/// Configured addresses for a trusted device, across all matching entries.
+#[must_use]
+pub fn addresses_for(peers: &[PeerConfig], device: &TrustedDevice) -> Vec<String> {
+    peers
+        .iter()
+        .filter(|peer| peer.matches(device))
+        .flat_map(|peer| peer.addresses.iter().cloned())
+        .collect()
+}
+
+/// Probe all configured peers concurrently and record reachable addresses.
+///
+/// Entries naming devices that are not in the trust store are skipped.
+///
+/// # Errors
+///
+/// Returns an error if the trust store cannot be saved.
+pub async fn check_peers(
+    peers: &[PeerConfig],
+    store: &mut TrustStore,
+    default_port: u16,
+) -> Result<Vec<PeerStatus>> {
+    let mut checks = JoinSet::new();
+    for device in store.list() {
+        let addresses = addresses_for(peers, device);
+        if addresses.is_empty() {
+            continue;
+        }
+        let device = device.clone();
+        checks.spawn(
+            async move { check_peer(&device, &addresses, default_port, PROBE_TIMEOUT).await },
+        );
+    }
+    for peer in peers {
+        if !store.list().iter().any(|device| peer.matches(device)) {
+            tracing::warn!("Peer '{}' is not a trusted device, skipping", peer.device);
+        }
+    }
+
+    let mut statuses = Vec::new();
+    while let Some(result) = checks.join_next().await {
+        let status = result.map_err(|e| Error::Internal(format!("peer check failed: {e}")))?;
+        if let Some(addr) = status.address {
+            tracing::debug!("Peer {} reachable at {addr}", status.device_name);
+            store.update_address(&status.device_id, addr.ip(), addr.port())?;
+        }
+        statuses.push(status);
+    }
+    statuses.sort_by(|a, b| a.device_name.cmp(&b.device_name));
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(
+            split_host_port("laptop.tailnet.ts.net", 52530),
+            Some(("laptop.tailnet.ts.net".to_string(), 52530))
+        );
+        assert_eq!(
+            split_host_port("100.64.0.2:52531", 52530),
+            Some(("100.64.0.2".to_string(), 52531))
+        );
+        assert_eq!(
+            split_host_port("fd7a:115c::1", 52530),
+            Some(("fd7a:115c::1".to_string(), 52530))
+        );
+        assert_eq!(
+            split_host_port("[fd7a:115c::1]:52533", 52530),
+            Some(("fd7a:115c::1".to_string(), 52533))
+        );
+        assert_eq!(
+            split_host_port("[fd7a:115c::1]", 52530),
+            Some(("fd7a:115c::1".to_string(), 52530))
+        );
+        assert_eq!(split_host_port("host:notaport", 52530), None);
+        assert_eq!(split_host_port("", 52530), None);
+    }
+
+    #[tokio::test]
+    async fn test_check_peers_updates_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let port = listener.local_addr().expect("local addr").port();
+
+        let tmp_dir = TempDir::new().expect("temp dir");
+        let mut store = TrustStore::load_from(tmp_dir.path().join("trust.json")).expect("store");
+        let device = TrustedDevice::new(Uuid::new_v4(), "Laptop".to_string(), "key".to_string());
+        let device_id = device.device_id;
+        store.add(device).expect("add device");
+
+        let peers = vec![
+            PeerConfig {
+                device: "laptop".to_string(),
+                addresses: vec!["not a host:x".to_string(), format!("localhost:{port}")],
+            },
+            PeerConfig {
+                device: "Unknown".to_string(),
+                addresses: vec!["127.0.0.1".to_string()],
+            },
+        ];
+
+        let statuses = check_peers(&peers, &mut store, 1)
+            .await
+            .expect("check peers");
+        assert_eq!(statuses.len(), 1);
+        let status = &statuses[0];
+        assert!(status.is_reachable());
+        assert_eq!(status.probes.len(), 2);
+        assert!(status.probes[0].error.is_some());
+        assert_eq!(status.address.map(|a| a.port()), Some(port));
+
+        let (ip, stored_port) = store.get_address(&device_id).expect("address stored");
+        assert!(ip.is_loopback());
+        assert_eq!(stored_port, port);
+    }
+
+    #[tokio::test]
+    async fn test_probe_device_falls_back_to_stored_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let port = listener.local_addr().expect("local addr").port();
+
+        let mut device = TrustedDevice::new(Uuid::new_v4(), "Desk".to_string(), "key".to_string());
+        assert_eq!(probe_device(&device, &[], 52530).await, None);
+
+        device.last_known_ip = Some(IpAddr::from([127, 0, 0, 1]));
+        device.last_known_port = Some(port);
+        let addr = probe_device(&device, &["not a host:x".to_string()], 52530).await;
+        assert_eq!(addr.map(|a| a.port()), Some(port));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_peer() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let port = listener.local_addr().expect("local addr").port();
+        drop(listener);
+
+        let device = TrustedDevice::new(Uuid::new_v4(), "Desk".to_string(), "key".to_string());
+        let status = check_peer(
+            &device,
+            &[format!("127.0.0.1:{port}")],
+            52530,
+            Duration::from_millis(500),
+        )
+        .await;
+        assert!(!status.is_reachable());
+        assert!(status.probes[0].error.is_some());
+    }
+}
@@ -58,17 +58,52 @@ const elements = {
     btnDismissError: document.getElementById("btn-dismiss-error"),
 
     deviceName: document.getElementById("device-name"),
+    certFingerprint: document.getElementById("cert-fingerprint"),
+
+    modeSelector: document.getElementById("mode-selector"),
+    loginPanel: document.getElementById("login-panel"),
+    passwordInput: document.getElementById("password-input"),
+    loginError: document.getElementById("login-error"),
+    btnLogin: document.getElementById("btn-login"),
 };
 
+async function authFetch(url, options) {
+    const res = await fetch(url, options);
+    if (res.status === 401) {
+        showLogin();
+        throw new Error("Authentication required");
+    }
+    return res;
+}
+
 const api = {
+    async authStatus() {
+        const res = await fetch("/api/auth/status");
+        if (!res.ok) throw new Error("Failed to get auth status");
+        return res.json();
+    },
+
+    async login(password) {
+        const res = await fetch("/api/auth/login", {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify({ password }),
+        });
+        if (!res.ok) {
+            const err = await res.json();
+            throw new Error(err.message || "Login failed");
+        }
+        return res.json();
+    },
+
     async getStatus() {
-        const res = await fetch("/api/status");
+        const res = await authFetch("/api/status");
         if (!res.ok) throw new Error("Failed to get status");
         return res.json();
     },
 
     async getNetwork() {
-        const res = await fetch("/api/network");
+        const res = await authFetch("/api/network");
         if (!res.ok) throw new Error("Failed to get network info");
         return res.json();
     },
@@ -76,7 +111,7 @@ const api = {
     async createShare(files) {
         const formData = new FormData();
         files.forEach((f) => formData.append("files", f));
-        const res = await fetch("/api/share", {
+        const res = await authFetch("/api/share", {
             method: "POST",
             body: formData,
         });
@@ -88,12 +123,12 @@ const api = {
     },
 
     async cancelShare() {
-        const res = await fetch("/api/share", { method: "DELETE" });
+        const res = await authFetch("/api/share", { method: "DELETE" });
         if (!res.ok) throw new Error("Failed to cancel share");
     },
 
     async startReceive(code) {
-        const res = await fetch("/api/receive", {
+        const res = await authFetch("/api/receive", {
             method: "POST",
             headers: { "Content-Type": "application/json" },
             body: JSON.stringify({ code: code.toUpperCase() }),
@@ -106,7 +141,7 @@ const api = {
     },
 
     async acceptReceive() {
-        const res = await fetch("/api/receive/accept", { method: "POST" });
+        const res = await authFetch("/api/receive/accept", { method: "POST" });
         if (!res.ok) {
             const err = await res.json();
             throw new Error(err.message || "Failed to accept");
@@ -115,7 +150,7 @@ const api = {
     },
 
     async declineReceive() {
-        await fetch("/api/receive/decline", { method: "POST" });
+        await authFetch("/api/receive/decline", { method: "POST" });
     },
 };
 
@@ -143,6 +178,7 @@ function formatSpeed(bytesPerSec) {
 
 function showPanel(panelId) {
     [
+        "login-panel",
         "share-panel",
         "receive-panel",
         "progress-panel",
@@ -154,6 +190,37 @@ function showPanel(panelId) {
     });
 }
 
+function showLogin() {
+    if (state.eventSource) {
+        state.eventSource.close();
+        state.eventSource = null;
+    }
+    elements.modeSelector.hidden = true;
+    showPanel("login-panel");
+    elements.passwordInput.focus();
+}
+
+async function login() {
+    const password = elements.passwordInput.value;
+    if (!password) return;
+
+    elements.btnLogin.disabled = true;
+    elements.loginError.hidden = true;
+    try {
+        await api.login(password);
+        elements.passwordInput.value = "";
+        elements.modeSelector.hidden = false;
+        showPanel(state.view === "share" ? "share-panel" : "receive-panel");
+        switchView(state.view);
+        await loadState();
+    } catch (error) {
+        elements.loginError.textContent = error.message;
+        elements.loginError.hidden = false;
+    } finally {
+        elements.btnLogin.disabled = false;
+    }
+}
+
 function switchView(view) {
     state.view = view;
     elements.btnShare.classList.toggle("active", view === "share");
@@ -474,7 +541,36 @@ function newTransfer() {
     showPanel(state.view === "share" ? "share-panel" : "receive-panel");
 }
 
-async function init() {
+function expectedFingerprint() {
+    const match = window.location.hash.match(/(?:^#|&)fp=([0-9a-fA-F]+)/);
+    return match ? match[1].toUpperCase() : null;
+}
+
+function showFingerprint(fingerprint) {
+    const expected = expectedFingerprint();
+    const actual = fingerprint
+        ? fingerprint.replace(/:/g, "").toUpperCase()
+        : null;
+    const el = elements.certFingerprint;
+
+    if (expected && actual !== expected) {
+        el.textContent =
+            "Warning: this server's certificate does not match the QR code " +
+            "(expected " +
+            expected +
+            "). Do not send anything through this page.";
+        el.classList.add("mismatch");
+        el.hidden = false;
+    } else if (fingerprint) {
+        el.textContent = "Certificate SHA-256: " + fingerprint;
+        if (expected) {
+            el.textContent += " (matches the QR code)";
+        }
+        el.hidden = false;
+    }
+}
+
+async function loadState() {
     try {
         const [status, network] = await Promise.all([
             api.getStatus(),
@@ -482,6 +578,7 @@ async function init() {
         ]);
 
         elements.deviceName.textContent = network.device_name;
+        showFingerprint(network.fingerprint);
 
         if (status.mode === "sharing" && status.share_code) {
             state.mode = "sharing";
@@ -500,6 +597,24 @@ async function init() {
     } catch (error) {
         console.error("Init error:", error);
     }
+}
+
+async function init() {
+    try {
+        const auth = await api.authStatus();
+        if (auth.auth_required && !auth.authenticated) {
+            showLogin();
+        } else {
+            await loadState();
+        }
+    } catch (error) {
+        console.error("Init error:", error);
+    }
+
+    elements.btnLogin.addEventListener("click", login);
+    elements.passwordInput.addEventListener("keypress", (e) => {
+        if (e.key === "Enter") login();
+    });
 
     elements.btnShare.addEventListener("click", () => switchView("share"));
     elements.btnReceive.addEventListener("click", () => switchView("receive"));
@@ -0,0 +1,495 @@
+//! Password authentication for the web interface.
+//!
+//! With authentication enabled (`yoop web --auth`), every `/api` route except
+//! the `/api/auth/*` endpoints requires a session. `POST /api/auth/login`
+//! checks the password and returns a session token, both in the body (for
+//! `Authorization: Bearer` clients) and as an `HttpOnly` cookie, which the
+//! browser also sends for the progress stream and downloads.
+//!
+//! Each client gets `max_login_attempts` wrong passwords before it is
+//! locked out for `lockout`. IPv6 clients are counted per `/64`, since a
+//! single host usually controls a whole prefix. There is no lockout across
+//! clients, which would let anyone lock the owner out; guessing the
+//! generated 16-character password from many addresses is still hopeless.
+//! Clients whose failures have aged out are forgotten, and at most
+//! [`MAX_TRACKED_CLIENTS`] are remembered at once, so rotating addresses
+//! cannot grow the table without bound. Failed logins are recorded in the [`audit`](crate::audit) log.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::audit::{self, AuditEvent, AuditEventKind};
+use crate::crypto;
+use crate::error::Error;
+
+use super::{ApiError, ApiResult, SharedState, WebServerConfig};
+
+/// Name of the session cookie.
+pub const SESSION_COOKIE: &str = "yoop_session";
+
+/// How long a session stays valid after login.
+pub const SESSION_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Most clients whose failed logins are remembered at once; beyond this the
+/// oldest record is dropped.
+pub const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug, Default)]
+struct Failures {
+    count: u32,
+    first_at: Option<Instant>,
+    locked_until: Option<Instant>,
+}
+
+impl Failures {
+    /// Seconds left in the current lockout, clearing an expired one.
+    fn locked_for(&mut self, now: Instant) -> Option<u64> {
+        let until = self.locked_until?;
+        if until > now {
+            return Some(until.duration_since(now).as_secs().max(1));
+        }
+        *self = Self::default();
+        None
+    }
+
+    /// Whether the record no longer affects anything: no lockout is running
+    /// and the failures are older than `lockout`.
+    fn is_expired(&self, now: Instant, lockout: Duration) -> bool {
+        self.locked_until.is_none_or(|until| until <= now)
+            && self
+                .first_at
+                .is_none_or(|first| now.duration_since(first) > lockout)
+    }
+
+    /// Count a wrong password, locking out once `limit` is reached within
+    /// `lockout`.
+    fn fail(&mut self, now: Instant, limit: u32, lockout: Duration) {
+        if self
+            .first_at
+            .is_none_or(|first| now.duration_since(first) > lockout)
+        {
+            *self = Self {
+                first_at: Some(now),
+                ..Self::default()
+            };
+        }
+        self.count += 1;
+        if self.count >= limit {
+            self.locked_until = Some(now + lockout);
+        }
+    }
+}
+
+/// Rate-limit bucket for a client address: IPv4 addresses on their own,
+/// IPv6 addresses by `/64`.
+fn client_bucket(ip: IpAddr) -> IpAddr {
+    match ip.to_canonical() {
+        IpAddr::V6(v6) => {
+            let prefix = u128::from(v6) & !u128::from(u64::MAX);
+            IpAddr::V6(Ipv6Addr::from(prefix))
+        }
+        v4 @ IpAddr::V4(_) => v4,
+    }
+}
+
+/// Password check, sessions and lockout for the web server.
+#[derive(Debug)]
+pub struct WebAuth {
+    password_hash: Option<[u8; 32]>,
+    max_attempts: u32,
+    lockout: Duration,
+    sessions: Mutex<HashMap<String, Instant>>,
+    failures: Mutex<HashMap<IpAddr, Failures>>,
+}
+
+impl WebAuth {
+    /// Create the authenticator for a server configuration.
+    ///
+    /// Authentication is disabled unless `auth_enabled` is set and a
+    /// password is configured.
+    #[must_use]
+    pub fn new(config: &WebServerConfig) -> Self {
+        let password_hash = config
+            .auth_password
+            .as_deref()
+            .filter(|_| config.auth_enabled)
+            .map(|password| crypto::sha256(password.as_bytes()));
+        Self {
+            password_hash,
+            max_attempts: config.max_login_attempts.max(1),
+            lockout: config.lockout,
+            sessions: Mutex::new(HashMap::new()),
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether requests must be authenticated.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.password_hash.is_some()
+    }
+
+    /// Check a password from `ip` and start a session.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RateLimited`] while `ip` is locked out and [`Error::PermissionDenied`] for a wrong password.
+    pub fn login(&self, ip: IpAddr, password: &str) -> crate::Result<String> {
+        let Some(expected) = self.password_hash else {
+            return Ok(self.create_session());
+        };
+
+        let now = Instant::now();
+        let bucket = client_bucket(ip);
+        let mut failures = self
+            .failures
+            .lock()
+            .map_err(|_| Error::Internal("auth state poisoned".to_string()))?;
+
+        if let Some(secs) = failures
+            .get_mut(&bucket)
+            .and_then(|entry| entry.locked_for(now))
+        {
+            return Err(Error::RateLimited(secs));
+        }
+
+        let presented = crypto::sha256(password.as_bytes());
+        if crypto::constant_time_eq(&presented, &expected) {
+            failures.remove(&bucket);
+            drop(failures);
+            return Ok(self.create_session());
+        }
+
+        let lockout = self.lockout;
+        failures.retain(|_, entry| !entry.is_expired(now, lockout));
+        if failures.len() >= MAX_TRACKED_CLIENTS && !failures.contains_key(&bucket) {
+            let oldest = failures
+                .iter()
+                .min_by_key(|(_, entry)| entry.first_at)
+                .map(|(bucket, _)| *bucket);
+            if let Some(oldest) = oldest {
+                failures.remove(&oldest);
+            }
+        }
+        failures
+            .entry(bucket)
+            .or_default()
+            .fail(now, self.max_attempts, lockout);
+        drop(failures);
+
+        Err(Error::PermissionDenied("invalid password".to_string()))
+    }
+
+    /// Whether `token` belongs to a live session.
+    #[must_use]
+    pub fn validate(&self, token: &str) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+        let Ok(mut sessions) = self.sessions.lock() else {
+            return false;
+        };
+        let now = Instant::now();
+        sessions.retain(|_, expires| *expires > now);
+        sessions.contains_key(token)
+    }
+
+    /// End a session.
+    pub fn logout(&self, token: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(token);
+        }
+    }
+
+    fn create_session(&self) -> String {
+        let token = BASE64_URL_SAFE_NO_PAD.encode(crypto::random_bytes::<32>());
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.insert(token.clone(), Instant::now() + SESSION_TTL);
+        }
+        token
+    }
+}
+
+/// Session token from the `Authorization` header or the session cookie.
+#[must_use]
+pub fn request_token(headers: &HeaderMap) -> Option<String> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if let Some(token) = bearer {
+        return Some(token.trim().to_string());
+    }
+
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|pair| {
+            pair.trim()
+                .strip_prefix(SESSION_COOKIE)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(str::to_string)
+        })
+}
+
+/// Middleware rejecting requests without a valid session.
+pub async fn require_auth(
+    State(state): State<SharedState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorized = !state.auth.is_enabled()
+        || request_token(request.headers()).is_some_and(|token| state.auth.validate(&token));
+    if !authorized {
+        return ApiError::unauthorized("authentication required").into_response();
+    }
+    next.run(request).await
+}
+
+/// Login request body.
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    /// The server password
+    pub password: String,
+}
+
+/// Login response body.
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    /// Session token for the `Authorization: Bearer` header
+    pub token: String,
+    /// Seconds until the session expires
+    pub expires_in: u64,
+}
+
+/// Authentication status response.
+#[derive(Debug, Serialize)]
+pub struct AuthStatusResponse {
+    /// Whether the server requires a login
+    pub auth_required: bool,
+    /// Whether this request carries a valid session
+    pub authenticated: bool,
+}
+
+/// POST /api/auth/login - Exchange the password for a session.
+///
+/// # Errors
+///
+/// Returns 401 for a wrong password and 429 while the client is locked out.
+pub async fn login(
+    State(state): State<SharedState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(request): Json<LoginRequest>,
+) -> ApiResult<Response> {
+    let token = match state.auth.login(addr.ip(), &request.password) {
+        Ok(token) => token,
+        Err(Error::PermissionDenied(_)) => {
+            audit::record(
+                AuditEvent::new(AuditEventKind::AuthenticationFailed)
+                    .with_peer(addr)
+                    .with_detail("web UI login"),
+            );
+            return Err(ApiError::unauthorized("invalid password"));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let secure = if state.config.certificate.is_some() {
+        "; Secure"
+    } else {
+        ""
+    };
+    let cookie = format!(
+        "{SESSION_COOKIE}={token}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{secure}",
+        SESSION_TTL.as_secs()
+    );
+    let mut response = Json(LoginResponse {
+        token,
+        expires_in: SESSION_TTL.as_secs(),
+    })
+    .into_response();
+    if let Ok(value) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().insert(header::SET_COOKIE, value);
+    }
+    Ok(response)
+}
+
+/// POST /api/auth/logout - End the current session.
+pub async fn logout(State(state): State<SharedState>, headers: HeaderMap) -> Response {
+    if let Some(token) = request_token(&headers) {
+        state.auth.logout(&token);
+    }
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    response.headers_mut().insert(
+        header::SET_COOKIE,
+        HeaderValue::from_static("yoop_session=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0"),
+    );
+    response
+}
+
+/// GET /api/auth/status - Whether a login is needed.
+pub async fn status(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+) -> Json<AuthStatusResponse> {
+    let auth_required = state.auth.is_enabled();
+    Json(AuthStatusResponse {
+        auth_required,
+        authenticated: !auth_required
+            || request_token(&headers).is_some_and(|token| state.auth.validate(&token)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth(password: &str) -> WebAuth {
+        WebAuth::new(&WebServerConfig {
+            auth_enabled: true,
+            auth_password: Some(password.to_string()),
+            max_login_attempts: 3,
+            lockout: Duration::from_secs(60),
+            ..WebServerConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_login_and_sessions() {
+        let auth = auth("secret");
+        let ip: IpAddr = "192.168.1.5".parse().unwrap();
+        assert!(auth.is_enabled());
+        assert!(!auth.validate("not-a-session"));
+
+        let token = auth.login(ip, "secret").unwrap();
+        assert!(auth.validate(&token));
+
+        auth.logout(&token);
+        assert!(!auth.validate(&token));
+    }
+
+    #[test]
+    fn test_lockout_after_failures() {
+        let auth = auth("secret");
+        let ip: IpAddr = "192.168.1.5".parse().unwrap();
+
+        for _ in 0..3 {
+            assert!(matches!(
+                auth.login(ip, "guess"),
+                Err(Error::PermissionDenied(_))
+            ));
+        }
+        assert!(matches!(
+            auth.login(ip, "secret"),
+            Err(Error::RateLimited(_))
+        ));
+
+        let other: IpAddr = "192.168.1.6".parse().unwrap();
+        assert!(auth.login(other, "secret").is_ok());
+    }
+
+    #[test]
+    fn test_lockout_covers_ipv6_prefix() {
+        let auth = auth("secret");
+        for host in 1..=3 {
+            let ip: IpAddr = format!("2001:db8:1:2::{host}").parse().unwrap();
+            assert!(matches!(
+                auth.login(ip, "guess"),
+                Err(Error::PermissionDenied(_))
+            ));
+        }
+
+        let same_prefix: IpAddr = "2001:db8:1:2:ffff::9".parse().unwrap();
+        assert!(matches!(
+            auth.login(same_prefix, "secret"),
+            Err(Error::RateLimited(_))
+        ));
+        let other_prefix: IpAddr = "2001:db8:1:3::1".parse().unwrap();
+        assert!(auth.login(other_prefix, "secret").is_ok());
+    }
+
+    #[test]
+    fn test_failures_elsewhere_do_not_lock_out_others() {
+        let auth = auth("secret");
+        for host in 0..100 {
+            let ip = IpAddr::from(std::net::Ipv4Addr::from(0x0a00_0000 | host));
+            for _ in 0..3 {
+                let _ = auth.login(ip, "guess");
+            }
+        }
+
+        let owner: IpAddr = "192.168.1.7".parse().unwrap();
+        assert!(auth.login(owner, "secret").is_ok());
+    }
+
+    #[test]
+    fn test_expired_failures_are_pruned() {
+        let auth = WebAuth::new(&WebServerConfig {
+            auth_enabled: true,
+            auth_password: Some("secret".to_string()),
+            max_login_attempts: 3,
+            lockout: Duration::from_millis(20),
+            ..WebServerConfig::default()
+        });
+        let first: IpAddr = "192.168.1.5".parse().unwrap();
+        let second: IpAddr = "192.168.1.6".parse().unwrap();
+
+        let _ = auth.login(first, "guess");
+        std::thread::sleep(Duration::from_millis(50));
+        let _ = auth.login(second, "guess");
+
+        let tracked: Vec<IpAddr> = auth.failures.lock().unwrap().keys().copied().collect();
+        assert_eq!(tracked, vec![second]);
+    }
+
+    #[test]
+    fn test_tracked_clients_are_capped() {
+        let auth = auth("secret");
+        for host in 0..=MAX_TRACKED_CLIENTS {
+            let ip = IpAddr::from(std::net::Ipv4Addr::from(
+                0x0a00_0000 | u32::try_from(host).unwrap(),
+            ));
+            let _ = auth.login(ip, "guess");
+        }
+        assert_eq!(auth.failures.lock().unwrap().len(), MAX_TRACKED_CLIENTS);
+    }
+
+    #[test]
+    fn test_disabled_without_password() {
+        let auth = WebAuth::new(&WebServerConfig::default());
+        assert!(!auth.is_enabled());
+        assert!(auth.validate("anything"));
+    }
+
+    #[test]
+    fn test_request_token() {
+        let mut headers = HeaderMap::new();
+        assert!(request_token(&headers).is_none());
+
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; yoop_session=abc123"),
+        );
+        assert_eq!(request_token(&headers).as_deref(), Some("abc123"));
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer xyz"),
+        );
+        assert_eq!(request_token(&headers).as_deref(), Some("xyz"));
+    }
+}
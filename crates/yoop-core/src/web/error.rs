@@ -10,6 +10,9 @@ use axum::{
 };
 use serde::Serialize;
 
+/// Error code of responses to unauthenticated requests.
+pub const UNAUTHORIZED: &str = "AUTH";
+
 /// API error response body.
 #[derive(Debug, Clone, Serialize)]
 pub struct ApiError {
@@ -63,6 +66,7 @@ impl ApiError {
             Some("E007" | "E010") => StatusCode::FORBIDDEN,
             Some("E008") => StatusCode::INSUFFICIENT_STORAGE,
             Some("E009") => StatusCode::TOO_MANY_REQUESTS,
+            Some(UNAUTHORIZED) => StatusCode::UNAUTHORIZED,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -117,6 +121,16 @@ impl ApiError {
         }
     }
 
+    /// Create an unauthorized error (login required or failed).
+    #[must_use]
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self {
+            code: Some(UNAUTHORIZED.into()),
+            message: message.into(),
+            details: None,
+        }
+    }
+
     /// Create a gone error (resource expired).
     #[must_use]
     pub fn gone(message: impl Into<String>) -> Self {
@@ -226,6 +240,10 @@ mod tests {
             ApiError::with_code("E009", "").status_code(),
             StatusCode::TOO_MANY_REQUESTS
         );
+        assert_eq!(
+            ApiError::unauthorized("").status_code(),
+            StatusCode::UNAUTHORIZED
+        );
     }
 
     #[test]
@@ -23,11 +23,11 @@ use tokio_util::io::ReaderStream;
 use crate::code::ShareCode;
 use crate::file::FileMetadata;
 use crate::history::TransferHistoryEntry;
-use crate::transfer::{ReceiveSession, ShareSession, TransferConfig};
+use crate::transfer::{limits, ReceiveSession, ShareSession, TransferConfig};
 
 use super::error::{ApiError, ApiResult};
 use super::state::{
-    ActiveReceive, ActiveShare, CompletedReceive, PendingReceive, SharedState, WebMode,
+    ActiveReceive, ActiveShare, AppState, CompletedReceive, PendingReceive, SharedState, WebMode,
 };
 
 // ============================================================================
@@ -57,6 +57,9 @@ pub struct NetworkResponse {
     device_name: String,
     /// Local IP addresses
     addresses: Vec<String>,
+    /// SHA-256 fingerprint of the HTTPS certificate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
 }
 
 /// Share creation response.
@@ -216,6 +219,7 @@ pub async fn get_network(State(state): State<SharedState>) -> ApiResult<Json<Net
     Ok(Json(NetworkResponse {
         device_name: state.device_name.clone(),
         addresses,
+        fingerprint: state.config.fingerprint(),
     }))
 }
 
@@ -313,8 +317,8 @@ pub async fn create_share(
         share_wait_task(state_clone).await;
     });
 
-    let deep_link = crate::qr::create_deep_link(&code, &crate::qr::QrConfig::default());
-    let qr_svg = crate::qr::generate_svg(&code).ok();
+    let deep_link = crate::qr::create_deep_link(&code, &qr_config(&state));
+    let qr_svg = crate::qr::generate_svg_text(&deep_link).ok();
 
     Ok(Json(ShareResponse {
         code,
@@ -367,6 +371,14 @@ async fn share_wait_task(state: SharedState) {
     state.reset_to_idle().await;
 }
 
+/// QR settings carrying the server's certificate fingerprint, if any.
+fn qr_config(state: &AppState) -> crate::qr::QrConfig {
+    crate::qr::QrConfig {
+        fingerprint: state.config.fingerprint(),
+        ..crate::qr::QrConfig::default()
+    }
+}
+
 /// DELETE /api/share - Cancel the current share.
 pub async fn cancel_share(State(state): State<SharedState>) -> ApiResult<StatusCode> {
     let mode = *state.mode.read().await;
@@ -401,7 +413,8 @@ pub async fn get_share_qr(State(state): State<SharedState>) -> ApiResult<Respons
         .await
         .ok_or_else(|| ApiError::not_found("No active share"))?;
 
-    let svg = crate::qr::generate_svg(&code)
+    let deep_link = crate::qr::create_deep_link(&code, &qr_config(&state));
+    let svg = crate::qr::generate_svg_text(&deep_link)
         .map_err(|e| ApiError::internal(format!("Failed to generate QR code: {e}")))?;
 
     Ok(Response::builder()
@@ -436,12 +449,7 @@ pub async fn start_receive(
         .await
         .map_err(|e| ApiError::internal(format!("Failed to create output directory: {e}")))?;
 
-    let config = TransferConfig {
-        discovery_timeout: std::time::Duration::from_secs(30),
-        ..Default::default()
-    };
-
-    let session = ReceiveSession::connect(&code, output_dir.clone(), config)
+    let session = ReceiveSession::connect(&code, output_dir.clone(), state.config.transfer.clone())
         .await
         .map_err(|e| match e {
             crate::error::Error::CodeNotFound(_) => ApiError::not_found("Share code not found"),
@@ -451,7 +459,7 @@ pub async fn start_receive(
 
     let (sender_addr, sender_name) = session.sender();
     let files: Vec<FileInfo> = session.files().iter().map(FileInfo::from).collect();
-    let total_size: u64 = session.files().iter().map(|f| f.size).sum();
+    let total_size: u64 = limits::total_size(session.files());
 
     let response = ReceiveResponse {
         sender_name: sender_name.to_string(),
@@ -10,13 +10,32 @@
 //! yoop web --port 9000         # Custom port
 //! yoop web --localhost-only    # Restrict to localhost
 //! yoop web --auth              # Enable authentication
+//! yoop web --https             # Serve over HTTPS
 //! ```
 //!
+//! ## Authentication
+//!
+//! With `--auth`, the API requires a session obtained by logging in with the
+//! password printed on startup (or `web.password` from the config); see
+//! [`auth`].
+//!
+//! ## HTTPS
+//!
+//! With a certificate configured the server speaks HTTPS, using a
+//! self-signed certificate that is kept across restarts (see
+//! [`SelfSignedCertificate`](crate::crypto::SelfSignedCertificate)). Its
+//! fingerprint is printed on startup and added to share deep links so it
+//! can be checked against what the browser shows. Plain HTTP requests to
+//! the same port are redirected to `https://`.
+//!
 //! ## API Endpoints
 //!
 //! | Method | Endpoint | Description |
 //! |--------|----------|-------------|
 //! | GET | / | Web UI (SPA) |
+//! | POST | /api/auth/login | Log in with the password |
+//! | POST | /api/auth/logout | End the session |
+//! | GET | /api/auth/status | Whether a login is needed |
 //! | GET | /api/status | Current status |
 //! | GET | /api/network | Network info |
 //! | POST | /api/share | Start sharing (multipart upload) |
@@ -31,15 +50,19 @@
 //! | GET | /api/history | Transfer history |
 
 pub mod assets;
+pub mod auth;
 pub mod error;
 pub mod handlers;
 pub mod sse;
 pub mod state;
+mod tls;
 
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
+    middleware,
     routing::{delete, get, post},
     Router,
 };
@@ -47,6 +70,7 @@ use tokio::net::TcpListener;
 use tokio::sync::oneshot;
 use tower_http::{cors::CorsLayer, limit::RequestBodyLimitLayer};
 
+use crate::crypto::SelfSignedCertificate;
 use crate::error::Result;
 
 pub use error::{ApiError, ApiResult};
@@ -63,6 +87,14 @@ pub struct WebServerConfig {
     pub auth_enabled: bool,
     /// Authentication password (generated if auth enabled)
     pub auth_password: Option<String>,
+    /// Failed logins from one address before it is locked out
+    pub max_login_attempts: u32,
+    /// How long a locked out address has to wait
+    pub lockout: Duration,
+    /// Certificate to serve HTTPS with (plain HTTP if unset)
+    pub certificate: Option<SelfSignedCertificate>,
+    /// Transfer settings for receives started from the web UI
+    pub transfer: crate::transfer::TransferConfig,
 }
 
 impl Default for WebServerConfig {
@@ -72,18 +104,25 @@ impl Default for WebServerConfig {
             localhost_only: false,
             auth_enabled: false,
             auth_password: None,
+            max_login_attempts: 3,
+            lockout: Duration::from_secs(30),
+            certificate: None,
+            transfer: crate::transfer::TransferConfig::default(),
         }
     }
 }
 
 impl WebServerConfig {
     /// Generate a random password for authentication.
+    ///
+    /// 16 characters from a 57-character alphabet give over 90 bits, so the
+    /// password holds up even when guesses come from many addresses.
     #[must_use]
     pub fn generate_password() -> String {
         use rand::Rng;
         const CHARSET: &[u8] = b"abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
         let mut rng = rand::thread_rng();
-        (0..6)
+        (0..16)
             .map(|_| {
                 let idx = rng.gen_range(0..CHARSET.len());
                 CHARSET[idx] as char
@@ -91,6 +130,24 @@ impl WebServerConfig {
             .collect()
     }
 
+    /// URL scheme the server is reached with.
+    #[must_use]
+    pub const fn scheme(&self) -> &'static str {
+        if self.certificate.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// SHA-256 fingerprint of the HTTPS certificate, if any.
+    #[must_use]
+    pub fn fingerprint(&self) -> Option<String> {
+        self.certificate
+            .as_ref()
+            .map(SelfSignedCertificate::fingerprint)
+    }
+
     /// Get the bind address for the server.
     #[must_use]
     pub fn bind_addr(&self) -> SocketAddr {
@@ -116,7 +173,14 @@ fn create_router(state: SharedState) -> Router {
         .route("/receive/decline", post(handlers::decline_receive))
         .route("/receive/download", get(handlers::download_received))
         .route("/transfer/progress", get(sse::progress_sse))
-        .route("/history", get(handlers::get_history));
+        .route("/history", get(handlers::get_history))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            auth::require_auth,
+        ))
+        .route("/auth/login", post(auth::login))
+        .route("/auth/logout", post(auth::logout))
+        .route("/auth/status", get(auth::status));
 
     Router::new()
         .nest("/api", api_routes)
@@ -143,8 +207,13 @@ impl std::fmt::Debug for WebServer {
 
 impl WebServer {
     /// Create a new web server with the given configuration.
+    ///
+    /// A password is generated if authentication is enabled without one.
     #[must_use]
-    pub fn new(config: WebServerConfig) -> Self {
+    pub fn new(mut config: WebServerConfig) -> Self {
+        if config.auth_enabled && config.auth_password.is_none() {
+            config.auth_password = Some(WebServerConfig::generate_password());
+        }
         let state = Arc::new(AppState::new(config.clone()));
         Self {
             config,
@@ -178,7 +247,11 @@ impl WebServer {
             .await
             .map_err(|e| crate::error::Error::Internal(format!("Failed to bind to {addr}: {e}")))?;
 
-        tracing::info!("Web server listening on http://{}", addr);
+        tracing::info!(
+            "Web server listening on {}://{}",
+            self.config.scheme(),
+            addr
+        );
 
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
         self.shutdown_tx = Some(shutdown_tx);
@@ -198,10 +271,18 @@ impl WebServer {
             tracing::info!("Web server shutting down");
         };
 
-        axum::serve(listener, router)
-            .with_graceful_shutdown(shutdown)
-            .await
-            .map_err(|e| crate::error::Error::Internal(format!("Server error: {e}")))?;
+        if let Some(certificate) = &self.config.certificate {
+            tls::serve(listener, router, certificate.server_config()?, shutdown).await;
+            return Ok(());
+        }
+
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(|e| crate::error::Error::Internal(format!("Server error: {e}")))?;
 
         Ok(())
     }
@@ -217,12 +298,13 @@ impl WebServer {
     /// Get all network addresses the server is accessible from.
     #[must_use]
     pub fn addresses(&self) -> Vec<String> {
-        let mut addrs = vec![format!("http://localhost:{}", self.config.port)];
+        let scheme = self.config.scheme();
+        let mut addrs = vec![format!("{scheme}://localhost:{}", self.config.port)];
 
         if !self.config.localhost_only {
             if let Ok(interfaces) = get_local_addresses() {
                 for ip in interfaces {
-                    addrs.push(format!("http://{}:{}", ip, self.config.port));
+                    addrs.push(format!("{scheme}://{}:{}", ip, self.config.port));
                 }
             }
         }
@@ -354,7 +436,7 @@ mod tests {
     #[test]
     fn test_generate_password() {
         let password = WebServerConfig::generate_password();
-        assert_eq!(password.len(), 6);
+        assert_eq!(password.len(), 16);
         assert!(password.chars().all(|c| c.is_ascii_alphanumeric()));
     }
 
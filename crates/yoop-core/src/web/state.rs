@@ -13,6 +13,7 @@ use crate::file::FileMetadata;
 use crate::history::HistoryStore;
 use crate::transfer::{ReceiveSession, ShareSession, TransferProgress, TransferState};
 
+use super::auth::WebAuth;
 use super::WebServerConfig;
 
 /// The current mode of operation for the web interface.
@@ -132,6 +133,9 @@ pub struct AppState {
     /// Server configuration
     pub config: WebServerConfig,
 
+    /// Password check and sessions
+    pub auth: WebAuth,
+
     /// Temporary directory for uploaded files (share) and received files
     pub temp_dir: PathBuf,
 
@@ -176,6 +180,7 @@ impl AppState {
                 )
                 .unwrap_or_else(|_| panic!("Failed to create fallback history store"))
             })),
+            auth: WebAuth::new(&config),
             config,
             temp_dir,
             device_name,
@@ -0,0 +1,207 @@
+//! HTTPS serving for the web interface.
+//!
+//! `axum::serve` only speaks plain HTTP, so in HTTPS mode connections are
+//! accepted here and handed to hyper after the TLS handshake. HTTPS and
+//! HTTP share the configured port: a connection whose first byte is not a
+//! TLS handshake record gets a redirect to the same URL over `https://`,
+//! so typing the plain address into a browser still works.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, Request, Response, StatusCode},
+    Router,
+};
+use hyper::body::Incoming;
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+
+/// First byte of a TLS handshake record.
+const TLS_HANDSHAKE: u8 = 0x16;
+
+/// How long a new connection may take to send its first byte and finish
+/// the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serve `router` over TLS until `shutdown` completes.
+pub async fn serve(
+    listener: TcpListener,
+    router: Router,
+    tls: Arc<rustls::ServerConfig>,
+    shutdown: impl Future<Output = ()>,
+) {
+    let acceptor = TlsAcceptor::from(tls);
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            () = &mut shutdown => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::debug!("Failed to accept web connection: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        tokio::spawn(async move {
+            handle_connection(stream, peer, acceptor, router).await;
+        });
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    acceptor: TlsAcceptor,
+    router: Router,
+) {
+    let mut first = [0u8; 1];
+    match tokio::time::timeout(HANDSHAKE_TIMEOUT, stream.peek(&mut first)).await {
+        Ok(Ok(1..)) => {}
+        _ => return,
+    }
+
+    if first[0] != TLS_HANDSHAKE {
+        serve_http(stream, hyper::service::service_fn(redirect_to_https)).await;
+        return;
+    }
+
+    let stream = match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => {
+            tracing::debug!("TLS handshake with {} failed: {}", peer, e);
+            return;
+        }
+        Err(_) => {
+            tracing::debug!("TLS handshake with {} timed out", peer);
+            return;
+        }
+    };
+
+    let service = hyper::service::service_fn(move |mut request: Request<Incoming>| {
+        request.extensions_mut().insert(ConnectInfo(peer));
+        router.clone().oneshot(request.map(Body::new))
+    });
+    serve_http(stream, service).await;
+}
+
+async fn serve_http<S, E>(stream: S, service: E)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    E: hyper::service::Service<Request<Incoming>, Response = Response<Body>, Error = Infallible>,
+    E::Future: Send + 'static,
+{
+    if let Err(e) = hyper::server::conn::http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .await
+    {
+        tracing::debug!("Web connection error: {}", e);
+    }
+}
+
+/// Answer a plain HTTP request with a redirect to its `https://` URL.
+async fn redirect_to_https(request: Request<Incoming>) -> Result<Response<Body>, Infallible> {
+    let response = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .map(|host| {
+            let path = request.uri().path_and_query().map_or("/", |p| p.as_str());
+            format!("https://{host}{path}")
+        })
+        .and_then(|location| {
+            Response::builder()
+                .status(StatusCode::PERMANENT_REDIRECT)
+                .header(header::LOCATION, location)
+                .body(Body::empty())
+                .ok()
+        })
+        .unwrap_or_else(|| {
+            let mut response = Response::new(Body::from("Use https:// to connect"));
+            *response.status_mut() = StatusCode::BAD_REQUEST;
+            response
+        });
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{SelfSignedCertificate, TlsConfig};
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn start_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let cert = SelfSignedCertificate::generate(vec!["localhost".to_string()]).unwrap();
+        let router = Router::new().route(
+            "/peer",
+            get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.ip().to_string() }),
+        );
+        tokio::spawn(serve(
+            listener,
+            router,
+            cert.server_config().unwrap(),
+            std::future::pending(),
+        ));
+        addr
+    }
+
+    async fn read_response<S: AsyncRead + Unpin>(mut stream: S) -> String {
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await;
+        String::from_utf8_lossy(&response).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_plain_http_is_redirected() {
+        let addr = start_server().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /api/status?x=1 HTTP/1.1\r\nHost: 192.168.1.5:8080\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let response = read_response(stream).await;
+        assert!(response.starts_with("HTTP/1.1 308"));
+        assert!(response
+            .to_lowercase()
+            .contains("location: https://192.168.1.5:8080/api/status?x=1"));
+    }
+
+    #[tokio::test]
+    async fn test_https_request_reaches_router() {
+        let addr = start_server().await;
+        let client = TlsConfig::client().unwrap();
+        let connector =
+            tokio_rustls::TlsConnector::from(Arc::new(client.client_config().unwrap().clone()));
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = connector
+            .connect("localhost".try_into().unwrap(), stream)
+            .await
+            .unwrap();
+        stream
+            .write_all(b"GET /peer HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let response = read_response(stream).await;
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("127.0.0.1"));
+    }
+}
@@ -4,6 +4,13 @@
 //! - Single file transfers
 //! - Multiple file transfers
 //! - Large file transfers (multi-chunk)
+//! - Adaptive compression
+//! - Resuming after a dropped connection
+//! - Ending a share when a reconnection uses the wrong code
+//! - Transfers over IPv6
+//! - Transfers through a relay server
+//! - Concurrent shares binding separate ports from the transfer port range
+//! - Receive limits declining oversized file lists
 //! - Error handling (invalid codes, decline, etc.)
 //!
 //! Note: Most tests are ignored in CI because they rely on UDP broadcast
@@ -11,10 +18,18 @@
 
 mod common;
 
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 use yoop_core::code::ShareCode;
-use yoop_core::transfer::{ReceiveSession, ShareSession, TransferConfig, TransferState};
+use yoop_core::compression::CompressionMode;
+use yoop_core::error::Error;
+use yoop_core::relay::RelayServer;
+use yoop_core::transfer::{
+    ReceiveLimits, ReceiveSession, ShareSession, TransferConfig, TransferState,
+};
 
 use common::{
     assert_files_equal, create_temp_dir, create_test_directory, create_test_file, get_test_ports,
@@ -174,6 +189,430 @@ async fn test_large_file_transfer() {
     assert_files_equal(&test_file, &received_file);
 }
 
+/// Test a multi-chunk transfer with adaptive compression over a direct connection.
+#[tokio::test]
+async fn test_adaptive_compression_transfer() {
+    let temp_dir = create_temp_dir();
+    let content = b"adaptive compression picks an algorithm per chunk. ".repeat(80_000);
+    let test_file = create_test_file(temp_dir.path(), "log.txt", &content);
+    let output_dir = temp_dir.path().join("output");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let config = TransferConfig {
+        compression: CompressionMode::Adaptive,
+        chunk_size: 256 * 1024,
+        ..test_config()
+    };
+    let transfer_addr = SocketAddr::from(([127, 0, 0, 1], config.transfer_port));
+
+    let mut share_session = ShareSession::new(std::slice::from_ref(&test_file), config.clone())
+        .await
+        .expect("Failed to create share session");
+    let code = share_session.code().clone();
+
+    let share_handle = tokio::spawn(async move {
+        let result = share_session.wait().await;
+        (result, share_session)
+    });
+
+    let mut receive_session = ReceiveSession::connect_with_options(
+        &code,
+        output_dir.clone(),
+        Some(transfer_addr),
+        config,
+    )
+    .await
+    .expect("Failed to connect to share");
+
+    receive_session
+        .accept()
+        .await
+        .expect("Failed to accept transfer");
+
+    let (result, share_session) = share_handle.await.expect("Share task panicked");
+    result.expect("Share failed");
+
+    let received_file = output_dir.join("log.txt");
+    assert_files_equal(&test_file, &received_file);
+
+    let stats = share_session.compression_stats();
+    assert_eq!(stats.original_bytes, content.len() as u64);
+    assert!(stats.total_chunks() > 1);
+    assert!(!stats.per_algorithm.is_empty());
+}
+
+/// Test a transfer over IPv6 loopback using the dual-stack listener.
+#[tokio::test]
+async fn test_ipv6_direct_transfer() {
+    if std::net::TcpListener::bind("[::1]:0").is_err() {
+        eprintln!("IPv6 loopback unavailable, skipping");
+        return;
+    }
+
+    let temp_dir = create_temp_dir();
+    let content = random_bytes(64 * 1024);
+    let test_file = create_test_file(temp_dir.path(), "v6.bin", &content);
+    let output_dir = temp_dir.path().join("output");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let config = test_config();
+    let transfer_addr: SocketAddr = format!("[::1]:{}", config.transfer_port).parse().unwrap();
+
+    let mut share_session = ShareSession::new(std::slice::from_ref(&test_file), config.clone())
+        .await
+        .expect("Failed to create share session");
+    let code = share_session.code().clone();
+
+    let share_handle = tokio::spawn(async move { share_session.wait().await });
+
+    let mut receive_session = ReceiveSession::connect_with_options(
+        &code,
+        output_dir.clone(),
+        Some(transfer_addr),
+        config,
+    )
+    .await
+    .expect("Failed to connect over IPv6");
+
+    assert!(receive_session.sender().0.is_ipv6());
+
+    receive_session
+        .accept()
+        .await
+        .expect("Failed to accept transfer");
+
+    share_handle
+        .await
+        .expect("Share task panicked")
+        .expect("Share failed");
+
+    assert_files_equal(&test_file, &output_dir.join("v6.bin"));
+}
+
+/// Test that a receiver declines a file list breaking its receive limits.
+#[tokio::test]
+async fn test_receive_limits_decline_transfer() {
+    let temp_dir = create_temp_dir();
+    let first = create_test_file(temp_dir.path(), "a.txt", b"first");
+    let second = create_test_file(temp_dir.path(), "b.txt", b"second");
+    let output_dir = temp_dir.path().join("output");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let config = test_config();
+    let transfer_addr: SocketAddr = format!("127.0.0.1:{}", config.transfer_port)
+        .parse()
+        .unwrap();
+
+    let mut share_session = ShareSession::new(&[first, second], config.clone())
+        .await
+        .expect("Failed to create share session");
+    let code = share_session.code().clone();
+    let share_handle = tokio::spawn(async move { share_session.wait().await });
+
+    let receive_config = TransferConfig {
+        limits: ReceiveLimits {
+            max_files: Some(1),
+            ..ReceiveLimits::default()
+        },
+        ..config
+    };
+    let result = ReceiveSession::connect_with_options(
+        &code,
+        output_dir.clone(),
+        Some(transfer_addr),
+        receive_config,
+    )
+    .await;
+
+    assert!(matches!(
+        result,
+        Err(Error::ReceiveLimitExceeded {
+            limit: "file count",
+            actual: 2,
+            max: 1
+        })
+    ));
+    let share_result = tokio::time::timeout(Duration::from_secs(10), share_handle)
+        .await
+        .expect("Share did not finish")
+        .expect("Share task panicked");
+    assert!(share_result.is_err());
+    assert_eq!(std::fs::read_dir(&output_dir).unwrap().count(), 0);
+}
+
+/// Test that concurrent shares pick separate ports from the transfer port range.
+#[tokio::test]
+async fn test_concurrent_shares_use_separate_ports() {
+    let temp_dir = create_temp_dir();
+    let test_file = create_test_file(temp_dir.path(), "a.txt", b"port range");
+
+    let first_config = test_config();
+    let port = first_config.transfer_port;
+    let first_config = TransferConfig {
+        transfer_port_end: port + 5,
+        ..first_config
+    };
+    let second_config = TransferConfig {
+        transfer_port: port,
+        transfer_port_end: port + 5,
+        ..test_config()
+    };
+
+    let first = ShareSession::new(std::slice::from_ref(&test_file), first_config)
+        .await
+        .expect("Failed to create first share");
+    let second = ShareSession::new(std::slice::from_ref(&test_file), second_config)
+        .await
+        .expect("Failed to create second share");
+
+    let first_port = first.local_addr().unwrap().port();
+    let second_port = second.local_addr().unwrap().port();
+    assert_eq!(first_port, port);
+    assert_ne!(first_port, second_port);
+    assert!(second_port > port && second_port <= port + 5);
+}
+
+/// Test a transfer that falls back to a relay when the sender is unreachable.
+#[tokio::test]
+async fn test_relay_transfer() {
+    let relay = RelayServer::new(0, false)
+        .await
+        .expect("Failed to start relay");
+    let relay_addr = format!("127.0.0.1:{}", relay.local_addr().unwrap().port());
+    tokio::spawn(async move { relay.run().await });
+
+    let temp_dir = create_temp_dir();
+    let content = random_bytes(3 * 1024 * 1024);
+    let test_file = create_test_file(temp_dir.path(), "relayed.bin", &content);
+    let output_dir = temp_dir.path().join("output");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let config = TransferConfig {
+        relay: Some(relay_addr),
+        ..test_config()
+    };
+
+    let mut share_session = ShareSession::new(std::slice::from_ref(&test_file), config.clone())
+        .await
+        .expect("Failed to create share session");
+    let code = share_session.code().clone();
+
+    let share_handle = tokio::spawn(async move {
+        let result = share_session.wait().await;
+        (result, share_session.receiver_addr())
+    });
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Nothing listens here, so the direct connection fails.
+    let unreachable = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut receive_session =
+        ReceiveSession::connect_with_options(&code, output_dir.clone(), Some(unreachable), config)
+            .await
+            .expect("Failed to connect through relay");
+
+    assert!(receive_session.is_relayed());
+
+    receive_session
+        .accept()
+        .await
+        .expect("Failed to accept transfer");
+
+    let (result, receiver_addr) = share_handle.await.expect("Share task panicked");
+    result.expect("Share failed");
+    assert!(receiver_addr.is_none());
+
+    assert_files_equal(&test_file, &output_dir.join("relayed.bin"));
+}
+
+/// Forward connections to `target`, cutting the first one after `cut_after`
+/// bytes have been sent back to the client. Later connections are held until
+/// `reconnects` is `true`, then forwarded.
+///
+/// Returns the proxy address and a counter of accepted connections.
+async fn start_flaky_proxy(
+    target: SocketAddr,
+    cut_after: usize,
+    reconnects: tokio::sync::watch::Receiver<bool>,
+) -> (SocketAddr, Arc<AtomicUsize>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_addr = listener.local_addr().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&connections);
+
+    tokio::spawn(async move {
+        while let Ok((mut client, _)) = listener.accept().await {
+            let first = counter.fetch_add(1, Ordering::SeqCst) == 0;
+            if !first {
+                let mut reconnects = reconnects.clone();
+                tokio::spawn(async move {
+                    if reconnects.wait_for(|open| *open).await.is_err() {
+                        return;
+                    }
+                    let mut upstream = TcpStream::connect(target).await.unwrap();
+                    let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+                });
+                continue;
+            }
+            let upstream = TcpStream::connect(target).await.unwrap();
+
+            let (mut client_read, mut client_write) = client.into_split();
+            let (mut upstream_read, mut upstream_write) = upstream.into_split();
+            let forward = tokio::spawn(async move {
+                let _ = tokio::io::copy(&mut client_read, &mut upstream_write).await;
+            });
+
+            let mut forwarded = 0;
+            let mut buf = vec![0u8; 16 * 1024];
+            while forwarded < cut_after {
+                let n = match upstream_read.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                if client_write.write_all(&buf[..n]).await.is_err() {
+                    break;
+                }
+                forwarded += n;
+            }
+            forward.abort();
+        }
+    });
+
+    (proxy_addr, connections)
+}
+
+/// Test that a transfer resumes after the connection drops mid-transfer.
+#[tokio::test]
+async fn test_transfer_resumes_after_connection_drop() {
+    let temp_dir = create_temp_dir();
+    let content = random_bytes(2 * 1024 * 1024);
+    let test_file = create_test_file(temp_dir.path(), "roaming.bin", &content);
+    let output_dir = temp_dir.path().join("output");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let config = TransferConfig {
+        chunk_size: 256 * 1024,
+        reconnect_grace: Duration::from_secs(10),
+        ..test_config()
+    };
+    let transfer_addr = SocketAddr::from(([127, 0, 0, 1], config.transfer_port));
+    let (_open, reconnects) = tokio::sync::watch::channel(true);
+    let (proxy_addr, connections) = start_flaky_proxy(transfer_addr, 700 * 1024, reconnects).await;
+
+    let mut share_session = ShareSession::new(std::slice::from_ref(&test_file), config.clone())
+        .await
+        .expect("Failed to create share session");
+    let code = share_session.code().clone();
+
+    let share_handle = tokio::spawn(async move { share_session.wait().await });
+
+    let mut receive_session =
+        ReceiveSession::connect_with_options(&code, output_dir.clone(), Some(proxy_addr), config)
+            .await
+            .expect("Failed to connect to share");
+
+    receive_session
+        .accept()
+        .await
+        .expect("Transfer did not resume after reconnect");
+
+    share_handle
+        .await
+        .expect("Share task panicked")
+        .expect("Share failed");
+
+    assert_eq!(
+        connections.load(Ordering::SeqCst),
+        2,
+        "Receiver should reconnect exactly once"
+    );
+
+    let received_file = output_dir.join("roaming.bin");
+    assert_files_equal(&test_file, &received_file);
+}
+
+/// Test that a reconnection with the wrong code is turned away without
+/// ending the share, so the real receiver can still resume.
+#[tokio::test]
+async fn test_wrong_code_reconnect_keeps_share_waiting() {
+    let temp_dir = create_temp_dir();
+    let content = random_bytes(2 * 1024 * 1024);
+    let test_file = create_test_file(temp_dir.path(), "guarded.bin", &content);
+    let output_dir = temp_dir.path().join("output");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let config = TransferConfig {
+        chunk_size: 256 * 1024,
+        reconnect_grace: Duration::from_secs(30),
+        ..test_config()
+    };
+    let transfer_addr = SocketAddr::from(([127, 0, 0, 1], config.transfer_port));
+    let (open, reconnects) = tokio::sync::watch::channel(false);
+    let (proxy_addr, _) = start_flaky_proxy(transfer_addr, 700 * 1024, reconnects).await;
+
+    let mut share_session = ShareSession::new(std::slice::from_ref(&test_file), config.clone())
+        .await
+        .expect("Failed to create share session");
+    let code = share_session.code().clone();
+    let mut progress = share_session.progress();
+
+    let share_handle = tokio::spawn(async move { share_session.wait().await });
+
+    let mut receive_session = ReceiveSession::connect_with_options(
+        &code,
+        output_dir.clone(),
+        Some(proxy_addr),
+        config.clone(),
+    )
+    .await
+    .expect("Failed to connect to share");
+    let receive_handle = tokio::spawn(async move { receive_session.accept().await });
+
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        progress.wait_for(|p| p.state == TransferState::Reconnecting),
+    )
+    .await
+    .expect("Sender did not wait for a reconnect")
+    .expect("Share session ended");
+
+    let wrong = if code.as_str() == "A7K9" {
+        "B2C3"
+    } else {
+        "A7K9"
+    };
+    let wrong_code = ShareCode::parse(wrong).expect("Invalid test code");
+    let guess = ReceiveSession::connect_with_options(
+        &wrong_code,
+        temp_dir.path().join("guess"),
+        Some(transfer_addr),
+        config,
+    )
+    .await;
+    assert!(guess.is_err(), "Wrong code should not be accepted");
+
+    open.send(true).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(20), receive_handle)
+        .await
+        .expect("Receiver did not finish after reconnecting")
+        .expect("Receive task panicked")
+        .expect("Transfer did not resume after a wrong-code reconnect");
+    share_handle
+        .await
+        .expect("Share task panicked")
+        .expect("Share failed");
+
+    assert_files_equal(&test_file, &output_dir.join("guarded.bin"));
+}
+
 /// Test that an invalid share code is rejected.
 #[tokio::test]
 async fn test_invalid_code_rejection() {
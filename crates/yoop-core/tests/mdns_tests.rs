@@ -24,6 +24,9 @@ fn test_mdns_properties_to_txt_properties() {
         file_count: 5,
         total_size: 1_024_000,
         protocol_version: "1.0".to_string(),
+        timestamp: 0,
+        nonce: String::new(),
+        mac: None,
     };
 
     let txt = props.to_txt_properties();
@@ -95,6 +98,9 @@ async fn test_mdns_service_registration_lifecycle() {
         file_count: 1,
         total_size: 1024,
         protocol_version: "1.0".to_string(),
+        timestamp: 0,
+        nonce: String::new(),
+        mac: None,
     };
 
     let result = broadcaster.register(props).await;
@@ -270,6 +276,9 @@ async fn test_mdns_registration_discovery_roundtrip() {
         file_count: 3,
         total_size: 4096,
         protocol_version: "1.0".to_string(),
+        timestamp: 0,
+        nonce: String::new(),
+        mac: None,
     };
 
     broadcaster.register(props).await.expect("register");